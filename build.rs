@@ -0,0 +1,19 @@
+//! Exposes the version of the pmrs dependency as `PMRS_VERSION`, read from
+//! Cargo.lock. The OCDG cache includes it in its keys.
+use std::fs;
+
+fn main() {
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    let lock = fs::read_to_string("Cargo.lock").unwrap_or_default();
+    let version = lock
+        .split("[[package]]")
+        .find_map(|package| {
+            let mut lines = package.lines().map(str::trim);
+            if !lines.any(|line| line == r#"name = "pmrs""#) {
+                return None;
+            }
+            lines.find_map(|line| line.strip_prefix("version = ")).map(|version| version.trim_matches('"').to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=PMRS_VERSION={}", version);
+}
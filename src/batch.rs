@@ -0,0 +1,137 @@
+//! `pmrs-cli batch`: one command run over many logs at once. Every input is handled
+//! by its own pmrs-cli process, at most `--jobs` at a time, so a failing log does
+//! not stop the others.
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Instant;
+
+use clap::Args;
+use log::{debug, error};
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+
+use crate::compression;
+use crate::ocel::model::Format;
+use crate::ocel::time::format_seconds;
+
+#[derive(Args, Debug)]
+pub struct Batch {
+    /// Logs to process: files, directories of logs or glob patterns like
+    /// `logs/*.jsonocel`. May be repeated
+    #[clap(short, long = "input", value_name = "INPUT", required = true, multiple_occurrences = true)]
+    pub inputs: Vec<String>,
+
+    /// Number of logs processed at the same time. 0 uses one per core
+    #[clap(short, long, default_value_t = 1)]
+    pub jobs: usize,
+
+    /// Output path of each run, substituted for `{output}` in the command. `{stem}`
+    /// is replaced by the file name of the input without its extensions and
+    /// `{name}` by the whole file name
+    #[clap(short, long, value_name = "TEMPLATE")]
+    pub output_template: Option<String>,
+
+    /// The pmrs-cli command to run, with `{input}` and `{output}` placeholders, e.g.
+    /// `-- ocdg generate {input} -o {output}`
+    #[clap(last = true, required = true)]
+    pub command: Vec<String>,
+}
+
+/// The inputs as files: directories are listed for logs and glob patterns
+/// expanded, both in name order. Duplicates are dropped.
+fn expand(inputs: &[String]) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for input in inputs {
+        let path = Path::new(input);
+        let mut found: Vec<PathBuf> = if path.is_dir() {
+            let entries = fs::read_dir(path)?.map(|entry| entry.map(|e| e.path())).collect::<Result<Vec<_>, _>>()?;
+            entries.into_iter().filter(|entry| entry.is_file() && Format::from_path(entry).is_ok()).collect()
+        } else if input.contains(|c| matches!(c, '*' | '?' | '[')) {
+            glob::glob(input)?.collect::<Result<Vec<_>, _>>()?
+        } else {
+            vec![path.to_path_buf()]
+        };
+        if found.is_empty() {
+            return Err(format!("{:?} matches no logs", input).into());
+        }
+        found.sort();
+        for file in found {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn output_for(template: &str, input: &Path) -> String {
+    let name = input.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    let stem = compression::inner_path(input).file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    template.replace("{stem}", &stem).replace("{name}", &name)
+}
+
+pub fn run(batch: &Batch) -> Result<(), Box<dyn Error>> {
+    let uses_output = batch.command.iter().any(|arg| arg.contains("{output}"));
+    if uses_output && batch.output_template.is_none() {
+        return Err("the command uses {output}, pass --output-template".into());
+    }
+    if !batch.command.iter().any(|arg| arg.contains("{input}")) {
+        return Err("the command has to contain {input}".into());
+    }
+    let files = expand(&batch.inputs)?;
+    let program = std::env::current_exe()?;
+    debug!("Running {:?} on {} logs with {} jobs", batch.command, files.len(), batch.jobs);
+
+    let started = Instant::now();
+    let run_one = |input: &PathBuf| -> bool {
+        let input_text = input.to_string_lossy();
+        let output = batch.output_template.as_deref().map(|template| output_for(template, input));
+        if let Some(parent) = output.as_deref().and_then(|output| Path::new(output).parent()) {
+            if let Err(e) = fs::create_dir_all(parent) {
+                error!("Could not create the directory {:?} for {:?}: {}", parent, input, e);
+                return false;
+            }
+        }
+        let args = batch.command.iter().map(|arg| {
+            let arg = arg.replace("{input}", &input_text);
+            match &output {
+                Some(output) => arg.replace("{output}", output),
+                None => arg,
+            }
+        });
+        match Command::new(&program).args(args).output() {
+            Ok(result) => {
+                // the output of a run is printed in one piece so that parallel runs do not interleave
+                let stdout = io::stdout();
+                let mut out = stdout.lock();
+                let _ = out.write_all(&result.stdout);
+                let _ = io::stderr().write_all(&result.stderr);
+                if !result.status.success() {
+                    error!("{:?} failed with {}", input, result.status);
+                }
+                result.status.success()
+            }
+            Err(e) => {
+                error!("Could not run pmrs-cli on {:?}: {}", input, e);
+                false
+            }
+        }
+    };
+
+    let pool = ThreadPoolBuilder::new().num_threads(batch.jobs).build()?;
+    let succeeded: Vec<bool> = pool.install(|| files.par_iter().map(run_one).collect());
+    let failed = succeeded.iter().filter(|ok| !**ok).count();
+    status!(
+        "{} of {} logs processed successfully in {}",
+        files.len() - failed,
+        files.len(),
+        format_seconds(started.elapsed().as_secs_f64())
+    );
+    if failed > 0 {
+        return Err(format!("{} of {} runs failed", failed, files.len()).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,142 @@
+//! `pmrs-cli benchmark`: repeatable timings of the pmrs phases behind a command. Every
+//! iteration imports the log again, so the numbers include the cost a real run pays.
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use clap::{Args, ValueEnum};
+use indexmap::IndexMap;
+use log::debug;
+use pmrs::objects::ocdg::exporter::export_ocdg;
+use pmrs::objects::ocdg::{generate_ocdg, Relations};
+use pmrs::objects::ocel::importer::import_ocel;
+use serde_json::{json, Value};
+use strum::IntoEnumIterator;
+
+use crate::ocdg::relations::parse_relations;
+use crate::ocel::as_jsonocel;
+use crate::ocel::performance::Stats;
+use crate::table::{Cell, Table};
+
+#[derive(Args, Debug)]
+pub struct Benchmark {
+    /// Operation to measure
+    #[clap(value_enum)]
+    pub target: Target,
+
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Number of measured runs
+    #[clap(long, default_value_t = 3)]
+    pub iterations: usize,
+
+    /// Relations to generate for ocdg-generate, all relations if unset
+    #[clap(long, value_name = "RELATION", use_value_delimiter = true, multiple_occurrences = true)]
+    pub relations: Option<Vec<String>>,
+
+    /// Print the report as JSON
+    #[clap(long)]
+    pub json: bool,
+
+    /// Additionally write the JSON report to this file
+    #[clap(long, value_name = "PATH")]
+    pub report: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Importing the log with pmrs
+    OcelImport,
+    /// Importing, generating every relation on its own and exporting the OCDG
+    OcdgGenerate,
+}
+
+fn selected_relations(benchmark: &Benchmark) -> Result<Vec<Relations>, String> {
+    match &benchmark.relations {
+        Some(names) => parse_relations(names),
+        None => Ok(Relations::iter().collect()),
+    }
+}
+
+/// Peak resident memory of the process in kilobytes, from `/proc` where available.
+fn peak_memory_kb() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+pub fn run(benchmark: &Benchmark) -> Result<(), Box<dyn Error>> {
+    if benchmark.iterations == 0 {
+        return Err("--iterations must be at least 1".into());
+    }
+    // checked before the first import, and again per iteration as generating consumes them
+    selected_relations(benchmark)?;
+    // pmrs imports JSON-OCEL only, other formats are converted once up front
+    let source = as_jsonocel(&benchmark.path)?;
+    let source = source.to_string_lossy();
+    let staging = tempfile::Builder::new().suffix(".gexf").tempfile()?.into_temp_path();
+
+    // seconds per phase and iteration, phases in the order they run
+    let mut phases: IndexMap<String, Vec<f64>> = IndexMap::new();
+    for iteration in 0..benchmark.iterations {
+        debug!("Iteration {} of {}", iteration + 1, benchmark.iterations);
+        let started = Instant::now();
+        let log = import_ocel(&source)?;
+        phases.entry("import".to_string()).or_default().push(started.elapsed().as_secs_f64());
+        if benchmark.target == Target::OcelImport {
+            continue;
+        }
+
+        for relation in selected_relations(benchmark)? {
+            let phase = format!("generate {:?}", relation).to_lowercase();
+            let started = Instant::now();
+            let ocdg = generate_ocdg(&log, &vec![relation]);
+            phases.entry(phase).or_default().push(started.elapsed().as_secs_f64());
+            drop(ocdg);
+        }
+        let ocdg = generate_ocdg(&log, &selected_relations(benchmark)?);
+        let started = Instant::now();
+        export_ocdg(&ocdg, &staging.to_string_lossy())?;
+        phases.entry("export".to_string()).or_default().push(started.elapsed().as_secs_f64());
+    }
+    let peak = peak_memory_kb();
+
+    let rows: Vec<(&str, &Vec<f64>, Stats)> =
+        phases.iter().filter_map(|(phase, runs)| Stats::of(runs).map(|stats| (phase.as_str(), runs, stats))).collect();
+    let report = json!({
+        "target": format!("{:?}", benchmark.target),
+        "path": benchmark.path,
+        "iterations": benchmark.iterations,
+        "version": env!("CARGO_PKG_VERSION"),
+        "peak_memory_kb": peak,
+        "phases": rows.iter().map(|(phase, runs, stats)| json!({
+            "phase": phase,
+            "mean": stats.mean,
+            "median": stats.median,
+            "min": stats.min,
+            "max": stats.max,
+            "runs": runs,
+        })).collect::<Vec<Value>>(),
+    });
+    if let Some(path) = &benchmark.report {
+        fs::write(path, serde_json::to_string_pretty(&report)?)?;
+    }
+
+    if benchmark.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    let mut table = Table::new(&["phase", "mean", "median", "min", "max"]).right(&[1, 2, 3, 4]);
+    for (phase, _, stats) in &rows {
+        let seconds = |value: f64| Cell::from(format!("{:.3}s", value));
+        table.row(vec![(*phase).into(), seconds(stats.mean), seconds(stats.median), seconds(stats.min), seconds(stats.max)]);
+    }
+    table.print();
+    match peak {
+        Some(kb) => println!("peak memory: {:.1} MB", kb as f64 / 1024.0),
+        None => println!("peak memory: not available on this platform"),
+    }
+    Ok(())
+}
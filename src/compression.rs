@@ -0,0 +1,79 @@
+//! Transparent `.gz` and `.zst` compression of input and output files, chosen by the
+//! last extension of the path.
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+
+use crate::stdio;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub fn from_path(path: &Path) -> Compression {
+        match path.extension().and_then(OsStr::to_str) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+}
+
+/// `path` without its compression extension, e.g. `log.jsonocel` for
+/// `log.jsonocel.gz`. Used to tell the serialization inside.
+pub fn inner_path(path: &Path) -> PathBuf {
+    match Compression::from_path(path) {
+        Compression::None => path.to_path_buf(),
+        _ => path.with_extension(""),
+    }
+}
+
+/// Opens `path` for reading, `-` reads standard input.
+pub fn open(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    if stdio::is_stdio(path) {
+        return Ok(Box::new(stdio::stdin()?));
+    }
+    let file = File::open(path)?;
+    Ok(match Compression::from_path(path) {
+        Compression::None => Box::new(BufReader::new(file)),
+        Compression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(file))),
+        Compression::Zstd => Box::new(BufReader::new(zstd::stream::read::Decoder::new(file)?)),
+    })
+}
+
+/// Creates `path` for writing, `-` writes to standard output. Compressed streams are
+/// finished when the writer is dropped, so callers should flush it explicitly to see
+/// write errors.
+pub fn create(path: &Path) -> io::Result<Box<dyn Write>> {
+    if stdio::is_stdio(path) {
+        return Ok(Box::new(BufWriter::new(io::stdout())));
+    }
+    let file = File::create(path)?;
+    Ok(match Compression::from_path(path) {
+        Compression::None => Box::new(BufWriter::new(file)),
+        Compression::Gzip => Box::new(BufWriter::new(GzEncoder::new(file, flate2::Compression::default()))),
+        Compression::Zstd => Box::new(BufWriter::new(zstd::stream::write::Encoder::new(file, 0)?.auto_finish())),
+    })
+}
+
+/// A decompressed copy of `path` for the pmrs functions that only take plain file
+/// paths. `None` if the file is neither compressed nor standard input. The copy keeps
+/// the inner extension and is removed on drop.
+pub fn decompressed_copy(path: &Path) -> io::Result<Option<tempfile::TempPath>> {
+    if Compression::from_path(path) == Compression::None && !stdio::is_stdio(path) {
+        return Ok(None);
+    }
+    let suffix = inner_path(path).extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+    let mut file = tempfile::Builder::new().suffix(&suffix).tempfile()?;
+    io::copy(&mut open(path)?, &mut file)?;
+    file.flush()?;
+    Ok(Some(file.into_temp_path()))
+}
@@ -0,0 +1,176 @@
+//! `pmrs.toml` configuration. The file is read from `--config` or, if that is not
+//! given, from `pmrs.toml` in the working directory when it exists.
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Subcommand, ValueEnum};
+use log::LevelFilter;
+use serde::{Deserialize, Deserializer};
+
+use crate::exit::{self, Failure};
+use crate::ocdg::formats::GraphFormat;
+use crate::ocel::ReportFormat;
+
+const DEFAULT_PATH: &str = "pmrs.toml";
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Defaults for every command, `[defaults]`
+    #[serde(default)]
+    pub defaults: Defaults,
+
+    /// Named `ocdg generate` presets, e.g. `[profile.quick]`
+    #[serde(default)]
+    pub profile: BTreeMap<String, Profile>,
+}
+
+/// Defaults that apply to every command taking the option. Flags given on the
+/// command line and profile values take precedence.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Defaults {
+    /// Directory for outputs written under their default name, e.g. `output.gexf`
+    pub output_dir: Option<PathBuf>,
+    /// `ocdg generate --relations`
+    pub relations: Option<Vec<String>>,
+    /// `ocdg generate --threads`
+    pub threads: Option<usize>,
+    /// `ocdg generate --cache-dir`
+    pub cache_dir: Option<PathBuf>,
+    /// Log level when neither `-v`, `--quiet` nor `RUST_LOG` is given, e.g. `info`
+    #[serde(default, deserialize_with = "level")]
+    pub log_level: Option<LevelFilter>,
+    /// Graph format of outputs whose format is neither given by `--format` nor implied
+    /// by their extension
+    #[serde(default, deserialize_with = "value_enum")]
+    pub graph_format: Option<GraphFormat>,
+    /// `--format` of the analysis reports such as `ocel performance`
+    #[serde(default, deserialize_with = "value_enum")]
+    pub report_format: Option<ReportFormat>,
+}
+
+impl Defaults {
+    /// `name` in the output directory, if there is one.
+    pub fn output_path(&self, name: &str) -> PathBuf {
+        match &self.output_dir {
+            Some(dir) => dir.join(name),
+            None => PathBuf::from(name),
+        }
+    }
+
+    /// `explicit`, or the configured graph format if `path` does not imply one.
+    pub fn graph_format(&self, explicit: Option<GraphFormat>, path: &Path) -> Option<GraphFormat> {
+        explicit.or_else(|| GraphFormat::from_path(path).or(self.graph_format))
+    }
+}
+
+fn value_enum<'de, D: Deserializer<'de>, T: ValueEnum>(deserializer: D) -> Result<Option<T>, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    T::from_str(&name, true).map(Some).map_err(serde::de::Error::custom)
+}
+
+fn level<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<LevelFilter>, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    name.parse().map(Some).map_err(|_| serde::de::Error::custom(format!("unknown log level {:?}", name)))
+}
+
+/// Defaults for `ocdg generate`. Every key mirrors the flag of the same name and is
+/// overridden by it.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Profile {
+    pub relations: Option<Vec<String>>,
+    pub output: Option<String>,
+    pub two_mode: Option<Vec<String>>,
+    pub relation_color_map: Option<PathBuf>,
+    pub suppress_relations_with_types: Option<Vec<String>>,
+    pub max_output_size: Option<u64>,
+}
+
+impl Config {
+    pub fn load(explicit: Option<&Path>) -> Result<Config, Box<dyn Error>> {
+        let path = match explicit {
+            Some(path) => path,
+            None if Path::new(DEFAULT_PATH).is_file() => Path::new(DEFAULT_PATH),
+            None => return Ok(Config::default()),
+        };
+        let text = fs::read_to_string(path).map_err(|e| Failure::new(exit::IO_ERROR, format!("could not read {:?}: {}", path, e)))?;
+        toml::from_str(&text).map_err(|e| Failure::new(exit::IMPORT_ERROR, format!("invalid configuration in {:?}: {}", path, e)).into())
+    }
+
+    pub fn profile(&self, name: &str) -> Result<&Profile, Box<dyn Error>> {
+        self.profile.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.profile.keys().map(String::as_str).collect();
+            format!("unknown profile {:?}. Available profiles: {}", name, known.join(", ")).into()
+        })
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct ConfigBase {
+    #[clap(subcommand)]
+    pub commands: ConfigCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommands {
+    /// List the profiles defined in the configuration
+    Profiles,
+    /// Print the defaults that apply to every command
+    Defaults,
+}
+
+pub fn run(base: &ConfigBase, config: &Config) {
+    match base.commands {
+        ConfigCommands::Profiles => {
+            for (name, profile) in &config.profile {
+                println!("{}", name);
+                if let Some(relations) = &profile.relations {
+                    println!("    relations: {}", relations.join(", "));
+                }
+                if let Some(output) = &profile.output {
+                    println!("    output: {}", output);
+                }
+                if let Some(types) = &profile.two_mode {
+                    println!("    two-mode: {}", types.join(" "));
+                }
+                if let Some(pairs) = &profile.suppress_relations_with_types {
+                    println!("    suppress-relations-with-types: {}", pairs.join(", "));
+                }
+                if let Some(path) = &profile.relation_color_map {
+                    println!("    relation-color-map: {}", path.display());
+                }
+                if let Some(limit) = profile.max_output_size {
+                    println!("    max-output-size: {}", limit);
+                }
+            }
+        }
+        ConfigCommands::Defaults => {
+            let defaults = &config.defaults;
+            if let Some(dir) = &defaults.output_dir {
+                println!("output-dir: {}", dir.display());
+            }
+            if let Some(relations) = &defaults.relations {
+                println!("relations: {}", relations.join(", "));
+            }
+            if let Some(threads) = defaults.threads {
+                println!("threads: {}", threads);
+            }
+            if let Some(dir) = &defaults.cache_dir {
+                println!("cache-dir: {}", dir.display());
+            }
+            if let Some(level) = defaults.log_level {
+                println!("log-level: {}", level.to_string().to_lowercase());
+            }
+            if let Some(format) = defaults.graph_format {
+                println!("graph-format: {}", format!("{:?}", format).to_lowercase());
+            }
+            if let Some(format) = defaults.report_format {
+                println!("report-format: {}", format!("{:?}", format).to_lowercase());
+            }
+        }
+    }
+}
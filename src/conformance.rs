@@ -0,0 +1,445 @@
+//! Conformance checking of a log against an object-centric Petri net (PNML) or an
+//! object-centric directly-follows graph. The traces of the objects of each type are
+//! replayed on the part of the model that belongs to that type, which yields a
+//! fitness and a precision per type plus the events the model does not allow.
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::path::PathBuf;
+use std::process;
+
+use clap::Args;
+use log::{debug, warn};
+use serde_json::json;
+
+use crate::compression;
+use crate::discover::petri::{PetriNet, Place};
+use crate::exit;
+use crate::ocdg::graph::Graph;
+use crate::ocel::model::OcelLog;
+use crate::table::{Cell, Style, Table};
+
+/// How many silent transitions replay fires in a row to enable the next activity.
+const MAX_SILENT: usize = 8;
+
+#[derive(Args, Debug)]
+pub struct ConformanceCheck {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Model to check against: a `.pnml` Petri net, or an OC-DFG graph as written by
+    /// `discover ocdfg` in GEXF
+    #[clap(short, long)]
+    pub model: PathBuf,
+
+    /// Only check these object types. May be repeated or comma separated
+    #[clap(long, value_name = "TYPE", use_value_delimiter = true, multiple_occurrences = true)]
+    pub object_type: Vec<String>,
+
+    /// Number of deviations listed per object type
+    #[clap(long, default_value_t = 10)]
+    pub deviations: usize,
+
+    /// Exit with a failure if the fitness of any object type is below this value
+    #[clap(long, value_name = "FITNESS")]
+    pub min_fitness: Option<f64>,
+
+    /// Print the report as JSON
+    #[clap(long)]
+    pub json: bool,
+}
+
+struct Deviation {
+    event: String,
+    object: String,
+    activity: String,
+    reason: &'static str,
+}
+
+impl Deviation {
+    fn new(event: &str, object: &str, activity: &str, reason: &'static str) -> Deviation {
+        Deviation { event: event.to_string(), object: object.to_string(), activity: activity.to_string(), reason }
+    }
+}
+
+struct TypeReport {
+    obj_type: String,
+    objects: usize,
+    events: usize,
+    fitness: f64,
+    precision: f64,
+    deviations: Vec<Deviation>,
+}
+
+impl TypeReport {
+    fn deviating_events(&self) -> usize {
+        self.deviations.iter().map(|d| d.event.as_str()).collect::<HashSet<_>>().len()
+    }
+}
+
+/// The traces of the objects of one type, as (object, [(event, activity)]).
+type Traces<'a> = Vec<(&'a str, Vec<(&'a str, &'a str)>)>;
+
+fn traces_of<'a>(log: &'a OcelLog, obj_type: &str) -> Traces<'a> {
+    log.object_traces()
+        .into_iter()
+        .filter(|(oid, _)| log.objects.get(*oid).map(|o| o.obj_type.as_str()) == Some(obj_type))
+        .map(|(oid, trace)| (oid, trace.into_iter().map(|eid| (eid, log.events[eid].activity.as_str())).collect()))
+        .collect()
+}
+
+/// A transition of the part of a net that one object type moves through, with the
+/// indices of its input and output places in that part.
+struct Step<'a> {
+    label: Option<&'a str>,
+    inputs: Vec<usize>,
+    outputs: Vec<usize>,
+}
+
+struct Projection<'a> {
+    places: usize,
+    sources: Vec<usize>,
+    sinks: Vec<usize>,
+    steps: Vec<Step<'a>>,
+}
+
+type Marking = Vec<u32>;
+
+impl<'a> Projection<'a> {
+    /// The places of `obj_type` and the transitions connected to them. Nets without
+    /// object types are projected as a whole.
+    fn new(net: &'a PetriNet, obj_type: &str) -> Projection<'a> {
+        let typed = net.places.iter().any(|p| !p.obj_type.is_empty());
+        let index: HashMap<&str, usize> = net
+            .places
+            .iter()
+            .filter(|p| !typed || p.obj_type == obj_type)
+            .enumerate()
+            .map(|(i, p)| (p.id.as_str(), i))
+            .collect();
+        let places_of = |filter: fn(&Place) -> bool| -> Vec<usize> {
+            net.places.iter().filter(|p| filter(p)).filter_map(|p| index.get(p.id.as_str()).copied()).collect()
+        };
+        let mut steps = Vec::new();
+        for transition in &net.transitions {
+            let inputs: Vec<usize> =
+                net.arcs.iter().filter(|a| a.target == transition.id).filter_map(|a| index.get(a.source.as_str()).copied()).collect();
+            let outputs: Vec<usize> =
+                net.arcs.iter().filter(|a| a.source == transition.id).filter_map(|a| index.get(a.target.as_str()).copied()).collect();
+            if !inputs.is_empty() || !outputs.is_empty() {
+                steps.push(Step { label: transition.label.as_deref(), inputs, outputs });
+            }
+        }
+        Projection { places: index.len(), sources: places_of(|p| p.source), sinks: places_of(|p| p.sink), steps }
+    }
+
+    fn enabled(&self, step: &Step, marking: &Marking) -> bool {
+        step.inputs.iter().all(|p| marking[*p] > 0)
+    }
+
+    /// The shortest sequence of silent steps after which `goal` holds, if there is
+    /// one of at most [`MAX_SILENT`] steps.
+    fn silent_path(&self, marking: &Marking, mut goal: impl FnMut(&Marking) -> bool) -> Option<Vec<usize>> {
+        let mut queue: VecDeque<(Marking, Vec<usize>)> = VecDeque::new();
+        let mut seen: HashSet<Marking> = HashSet::new();
+        queue.push_back((marking.clone(), Vec::new()));
+        while let Some((current, path)) = queue.pop_front() {
+            if goal(&current) {
+                return Some(path);
+            }
+            if path.len() == MAX_SILENT || !seen.insert(current.clone()) {
+                continue;
+            }
+            for (index, step) in self.steps.iter().enumerate() {
+                if step.label.is_none() && self.enabled(step, &current) {
+                    let mut next = current.clone();
+                    fire(step, &mut next);
+                    let mut longer = path.clone();
+                    longer.push(index);
+                    queue.push_back((next, longer));
+                }
+            }
+        }
+        None
+    }
+
+    /// Activities enabled in `marking` directly or after silent steps.
+    fn enabled_activities(&self, marking: &Marking) -> HashSet<&'a str> {
+        let mut activities = HashSet::new();
+        // the goal never holds, so the search visits every marking in reach
+        self.silent_path(marking, |reached| {
+            for step in &self.steps {
+                if let (Some(label), true) = (step.label, self.enabled(step, reached)) {
+                    activities.insert(label);
+                }
+            }
+            false
+        });
+        activities
+    }
+}
+
+fn fire(step: &Step, marking: &mut Marking) {
+    for p in &step.inputs {
+        marking[*p] = marking[*p].saturating_sub(1);
+    }
+    for p in &step.outputs {
+        marking[*p] += 1;
+    }
+}
+
+/// Token-based replay of every trace of `obj_type`: fitness from produced, consumed,
+/// missing and remaining tokens, precision from the activities the model enables in
+/// each visited marking that the log actually continues with.
+fn replay_net(net: &PetriNet, obj_type: &str, traces: &Traces) -> TypeReport {
+    let projection = Projection::new(net, obj_type);
+    let (mut produced, mut consumed, mut missing, mut remaining) = (0usize, 0usize, 0usize, 0usize);
+    let mut deviations = Vec::new();
+    let mut states: HashMap<Marking, (HashSet<&str>, usize)> = HashMap::new();
+    let mut events = 0;
+
+    for (oid, trace) in traces {
+        let mut marking: Marking = vec![0; projection.places];
+        for p in &projection.sources {
+            marking[*p] += 1;
+            produced += 1;
+        }
+        for (eid, activity) in trace {
+            events += 1;
+            let state = states.entry(marking.clone()).or_default();
+            state.0.insert(*activity);
+            state.1 += 1;
+
+            let candidates: Vec<&Step> = projection.steps.iter().filter(|s| s.label == Some(*activity)).collect();
+            if candidates.is_empty() {
+                deviations.push(Deviation::new(eid, oid, activity, "activity not in the model"));
+                continue;
+            }
+            let path = projection.silent_path(&marking, |m| candidates.iter().any(|s| projection.enabled(s, m)));
+            let step = match path {
+                Some(path) => {
+                    for index in path {
+                        let silent = &projection.steps[index];
+                        consumed += silent.inputs.len();
+                        produced += silent.outputs.len();
+                        fire(silent, &mut marking);
+                    }
+                    *candidates.iter().find(|s| projection.enabled(s, &marking)).expect("the silent path enables a candidate")
+                }
+                None => {
+                    deviations.push(Deviation::new(eid, oid, activity, "not enabled"));
+                    let step = candidates[0];
+                    for p in &step.inputs {
+                        if marking[*p] == 0 {
+                            marking[*p] += 1;
+                            missing += 1;
+                        }
+                    }
+                    step
+                }
+            };
+            consumed += step.inputs.len();
+            produced += step.outputs.len();
+            fire(step, &mut marking);
+        }
+
+        if let Some(path) = projection.silent_path(&marking, |m| projection.sinks.iter().all(|p| m[*p] > 0)) {
+            for index in path {
+                let silent = &projection.steps[index];
+                consumed += silent.inputs.len();
+                produced += silent.outputs.len();
+                fire(silent, &mut marking);
+            }
+        }
+        for p in &projection.sinks {
+            consumed += 1;
+            if marking[*p] == 0 {
+                missing += 1;
+            } else {
+                marking[*p] -= 1;
+            }
+        }
+        remaining += marking.iter().map(|tokens| *tokens as usize).sum::<usize>();
+    }
+
+    let ratio = |part: usize, whole: usize| if whole == 0 { 1.0 } else { 1.0 - part as f64 / whole as f64 };
+    let fitness = 0.5 * ratio(missing, consumed) + 0.5 * ratio(remaining, produced);
+
+    let (mut weighted, mut visits) = (0.0, 0usize);
+    for (marking, (observed, count)) in &states {
+        let enabled = projection.enabled_activities(marking);
+        if enabled.is_empty() {
+            continue;
+        }
+        weighted += *count as f64 * enabled.intersection(observed).count() as f64 / enabled.len() as f64;
+        visits += count;
+    }
+    let precision = if visits == 0 { 1.0 } else { weighted / visits as f64 };
+
+    TypeReport { obj_type: obj_type.to_string(), objects: traces.len(), events, fitness, precision, deviations }
+}
+
+/// Arcs of an OC-DFG per object type. `None` stands for the start and end nodes.
+type DfgArcs = HashMap<String, HashSet<(Option<String>, Option<String>)>>;
+
+fn dfg_arcs(graph: &Graph) -> DfgArcs {
+    let activity = |id: &str| match graph.nodes.get(id).and_then(|n| n.attributes.get("kind")).map(String::as_str) {
+        Some("start") | Some("end") => None,
+        _ => Some(id.to_string()),
+    };
+    let mut arcs = DfgArcs::new();
+    for edge in &graph.edges {
+        if let Some(obj_type) = edge.attributes.get("object_type") {
+            arcs.entry(obj_type.clone()).or_default().insert((activity(&edge.source), activity(&edge.target)));
+        }
+    }
+    arcs
+}
+
+/// Fitness as the share of directly-follows steps, start and end included, that the
+/// graph contains; precision as the share of arcs leaving each activity that the log
+/// uses, weighted by how often the activity occurs.
+fn replay_dfg(model: &HashSet<(Option<String>, Option<String>)>, obj_type: &str, traces: &Traces) -> TypeReport {
+    let (mut steps, mut allowed) = (0usize, 0usize);
+    let mut deviations = Vec::new();
+    let mut used: HashMap<Option<String>, (HashSet<Option<String>>, usize)> = HashMap::new();
+    let mut events = 0;
+
+    for (oid, trace) in traces {
+        if trace.is_empty() {
+            continue;
+        }
+        let mut previous: Option<String> = None;
+        for (eid, activity) in trace.iter().map(|(e, a)| (Some(*e), Some(a.to_string()))).chain(std::iter::once((None, None))) {
+            let key = (previous.clone(), activity.clone());
+            steps += 1;
+            let entry = used.entry(previous.clone()).or_default();
+            entry.1 += 1;
+            if model.contains(&key) {
+                allowed += 1;
+                entry.0.insert(activity.clone());
+            } else if let (Some(eid), Some(activity)) = (eid, &activity) {
+                deviations.push(Deviation::new(eid, oid, activity, "does not follow its predecessor in the model"));
+            } else {
+                let last = trace[trace.len() - 1];
+                deviations.push(Deviation::new(last.0, oid, last.1, "cannot end the trace in the model"));
+            }
+            if eid.is_some() {
+                events += 1;
+            }
+            previous = activity;
+        }
+    }
+
+    let (mut weighted, mut occurrences) = (0.0, 0usize);
+    for (source, (observed, count)) in &used {
+        let outgoing = model.iter().filter(|(s, _)| s == source).count();
+        if outgoing > 0 {
+            weighted += *count as f64 * observed.len() as f64 / outgoing as f64;
+            occurrences += count;
+        }
+    }
+    let fitness = if steps == 0 { 1.0 } else { allowed as f64 / steps as f64 };
+    let precision = if occurrences == 0 { 1.0 } else { weighted / occurrences as f64 };
+    TypeReport { obj_type: obj_type.to_string(), objects: traces.len(), events, fitness, precision, deviations }
+}
+
+pub fn run(check: &ConformanceCheck) -> Result<(), Box<dyn Error>> {
+    debug!("Importing log: {:?}", check.path);
+    let log = OcelLog::import(&check.path)?;
+    let types = log.object_types();
+    if let Some(unknown) = check.object_type.iter().find(|t| !types.contains(&t.as_str())) {
+        return Err(format!("no object is of type {:?}, available types: {}", unknown, types.join(", ")).into());
+    }
+    let selected: Vec<&str> = types.into_iter().filter(|t| check.object_type.is_empty() || check.object_type.iter().any(|s| s == t)).collect();
+
+    debug!("Importing model: {:?}", check.model);
+    let is_pnml = compression::inner_path(&check.model).extension().map_or(false, |ext| ext.eq_ignore_ascii_case("pnml"));
+    let mut reports = Vec::new();
+    if is_pnml {
+        let net = PetriNet::read_pnml(&check.model)?;
+        let modelled = net.object_types();
+        let untyped = modelled.iter().all(|t| t.is_empty());
+        for obj_type in selected {
+            if !untyped && !modelled.contains(&obj_type) {
+                warn!("The model has no places of type {:?}, its objects are not checked.", obj_type);
+                continue;
+            }
+            reports.push(replay_net(&net, obj_type, &traces_of(&log, obj_type)));
+        }
+    } else {
+        let arcs = dfg_arcs(&Graph::import(&check.model)?);
+        for obj_type in selected {
+            match arcs.get(obj_type) {
+                Some(model) => reports.push(replay_dfg(model, obj_type, &traces_of(&log, obj_type))),
+                None => warn!("The model has no arcs of type {:?}, its objects are not checked.", obj_type),
+            }
+        }
+    }
+    if reports.is_empty() {
+        return Err("the model shares no object type with the log".into());
+    }
+
+    if check.json {
+        print_json(check, &reports)?;
+    } else {
+        print_table(&reports, check.deviations, check.min_fitness);
+    }
+
+    if let Some(min) = check.min_fitness {
+        if reports.iter().any(|r| r.fitness < min) {
+            process::exit(exit::VALIDATION_FAILED);
+        }
+    }
+    Ok(())
+}
+
+/// Fitness below `min_fitness` is marked as a failure.
+fn print_table(reports: &[TypeReport], deviations: usize, min_fitness: Option<f64>) {
+    let mut table = Table::new(&["object type", "objects", "events", "fitness", "precision", "deviating"]).right(&[1, 2, 3, 4, 5]);
+    for report in reports {
+        let fitness_style = if min_fitness.map_or(false, |min| report.fitness < min) { Style::Bad } else { Style::Plain };
+        table.row(vec![
+            report.obj_type.as_str().into(),
+            report.objects.into(),
+            report.events.into(),
+            Cell::styled(format!("{:.3}", report.fitness), fitness_style),
+            format!("{:.3}", report.precision).into(),
+            report.deviating_events().into(),
+        ]);
+    }
+    table.print();
+    for report in reports.iter().filter(|r| !r.deviations.is_empty() && deviations > 0) {
+        println!();
+        println!("{} deviations: {}", report.obj_type, report.deviations.len());
+        for deviation in report.deviations.iter().take(deviations) {
+            println!("    {} on {} ({}): {}", deviation.event, deviation.object, deviation.activity, deviation.reason);
+        }
+    }
+}
+
+fn print_json(check: &ConformanceCheck, reports: &[TypeReport]) -> Result<(), Box<dyn Error>> {
+    let types: Vec<_> = reports
+        .iter()
+        .map(|report| {
+            let listed: Vec<_> = report
+                .deviations
+                .iter()
+                .take(check.deviations)
+                .map(|d| json!({"event": d.event, "object": d.object, "activity": d.activity, "reason": d.reason}))
+                .collect();
+            json!({
+                "object_type": report.obj_type,
+                "objects": report.objects,
+                "events": report.events,
+                "fitness": report.fitness,
+                "precision": report.precision,
+                "deviating_events": report.deviating_events(),
+                "deviations_total": report.deviations.len(),
+                "deviations": listed,
+            })
+        })
+        .collect();
+    let report = json!({"log": check.path, "model": check.model, "object_types": types});
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
@@ -0,0 +1,4 @@
+pub mod ocdfg;
+pub mod ocpn;
+pub mod petri;
+pub mod tree;
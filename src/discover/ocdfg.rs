@@ -0,0 +1,211 @@
+//! Object-centric directly-follows graphs: for every object type, which activity
+//! directly follows which in the traces of its objects, how often, and how long the
+//! step from one to the other takes.
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use indexmap::IndexMap;
+use log::debug;
+
+use crate::ocdg::formats::{self, GraphFormat};
+use crate::ocdg::graph::{Edge, Graph, Node, TYPE_KEY};
+use crate::ocdg::palette;
+use crate::ocel::model::OcelLog;
+use crate::ocel::performance::Stats;
+use crate::ocel::time::format_seconds;
+use crate::ocel::window::TimeWindow;
+
+#[derive(Args, Debug)]
+pub struct DiscoverOcdfg {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    #[clap(flatten)]
+    pub window: TimeWindow,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// Graph format to write. Inferred from the output extension if unset, GEXF
+    /// otherwise
+    #[clap(long, value_enum)]
+    pub format: Option<GraphFormat>,
+
+    /// Only discover the directly-follows relations of these object types. May be
+    /// repeated or comma separated
+    #[clap(long, value_name = "TYPE", use_value_delimiter = true, multiple_occurrences = true)]
+    pub object_type: Vec<String>,
+
+    /// Drop arcs observed fewer times than this
+    #[clap(long, default_value_t = 1)]
+    pub min_frequency: usize,
+
+    /// Leave out the start and end node of every object type
+    #[clap(long)]
+    pub no_start_end: bool,
+}
+
+/// An arc of one object type. `None` stands for the start of a trace as the source
+/// and for its end as the target.
+pub type ArcKey = (Option<String>, Option<String>);
+
+#[derive(Debug, Clone, Default)]
+pub struct Arc {
+    /// How often the target directly followed the source
+    pub frequency: usize,
+    /// Number of objects whose trace contains the arc
+    pub objects: usize,
+    /// Seconds between the two events, one entry per occurrence. Empty for start
+    /// and end arcs
+    pub durations: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Ocdfg {
+    /// Number of events per activity, in order of first occurrence
+    pub activities: IndexMap<String, usize>,
+    /// Arcs per object type, in order of first occurrence within the type
+    pub arcs: BTreeMap<String, IndexMap<ArcKey, Arc>>,
+}
+
+/// Discovers the directly-follows graph of `log` for the given object types, all of
+/// them if `object_types` is empty.
+pub fn discover(log: &OcelLog, object_types: &[String]) -> Ocdfg {
+    let selected = |obj_type: &str| object_types.is_empty() || object_types.iter().any(|t| t == obj_type);
+    let mut dfg = Ocdfg::default();
+    let mut counted: HashSet<&str> = HashSet::new();
+
+    for (oid, trace) in log.object_traces() {
+        let obj_type = match log.objects.get(oid) {
+            Some(object) if selected(&object.obj_type) => object.obj_type.as_str(),
+            _ => continue,
+        };
+        let arcs = dfg.arcs.entry(obj_type.to_string()).or_default();
+        let mut seen: HashSet<ArcKey> = HashSet::new();
+        let mut previous: Option<&str> = None;
+        for eid in trace.iter().copied().map(Some).chain(std::iter::once(None)) {
+            let key = (
+                previous.map(|p| log.events[p].activity.clone()),
+                eid.map(|e| log.events[e].activity.clone()),
+            );
+            // objects without events have no arcs at all
+            if key.0.is_none() && key.1.is_none() {
+                break;
+            }
+            let arc = arcs.entry(key.clone()).or_default();
+            arc.frequency += 1;
+            if let (Some(p), Some(e)) = (previous, eid) {
+                let seconds = (log.events[e].timestamp - log.events[p].timestamp).num_milliseconds() as f64 / 1000.0;
+                arc.durations.push(seconds);
+            }
+            if seen.insert(key) {
+                arc.objects += 1;
+            }
+            if let Some(eid) = eid {
+                if counted.insert(eid) {
+                    *dfg.activities.entry(log.events[eid].activity.clone()).or_default() += 1;
+                }
+            }
+            previous = eid;
+        }
+    }
+    dfg
+}
+
+fn start_id(obj_type: &str) -> String {
+    format!("start:{}", obj_type)
+}
+
+fn end_id(obj_type: &str) -> String {
+    format!("end:{}", obj_type)
+}
+
+/// The directly-follows graph as a directed graph with one node per activity, a
+/// start and end node per object type unless `start_end` is unset, and one edge per
+/// arc colored after its object type.
+pub fn to_graph(dfg: &Ocdfg, start_end: bool) -> Graph {
+    let mut graph = Graph { directed: true, ..Graph::default() };
+    for (activity, frequency) in &dfg.activities {
+        let mut node = Node { label: activity.clone(), ..Node::default() };
+        node.attributes.insert("kind".to_string(), "activity".to_string());
+        node.attributes.insert("frequency".to_string(), frequency.to_string());
+        graph.nodes.insert(activity.clone(), node);
+    }
+
+    for (index, (obj_type, arcs)) in dfg.arcs.iter().enumerate() {
+        let color = palette::nth(index);
+        if start_end {
+            for (kind, id) in [("start", start_id(obj_type)), ("end", end_id(obj_type))] {
+                let mut node = Node { label: format!("{} {}", kind, obj_type), color: Some(color), ..Node::default() };
+                node.attributes.insert("kind".to_string(), kind.to_string());
+                node.attributes.insert(TYPE_KEY.to_string(), obj_type.clone());
+                graph.nodes.insert(id, node);
+            }
+        }
+        for ((source, target), arc) in arcs {
+            if !start_end && (source.is_none() || target.is_none()) {
+                continue;
+            }
+            let source = source.clone().unwrap_or_else(|| start_id(obj_type));
+            let target = target.clone().unwrap_or_else(|| end_id(obj_type));
+            let mut edge = Edge {
+                id: graph.edges.len().to_string(),
+                source,
+                target,
+                weight: Some(arc.frequency as f64),
+                color: Some(color),
+                ..Edge::default()
+            };
+            let mut label = format!("{} {}", obj_type, arc.frequency);
+            edge.attributes.insert("object_type".to_string(), obj_type.clone());
+            edge.attributes.insert("frequency".to_string(), arc.frequency.to_string());
+            edge.attributes.insert("objects".to_string(), arc.objects.to_string());
+            if let Some(stats) = Stats::of(&arc.durations) {
+                edge.attributes.insert("mean_seconds".to_string(), stats.mean.to_string());
+                edge.attributes.insert("median_seconds".to_string(), stats.median.to_string());
+                edge.attributes.insert("max_seconds".to_string(), stats.max.to_string());
+                label.push_str(&format!(" ({})", format_seconds(stats.median)));
+            }
+            edge.attributes.insert("label".to_string(), label);
+            graph.edges.push(edge);
+        }
+    }
+    graph
+}
+
+pub fn run(discovery: &DiscoverOcdfg) -> Result<(), Box<dyn Error>> {
+    debug!("Importing log: {:?}", discovery.path);
+    let log = discovery.window.import(&discovery.path)?;
+    let types = log.object_types();
+    if let Some(unknown) = discovery.object_type.iter().find(|t| !types.contains(&t.as_str())) {
+        return Err(format!("no object is of type {:?}, available types: {}", unknown, types.join(", ")).into());
+    }
+
+    let mut dfg = discover(&log, &discovery.object_type);
+    let mut dropped = 0;
+    for arcs in dfg.arcs.values_mut() {
+        let before = arcs.len();
+        arcs.retain(|_, arc| arc.frequency >= discovery.min_frequency);
+        dropped += before - arcs.len();
+    }
+    for (obj_type, arcs) in &dfg.arcs {
+        debug!("{}: {} arcs", obj_type, arcs.len());
+    }
+
+    let graph = to_graph(&dfg, !discovery.no_start_end);
+    let format = GraphFormat::resolve(discovery.format, &discovery.output);
+    debug!("Writing the directly-follows graph to {:?} as {:?}", discovery.output, format);
+    formats::write(&graph, &discovery.output, format)?;
+    status!(
+        "{} object types, {} activities, {} arcs ({} below --min-frequency dropped) -> {}",
+        dfg.arcs.len(),
+        dfg.activities.len(),
+        dfg.arcs.values().map(IndexMap::len).sum::<usize>(),
+        dropped,
+        discovery.output.display()
+    );
+    Ok(())
+}
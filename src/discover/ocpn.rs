@@ -0,0 +1,125 @@
+//! Object-centric Petri net discovery: the log is flattened on every object type, a
+//! process tree is mined from the directly-follows graph of each flattening, and the
+//! resulting nets are composed on their shared activities.
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::io::Write;
+use std::path::PathBuf;
+
+use clap::Args;
+use indexmap::IndexMap;
+use log::debug;
+
+use super::ocdfg::{self, Arc, ArcKey};
+use super::petri::PetriNet;
+use super::tree::{self, Dfg};
+use crate::compression;
+use crate::ocel::window::TimeWindow;
+
+#[derive(Args, Debug)]
+pub struct DiscoverOcpn {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    #[clap(flatten)]
+    pub window: TimeWindow,
+
+    /// PNML file to write the net to
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// Also write a Graphviz DOT visualization of the net to this file
+    #[clap(long, value_name = "PATH")]
+    pub dot: Option<PathBuf>,
+
+    /// Only discover the nets of these object types. May be repeated or comma
+    /// separated
+    #[clap(long, value_name = "TYPE", use_value_delimiter = true, multiple_occurrences = true)]
+    pub object_type: Vec<String>,
+
+    /// Ignore directly-follows arcs less frequent than this fraction of the most
+    /// frequent arc leaving the same activity, between 0 and 1
+    #[clap(long, default_value_t = 0.0)]
+    pub noise_threshold: f64,
+}
+
+/// The directly-follows graph of one object type without arcs below `threshold`
+/// times the most frequent arc with the same source.
+fn filtered_dfg(arcs: &IndexMap<ArcKey, Arc>, threshold: f64) -> Dfg {
+    let mut strongest: HashMap<&Option<String>, usize> = HashMap::new();
+    for ((source, _), arc) in arcs {
+        let max = strongest.entry(source).or_default();
+        *max = (*max).max(arc.frequency);
+    }
+    let mut dfg = Dfg::default();
+    for ((source, target), arc) in arcs {
+        if (arc.frequency as f64) < threshold * strongest[source] as f64 {
+            continue;
+        }
+        dfg.activities.extend(source.iter().chain(target.iter()).cloned());
+        match (source, target) {
+            (Some(source), Some(target)) => {
+                dfg.edges.insert((source.clone(), target.clone()));
+            }
+            (None, Some(target)) => {
+                dfg.start.insert(target.clone());
+            }
+            (Some(source), None) => {
+                dfg.end.insert(source.clone());
+            }
+            (None, None) => {}
+        }
+    }
+    dfg
+}
+
+pub fn run(discovery: &DiscoverOcpn) -> Result<(), Box<dyn Error>> {
+    if !(0.0..=1.0).contains(&discovery.noise_threshold) {
+        return Err(format!("--noise-threshold must be between 0 and 1, not {}", discovery.noise_threshold).into());
+    }
+    debug!("Importing log: {:?}", discovery.path);
+    let log = discovery.window.import(&discovery.path)?;
+    let types = log.object_types();
+    if let Some(unknown) = discovery.object_type.iter().find(|t| !types.contains(&t.as_str())) {
+        return Err(format!("no object is of type {:?}, available types: {}", unknown, types.join(", ")).into());
+    }
+
+    let dfg = ocdfg::discover(&log, &discovery.object_type);
+    let mut net = PetriNet::default();
+    for (obj_type, arcs) in &dfg.arcs {
+        let process_tree = tree::discover(&filtered_dfg(arcs, discovery.noise_threshold));
+        status!("{}: {}", obj_type, process_tree);
+        net.add_tree(&process_tree, obj_type);
+    }
+
+    // an arc is variable if an event of the activity has several objects of the type
+    let mut variable: HashSet<(&str, &str)> = HashSet::new();
+    for event in log.events.values() {
+        let mut per_type: HashMap<&str, usize> = HashMap::new();
+        for object in event.omap.iter().filter_map(|oid| log.objects.get(oid)) {
+            *per_type.entry(&object.obj_type).or_default() += 1;
+        }
+        variable.extend(per_type.into_iter().filter(|(_, count)| *count > 1).map(|(t, _)| (event.activity.as_str(), t)));
+    }
+    net.mark_variable(|activity, obj_type| variable.contains(&(activity, obj_type)));
+
+    debug!("Writing the net to {:?}", discovery.output);
+    let mut out = compression::create(&discovery.output)?;
+    net.write_pnml(&mut out)?;
+    out.flush()?;
+    if let Some(path) = &discovery.dot {
+        debug!("Writing the visualization to {:?}", path);
+        let mut out = compression::create(path)?;
+        net.write_dot(&mut out)?;
+        out.flush()?;
+    }
+    status!(
+        "{} places, {} transitions, {} arcs ({} variable) -> {}",
+        net.places.len(),
+        net.transitions.len(),
+        net.arcs.len(),
+        net.arcs.iter().filter(|a| a.variable).count(),
+        discovery.output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,362 @@
+//! Object-centric Petri nets: every place belongs to an object type, and an arc is
+//! variable if one firing of its transition moves several tokens of that type.
+//! Transitions of the same activity are shared by all object types.
+use std::collections::{BTreeSet, HashSet};
+use std::error::Error;
+use std::io::Write;
+use std::path::Path;
+
+use quick_xml::events::Event as XmlEvent;
+use quick_xml::Reader;
+
+use super::tree::ProcessTree;
+use crate::compression;
+use crate::ocdg::palette;
+use crate::xml::{attributes, escape};
+
+/// Tool name of the PNML `toolspecific` elements that hold object types and
+/// variable arcs.
+const TOOL: &str = "pmrs-cli";
+
+#[derive(Debug, Clone, Default)]
+pub struct PetriNet {
+    pub places: Vec<Place>,
+    pub transitions: Vec<Transition>,
+    pub arcs: Vec<Arc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Place {
+    pub id: String,
+    pub obj_type: String,
+    /// Holds a token per object in the initial marking
+    pub source: bool,
+    /// Holds a token per object in the final marking
+    pub sink: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Transition {
+    pub id: String,
+    /// The activity, `None` for silent transitions
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Arc {
+    pub source: String,
+    pub target: String,
+    pub variable: bool,
+}
+
+impl PetriNet {
+    fn place(&mut self, obj_type: &str) -> String {
+        let id = format!("p{}", self.places.len());
+        self.places.push(Place { id: id.clone(), obj_type: obj_type.to_string(), source: false, sink: false });
+        id
+    }
+
+    /// A new silent transition, or the transition of `label` shared across types.
+    fn transition(&mut self, label: Option<&str>) -> String {
+        if let Some(label) = label {
+            if let Some(existing) = self.transitions.iter().find(|t| t.label.as_deref() == Some(label)) {
+                return existing.id.clone();
+            }
+        }
+        let id = format!("t{}", self.transitions.len());
+        self.transitions.push(Transition { id: id.clone(), label: label.map(str::to_string) });
+        id
+    }
+
+    fn arc(&mut self, source: &str, target: &str) {
+        self.arcs.push(Arc { source: source.to_string(), target: target.to_string(), variable: false });
+    }
+
+    pub fn place_by_id(&self, id: &str) -> Option<&Place> {
+        self.places.iter().find(|p| p.id == id)
+    }
+
+    pub fn transition_by_id(&self, id: &str) -> Option<&Transition> {
+        self.transitions.iter().find(|t| t.id == id)
+    }
+
+    /// Object types with at least one place, sorted.
+    pub fn object_types(&self) -> Vec<&str> {
+        let types: BTreeSet<&str> = self.places.iter().map(|p| p.obj_type.as_str()).collect();
+        types.into_iter().collect()
+    }
+
+    /// Adds the places of `obj_type` and the transitions of `tree` between a new
+    /// source and sink place of that type.
+    pub fn add_tree(&mut self, tree: &ProcessTree, obj_type: &str) {
+        let source = self.place(obj_type);
+        let sink = self.place(obj_type);
+        self.places[self.places.len() - 2].source = true;
+        self.places[self.places.len() - 1].sink = true;
+        self.translate(tree, obj_type, &source, &sink);
+    }
+
+    fn step(&mut self, label: Option<&str>, from: &str, to: &str) {
+        let transition = self.transition(label);
+        self.arc(from, &transition);
+        self.arc(&transition, to);
+    }
+
+    fn translate(&mut self, tree: &ProcessTree, obj_type: &str, from: &str, to: &str) {
+        match tree {
+            ProcessTree::Activity(activity) => self.step(Some(activity), from, to),
+            ProcessTree::Silent => self.step(None, from, to),
+            ProcessTree::Exclusive(children) => {
+                for child in children {
+                    self.translate(child, obj_type, from, to);
+                }
+            }
+            ProcessTree::Sequence(children) => {
+                let mut current = from.to_string();
+                for (index, child) in children.iter().enumerate() {
+                    let next = if index + 1 == children.len() { to.to_string() } else { self.place(obj_type) };
+                    self.translate(child, obj_type, &current, &next);
+                    current = next;
+                }
+            }
+            ProcessTree::Parallel(children) => {
+                let (split, join) = (self.transition(None), self.transition(None));
+                self.arc(from, &split);
+                self.arc(&join, to);
+                for child in children {
+                    let (start, end) = (self.place(obj_type), self.place(obj_type));
+                    self.arc(&split, &start);
+                    self.arc(&end, &join);
+                    self.translate(child, obj_type, &start, &end);
+                }
+            }
+            ProcessTree::Loop(children) => {
+                let (enter, exit) = (self.transition(None), self.transition(None));
+                let (start, end) = (self.place(obj_type), self.place(obj_type));
+                self.arc(from, &enter);
+                self.arc(&enter, &start);
+                self.arc(&end, &exit);
+                self.arc(&exit, to);
+                if let Some((body, redo)) = children.split_first() {
+                    self.translate(body, obj_type, &start, &end);
+                    for child in redo {
+                        self.translate(child, obj_type, &end, &start);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Marks the arcs between a labelled transition and a place as variable where
+    /// `variable` holds for the activity and the object type of the place.
+    pub fn mark_variable(&mut self, variable: impl Fn(&str, &str) -> bool) {
+        let mut marked = Vec::new();
+        for (index, arc) in self.arcs.iter().enumerate() {
+            let (place, transition) = match self.place_by_id(&arc.source) {
+                Some(place) => (place, self.transition_by_id(&arc.target)),
+                None => (self.place_by_id(&arc.target).expect("arcs connect places and transitions"), self.transition_by_id(&arc.source)),
+            };
+            if let Some(label) = transition.and_then(|t| t.label.as_deref()) {
+                if variable(label, &place.obj_type) {
+                    marked.push(index);
+                }
+            }
+        }
+        for index in marked {
+            self.arcs[index].variable = true;
+        }
+    }
+
+    /// Reads a PNML place/transition net as written by [`PetriNet::write_pnml`] or by
+    /// other tools. Places without an object type get an empty one; if the file has
+    /// no final marking, places without outgoing arcs are taken as sinks.
+    pub fn read_pnml(path: &Path) -> Result<PetriNet, Box<dyn Error>> {
+        let mut reader = Reader::from_reader(compression::open(path)?);
+        reader.trim_text(true);
+
+        let mut net = PetriNet::default();
+        let mut silent: HashSet<String> = HashSet::new();
+        let mut stack: Vec<String> = Vec::new();
+        let mut buf = Vec::new();
+        loop {
+            let event = reader.read_event_into(&mut buf)?;
+            match &event {
+                XmlEvent::Start(tag) | XmlEvent::Empty(tag) => {
+                    let name = String::from_utf8_lossy(tag.local_name().as_ref()).into_owned();
+                    let attrs = attributes(tag)?;
+                    let id = attrs.get("id").cloned().unwrap_or_default();
+                    match (name.as_str(), stack.last().map(String::as_str)) {
+                        ("place", Some("marking")) => {
+                            let idref = attrs.get("idref").cloned().unwrap_or_default();
+                            if let Some(place) = net.places.iter_mut().find(|p| p.id == idref) {
+                                place.sink = true;
+                            }
+                        }
+                        ("place", _) => net.places.push(Place { id, obj_type: String::new(), source: false, sink: false }),
+                        ("transition", _) => net.transitions.push(Transition { id, label: None }),
+                        ("arc", _) => net.arcs.push(Arc {
+                            source: attrs.get("source").cloned().unwrap_or_default(),
+                            target: attrs.get("target").cloned().unwrap_or_default(),
+                            variable: false,
+                        }),
+                        ("toolspecific", Some("place")) => {
+                            if let (Some(place), Some(obj_type)) = (net.places.last_mut(), attrs.get("objectType")) {
+                                place.obj_type = obj_type.clone();
+                            }
+                        }
+                        ("toolspecific", Some("arc")) => {
+                            if let Some(arc) = net.arcs.last_mut() {
+                                arc.variable |= attrs.get("variable").map(String::as_str) == Some("true");
+                            }
+                        }
+                        ("toolspecific", Some("transition")) => {
+                            if let (Some(transition), Some("$invisible$")) = (net.transitions.last(), attrs.get("activity").map(String::as_str)) {
+                                silent.insert(transition.id.clone());
+                            }
+                        }
+                        _ => {}
+                    }
+                    if matches!(event, XmlEvent::Start(_)) {
+                        stack.push(name);
+                    }
+                }
+                XmlEvent::Text(text) => {
+                    let text = text.unescape()?;
+                    let path: Vec<&str> = stack.iter().rev().take(3).map(String::as_str).collect();
+                    match path.as_slice() {
+                        ["text", "name", "transition"] => {
+                            if let Some(transition) = net.transitions.last_mut() {
+                                transition.label = Some(text.into_owned());
+                            }
+                        }
+                        ["text", "initialMarking", "place"] => {
+                            if let Some(place) = net.places.last_mut() {
+                                place.source = text.trim().parse::<u32>().map_or(false, |tokens| tokens > 0);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                XmlEvent::End(_) => {
+                    stack.pop();
+                }
+                XmlEvent::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        for transition in &mut net.transitions {
+            if silent.contains(&transition.id) {
+                transition.label = None;
+            }
+        }
+        if !net.places.iter().any(|p| p.sink) {
+            let sources: HashSet<&str> = net.arcs.iter().map(|a| a.source.as_str()).collect();
+            let sinks: Vec<usize> = (0..net.places.len()).filter(|i| !sources.contains(net.places[*i].id.as_str())).collect();
+            for index in sinks {
+                net.places[index].sink = true;
+            }
+        }
+        Ok(net)
+    }
+
+    /// Writes the net as a PNML place/transition net. Object types and variable arcs
+    /// are kept in `toolspecific` elements, silent transitions are marked the way
+    /// ProM does.
+    pub fn write_pnml<W: Write>(&self, out: &mut W) -> Result<(), Box<dyn Error>> {
+        writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(out, "<pnml>")?;
+        writeln!(out, r#"  <net id="ocpn" type="http://www.pnml.org/version-2009/grammar/ptnet">"#)?;
+        writeln!(out, r#"    <page id="page">"#)?;
+        for place in &self.places {
+            writeln!(out, r#"      <place id="{}">"#, escape(&place.id))?;
+            writeln!(out, "        <name><text>{}</text></name>", escape(&place.id))?;
+            if place.source {
+                writeln!(out, "        <initialMarking><text>1</text></initialMarking>")?;
+            }
+            writeln!(out, r#"        <toolspecific tool="{}" version="1.0" objectType="{}"/>"#, TOOL, escape(&place.obj_type))?;
+            writeln!(out, "      </place>")?;
+        }
+        for transition in &self.transitions {
+            writeln!(out, r#"      <transition id="{}">"#, escape(&transition.id))?;
+            match &transition.label {
+                Some(label) => writeln!(out, "        <name><text>{}</text></name>", escape(label))?,
+                None => {
+                    writeln!(out, "        <name><text>tau</text></name>")?;
+                    writeln!(
+                        out,
+                        r#"        <toolspecific tool="ProM" version="6.4" activity="$invisible$" localNodeID="{}"/>"#,
+                        escape(&transition.id)
+                    )?;
+                }
+            }
+            writeln!(out, "      </transition>")?;
+        }
+        for (index, arc) in self.arcs.iter().enumerate() {
+            write!(out, r#"      <arc id="a{}" source="{}" target="{}""#, index, escape(&arc.source), escape(&arc.target))?;
+            if arc.variable {
+                writeln!(out, ">")?;
+                writeln!(out, r#"        <toolspecific tool="{}" version="1.0" variable="true"/>"#, TOOL)?;
+                writeln!(out, "      </arc>")?;
+            } else {
+                writeln!(out, "/>")?;
+            }
+        }
+        writeln!(out, "    </page>")?;
+        writeln!(out, "    <finalmarkings>")?;
+        writeln!(out, "      <marking>")?;
+        for place in self.places.iter().filter(|p| p.sink) {
+            writeln!(out, r#"        <place idref="{}"><text>1</text></place>"#, escape(&place.id))?;
+        }
+        writeln!(out, "      </marking>")?;
+        writeln!(out, "    </finalmarkings>")?;
+        writeln!(out, "  </net>")?;
+        writeln!(out, "</pnml>")?;
+        Ok(())
+    }
+
+    /// Writes the net for Graphviz: places are circles colored after their object
+    /// type, silent transitions small black boxes and variable arcs double lines.
+    pub fn write_dot<W: Write>(&self, out: &mut W) -> Result<(), Box<dyn Error>> {
+        let types = self.object_types();
+        let color = |obj_type: &str| {
+            let c = palette::nth(types.iter().position(|t| *t == obj_type).unwrap_or(0));
+            format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b)
+        };
+        let quote = |value: &str| format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""));
+
+        writeln!(out, "digraph ocpn {{")?;
+        writeln!(out, "  rankdir=LR;")?;
+        for place in &self.places {
+            let label = if place.source { place.obj_type.as_str() } else { "" };
+            let shape = if place.sink { "doublecircle" } else { "circle" };
+            writeln!(
+                out,
+                "  {} [shape={}, label={}, style=filled, fillcolor=\"{}\"];",
+                quote(&place.id),
+                shape,
+                quote(label),
+                color(&place.obj_type)
+            )?;
+        }
+        for transition in &self.transitions {
+            match &transition.label {
+                Some(label) => writeln!(out, "  {} [shape=box, label={}];", quote(&transition.id), quote(label))?,
+                None => writeln!(
+                    out,
+                    "  {} [shape=box, label=\"\", style=filled, fillcolor=black, width=0.2, height=0.2];",
+                    quote(&transition.id)
+                )?,
+            }
+        }
+        for arc in &self.arcs {
+            let place = self.place_by_id(&arc.source).or_else(|| self.place_by_id(&arc.target));
+            let c = place.map(|p| color(&p.obj_type)).unwrap_or_else(|| "black".to_string());
+            let stroke = if arc.variable { format!("{}:invis:{}", c, c) } else { c };
+            writeln!(out, "  {} -> {} [color=\"{}\"];", quote(&arc.source), quote(&arc.target), stroke)?;
+        }
+        writeln!(out, "}}")?;
+        Ok(())
+    }
+}
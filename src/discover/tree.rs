@@ -0,0 +1,303 @@
+//! Process trees and their discovery from a directly-follows graph with the
+//! directly-follows variant of the inductive miner (IMd). It only sees which activity
+//! follows which, not whole traces, so steps that are sometimes skipped are modelled
+//! as mandatory.
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProcessTree {
+    Activity(String),
+    Silent,
+    Sequence(Vec<ProcessTree>),
+    Exclusive(Vec<ProcessTree>),
+    Parallel(Vec<ProcessTree>),
+    /// The first child is the body, the others are alternative ways back to it
+    Loop(Vec<ProcessTree>),
+}
+
+impl fmt::Display for ProcessTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (operator, children) = match self {
+            ProcessTree::Activity(activity) => return write!(f, "'{}'", activity),
+            ProcessTree::Silent => return write!(f, "tau"),
+            ProcessTree::Sequence(children) => ("->", children),
+            ProcessTree::Exclusive(children) => ("X", children),
+            ProcessTree::Parallel(children) => ("+", children),
+            ProcessTree::Loop(children) => ("*", children),
+        };
+        write!(f, "{}(", operator)?;
+        for (index, child) in children.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", child)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// A directly-follows graph over activity names with the activities traces start
+/// and end with.
+#[derive(Debug, Clone, Default)]
+pub struct Dfg {
+    pub activities: BTreeSet<String>,
+    pub edges: BTreeSet<(String, String)>,
+    pub start: BTreeSet<String>,
+    pub end: BTreeSet<String>,
+}
+
+impl Dfg {
+    fn has_edge(&self, source: &str, target: &str) -> bool {
+        self.edges.contains(&(source.to_string(), target.to_string()))
+    }
+
+    /// Every activity reachable from each activity through at least one edge.
+    fn reachability(&self) -> HashMap<&str, HashSet<&str>> {
+        let mut successors: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (source, target) in &self.edges {
+            successors.entry(source).or_default().push(target);
+        }
+        self.activities
+            .iter()
+            .map(|activity| {
+                let mut reached: HashSet<&str> = HashSet::new();
+                let mut stack: Vec<&str> = successors.get(activity.as_str()).cloned().unwrap_or_default();
+                while let Some(next) = stack.pop() {
+                    if reached.insert(next) {
+                        stack.extend(successors.get(next).into_iter().flatten());
+                    }
+                }
+                (activity.as_str(), reached)
+            })
+            .collect()
+    }
+
+    /// The part of the graph between the activities of `part`. With `boundary`,
+    /// activities entered or left from outside the part also count as start and end
+    /// activities.
+    fn project(&self, part: &BTreeSet<String>, boundary: bool) -> Dfg {
+        let mut projected = Dfg { activities: part.clone(), ..Dfg::default() };
+        projected.start = self.start.intersection(part).cloned().collect();
+        projected.end = self.end.intersection(part).cloned().collect();
+        for (source, target) in &self.edges {
+            match (part.contains(source), part.contains(target)) {
+                (true, true) => {
+                    projected.edges.insert((source.clone(), target.clone()));
+                }
+                (false, true) if boundary => {
+                    projected.start.insert(target.clone());
+                }
+                (true, false) if boundary => {
+                    projected.end.insert(source.clone());
+                }
+                _ => {}
+            }
+        }
+        // a part that cannot be entered or left may be entered and left anywhere
+        if projected.start.is_empty() {
+            projected.start = part.clone();
+        }
+        if projected.end.is_empty() {
+            projected.end = part.clone();
+        }
+        projected
+    }
+}
+
+/// Groups `activities` into the classes of the smallest equivalence relation that
+/// contains every pair for which `together` holds.
+fn partition(activities: &BTreeSet<String>, together: impl Fn(&str, &str) -> bool) -> Vec<BTreeSet<String>> {
+    let names: Vec<&String> = activities.iter().collect();
+    let mut group: Vec<usize> = (0..names.len()).collect();
+    fn root(group: &mut [usize], mut index: usize) -> usize {
+        while group[index] != index {
+            group[index] = group[group[index]];
+            index = group[index];
+        }
+        index
+    }
+    for a in 0..names.len() {
+        for b in a + 1..names.len() {
+            if together(names[a], names[b]) {
+                let (ra, rb) = (root(&mut group, a), root(&mut group, b));
+                group[ra] = rb;
+            }
+        }
+    }
+    let mut parts: BTreeMap<usize, BTreeSet<String>> = BTreeMap::new();
+    for (index, name) in names.iter().enumerate() {
+        let r = root(&mut group, index);
+        parts.entry(r).or_default().insert((*name).clone());
+    }
+    parts.into_values().collect()
+}
+
+/// Discovers a process tree describing `dfg`.
+pub fn discover(dfg: &Dfg) -> ProcessTree {
+    if dfg.activities.is_empty() {
+        return ProcessTree::Silent;
+    }
+    if dfg.activities.len() == 1 {
+        let activity = dfg.activities.iter().next().expect("one activity").clone();
+        let repeats = dfg.has_edge(&activity, &activity);
+        let leaf = ProcessTree::Activity(activity);
+        return if repeats { ProcessTree::Loop(vec![leaf, ProcessTree::Silent]) } else { leaf };
+    }
+
+    let exclusive = partition(&dfg.activities, |a, b| dfg.has_edge(a, b) || dfg.has_edge(b, a));
+    if exclusive.len() > 1 {
+        return ProcessTree::Exclusive(exclusive.iter().map(|part| discover(&dfg.project(part, false))).collect());
+    }
+    if let Some(parts) = sequence_cut(dfg) {
+        return ProcessTree::Sequence(parts.iter().map(|part| discover(&dfg.project(part, true))).collect());
+    }
+    if let Some(parts) = parallel_cut(dfg) {
+        return ProcessTree::Parallel(parts.iter().map(|part| discover(&dfg.project(part, false))).collect());
+    }
+    if let Some((body, redo)) = loop_cut(dfg) {
+        let mut children = vec![discover(&body)];
+        children.extend(redo.iter().map(discover));
+        if children.len() == 1 {
+            children.push(ProcessTree::Silent);
+        }
+        return ProcessTree::Loop(children);
+    }
+
+    // flower model: any activity in any order
+    let choice = ProcessTree::Exclusive(dfg.activities.iter().cloned().map(ProcessTree::Activity).collect());
+    ProcessTree::Loop(vec![ProcessTree::Silent, choice])
+}
+
+/// Parts that are always executed one after the other: activities end up in the
+/// same part if they reach each other or neither reaches the other.
+fn sequence_cut(dfg: &Dfg) -> Option<Vec<BTreeSet<String>>> {
+    let reach = dfg.reachability();
+    let reaches = |a: &str, b: &str| reach.get(a).map_or(false, |r| r.contains(b));
+    let mut parts = partition(&dfg.activities, |a, b| reaches(a, b) == reaches(b, a));
+    if parts.len() < 2 {
+        return None;
+    }
+    // every activity of an earlier part reaches every activity of a later one
+    let precedes = |x: &BTreeSet<String>, y: &BTreeSet<String>| {
+        x.iter().all(|a| y.iter().all(|b| reaches(a, b) && !reaches(b, a)))
+    };
+    for (i, x) in parts.iter().enumerate() {
+        for y in &parts[i + 1..] {
+            if !precedes(x, y) && !precedes(y, x) {
+                return None;
+            }
+        }
+    }
+    let later: Vec<usize> = parts.iter().map(|x| parts.iter().filter(|y| precedes(x, y)).count()).collect();
+    let mut order: Vec<usize> = (0..parts.len()).collect();
+    order.sort_by(|a, b| later[*b].cmp(&later[*a]));
+    let mut ordered = Vec::new();
+    for index in order {
+        ordered.push(std::mem::take(&mut parts[index]));
+    }
+    Some(ordered)
+}
+
+/// Parts whose activities all directly follow each other both ways, each containing
+/// start and end activities.
+fn parallel_cut(dfg: &Dfg) -> Option<Vec<BTreeSet<String>>> {
+    let parts = partition(&dfg.activities, |a, b| !(dfg.has_edge(a, b) && dfg.has_edge(b, a)));
+    let (mut complete, incomplete): (Vec<_>, Vec<_>) =
+        parts.into_iter().partition(|part| !part.is_disjoint(&dfg.start) && !part.is_disjoint(&dfg.end));
+    if complete.len() < 2 {
+        return None;
+    }
+    // parts without a start or end activity cannot run on their own
+    for part in incomplete {
+        complete[0].extend(part);
+    }
+    Some(complete)
+}
+
+/// The body of a loop around the start and end activities, and the parts leading
+/// from its end back to its start. Direct edges from end to start activities make a
+/// loop with a silent way back.
+fn loop_cut(dfg: &Dfg) -> Option<(Dfg, Vec<Dfg>)> {
+    let mut body: BTreeSet<String> = dfg.start.union(&dfg.end).cloned().collect();
+    let rest: BTreeSet<String> = dfg.activities.difference(&body).cloned().collect();
+    let components = partition(&rest, |a, b| dfg.has_edge(a, b) || dfg.has_edge(b, a));
+
+    let mut redo = Vec::new();
+    for component in components {
+        let enters_from_end = dfg.edges.iter().any(|(s, t)| component.contains(t) && !component.contains(s))
+            && dfg.edges.iter().all(|(s, t)| !component.contains(t) || component.contains(s) || dfg.end.contains(s));
+        let leaves_to_start = dfg.edges.iter().any(|(s, t)| component.contains(s) && !component.contains(t))
+            && dfg.edges.iter().all(|(s, t)| !component.contains(s) || component.contains(t) || dfg.start.contains(t));
+        let from_start = dfg.edges.iter().any(|(s, t)| component.contains(t) && dfg.start.contains(s) && !dfg.end.contains(s));
+        if enters_from_end && leaves_to_start && !from_start {
+            redo.push(component);
+        } else {
+            body.extend(component);
+        }
+    }
+
+    let back_edges: Vec<(String, String)> =
+        dfg.edges.iter().filter(|(s, t)| dfg.end.contains(s) && dfg.start.contains(t)).cloned().collect();
+    if redo.is_empty() && back_edges.is_empty() {
+        return None;
+    }
+    let mut body_dfg = dfg.project(&body, false);
+    for edge in &back_edges {
+        body_dfg.edges.remove(edge);
+    }
+    Some((body_dfg, redo.iter().map(|part| dfg.project(part, true)).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dfg(edges: &[(&str, &str)], start: &[&str], end: &[&str]) -> Dfg {
+        let set = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<BTreeSet<String>>();
+        let mut dfg = Dfg { start: set(start), end: set(end), ..Dfg::default() };
+        for (source, target) in edges {
+            dfg.activities.insert(source.to_string());
+            dfg.activities.insert(target.to_string());
+            dfg.edges.insert((source.to_string(), target.to_string()));
+        }
+        dfg.activities.extend(dfg.start.iter().chain(&dfg.end).cloned());
+        dfg
+    }
+
+    fn activity(name: &str) -> ProcessTree {
+        ProcessTree::Activity(name.to_string())
+    }
+
+    #[test]
+    fn discovers_a_sequence() {
+        let tree = discover(&dfg(&[("a", "b"), ("b", "c")], &["a"], &["c"]));
+        assert_eq!(tree, ProcessTree::Sequence(vec![activity("a"), activity("b"), activity("c")]));
+        assert_eq!(tree.to_string(), "->('a', 'b', 'c')");
+    }
+
+    #[test]
+    fn discovers_a_choice() {
+        let tree = discover(&dfg(&[], &["a", "b"], &["a", "b"]));
+        assert_eq!(tree, ProcessTree::Exclusive(vec![activity("a"), activity("b")]));
+    }
+
+    #[test]
+    fn discovers_parallel_activities() {
+        let tree = discover(&dfg(&[("a", "b"), ("b", "a")], &["a", "b"], &["a", "b"]));
+        assert_eq!(tree, ProcessTree::Parallel(vec![activity("a"), activity("b")]));
+    }
+
+    #[test]
+    fn discovers_loops() {
+        let tree = discover(&dfg(&[("a", "b"), ("b", "a")], &["a"], &["a"]));
+        assert_eq!(tree, ProcessTree::Loop(vec![activity("a"), activity("b")]));
+        let tree = discover(&dfg(&[("a", "a")], &["a"], &["a"]));
+        assert_eq!(tree, ProcessTree::Loop(vec![activity("a"), ProcessTree::Silent]));
+    }
+
+    #[test]
+    fn an_empty_graph_is_silent() {
+        assert_eq!(discover(&Dfg::default()), ProcessTree::Silent);
+    }
+}
@@ -0,0 +1,219 @@
+//! Process exit codes shared by all subcommands:
+//!
+//! | code | meaning |
+//! |------|---------|
+//! | 0 | success |
+//! | 1 | the input is invalid or differs from what was expected |
+//! | 2 | the command line is invalid, reported by clap |
+//! | 3 | a file could not be read or written |
+//! | 4 | a file could be read, but not parsed |
+//! | 5 | a limit given by `--max-memory` or `--timeout` was reached |
+//!
+//! Every failing command exits with one of them, never with 0. Errors are reported
+//! as an [`ErrorReport`], whose [`ErrorCode`] tells the cause in more detail.
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use log::error;
+use serde::Serialize;
+use serde_json::json;
+
+/// The input was read, but is invalid or differs from what was expected.
+pub const VALIDATION_FAILED: i32 = 1;
+/// The command line is invalid. clap exits with this code on its own errors.
+pub const USAGE: i32 = 2;
+/// A file could not be read or written.
+pub const IO_ERROR: i32 = 3;
+/// A file could be read, but not parsed.
+pub const IMPORT_ERROR: i32 = 4;
+/// The run was aborted by `--max-memory` or `--timeout`.
+pub const RESOURCE_LIMIT: i32 = 5;
+
+/// An error whose exit code cannot be told from its type, e.g. because its cause
+/// was turned into a message.
+#[derive(Debug)]
+pub struct Failure {
+    pub code: i32,
+    pub message: String,
+}
+
+impl Failure {
+    pub fn new(code: i32, message: impl Into<String>) -> Failure {
+        Failure { code, message: message.into() }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for Failure {}
+
+/// What went wrong, for tooling that has to tell e.g. a missing file from an invalid
+/// log. Every code belongs to one exit code.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorCode {
+    /// A file does not exist
+    NotFound,
+    /// A file may not be read or written
+    PermissionDenied,
+    /// Any other failure to read or write
+    Io,
+    /// A file is not well-formed JSON, XML, CSV or TOML
+    Parse,
+    /// The input is well-formed but invalid, or a check failed
+    Invalid,
+    /// The command line asks for something that is not possible
+    Usage,
+    /// `--max-memory` or `--timeout` aborted the run
+    ResourceLimit,
+}
+
+impl ErrorCode {
+    /// The code of an error, judged by the first [`Failure`], I/O or parse error in
+    /// its source chain. Anything else counts as invalid input.
+    pub fn of(error: &(dyn Error + 'static)) -> ErrorCode {
+        let mut current = Some(error);
+        while let Some(e) = current {
+            if let Some(failure) = e.downcast_ref::<Failure>() {
+                return ErrorCode::from_exit_code(failure.code);
+            }
+            if let Some(io) = e.downcast_ref::<io::Error>() {
+                return match io.kind() {
+                    io::ErrorKind::NotFound => ErrorCode::NotFound,
+                    io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+                    _ => ErrorCode::Io,
+                };
+            }
+            if let Some(json) = e.downcast_ref::<serde_json::Error>() {
+                return if json.is_io() { ErrorCode::Io } else { ErrorCode::Parse };
+            }
+            if let Some(csv) = e.downcast_ref::<csv::Error>() {
+                return if matches!(csv.kind(), csv::ErrorKind::Io(_)) { ErrorCode::Io } else { ErrorCode::Parse };
+            }
+            if let Some(xml) = e.downcast_ref::<quick_xml::Error>() {
+                return if matches!(xml, quick_xml::Error::Io(_)) { ErrorCode::Io } else { ErrorCode::Parse };
+            }
+            if e.is::<toml::de::Error>() || e.is::<quick_xml::events::attributes::AttrError>() {
+                return ErrorCode::Parse;
+            }
+            current = e.source();
+        }
+        ErrorCode::Invalid
+    }
+
+    fn from_exit_code(code: i32) -> ErrorCode {
+        match code {
+            USAGE => ErrorCode::Usage,
+            IO_ERROR => ErrorCode::Io,
+            IMPORT_ERROR => ErrorCode::Parse,
+            RESOURCE_LIMIT => ErrorCode::ResourceLimit,
+            _ => ErrorCode::Invalid,
+        }
+    }
+
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorCode::NotFound | ErrorCode::PermissionDenied | ErrorCode::Io => IO_ERROR,
+            ErrorCode::Parse => IMPORT_ERROR,
+            ErrorCode::Invalid => VALIDATION_FAILED,
+            ErrorCode::Usage => USAGE,
+            ErrorCode::ResourceLimit => RESOURCE_LIMIT,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ErrorCode::NotFound => "not-found",
+            ErrorCode::PermissionDenied => "permission-denied",
+            ErrorCode::Io => "io",
+            ErrorCode::Parse => "parse",
+            ErrorCode::Invalid => "invalid",
+            ErrorCode::Usage => "usage",
+            ErrorCode::ResourceLimit => "resource-limit",
+        }
+    }
+}
+
+/// Exit code for an error, see [`ErrorCode::of`].
+pub fn code(error: &(dyn Error + 'static)) -> i32 {
+    ErrorCode::of(error).exit_code()
+}
+
+/// A failed command as reported to the user: what it tried, on which file and why.
+#[derive(Serialize, Debug, Clone)]
+pub struct ErrorReport {
+    pub code: ErrorCode,
+    pub exit_code: i32,
+    /// What failed, e.g. `Could not compute statistics for`
+    pub context: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    pub message: String,
+}
+
+impl ErrorReport {
+    pub fn of(context: &str, path: Option<&Path>, error: &(dyn Error + 'static)) -> ErrorReport {
+        ErrorReport::with_code(ErrorCode::of(error), context, path, error)
+    }
+
+    /// A report for errors that are not [`Error`]s, such as some returned by pmrs.
+    pub fn with_code(code: ErrorCode, context: &str, path: Option<&Path>, message: impl fmt::Display) -> ErrorReport {
+        ErrorReport {
+            code,
+            exit_code: code.exit_code(),
+            context: context.to_string(),
+            path: path.map(Path::to_path_buf),
+            message: message.to_string(),
+        }
+    }
+
+    /// Reports the error, as a JSON object on stdout with `--output-format json` and
+    /// as an error log record otherwise, and exits with its exit code.
+    pub fn exit(&self) -> ! {
+        if JSON.load(Ordering::Relaxed) {
+            println!("{}", json!({ "error": self }));
+        } else {
+            error!("{}", self);
+        }
+        process::exit(self.exit_code)
+    }
+}
+
+impl fmt::Display for ErrorReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{} {:?}: {} [{}]", self.context, path, self.message, self.code.name()),
+            None => write!(f, "{}: {} [{}]", self.context, self.message, self.code.name()),
+        }
+    }
+}
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Reports errors as JSON from now on, set by `--output-format json`.
+pub fn set_json(json: bool) {
+    JSON.store(json, Ordering::Relaxed);
+}
+
+/// Exits with the code matching `error`. The error is expected to be reported already.
+pub fn fail(error: &(dyn Error + 'static)) -> ! {
+    process::exit(code(error))
+}
+
+/// Reports `error` as the reason `context` failed on the file at `path` and exits.
+pub fn fail_on(path: impl AsRef<Path>, context: &str, error: &(dyn Error + 'static)) -> ! {
+    ErrorReport::of(context, Some(path.as_ref()), error).exit()
+}
+
+/// Reports `error` as the reason `context` failed and exits.
+pub fn fail_with(context: &str, error: &(dyn Error + 'static)) -> ! {
+    ErrorReport::of(context, None, error).exit()
+}
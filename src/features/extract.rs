@@ -0,0 +1,176 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use log::debug;
+use serde_json::Value;
+
+use super::Table;
+use crate::ocel::model::{numeric, OcelLog};
+use crate::ocel::window::TimeWindow;
+
+#[derive(Args, Debug)]
+pub struct FeaturesExtract {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    #[clap(flatten)]
+    pub window: TimeWindow,
+
+    /// Whether the rows stand for objects or for events
+    #[clap(long, value_enum, default_value_t = Level::Object)]
+    pub level: Level,
+
+    /// Features to compute. May be repeated or comma separated, all features of the
+    /// level if unset
+    #[clap(long, value_name = "FEATURE", use_value_delimiter = true, multiple_occurrences = true)]
+    pub features: Vec<String>,
+
+    /// Output file, JSON records if it ends in .json, Parquet if it ends in .parquet
+    /// and CSV otherwise
+    #[clap(short, long)]
+    pub output: PathBuf,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Level {
+    Object,
+    Event,
+}
+
+/// Object features and what they measure.
+pub const OBJECT_FEATURES: [(&str, &str); 7] = [
+    ("object_type", "type of the object"),
+    ("activity_count", "number of events of the object"),
+    ("unique_activities", "number of distinct activities of the object"),
+    ("lifetime", "seconds from the first to the last event of the object"),
+    ("unique_neighbors", "number of other objects sharing an event with the object"),
+    ("first_activity", "activity of the first event of the object"),
+    ("last_activity", "activity of the last event of the object"),
+];
+
+/// Event features and what they measure.
+pub const EVENT_FEATURES: [(&str, &str); 6] = [
+    ("activity", "activity of the event"),
+    ("object_count", "number of objects of the event"),
+    ("object_type_count", "number of distinct object types of the event"),
+    ("elapsed", "seconds since the first event of the log"),
+    ("preceding_events", "number of events before the event"),
+    ("numeric_vmap", "one `vmap:<key>` column per numeric event attribute"),
+];
+
+/// `requested` with unknown names rejected, or every name of `known` if it is empty.
+fn select<'a>(requested: &'a [String], known: &[(&'a str, &str)]) -> Result<Vec<&'a str>, String> {
+    if requested.is_empty() {
+        return Ok(known.iter().map(|(name, _)| *name).collect());
+    }
+    match requested.iter().find(|name| !known.iter().any(|(k, _)| k == name)) {
+        Some(unknown) => {
+            let listing: Vec<String> = known.iter().map(|(name, about)| format!("    {}: {}", name, about)).collect();
+            Err(format!("unknown feature {:?}. Known features:\n{}", unknown, listing.join("\n")))
+        }
+        None => Ok(requested.iter().map(String::as_str).collect()),
+    }
+}
+
+/// One row per object with the given features.
+pub fn object_features(log: &OcelLog, features: &[&str]) -> Table {
+    let traces = log.object_traces();
+    let mut neighbors: HashMap<&str, HashSet<&str>> = HashMap::new();
+    if features.contains(&"unique_neighbors") {
+        for event in log.events.values() {
+            for a in &event.omap {
+                let entry = neighbors.entry(a).or_default();
+                entry.extend(event.omap.iter().filter(|b| *b != a).map(String::as_str));
+            }
+        }
+    }
+
+    let mut table = Table { columns: vec!["object_id".to_string()], ..Table::default() };
+    table.columns.extend(features.iter().map(|f| f.to_string()));
+    for (oid, object) in &log.objects {
+        let trace: Vec<_> = traces.get(oid.as_str()).into_iter().flatten().map(|eid| &log.events[*eid]).collect();
+        let mut row = vec![Value::from(oid.as_str())];
+        for feature in features {
+            row.push(match *feature {
+                "object_type" => Value::from(object.obj_type.as_str()),
+                "activity_count" => Value::from(trace.len()),
+                "unique_activities" => Value::from(trace.iter().map(|e| e.activity.as_str()).collect::<HashSet<_>>().len()),
+                "lifetime" => match (trace.first(), trace.last()) {
+                    (Some(first), Some(last)) => Value::from((last.timestamp - first.timestamp).num_milliseconds() as f64 / 1000.0),
+                    _ => Value::Null,
+                },
+                "unique_neighbors" => Value::from(neighbors.get(oid.as_str()).map_or(0, HashSet::len)),
+                "first_activity" => trace.first().map_or(Value::Null, |e| Value::from(e.activity.as_str())),
+                "last_activity" => trace.last().map_or(Value::Null, |e| Value::from(e.activity.as_str())),
+                _ => Value::Null,
+            });
+        }
+        table.rows.push(row);
+    }
+    table
+}
+
+/// One row per event with the given features.
+pub fn event_features(log: &OcelLog, features: &[&str]) -> Table {
+    let mut timestamps: Vec<_> = log.events.values().map(|e| e.timestamp).collect();
+    timestamps.sort();
+    let vmap_keys: BTreeSet<&str> = if features.contains(&"numeric_vmap") {
+        log.events.values().flat_map(|e| e.vmap.iter()).filter(|(_, v)| numeric(v).is_some()).map(|(k, _)| k.as_str()).collect()
+    } else {
+        BTreeSet::new()
+    };
+
+    let mut table = Table { columns: vec!["event_id".to_string()], ..Table::default() };
+    for feature in features {
+        if *feature == "numeric_vmap" {
+            table.columns.extend(vmap_keys.iter().map(|key| format!("vmap:{}", key)));
+        } else {
+            table.columns.push(feature.to_string());
+        }
+    }
+    for (eid, event) in &log.events {
+        let mut row = vec![Value::from(eid.as_str())];
+        for feature in features {
+            match *feature {
+                "activity" => row.push(Value::from(event.activity.as_str())),
+                "object_count" => row.push(Value::from(event.omap.len())),
+                "object_type_count" => {
+                    let types: HashSet<&str> =
+                        event.omap.iter().filter_map(|oid| log.objects.get(oid)).map(|o| o.obj_type.as_str()).collect();
+                    row.push(Value::from(types.len()));
+                }
+                "elapsed" => {
+                    let elapsed = (event.timestamp - timestamps[0]).num_milliseconds() as f64 / 1000.0;
+                    row.push(Value::from(elapsed));
+                }
+                "preceding_events" => row.push(Value::from(timestamps.partition_point(|t| *t < event.timestamp))),
+                "numeric_vmap" => {
+                    row.extend(vmap_keys.iter().map(|key| event.vmap.get(*key).and_then(numeric).map_or(Value::Null, Value::from)));
+                }
+                _ => row.push(Value::Null),
+            }
+        }
+        table.rows.push(row);
+    }
+    table
+}
+
+pub fn run(extract: &FeaturesExtract) -> Result<(), Box<dyn Error>> {
+    let features = match extract.level {
+        Level::Object => select(&extract.features, &OBJECT_FEATURES)?,
+        Level::Event => select(&extract.features, &EVENT_FEATURES)?,
+    };
+    debug!("Importing log: {:?}", extract.path);
+    let log = extract.window.import(&extract.path)?;
+
+    let table = match extract.level {
+        Level::Object => object_features(&log, &features),
+        Level::Event => event_features(&log, &features),
+    };
+    debug!("Writing {} rows to {:?}", table.rows.len(), extract.output);
+    table.write(&extract.output)?;
+    status!("{} rows with {} features -> {}", table.rows.len(), table.columns.len() - 1, extract.output.display());
+    Ok(())
+}
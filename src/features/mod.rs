@@ -0,0 +1,181 @@
+//! Feature tables for machine learning: one row per object or event, keyed by its
+//! id, written as CSV, as JSON records or as Parquet.
+pub mod extract;
+pub mod targets;
+
+use std::error::Error;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use parquet::basic::{ConvertedType, Repetition, Type as PhysicalType};
+use parquet::data_type::{BoolType, ByteArray, ByteArrayType, DoubleType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+use serde_json::{Map, Value};
+
+use crate::compression;
+use crate::ocel::model::display_value;
+
+/// Rows of feature values under named columns. The first column holds the ids.
+#[derive(Debug, Default)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// The Parquet type of a column, the narrowest one all its values fit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ColumnType {
+    Boolean,
+    Integer,
+    Double,
+    Text,
+}
+
+impl ColumnType {
+    /// Columns without any values are text.
+    fn of<'a>(values: impl Iterator<Item = &'a Value>) -> ColumnType {
+        let mut column_type = None;
+        for value in values {
+            let value_type = match value {
+                Value::Null => continue,
+                Value::Bool(_) => ColumnType::Boolean,
+                Value::Number(number) if number.is_i64() => ColumnType::Integer,
+                Value::Number(_) => ColumnType::Double,
+                _ => return ColumnType::Text,
+            };
+            column_type = match (column_type, value_type) {
+                (None, value_type) => Some(value_type),
+                (Some(current), value_type) if current == value_type => Some(current),
+                (Some(ColumnType::Integer), ColumnType::Double) | (Some(ColumnType::Double), ColumnType::Integer) => {
+                    Some(ColumnType::Double)
+                }
+                _ => return ColumnType::Text,
+            };
+        }
+        column_type.unwrap_or(ColumnType::Text)
+    }
+
+    fn field(self, name: &str) -> Result<Type, Box<dyn Error>> {
+        let builder = match self {
+            ColumnType::Boolean => Type::primitive_type_builder(name, PhysicalType::BOOLEAN),
+            ColumnType::Integer => Type::primitive_type_builder(name, PhysicalType::INT64),
+            ColumnType::Double => Type::primitive_type_builder(name, PhysicalType::DOUBLE),
+            ColumnType::Text => Type::primitive_type_builder(name, PhysicalType::BYTE_ARRAY).with_converted_type(ConvertedType::UTF8),
+        };
+        Ok(builder.with_repetition(Repetition::OPTIONAL).build()?)
+    }
+}
+
+impl Table {
+    /// Writes the table as JSON records if `path` ends in `.json`, as Parquet if it
+    /// ends in `.parquet` and as CSV otherwise. Missing values are empty CSV cells,
+    /// JSON nulls and Parquet nulls.
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let extension = compression::inner_path(path).extension().and_then(|ext| ext.to_str()).map(str::to_lowercase);
+        let mut out = compression::create(path)?;
+        match extension.as_deref() {
+            Some("parquet") => self.write_parquet(&mut out)?,
+            Some("json") => {
+                let records: Vec<Map<String, Value>> =
+                    self.rows.iter().map(|row| self.columns.iter().cloned().zip(row.iter().cloned()).collect()).collect();
+                serde_json::to_writer_pretty(&mut out, &records)?;
+                writeln!(out)?;
+            }
+            _ => {
+                let mut writer = csv::Writer::from_writer(&mut out);
+                writer.write_record(&self.columns)?;
+                for row in &self.rows {
+                    writer.write_record(row.iter().map(|value| match value {
+                        Value::Null => String::new(),
+                        other => display_value(other),
+                    }))?;
+                }
+                writer.flush()?;
+            }
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    /// One row group with a nullable column per table column, typed by
+    /// [`ColumnType::of`].
+    fn write_parquet<W: Write>(&self, out: W) -> Result<(), Box<dyn Error>> {
+        let column = |index: usize| self.rows.iter().map(move |row| row.get(index).unwrap_or(&Value::Null));
+        let types: Vec<ColumnType> = (0..self.columns.len()).map(|index| ColumnType::of(column(index))).collect();
+        let mut fields = Vec::new();
+        for (name, column_type) in self.columns.iter().zip(&types) {
+            fields.push(Arc::new(column_type.field(name)?));
+        }
+        let schema = Type::group_type_builder("features").with_fields(&mut fields).build()?;
+        let mut writer = SerializedFileWriter::new(out, Arc::new(schema), Arc::new(WriterProperties::builder().build()))?;
+        let mut row_group = writer.next_row_group()?;
+        let mut index = 0;
+        while let Some(mut column_writer) = row_group.next_column()? {
+            let values: Vec<&Value> = column(index).collect();
+            // definition level 1 marks a present value, 0 a null
+            let levels: Vec<i16> = values.iter().map(|value| i16::from(!value.is_null())).collect();
+            let present = values.iter().filter(|value| !value.is_null());
+            match types[index] {
+                ColumnType::Boolean => {
+                    let data: Vec<bool> = present.filter_map(|value| value.as_bool()).collect();
+                    column_writer.typed::<BoolType>().write_batch(&data, Some(&levels), None)?;
+                }
+                ColumnType::Integer => {
+                    let data: Vec<i64> = present.filter_map(|value| value.as_i64()).collect();
+                    column_writer.typed::<Int64Type>().write_batch(&data, Some(&levels), None)?;
+                }
+                ColumnType::Double => {
+                    let data: Vec<f64> = present.filter_map(|value| value.as_f64()).collect();
+                    column_writer.typed::<DoubleType>().write_batch(&data, Some(&levels), None)?;
+                }
+                ColumnType::Text => {
+                    let data: Vec<ByteArray> = present.map(|value| ByteArray::from(display_value(value).as_str())).collect();
+                    column_writer.typed::<ByteArrayType>().write_batch(&data, Some(&levels), None)?;
+                }
+            }
+            column_writer.close()?;
+            index += 1;
+        }
+        row_group.close()?;
+        writer.close()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use serde_json::json;
+
+    #[test]
+    fn column_types_widen_integers_and_fall_back_to_text() {
+        assert_eq!(ColumnType::of([json!(1), Value::Null, json!(2)].iter()), ColumnType::Integer);
+        assert_eq!(ColumnType::of([json!(1), json!(2.5)].iter()), ColumnType::Double);
+        assert_eq!(ColumnType::of([json!(true), json!(1)].iter()), ColumnType::Text);
+        assert_eq!(ColumnType::of([Value::Null].iter()), ColumnType::Text);
+    }
+
+    #[test]
+    fn writes_parquet_with_one_typed_column_per_feature() {
+        let table = Table {
+            columns: vec!["id".to_string(), "activity_count".to_string(), "lifetime".to_string()],
+            rows: vec![vec![json!("o1"), json!(3), json!(1.5)], vec![json!("o2"), json!(1), Value::Null]],
+        };
+        let file = tempfile::Builder::new().suffix(".parquet").tempfile().unwrap();
+        table.write(file.path()).unwrap();
+
+        let reader = SerializedFileReader::new(std::fs::File::open(file.path()).unwrap()).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), 2);
+        let columns: Vec<(&str, PhysicalType)> =
+            metadata.schema_descr().columns().iter().map(|column| (column.name(), column.physical_type())).collect();
+        assert_eq!(
+            columns,
+            [("id", PhysicalType::BYTE_ARRAY), ("activity_count", PhysicalType::INT64), ("lifetime", PhysicalType::DOUBLE)]
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! Training tables for predictive monitoring. Every row is a situation, the state of
+//! an object right after one of its events or an event itself, described by the
+//! event features and labelled with the value to predict.
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use log::debug;
+use serde_json::Value;
+
+use super::extract::{event_features, EVENT_FEATURES};
+use super::Table;
+use crate::ocel::model::OcelLog;
+use crate::ocel::window::TimeWindow;
+
+#[derive(Args, Debug)]
+pub struct FeaturesTargets {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    #[clap(flatten)]
+    pub window: TimeWindow,
+
+    /// Prediction problem the label column is computed for
+    #[clap(long, value_enum)]
+    pub target: Target,
+
+    /// Object type whose objects the situations follow. Required for remaining-time
+    /// and next-activity
+    #[clap(long, value_name = "TYPE")]
+    pub object_type: Option<String>,
+
+    /// Output file, JSON records if it ends in .json, Parquet if it ends in .parquet
+    /// and CSV otherwise
+    #[clap(short, long)]
+    pub output: PathBuf,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Seconds from the event to the last event of the object
+    RemainingTime,
+    /// Activity of the next event of the object, `END` after its last event
+    NextActivity,
+    /// Number of objects of the event, from the features known before it
+    ObjectCount,
+}
+
+/// Label of the situations after the last event of an object for next-activity.
+const END: &str = "END";
+
+pub fn run(args: &FeaturesTargets) -> Result<(), Box<dyn Error>> {
+    debug!("Importing log: {:?}", args.path);
+    let log = args.window.import(&args.path)?;
+
+    // features that would give the label away are left out
+    let leaking: &[&str] = match args.target {
+        Target::ObjectCount => &["object_count", "object_type_count"],
+        _ => &[],
+    };
+    let features: Vec<&str> = EVENT_FEATURES.iter().map(|(name, _)| *name).filter(|name| !leaking.contains(name)).collect();
+    let events = event_features(&log, &features);
+
+    let table = match args.target {
+        Target::ObjectCount => {
+            let mut table = Table { columns: events.columns.clone(), ..Table::default() };
+            table.columns.push("target".to_string());
+            for (row, event) in events.rows.iter().zip(log.events.values()) {
+                let mut row = row.clone();
+                row.push(Value::from(event.omap.len()));
+                table.rows.push(row);
+            }
+            table
+        }
+        Target::RemainingTime | Target::NextActivity => {
+            let obj_type =
+                args.object_type.as_deref().ok_or("--object-type is required for the remaining-time and next-activity targets")?;
+            let types = log.object_types();
+            if !types.contains(&obj_type) {
+                return Err(format!("no object is of type {:?}, available types: {}", obj_type, types.join(", ")).into());
+            }
+            object_situations(&log, &events, obj_type, args.target)
+        }
+    };
+
+    debug!("Writing {} situations to {:?}", table.rows.len(), args.output);
+    table.write(&args.output)?;
+    status!("{} situations with {} columns -> {}", table.rows.len(), table.columns.len(), args.output.display());
+    Ok(())
+}
+
+/// One situation per event of every object of `obj_type`, with the position of the
+/// event in the trace of the object added to the event features.
+fn object_situations(log: &OcelLog, events: &Table, obj_type: &str, target: Target) -> Table {
+    let rows: HashMap<&str, &Vec<Value>> =
+        log.events.keys().map(String::as_str).zip(events.rows.iter()).collect();
+    let mut table = Table { columns: vec!["situation_id".to_string(), "object_id".to_string()], ..Table::default() };
+    table.columns.extend(events.columns.iter().cloned());
+    table.columns.extend(["prefix_length".to_string(), "object_elapsed".to_string(), "target".to_string()]);
+
+    for (oid, trace) in log.object_traces() {
+        if log.objects.get(oid).map(|o| o.obj_type.as_str()) != Some(obj_type) {
+            continue;
+        }
+        let (first, last) = match (trace.first(), trace.last()) {
+            (Some(first), Some(last)) => (&log.events[*first], &log.events[*last]),
+            _ => continue,
+        };
+        for (index, eid) in trace.iter().enumerate() {
+            let event = &log.events[*eid];
+            let label = match target {
+                Target::RemainingTime => Value::from((last.timestamp - event.timestamp).num_milliseconds() as f64 / 1000.0),
+                _ => Value::from(trace.get(index + 1).map_or(END, |next| log.events[*next].activity.as_str())),
+            };
+            let mut row = vec![Value::from(format!("{}@{}", eid, oid)), Value::from(oid)];
+            row.extend(rows[eid].iter().cloned());
+            row.push(Value::from(index + 1));
+            row.push(Value::from((event.timestamp - first.timestamp).num_milliseconds() as f64 / 1000.0));
+            row.push(label);
+            table.rows.push(row);
+        }
+    }
+    table
+}
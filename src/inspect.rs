@@ -0,0 +1,59 @@
+//! `pmrs-cli inspect`: the provenance recorded in a graph file by `ocdg generate`.
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Args;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::compression;
+use crate::exit::{self, Failure};
+use crate::ocdg::formats::GraphFormat;
+use crate::ocdg::gexf;
+use crate::ocdg::provenance::Provenance;
+
+#[derive(Args, Debug)]
+pub struct Inspect {
+    /// Path to a GEXF or JSON graph file
+    pub path: PathBuf,
+
+    /// Print the provenance as JSON
+    #[clap(long)]
+    pub json: bool,
+}
+
+pub fn run(inspect: &Inspect) -> Result<(), Box<dyn Error>> {
+    let provenance = match GraphFormat::from_path(&inspect.path) {
+        Some(GraphFormat::Gexf) | None => gexf::read_provenance(&inspect.path)?,
+        Some(GraphFormat::Json) => {
+            let document: Value = serde_json::from_reader(compression::open(&inspect.path)?)?;
+            match document.get("provenance") {
+                Some(value) => Some(serde_json::from_value::<Provenance>(value.clone())?),
+                None => None,
+            }
+        }
+        Some(format) => return Err(format!("{:?} files do not record provenance, only GEXF and JSON do", format).into()),
+    };
+    let provenance = provenance.ok_or_else(|| {
+        Failure::new(exit::VALIDATION_FAILED, format!("{} records no provenance", inspect.path.display()))
+    })?;
+
+    if inspect.json {
+        println!("{}", serde_json::to_string_pretty(&provenance)?);
+        return Ok(());
+    }
+    println!("tool:         {}", provenance.tool);
+    println!("pmrs:         {}", provenance.pmrs_version);
+    println!("created:      {}", provenance.created);
+    println!("command:      {}", provenance.command.join(" "));
+    println!("input:        {}", provenance.input);
+    println!("input sha256: {}", provenance.input_sha256);
+    println!("relations:    {}", provenance.relations.join(", "));
+    // a different hash means the graph no longer matches the log at the recorded path
+    if let Ok(bytes) = fs::read(&provenance.input) {
+        let current: String = Sha256::digest(&bytes).iter().map(|byte| format!("{:02x}", byte)).collect();
+        println!("input now:    {}", if current == provenance.input_sha256 { "unchanged" } else { "changed" });
+    }
+    Ok(())
+}
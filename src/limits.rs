@@ -0,0 +1,89 @@
+//! Soft resource limits, `--max-memory` and `--timeout`. A watchdog thread samples
+//! the resident memory and the elapsed time, and once a limit is passed reports what
+//! the run was doing, removes the outputs it was still writing and exits, rather than
+//! leave a half-written file behind when the system runs out of memory.
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::{debug, warn};
+
+use crate::exit::{ErrorCode, ErrorReport};
+use crate::ocel::time::format_seconds;
+use crate::progress;
+
+const INTERVAL: Duration = Duration::from_millis(250);
+
+/// Outputs being written, removed when a limit aborts the run.
+static WRITING: Mutex<Option<HashSet<PathBuf>>> = Mutex::new(None);
+
+/// Starts the watchdog if any limit is set.
+pub fn start(max_memory_mb: Option<u64>, timeout: Option<Duration>) {
+    if max_memory_mb.is_none() && timeout.is_none() {
+        return;
+    }
+    if max_memory_mb.is_some() && resident_kb().is_none() {
+        warn!("--max-memory is not supported on this platform and is ignored");
+    }
+    let started = Instant::now();
+    thread::spawn(move || loop {
+        thread::sleep(INTERVAL);
+        let elapsed = started.elapsed();
+        if let Some(timeout) = timeout.filter(|timeout| elapsed > *timeout) {
+            abort(format!("the timeout of {} passed", format_seconds(timeout.as_secs_f64())), elapsed);
+        }
+        if let (Some(limit), Some(resident)) = (max_memory_mb, resident_kb()) {
+            if resident / 1024 > limit {
+                abort(format!("memory use of {} MB passed the limit of {} MB", resident / 1024, limit), elapsed);
+            }
+        }
+    });
+}
+
+/// Resident memory of the process in kilobytes, from `/proc` where available.
+fn resident_kb() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn abort(reason: String, elapsed: Duration) -> ! {
+    let outputs: Vec<PathBuf> = WRITING.lock().ok().and_then(|mut writing| writing.take()).into_iter().flatten().collect();
+    for output in &outputs {
+        debug!("Removing the partial output {:?}", output);
+        let _ = fs::remove_file(output);
+    }
+    let progress = match progress::current() {
+        Some(step) => format!(", during {}", step),
+        None => String::new(),
+    };
+    let removed = match outputs.len() {
+        0 => String::new(),
+        n => format!(", removed {} partial outputs", n),
+    };
+    let message = format!("{} after {}{}{}", reason, format_seconds(elapsed.as_secs_f64()), progress, removed);
+    ErrorReport::with_code(ErrorCode::ResourceLimit, "Aborted", None, message).exit()
+}
+
+/// Marks `path` as being written until the guard is dropped.
+pub struct Writing(PathBuf);
+
+pub fn writing(path: &Path) -> Writing {
+    if let Ok(mut writing) = WRITING.lock() {
+        writing.get_or_insert_with(HashSet::new).insert(path.to_path_buf());
+    }
+    Writing(path.to_path_buf())
+}
+
+impl Drop for Writing {
+    fn drop(&mut self) {
+        if let Ok(mut writing) = WRITING.lock() {
+            if let Some(writing) = writing.as_mut() {
+                writing.remove(&self.0);
+            }
+        }
+    }
+}
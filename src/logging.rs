@@ -0,0 +1,57 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use clap::ValueEnum;
+use env_logger::{Builder, Target};
+use log::LevelFilter;
+use serde_json::json;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human readable lines
+    Text,
+    /// One JSON object per record with level, target, message and timestamp
+    Json,
+}
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Whether `--quiet` was given, which also leaves out the summary lines.
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Sets up logging to stderr, keeping stdout for data. The level is taken from, in
+/// order, `--quiet` or the number of `-v`, `RUST_LOG`, the configured `level` and
+/// finally `warn`.
+pub fn init(verbose: u64, quiet: bool, format: LogFormat, level: Option<LevelFilter>) {
+    QUIET.store(quiet, Ordering::Relaxed);
+    let explicit = match (quiet, verbose) {
+        (true, _) => Some(LevelFilter::Error),
+        (false, 0) => None,
+        (false, 1) => Some(LevelFilter::Info),
+        (false, 2) => Some(LevelFilter::Debug),
+        _ => Some(LevelFilter::Trace),
+    };
+
+    let mut builder = Builder::new();
+    builder.target(Target::Stderr);
+    match explicit {
+        Some(level) => builder.filter_level(level),
+        None if std::env::var_os("RUST_LOG").is_some() => builder.parse_default_env(),
+        None => builder.filter_level(level.unwrap_or(LevelFilter::Warn)),
+    };
+
+    if format == LogFormat::Json {
+        builder.format(|buf, record| {
+            let line = json!({
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+                "level": record.level().to_string(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+            });
+            writeln!(buf, "{}", line)
+        });
+    }
+    builder.init();
+}
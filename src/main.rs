@@ -1,24 +1,148 @@
 use std::path::{Path, PathBuf};
-use clap::{Parser, Subcommand, Args};
-use pmrs::objects::ocdg::decomposition::decompose_in_place;
-use pmrs::objects::ocdg::importer::import_ocdg;
-use pmrs::objects::ocel::validator::{validate_ocel, validate_ocel_verbose};
-use pmrs::objects::ocel::importer::import_ocel;
-use pmrs::objects::ocdg::{generate_ocdg, Relations};
-use pmrs::objects::ocdg::exporter::export_ocdg;
-use strum::IntoEnumIterator;
+use std::process;
+use clap::{CommandFactory, ErrorKind, Parser, Subcommand, Args, ValueEnum};
+use clap_complete::Shell;
 
-use log::{debug, error, LevelFilter};
-use env_logger::{Builder, Target};
+use log::error;
+
+/// Prints a summary line like `println!`, to stderr while standard input or output
+/// carries data, see [`stdio::piped`], and not at all with `--quiet`.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if crate::logging::quiet() {
+            // summary lines are left out with --quiet
+        } else if crate::stdio::piped() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+mod batch;
+mod benchmark;
+mod compression;
+mod config;
+mod conformance;
+mod discover;
+mod exit;
+mod features;
+mod inspect;
+mod logging;
+mod limits;
+mod man;
+mod ocdg;
+mod ocel;
+mod plan;
+mod plugin;
+mod progress;
+mod serve;
+mod shell;
+mod stdio;
+mod table;
+mod watch;
+mod xml;
+
+use batch::Batch;
+use benchmark::Benchmark;
+use config::{Config, ConfigBase, Defaults};
+use exit::{ErrorCode, ErrorReport};
+use conformance::ConformanceCheck;
+use discover::ocdfg::DiscoverOcdfg;
+use discover::ocpn::DiscoverOcpn;
+use features::extract::FeaturesExtract;
+use features::targets::FeaturesTargets;
+use inspect::Inspect;
+use logging::LogFormat;
+use serve::Serve;
+use shell::Shell;
+use ocdg::components::OcdgComponents;
+use ocdg::convert::OcdgConvert;
+use ocdg::decompose::OcdgDecompose;
+use ocdg::diff::OcdgDiff;
+use ocdg::edges::OcdgExportEdges;
+use ocdg::filter::OcdgFilter;
+use ocdg::formats::GraphFormat;
+use ocdg::generate::OcdgGeneration;
+use ocdg::merge::OcdgMerge;
+use ocdg::metrics::OcdgMetrics;
+use ocdg::neighbors::OcdgNeighbors;
+use ocdg::path::OcdgPath;
+use ocdg::render::OcdgRender;
+use ocdg::stats::OcdgStats;
+use ocdg::subgraph::OcdgSubgraph;
+use ocdg::verify::OcdgVerify;
+use ocel::abstraction::OcelAbstract;
+use ocel::anonymize::OcelAnonymize;
+use ocel::bottlenecks::OcelBottlenecks;
+use ocel::convert::OcelConvert;
+use ocel::csvexport::OcelToCsv;
+use ocel::csvimport::OcelFromCsv;
+use ocel::dedupe::OcelDedupe;
+use ocel::describe::OcelDescribe;
+use ocel::diff::OcelDiff;
+use ocel::filter::OcelFilter;
+use ocel::flatten::OcelFlatten;
+use ocel::head::OcelHead;
+use ocel::interactions::OcelInteractions;
+use ocel::lifecycle::OcelLifecycle;
+use ocel::merge::OcelMerge;
+use ocel::performance::OcelPerformance;
+use ocel::project::OcelProject;
+use ocel::query::OcelQuery;
+use ocel::remap::{OcelRemapActivities, OcelRemapTypes};
+use ocel::repair::OcelRepair;
+use ocel::sample::OcelSample;
+use ocel::schema::OcelSchema;
+use ocel::sort::OcelSort;
+use ocel::split::OcelSplit;
+use ocel::stats::OcelStats;
+use ocel::timeline::OcelTimeline;
+use ocel::validate::Validate;
+use ocel::variants::OcelVariants;
+use ocel::ReportFormat;
 
 
 #[derive(Parser, Debug)]
 #[clap(name = "pmrs-cli", author, version, about, long_about = None)]
+#[clap(after_help = "Paths may be `-` for standard input or output, and end in .gz or .zst for compressed files. Unknown commands run `pmrs-cli-<command>` from the PATH, see `pmrs-cli plugins`.")]
 struct Cli {
-    /// Generate debug text in stdout
-    #[clap(short, long, global = true)]
+    /// Log more: -v for progress, -vv for debug and -vvv for trace output. -v also
+    /// lists every error found by `ocel validate`
+    #[clap(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u64,
+
+    /// Only log errors and leave out summary lines
+    #[clap(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Same as -vv
+    #[clap(short, long, global = true, hide = true)]
     debug: bool,
 
+    /// Format of log records
+    #[clap(long, value_enum, global = true, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// Configuration file. Default: pmrs.toml in the working directory, if present
+    #[clap(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Print results on stdout as text or as JSON. `json` turns on the JSON output of
+    /// every command that has one, such as stats, validate, diff and the reports
+    #[clap(long, value_enum, global = true, default_value_t = OutputFormat::Text)]
+    output_format: OutputFormat,
+
+    /// Abort once the process holds more than this many megabytes of memory, removing
+    /// outputs still being written. Memory is sampled a few times a second, so short
+    /// peaks may pass unnoticed
+    #[clap(long, global = true, value_name = "MB")]
+    max_memory: Option<u64>,
+
+    /// Abort once the command has run this long, e.g. `90s`, `30m` or `2h`
+    #[clap(long, global = true, value_name = "DURATION", value_parser = ocel::time::parse_timeout)]
+    timeout: Option<std::time::Duration>,
+
     #[clap(subcommand)]
     commands: BaseCommands
 }
@@ -26,7 +150,55 @@ struct Cli {
 #[derive(Subcommand, Debug)]
 enum BaseCommands {
     Ocel(OcelBase),
-    Ocdg(OcdgBase)
+    Ocdg(OcdgBase),
+    /// Discover process models from a log
+    Discover(DiscoverBase),
+    /// Check a log against a Petri net or a directly-follows graph
+    Conformance(ConformanceCheck),
+    /// Extract feature tables for machine learning
+    Features(FeaturesBase),
+    /// Explore a log interactively, importing it only once
+    Shell(Shell),
+    /// Serve validate, stats, filter and ocdg generate over HTTP
+    Serve(Serve),
+    /// Run a command over many logs in parallel, e.g.
+    /// `batch -i logs/ -j 8 -o 'out/{stem}.gexf' -- ocdg generate {input} -o {output}`
+    Batch(Batch),
+    /// Time the phases of an operation over several runs
+    Benchmark(Benchmark),
+    /// Print how a graph file was produced, from the provenance written by `ocdg generate`
+    Inspect(Inspect),
+    /// Inspect the configuration file
+    Config(ConfigBase),
+    /// Print a shell completion script, e.g. `pmrs-cli completions bash > /etc/bash_completion.d/pmrs-cli`
+    Completions(Completions),
+    /// List the external subcommands, `pmrs-cli-<name>` programs on the PATH
+    Plugins,
+    /// Write roff manual pages for every command into a directory
+    #[clap(hide = true)]
+    Man(ManPages),
+    #[clap(external_subcommand)]
+    External(Vec<String>)
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Args, Debug)]
+struct Completions {
+    /// Shell to complete in
+    #[clap(value_enum)]
+    shell: Shell
+}
+
+#[derive(Args, Debug)]
+struct ManPages {
+    /// Directory to write the pages to
+    #[clap(default_value = "man")]
+    dir: PathBuf
 }
 
 #[derive(Parser, Debug)]
@@ -38,7 +210,65 @@ struct OcelBase {
 #[derive(Subcommand, Debug)]
 enum OcelCommands {
     Validate(Validate),
-    Situations(OcelSituations)
+    Situations(OcelSituations),
+    /// Print the first events of a log
+    Head(OcelHead),
+    /// Print the last events of a log
+    Tail(OcelHead),
+    /// Combine several logs into one
+    Merge(OcelMerge),
+    /// Summarize a log
+    Stats(OcelStats),
+    /// List the attributes of every activity and object type
+    Describe(OcelDescribe),
+    /// Convert a log to another serialization
+    Convert(OcelConvert),
+    /// Split a log into several valid logs
+    Split(OcelSplit),
+    /// Keep or drop events by activity, object type and time
+    Filter(OcelFilter),
+    /// Draw a random, optionally stratified, subset of the events
+    Sample(OcelSample),
+    /// Pseudonymize ids and attribute values
+    Anonymize(OcelAnonymize),
+    /// Flatten a log to XES on one object type
+    Flatten(OcelFlatten),
+    /// Build a log from a CSV table with one event per row
+    FromCsv(OcelFromCsv),
+    /// Export a log as normalized CSV tables
+    ToCsv(OcelToCsv),
+    /// Apply safe fixes for common validation failures
+    Repair(OcelRepair),
+    /// Compare two logs event by event and object by object
+    Diff(OcelDiff),
+    /// Count events per time bucket, by activity and object type
+    Timeline(OcelTimeline),
+    /// Order the events by timestamp
+    Sort(OcelSort),
+    /// Remove duplicate events and objects
+    Dedupe(OcelDedupe),
+    /// Rename and merge activities with a mapping table
+    RemapActivities(OcelRemapActivities),
+    /// Rename and merge object types with a mapping table
+    RemapTypes(OcelRemapTypes),
+    /// Keep or drop event and object attributes
+    Project(OcelProject),
+    /// Collapse bursts of low-level events on the same objects into single events
+    Abstract(OcelAbstract),
+    /// Select events with an expression, e.g. `activity == "ship" and vmap.price > 100`
+    Query(OcelQuery),
+    /// Print the attributes and value types of every activity and object type
+    Schema(OcelSchema),
+    /// Count the trace variants of every object type
+    Variants(OcelVariants),
+    /// Measure lifecycle, waiting and service times per object type
+    Performance(OcelPerformance),
+    /// Rank activity transitions by accumulated waiting time
+    Bottlenecks(OcelBottlenecks),
+    /// Report the first and last event, activities and lifespan of every object
+    Lifecycle(OcelLifecycle),
+    /// Build the network of objects, or object types, that share events
+    Interactions(OcelInteractions)
 }
 
 #[derive(Args, Debug)]
@@ -47,145 +277,491 @@ struct OcelSituations {
 }
 
 #[derive(Parser, Debug)]
-struct OcdgBase {
+struct DiscoverBase {
     #[clap(subcommand)]
-    commands: OcdgCommands
+    commands: DiscoverCommands
 }
 
 #[derive(Subcommand, Debug)]
-enum OcdgCommands {
-    Generate(OcdgGeneration),
-    Decompose(OcdgDecompose)
+enum DiscoverCommands {
+    /// Discover an object-centric directly-follows graph with frequencies and durations
+    Ocdfg(DiscoverOcdfg),
+    /// Discover an object-centric Petri net and write it as PNML
+    Ocpn(DiscoverOcpn)
 }
 
-#[derive(Args, Debug)]
-struct OcdgGeneration {
-    /// Path to OCEL file
-    path: String,
-
-    /// Output file name and location. Default: output.gexf
-    #[clap(short, long)]
-    output: Option<String>,
+#[derive(Parser, Debug)]
+struct FeaturesBase {
+    #[clap(subcommand)]
+    commands: FeaturesCommands
 }
 
-#[derive(Args, Debug)]
-struct OcdgDecompose {
-    /// Path to OCEL file
-    path: PathBuf,
+#[derive(Subcommand, Debug)]
+enum FeaturesCommands {
+    /// Write one row of features per object or event
+    Extract(FeaturesExtract),
+    /// Write labelled situations for a prediction problem
+    Targets(FeaturesTargets)
+}
 
-    /// Output file name and location. Default: output.gexf
-    #[clap(short, long)]
-    output: Option<PathBuf>,
+#[derive(Parser, Debug)]
+struct OcdgBase {
+    #[clap(subcommand)]
+    commands: OcdgCommands
 }
 
-#[derive(Args, Debug)]
-struct Validate {
-    path: String,
-    #[clap(short, long)]
-    verbose: bool
+#[derive(Subcommand, Debug)]
+enum OcdgCommands {
+    Generate(OcdgGeneration),
+    Decompose(OcdgDecompose),
+    /// Summarize an OCDG and export its most central nodes
+    Stats(OcdgStats),
+    /// Find the shortest chain of relations between two objects
+    Path(OcdgPath),
+    /// Check that a file is well-formed GEXF and importable by pmrs
+    Verify(OcdgVerify),
+    /// Show the neighbourhood of an object
+    Neighbors(OcdgNeighbors),
+    /// Write a copy of an OCDG reduced to selected relations, object types and weights
+    Filter(OcdgFilter),
+    /// Compute centralities, clustering and component sizes per node as CSV
+    Metrics(OcdgMetrics),
+    /// Extract the subgraph induced by seed objects and their surroundings
+    Subgraph(OcdgSubgraph),
+    /// Compare two OCDGs node by node and edge by edge
+    Diff(OcdgDiff),
+    /// Union several OCDGs, reconciling shared objects by id
+    Merge(OcdgMerge),
+    /// Write an existing OCDG in another graph format
+    Convert(OcdgConvert),
+    /// Render an OCDG to an image with Graphviz
+    Render(OcdgRender),
+    /// Write the edges of an OCDG as a CSV edge list with relations and time spans
+    ExportEdges(OcdgExportEdges),
+    /// List the connected components of an OCDG and optionally write each to its own file
+    Components(OcdgComponents)
 }
 
 fn main() {
 
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+    stdio::set_piped(std::env::args_os().skip(1).any(|arg| arg == stdio::STDIO));
+    exit::set_json(cli.output_format == OutputFormat::Json);
 
-    if cli.debug {
-        Builder::new().target(Target::Stdout).filter_level(LevelFilter::Debug).init();
-    } else {
-        env_logger::init();
-    } 
+    let config = Config::load(cli.config.as_deref());
+    let verbose = if cli.debug { cli.verbose.max(2) } else { cli.verbose };
+    logging::init(verbose, cli.quiet, cli.log_format, config.as_ref().ok().and_then(|config| config.defaults.log_level));
+
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => exit::fail_with("Could not load the configuration", &*e),
+    };
+    apply_defaults(&mut cli.commands, &config.defaults);
+    if let BaseCommands::Ocel(OcelBase { commands: OcelCommands::Validate(validate) }) = &mut cli.commands {
+        validate.verbose = verbose > 0;
+    }
+    if cli.output_format == OutputFormat::Json {
+        request_json(&mut cli.commands);
+    }
+    limits::start(cli.max_memory, cli.timeout);
 
     match &cli.commands {
         BaseCommands::Ocel(ocel_sub) => {
             match &ocel_sub.commands {
                 OcelCommands::Validate(validate) => {
-                    if validate.path.ends_with(".jsonocel") {
-                        if validate.verbose {
-                            match validate_ocel_verbose(&validate.path) {
-                                Ok(v) => {
-                                    for (i, error) in v.iter().enumerate() {
-                                        println!("Error {}: {} at {}", i+1, error.0, error.1);
-                                    }
-                                    
-                                    println!("{}: {}", validate.path, v.is_empty());
-                                }
-                                Err(e) => println!("There was an Error: {}", e),
-                            }
-                        } else {
-                            match validate_ocel(&validate.path) {
-                                Ok(v) => {
-                                    println!("{}: {}", validate.path, v);
-                                }
-                                Err(e) => println!("There was an Error: {}", e),
-                            }
-                        }
-                    } else {
-                        error!("Error: {} file format is not supported.", validate.path);
+                    if validate.watch {
+                        watch::watch(&validate.paths, || {
+                            ocel::validate::check(validate);
+                        });
+                    }
+                    ocel::validate::run(validate)
+                },
+                OcelCommands::Situations(_) => {
+                    ErrorReport::with_code(ErrorCode::Usage, "ocel situations is not implemented", None, "`features targets` extracts labelled situations").exit();
+                },
+                OcelCommands::Head(head) => {
+                    if let Err(e) = ocel::head::run(head) {
+                        exit::fail_on(&head.path, "Could not preview", &*e);
+                    }
+                },
+                OcelCommands::Tail(tail) => {
+                    if let Err(e) = ocel::head::run_tail(tail) {
+                        exit::fail_on(&tail.path, "Could not preview", &*e);
+                    }
+                },
+                OcelCommands::Merge(merge) => {
+                    if let Err(e) = ocel::merge::run(merge) {
+                        exit::fail_with(&format!("Could not merge {:?}", merge.paths), &*e);
+                    }
+                },
+                OcelCommands::Stats(stats) => {
+                    if let Err(e) = ocel::stats::run(stats) {
+                        exit::fail_on(&stats.path, "Could not compute statistics for", &*e);
+                    }
+                },
+                OcelCommands::Describe(describe) => {
+                    if let Err(e) = ocel::describe::run(describe) {
+                        exit::fail_on(&describe.path, "Could not describe", &*e);
+                    }
+                },
+                OcelCommands::Convert(convert) => {
+                    if let Err(e) = ocel::convert::run(convert) {
+                        exit::fail_on(&convert.path, "Could not convert", &*e);
+                    }
+                },
+                OcelCommands::Split(split) => {
+                    if let Err(e) = ocel::split::run(split) {
+                        exit::fail_on(&split.path, "Could not split", &*e);
+                    }
+                },
+                OcelCommands::Filter(filter) => {
+                    if let Err(e) = ocel::filter::run(filter) {
+                        exit::fail_on(&filter.path, "Could not filter", &*e);
+                    }
+                },
+                OcelCommands::Sample(sample) => {
+                    if let Err(e) = ocel::sample::run(sample) {
+                        exit::fail_on(&sample.path, "Could not sample", &*e);
+                    }
+                },
+                OcelCommands::Anonymize(anonymize) => {
+                    if let Err(e) = ocel::anonymize::run(anonymize) {
+                        exit::fail_on(&anonymize.path, "Could not anonymize", &*e);
+                    }
+                },
+                OcelCommands::Flatten(flatten) => {
+                    if let Err(e) = ocel::flatten::run(flatten) {
+                        exit::fail_on(&flatten.path, "Could not flatten", &*e);
+                    }
+                },
+                OcelCommands::FromCsv(import) => {
+                    if let Err(e) = ocel::csvimport::run(import) {
+                        exit::fail_on(&import.path, "Could not import", &*e);
+                    }
+                },
+                OcelCommands::ToCsv(export) => {
+                    if let Err(e) = ocel::csvexport::run(export) {
+                        exit::fail_on(&export.path, "Could not export", &*e);
+                    }
+                },
+                OcelCommands::Repair(repair) => {
+                    if let Err(e) = ocel::repair::run(repair) {
+                        exit::fail_on(&repair.path, "Could not repair", &*e);
+                    }
+                },
+                OcelCommands::Diff(diff) => {
+                    if let Err(e) = ocel::diff::run(diff) {
+                        exit::fail_with(&format!("Could not compare {:?} and {:?}", diff.before, diff.after), &*e);
+                    }
+                },
+                OcelCommands::Timeline(timeline) => {
+                    if let Err(e) = ocel::timeline::run(timeline) {
+                        exit::fail_on(&timeline.path, "Could not build a timeline for", &*e);
+                    }
+                },
+                OcelCommands::Sort(sort) => {
+                    if let Err(e) = ocel::sort::run(sort) {
+                        exit::fail_on(&sort.path, "Could not sort", &*e);
+                    }
+                },
+                OcelCommands::Dedupe(dedupe) => {
+                    if let Err(e) = ocel::dedupe::run(dedupe) {
+                        exit::fail_on(&dedupe.path, "Could not deduplicate", &*e);
+                    }
+                },
+                OcelCommands::RemapActivities(remap) => {
+                    if let Err(e) = ocel::remap::activities(remap) {
+                        exit::fail_on(&remap.path, "Could not remap the activities of", &*e);
+                    }
+                },
+                OcelCommands::RemapTypes(remap) => {
+                    if let Err(e) = ocel::remap::types(remap) {
+                        exit::fail_on(&remap.path, "Could not remap the object types of", &*e);
+                    }
+                },
+                OcelCommands::Project(project) => {
+                    if let Err(e) = ocel::project::run(project) {
+                        exit::fail_on(&project.path, "Could not project the attributes of", &*e);
+                    }
+                },
+                OcelCommands::Abstract(abstraction) => {
+                    if let Err(e) = ocel::abstraction::run(abstraction) {
+                        exit::fail_on(&abstraction.path, "Could not abstract the events of", &*e);
                     }
                 },
-                OcelCommands::Situations(situations) => {}
+                OcelCommands::Query(query) => {
+                    if let Err(e) = ocel::query::run(query) {
+                        exit::fail_on(&query.path, "Could not query", &*e);
+                    }
+                },
+                OcelCommands::Schema(schema) => {
+                    if let Err(e) = ocel::schema::run(schema) {
+                        exit::fail_on(&schema.path, "Could not infer the schema of", &*e);
+                    }
+                },
+                OcelCommands::Variants(variants) => {
+                    if let Err(e) = ocel::variants::run(variants) {
+                        exit::fail_on(&variants.path, "Could not compute the variants of", &*e);
+                    }
+                },
+                OcelCommands::Performance(performance) => {
+                    if let Err(e) = ocel::performance::run(performance) {
+                        exit::fail_on(&performance.path, "Could not measure the performance of", &*e);
+                    }
+                },
+                OcelCommands::Bottlenecks(bottlenecks) => {
+                    if let Err(e) = ocel::bottlenecks::run(bottlenecks) {
+                        exit::fail_on(&bottlenecks.path, "Could not find the bottlenecks of", &*e);
+                    }
+                },
+                OcelCommands::Lifecycle(lifecycle) => {
+                    if let Err(e) = ocel::lifecycle::run(lifecycle) {
+                        exit::fail_on(&lifecycle.path, "Could not inspect the object lifecycles of", &*e);
+                    }
+                },
+                OcelCommands::Interactions(interactions) => {
+                    if let Err(e) = ocel::interactions::run(interactions) {
+                        exit::fail_on(&interactions.path, "Could not build the interaction network of", &*e);
+                    }
+                }
             }
         },
         BaseCommands::Ocdg(ocdg_sub) => {
             match &ocdg_sub.commands {
                 OcdgCommands::Generate(generation) => {
-                    let mut output_path = Path::new("output.gexf");
-
-                    if let Some(custom_name) = &generation.output {
-                        debug!("Setting custom output path to {:?}", custom_name);
-                        output_path = Path::new(custom_name);
-                    }
-
-                    // import ocel
-                    let relations: Vec<Relations> = Relations::iter().collect(); 
-                    debug!("Importing log: {:?}", &generation.path);
-                    match import_ocel(&generation.path) {
-                        Ok(log) => {
-                            debug!("Generating OCDG on relations: {:?}", relations);
-                            let ocdg = generate_ocdg(&log, &relations);
-                            debug!("Exporting the generated OCDG.");
-                            match export_ocdg(&ocdg, &output_path.to_string_lossy()) {
-                                Ok(_) => {debug!("Successfully exported the OCDG to: {:?}", output_path);},
-                                Err(e) => {error!("Generating the OCDG had the following error: {:?}", e);}
+                    if generation.watch {
+                        watch::watch(&[&generation.path], || {
+                            if let Err(e) = ocdg::generate::run(generation, &config) {
+                                error!("{}", ErrorReport::of("Could not generate an OCDG from", Some(Path::new(&generation.path)), &*e));
                             }
-                        }
-                        Err(e) => {error!("Importing the log had the following error: {:?}", e);}
+                        });
+                    }
+                    if let Err(e) = ocdg::generate::run(generation, &config) {
+                        exit::fail_on(&generation.path, "Could not generate an OCDG from", &*e);
                     }
                 },
                 OcdgCommands::Decompose(decompose) => {
-                    let output_path: PathBuf;
-                    match &decompose.output {
-                        Some(path) => {
-                            debug!("Custom path of {:?} selected", path.to_str());
-                            output_path = path.clone();
-                        },
-                        None => {output_path = Path::new("output-decomposed.gexf").to_path_buf();}
-                    }
-                    if let Some(ext) = decompose.path.extension() {
-                        if ext == "gexf" || ext == "gexfocdg" {
-                            debug!("Importing {:?}", decompose.path);
-                            match import_ocdg(&decompose.path.to_string_lossy()) {
-                                Ok(mut ocdg) => {
-                                    debug!("Decomposing OCDG.");
-                                    ocdg = decompose_in_place(ocdg);
-                                    debug!("Attempting to export the OCDG to {:?}", &output_path);
-                                    match export_ocdg(&ocdg, &output_path.to_string_lossy()) {
-                                        Ok(_) => {debug!("Successfully exported the decomposed OCDG to: {:?}", output_path);},
-                                        Err(e) => {error!("Could not export OCDG due to: {:?}", e);}
-                                    }
-                                },
-                                Err(e) => {error!("Failed to import {:?} with error: {:?}", decompose.path, e);}
-                            }
-
-                        } else {
-                            error!("Invalid file type: {:?}", ext);
-                        }
-                        
-                    } else {
-                        error!("Please provide a file with a file extension.");
+                    if let Err(e) = ocdg::decompose::run(decompose, &config) {
+                        exit::fail_on(&decompose.path, "Could not decompose", &*e);
+                    }
+                },
+                OcdgCommands::Stats(stats) => {
+                    if let Err(e) = ocdg::stats::run(stats) {
+                        exit::fail_on(&stats.path, "Could not compute statistics for", &*e);
+                    }
+                },
+                OcdgCommands::Path(path) => {
+                    if let Err(e) = ocdg::path::run(path) {
+                        exit::fail_with(&format!("Could not search {:?} for a path", path.path), &*e);
+                    }
+                },
+                OcdgCommands::Verify(verify) => {
+                    if let Err(e) = ocdg::verify::run(verify) {
+                        exit::fail_on(&verify.path, "Could not verify", &*e);
+                    }
+                },
+                OcdgCommands::Neighbors(neighbors) => {
+                    if let Err(e) = ocdg::neighbors::run(neighbors) {
+                        exit::fail_with(&format!("Could not extract the neighbourhood of {:?}", neighbors.object), &*e);
+                    }
+                },
+                OcdgCommands::Filter(filter) => {
+                    if let Err(e) = ocdg::filter::run(filter) {
+                        exit::fail_on(&filter.path, "Could not filter", &*e);
+                    }
+                },
+                OcdgCommands::Metrics(metrics) => {
+                    if let Err(e) = ocdg::metrics::run(metrics) {
+                        exit::fail_on(&metrics.path, "Could not compute metrics for", &*e);
+                    }
+                },
+                OcdgCommands::Subgraph(subgraph) => {
+                    if let Err(e) = ocdg::subgraph::run(subgraph) {
+                        exit::fail_on(&subgraph.path, "Could not extract a subgraph of", &*e);
+                    }
+                },
+                OcdgCommands::Diff(diff) => {
+                    if let Err(e) = ocdg::diff::run(diff) {
+                        exit::fail_with(&format!("Could not compare {:?} and {:?}", diff.before, diff.after), &*e);
+                    }
+                },
+                OcdgCommands::Merge(merge) => {
+                    if let Err(e) = ocdg::merge::run(merge) {
+                        exit::fail_with(&format!("Could not merge {:?}", merge.paths), &*e);
+                    }
+                },
+                OcdgCommands::Convert(convert) => {
+                    if let Err(e) = ocdg::convert::run(convert) {
+                        exit::fail_on(&convert.path, "Could not convert", &*e);
+                    }
+                },
+                OcdgCommands::Render(render) => {
+                    if let Err(e) = ocdg::render::run(render) {
+                        exit::fail_on(&render.path, "Could not render", &*e);
+                    }
+                },
+                OcdgCommands::ExportEdges(export) => {
+                    if let Err(e) = ocdg::edges::run(export) {
+                        exit::fail_on(&export.path, "Could not export the edges of", &*e);
+                    }
+                },
+                OcdgCommands::Components(components) => {
+                    if let Err(e) = ocdg::components::run(components) {
+                        exit::fail_on(&components.path, "Could not list the components of", &*e);
                     }
                 }
             }
+        },
+        BaseCommands::Discover(discover_sub) => {
+            match &discover_sub.commands {
+                DiscoverCommands::Ocdfg(discovery) => {
+                    if let Err(e) = discover::ocdfg::run(discovery) {
+                        exit::fail_on(&discovery.path, "Could not discover a directly-follows graph from", &*e);
+                    }
+                },
+                DiscoverCommands::Ocpn(discovery) => {
+                    if let Err(e) = discover::ocpn::run(discovery) {
+                        exit::fail_on(&discovery.path, "Could not discover a Petri net from", &*e);
+                    }
+                }
+            }
+        },
+        BaseCommands::Conformance(check) => {
+            if let Err(e) = conformance::run(check) {
+                exit::fail_with(&format!("Could not check {:?} against {:?}", check.path, check.model), &*e);
+            }
+        },
+        BaseCommands::Features(features_sub) => {
+            match &features_sub.commands {
+                FeaturesCommands::Extract(extract) => {
+                    if let Err(e) = features::extract::run(extract) {
+                        exit::fail_on(&extract.path, "Could not extract features from", &*e);
+                    }
+                },
+                FeaturesCommands::Targets(targets) => {
+                    if let Err(e) = features::targets::run(targets) {
+                        exit::fail_on(&targets.path, "Could not extract targets from", &*e);
+                    }
+                }
+            }
+        },
+        BaseCommands::Shell(shell) => {
+            if let Err(e) = shell::run(shell) {
+                exit::fail_on(&shell.path, "Could not explore", &*e);
+            }
+        },
+        BaseCommands::Serve(serve) => {
+            if let Err(e) = serve::run(serve) {
+                exit::fail_with(&format!("Could not serve on port {}", serve.port), &*e);
+            }
+        },
+        BaseCommands::Batch(batch) => {
+            if let Err(e) = batch::run(batch) {
+                exit::fail_with(&format!("Could not process {:?}", batch.inputs), &*e);
+            }
+        },
+        BaseCommands::Benchmark(benchmark) => {
+            if let Err(e) = benchmark::run(benchmark) {
+                exit::fail_on(&benchmark.path, "Could not benchmark", &*e);
+            }
+        },
+        BaseCommands::Inspect(inspect) => {
+            if let Err(e) = inspect::run(inspect) {
+                exit::fail_on(&inspect.path, "Could not inspect", &*e);
+            }
+        },
+        BaseCommands::Config(config_sub) => config::run(config_sub, &config),
+        BaseCommands::Completions(completions) => {
+            clap_complete::generate(completions.shell, &mut Cli::command(), "pmrs-cli", &mut std::io::stdout());
+        },
+        BaseCommands::Man(pages) => {
+            match man::write_pages(&Cli::command(), &pages.dir) {
+                Ok(count) => status!("{} manual pages -> {}", count, pages.dir.display()),
+                Err(e) => {
+                    exit::fail_on(&pages.dir, "Could not write the manual pages to", &e);
+                }
+            }
+        },
+        BaseCommands::Plugins => {
+            for (name, path) in plugin::discover() {
+                println!("{:<16} {}", name, path.display());
+            }
+        },
+        BaseCommands::External(args) => {
+            let output_format = format!("{:?}", cli.output_format).to_lowercase();
+            let context = plugin::Context { output_format: &output_format, config: cli.config.as_deref() };
+            match plugin::run(args, &context) {
+                Ok(code) => process::exit(code),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    let message = format!("unrecognized subcommand {:?} and no pmrs-cli-{} on the PATH", args[0], args[0]);
+                    Cli::command().error(ErrorKind::UnrecognizedSubcommand, message).exit();
+                },
+                Err(e) => {
+                    exit::fail_with(&format!("Could not run pmrs-cli-{}", args[0]), &e);
+                }
+            }
+        }
+    }
+}
+
+/// Fills the options of `commands` that were not given on the command line from the
+/// `[defaults]` of the configuration. `ocdg generate` applies them itself, after its
+/// profile.
+fn apply_defaults(commands: &mut BaseCommands, defaults: &Defaults) {
+    let graph_format = |format: &mut Option<GraphFormat>, output: &Path| *format = defaults.graph_format(*format, output);
+    match commands {
+        BaseCommands::Ocel(ocel_sub) => match &mut ocel_sub.commands {
+            OcelCommands::Diff(diff) => diff.format = diff.format.or(defaults.report_format),
+            OcelCommands::Variants(variants) => variants.format = variants.format.or(defaults.report_format),
+            OcelCommands::Performance(performance) => performance.format = performance.format.or(defaults.report_format),
+            OcelCommands::Bottlenecks(bottlenecks) => bottlenecks.format = bottlenecks.format.or(defaults.report_format),
+            OcelCommands::Lifecycle(lifecycle) => lifecycle.format = lifecycle.format.or(defaults.report_format),
+            OcelCommands::Interactions(interactions) => graph_format(&mut interactions.format, &interactions.output),
+            _ => {}
+        },
+        BaseCommands::Ocdg(ocdg_sub) => match &mut ocdg_sub.commands {
+            OcdgCommands::Diff(diff) => diff.format = diff.format.or(defaults.report_format),
+            OcdgCommands::Filter(filter) => graph_format(&mut filter.format, &filter.output),
+            OcdgCommands::Subgraph(subgraph) => graph_format(&mut subgraph.format, &subgraph.output),
+            OcdgCommands::Merge(merge) => graph_format(&mut merge.format, &merge.output),
+            OcdgCommands::Convert(convert) => graph_format(&mut convert.format, &convert.output),
+            _ => {}
+        },
+        BaseCommands::Discover(discover_sub) => {
+            if let DiscoverCommands::Ocdfg(discovery) = &mut discover_sub.commands {
+                graph_format(&mut discovery.format, &discovery.output);
+            }
         }
+        _ => {}
+    }
+}
+
+/// Switches every command with a JSON output to it, for `--output-format json`.
+fn request_json(commands: &mut BaseCommands) {
+    match commands {
+        BaseCommands::Ocel(ocel_sub) => match &mut ocel_sub.commands {
+            OcelCommands::Validate(validate) => validate.json = true,
+            OcelCommands::Head(head) | OcelCommands::Tail(head) => head.json = true,
+            OcelCommands::Stats(stats) => stats.json = true,
+            OcelCommands::Diff(diff) => diff.format = Some(ReportFormat::Json),
+            OcelCommands::Timeline(timeline) => timeline.json = true,
+            OcelCommands::Schema(schema) => schema.json = true,
+            OcelCommands::Variants(variants) => variants.format = Some(ReportFormat::Json),
+            OcelCommands::Performance(performance) => performance.format = Some(ReportFormat::Json),
+            OcelCommands::Bottlenecks(bottlenecks) => bottlenecks.format = Some(ReportFormat::Json),
+            OcelCommands::Lifecycle(lifecycle) => lifecycle.format = Some(ReportFormat::Json),
+            _ => {}
+        },
+        BaseCommands::Ocdg(ocdg_sub) => match &mut ocdg_sub.commands {
+            OcdgCommands::Stats(stats) => stats.json = true,
+            OcdgCommands::Verify(verify) => verify.json = true,
+            OcdgCommands::Diff(diff) => diff.format = Some(ReportFormat::Json),
+            OcdgCommands::Components(components) => components.json = true,
+            _ => {}
+        },
+        BaseCommands::Conformance(check) => check.json = true,
+        BaseCommands::Benchmark(benchmark) => benchmark.json = true,
+        BaseCommands::Inspect(inspect) => inspect.json = true,
+        _ => {}
     }
 }
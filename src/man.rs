@@ -0,0 +1,27 @@
+//! `pmrs-cli man`: roff manual pages for the whole command tree, one page per
+//! command named like `pmrs-cli-ocdg-generate.1`.
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use clap::Command;
+use clap_mangen::Man;
+
+/// Writes the page of `command` and of every visible subcommand below it into `dir`.
+pub fn write_pages(command: &Command, dir: &Path) -> io::Result<usize> {
+    fs::create_dir_all(dir)?;
+    write_page(command, command.get_name().to_string(), dir)
+}
+
+fn write_page(command: &Command, name: String, dir: &Path) -> io::Result<usize> {
+    let page = command.clone().name(name.clone());
+    let mut out = BufWriter::new(File::create(dir.join(format!("{}.1", name)))?);
+    Man::new(page).render(&mut out)?;
+    out.flush()?;
+
+    let mut written = 1;
+    for sub in command.get_subcommands().filter(|sub| !sub.is_hide_set()) {
+        written += write_page(sub, format!("{}-{}", name, sub.get_name()), dir)?;
+    }
+    Ok(written)
+}
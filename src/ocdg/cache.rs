@@ -0,0 +1,44 @@
+//! Generated OCDGs kept in a directory under a hash of what went into them, so that
+//! generating the same relations from an unchanged log again is a copy. Entries are
+//! the GEXF files exported by pmrs, before any of the graph-level options of
+//! `ocdg generate` are applied.
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use pmrs::objects::ocdg::Relations;
+use sha2::{Digest, Sha256};
+
+use crate::ocel::window::TimeWindow;
+
+/// The cache entry for generating `relations` from the `window` of the log at
+/// `input`. The key covers the content of the log, the window, the set of relations
+/// and the versions of pmrs and pmrs-cli, so that upgrading either starts afresh.
+pub fn entry(dir: &Path, input: &Path, relations: &[Relations], window: &TimeWindow) -> io::Result<PathBuf> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(input)?, &mut hasher)?;
+    let mut names: Vec<String> = relations.iter().map(|relation| format!("{:?}", relation).to_lowercase()).collect();
+    names.sort();
+    names.dedup();
+    hasher.update(names.join(",").as_bytes());
+    if window.is_set() {
+        hasher.update(format!("\0window {}", window).as_bytes());
+    }
+    hasher.update(b"\0pmrs ");
+    hasher.update(env!("PMRS_VERSION").as_bytes());
+    hasher.update(b"\0pmrs-cli ");
+    hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+    let key: String = hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect();
+    Ok(dir.join(format!("{}.gexf", key)))
+}
+
+/// Copies the GEXF file `exported` into the cache as `entry`. The copy is renamed
+/// into place, so concurrent runs never see a partial entry.
+pub fn store(entry: &Path, exported: &Path) -> io::Result<()> {
+    let dir = entry.parent().unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(dir)?;
+    let staging = tempfile::Builder::new().suffix(".partial").tempfile_in(dir)?;
+    fs::copy(exported, staging.path())?;
+    staging.persist(entry).map_err(|e| e.error)?;
+    Ok(())
+}
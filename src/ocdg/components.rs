@@ -0,0 +1,152 @@
+//! Connected components of an OCDG: a size summary, a listing of their members and
+//! one graph file per component.
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use log::debug;
+use serde_json::json;
+
+use super::formats::{self, GraphFormat};
+use super::graph::Graph;
+use crate::table::Table;
+
+#[derive(Args, Debug)]
+pub struct OcdgComponents {
+    /// Path to OCDG file
+    pub path: PathBuf,
+
+    /// Use strongly instead of weakly connected components. Only differs for
+    /// directed graphs
+    #[clap(long)]
+    pub strong: bool,
+
+    /// Only list components with at least this many nodes
+    #[clap(long, default_value_t = 1)]
+    pub min_size: usize,
+
+    /// Number of member object IDs printed per component, all of them if unset
+    #[clap(long)]
+    pub members: Option<usize>,
+
+    /// Print the components as JSON
+    #[clap(long)]
+    pub json: bool,
+
+    /// Directory to write every listed component to as a graph of its own
+    #[clap(long, value_name = "DIR")]
+    pub split: Option<PathBuf>,
+
+    /// File name of the split components, `{index}` is replaced by the component number
+    #[clap(long, default_value = "component-{index}.gexf", requires = "split")]
+    pub split_template: String,
+
+    /// Graph format of the split components. Inferred from the template extension if
+    /// unset, GEXF otherwise
+    #[clap(long, value_enum, requires = "split")]
+    pub format: Option<GraphFormat>,
+}
+
+/// Splits `graph` into one graph per component in `components`. Edges between
+/// different components are dropped.
+pub fn split(graph: &Graph, components: &[Vec<&str>]) -> Vec<Graph> {
+    let member: HashMap<&str, usize> =
+        components.iter().enumerate().flat_map(|(index, nodes)| nodes.iter().map(move |id| (*id, index))).collect();
+    let mut parts: Vec<Graph> = components.iter().map(|_| Graph { directed: graph.directed, ..Graph::default() }).collect();
+    // nodes are added in their original order rather than in traversal order
+    for (id, node) in &graph.nodes {
+        if let Some(&index) = member.get(id.as_str()) {
+            parts[index].nodes.insert(id.clone(), node.clone());
+        }
+    }
+    for edge in &graph.edges {
+        if let Some(&index) = member.get(edge.source.as_str()) {
+            if member.get(edge.target.as_str()) == Some(&index) {
+                parts[index].edges.push(edge.clone());
+            }
+        }
+    }
+    parts
+}
+
+/// Writes each part to `dir` named after `template` with `{index}` replaced by the
+/// 1-based component number.
+fn write_parts(parts: &[Graph], dir: &Path, template: &str, format: Option<GraphFormat>) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
+    for (index, part) in parts.iter().enumerate() {
+        let path = dir.join(template.replace("{index}", &(index + 1).to_string()));
+        let format = GraphFormat::resolve(format, &path);
+        debug!("Writing component {} to {:?}", index + 1, path);
+        formats::write(part, &path, format)?;
+    }
+    status!("wrote {} components to {}", parts.len(), dir.display());
+    Ok(())
+}
+
+/// Prints how many weakly connected components of which size the graph in `path`
+/// has and, with `dir`, writes each of them there.
+pub fn report(path: &Path, dir: Option<&Path>, template: &str, format: Option<GraphFormat>) -> Result<(), Box<dyn Error>> {
+    let graph = Graph::import(path)?;
+    let parts = split(&graph, &graph.components());
+
+    let mut sizes: BTreeMap<usize, usize> = BTreeMap::new();
+    for part in &parts {
+        *sizes.entry(part.nodes.len()).or_default() += 1;
+    }
+    status!("components: {}", parts.len());
+    for (size, count) in sizes.iter().rev() {
+        status!("    {} nodes: {}", size, count);
+    }
+
+    if let Some(dir) = dir {
+        write_parts(&parts, dir, template, format)?;
+    }
+    Ok(())
+}
+
+pub fn run(args: &OcdgComponents) -> Result<(), Box<dyn Error>> {
+    debug!("Importing {:?}", args.path);
+    let graph = Graph::import(&args.path)?;
+    let mut components = if args.strong { graph.strong_components() } else { graph.components() };
+    let total = components.len();
+    components.retain(|component| component.len() >= args.min_size);
+    // members in the order of the graph file, which is stable across runs
+    let order: HashMap<&str, usize> = graph.nodes.keys().enumerate().map(|(index, id)| (id.as_str(), index)).collect();
+    for component in &mut components {
+        component.sort_by_key(|id| order[id]);
+    }
+
+    let kind = if args.strong { "strongly" } else { "weakly" };
+    if args.json {
+        let listed: Vec<_> = components
+            .iter()
+            .enumerate()
+            .map(|(index, members)| {
+                json!({
+                    "index": index + 1,
+                    "size": members.len(),
+                    "members": members.iter().take(args.members.unwrap_or(usize::MAX)).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        let report = json!({"connectivity": kind, "total": total, "listed": listed.len(), "components": listed});
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("{} connected components: {} ({} listed)", kind, total, components.len());
+        let mut table = Table::new(&["component", "nodes", "members"]).right(&[0, 1]);
+        for (index, members) in components.iter().enumerate() {
+            let shown = members.len().min(args.members.unwrap_or(usize::MAX));
+            let more = if shown < members.len() { format!(", ... {} more", members.len() - shown) } else { String::new() };
+            table.row(vec![(index + 1).into(), members.len().into(), format!("{}{}", members[..shown].join(", "), more).into()]);
+        }
+        table.print();
+    }
+
+    if let Some(dir) = &args.split {
+        let parts = split(&graph, &components);
+        write_parts(&parts, dir, &args.split_template, args.format)?;
+    }
+    Ok(())
+}
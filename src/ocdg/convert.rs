@@ -0,0 +1,35 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use log::debug;
+
+use super::formats::{self, GraphFormat};
+use super::graph::Graph;
+
+#[derive(Args, Debug)]
+pub struct OcdgConvert {
+    /// Path to OCDG file
+    pub path: PathBuf,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// Graph format to write. Inferred from the output extension if unset
+    #[clap(long, value_enum)]
+    pub format: Option<GraphFormat>,
+}
+
+pub fn run(convert: &OcdgConvert) -> Result<(), Box<dyn Error>> {
+    let format = match convert.format.or_else(|| GraphFormat::from_path(&convert.output)) {
+        Some(format) => format,
+        None => return Err(format!("cannot tell the format of {:?} from its extension, pass --format", convert.output).into()),
+    };
+    debug!("Importing {:?}", convert.path);
+    let graph = Graph::import(&convert.path)?;
+    debug!("Writing {:?} as {:?}", convert.output, format);
+    formats::write(&graph, &convert.output, format)?;
+    status!("{} nodes and {} edges -> {}", graph.nodes.len(), graph.edges.len(), convert.output.display());
+    Ok(())
+}
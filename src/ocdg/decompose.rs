@@ -0,0 +1,106 @@
+//! Decomposition of an OCDG by pmrs, with an optional split of the result into its
+//! connected components.
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use log::debug;
+use pmrs::objects::ocdg::decomposition::decompose_in_place;
+use pmrs::objects::ocdg::exporter::export_ocdg;
+use pmrs::objects::ocdg::importer::import_ocdg;
+
+use super::components;
+use super::formats::{self, GraphFormat};
+use super::graph::Graph;
+use crate::compression;
+use crate::config::Config;
+use crate::exit::{Failure, IMPORT_ERROR, IO_ERROR};
+use crate::plan;
+use crate::progress::Progress;
+use crate::stdio;
+
+#[derive(Args, Debug)]
+pub struct OcdgDecompose {
+    /// Path to OCDG file
+    pub path: PathBuf,
+
+    /// Output file name and location. Default: output-decomposed.gexf
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Graph format to write. Inferred from the output extension if unset, GEXF
+    /// otherwise
+    #[clap(long, value_enum)]
+    pub format: Option<GraphFormat>,
+
+    /// Additionally write every connected component of the decomposed graph as its
+    /// own file into this directory
+    #[clap(long, value_name = "DIR")]
+    pub split: Option<PathBuf>,
+
+    /// File name pattern for --split, `{index}` is replaced by the component number,
+    /// largest component first
+    #[clap(long, default_value = "component-{index}.gexf", requires = "split")]
+    pub split_template: String,
+
+    /// Import the OCDG and report what would be written, without writing anything
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+pub fn run(decompose: &OcdgDecompose, config: &Config) -> Result<(), Box<dyn Error>> {
+    let output_path = match &decompose.output {
+        Some(path) => {
+            debug!("Custom path of {:?} selected", path);
+            path.clone()
+        }
+        None => config.defaults.output_path("output-decomposed.gexf"),
+    };
+    // standard input is taken to be GEXF
+    let inner_path = compression::inner_path(&decompose.path);
+    let extension = if stdio::is_stdio(&decompose.path) { Some("gexf") } else { inner_path.extension().and_then(|ext| ext.to_str()) };
+    match extension {
+        Some("gexf") | Some("gexfocdg") => {}
+        Some(ext) => return Err(Failure::new(IMPORT_ERROR, format!("invalid file type {:?}, expected gexf", ext)).into()),
+        None => return Err(Failure::new(IMPORT_ERROR, "please provide a file with a file extension").into()),
+    }
+
+    let progress = Progress::new("ocdg decompose", 3);
+    debug!("Importing {:?}", decompose.path);
+    progress.step(format!("importing {}", decompose.path.display()));
+    let copy = compression::decompressed_copy(&decompose.path)?;
+    let plain: &Path = copy.as_deref().unwrap_or(&decompose.path);
+    if decompose.dry_run {
+        let graph = Graph::import(plain)?;
+        // decomposing keeps the nodes and at most the edges of the input
+        println!("estimated: {} nodes, up to {} edges", graph.nodes.len(), graph.edges.len());
+        plan::output(&output_path);
+        if let Some(dir) = &decompose.split {
+            println!("would write: one file per connected component into {}", dir.display());
+        }
+        return Ok(());
+    }
+
+    let ocdg = import_ocdg(&plain.to_string_lossy()).map_err(|e| Failure::new(IMPORT_ERROR, format!("{:?}", e)))?;
+    progress.inc();
+    debug!("Decomposing OCDG.");
+    progress.step("decomposing");
+    let ocdg = decompose_in_place(ocdg);
+    progress.inc();
+
+    debug!("Attempting to export the OCDG to {:?}", &output_path);
+    progress.step(format!("exporting {}", output_path.display()));
+    let format = GraphFormat::resolve(config.defaults.graph_format(decompose.format, &output_path), &output_path);
+    let staging = formats::staging_file(&output_path, format, false)?;
+    let target: &Path = staging.as_deref().unwrap_or(&output_path);
+    export_ocdg(&ocdg, &target.to_string_lossy())
+        .map_err(|e| Failure::new(IO_ERROR, format!("could not export the decomposed OCDG to {:?}: {:?}", output_path, e)))?;
+    if let Some(staging) = &staging {
+        formats::convert(staging, &output_path, format)?;
+    }
+    components::report(target, decompose.split.as_deref(), &decompose.split_template, decompose.format)?;
+    progress.inc();
+    progress.finish();
+    debug!("Successfully exported the decomposed OCDG to: {:?}", output_path);
+    Ok(())
+}
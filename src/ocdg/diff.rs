@@ -0,0 +1,203 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::path::PathBuf;
+use std::process;
+
+use clap::Args;
+use log::debug;
+use serde_json::{json, Map, Value};
+
+use super::graph::Graph;
+use crate::exit;
+use crate::ocel::ReportFormat;
+use crate::table::{change_style, paint, Cell, Style, Table};
+
+#[derive(Args, Debug)]
+pub struct OcdgDiff {
+    /// Path to the original OCDG file
+    pub before: PathBuf,
+
+    /// Path to the OCDG file to compare against it
+    pub after: PathBuf,
+
+    /// Number of added and removed nodes and edges listed
+    #[clap(long, default_value_t = 10)]
+    pub examples: usize,
+
+    /// Output format. Default: table
+    #[clap(long, value_enum)]
+    pub format: Option<ReportFormat>,
+
+    /// Exit with status 1 if the graphs differ, like `diff`
+    #[clap(long)]
+    pub exit_code: bool,
+}
+
+/// An edge by its endpoints and relations. Endpoints of undirected edges are ordered
+/// so that both orientations compare equal.
+type EdgeKey = (String, String, String);
+
+fn edge_keys(graph: &Graph) -> BTreeSet<EdgeKey> {
+    graph
+        .edges
+        .iter()
+        .map(|edge| {
+            let (mut source, mut target) = (edge.source.clone(), edge.target.clone());
+            if !graph.directed && source > target {
+                std::mem::swap(&mut source, &mut target);
+            }
+            (source, target, edge.relations().join(","))
+        })
+        .collect()
+}
+
+/// Per-key counts before and after, for every key whose count changed.
+fn count_deltas(before: BTreeMap<String, usize>, after: BTreeMap<String, usize>) -> BTreeMap<String, (usize, usize)> {
+    let mut counts: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for (key, count) in before {
+        counts.entry(key).or_default().0 = count;
+    }
+    for (key, count) in after {
+        counts.entry(key).or_default().1 = count;
+    }
+    counts.retain(|_, (b, a)| b != a);
+    counts
+}
+
+fn per_type(graph: &Graph) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for node in graph.nodes.values() {
+        *counts.entry(node.obj_type().unwrap_or("unknown").to_string()).or_default() += 1;
+    }
+    counts
+}
+
+fn per_relation(graph: &Graph) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for edge in &graph.edges {
+        for relation in edge.relations() {
+            *counts.entry(relation).or_default() += 1;
+        }
+    }
+    counts
+}
+
+struct GraphDiff {
+    nodes_added: Vec<String>,
+    nodes_removed: Vec<String>,
+    edges_added: Vec<EdgeKey>,
+    edges_removed: Vec<EdgeKey>,
+    object_types: BTreeMap<String, (usize, usize)>,
+    relations: BTreeMap<String, (usize, usize)>,
+}
+
+impl GraphDiff {
+    fn is_empty(&self) -> bool {
+        self.nodes_added.is_empty() && self.nodes_removed.is_empty() && self.edges_added.is_empty() && self.edges_removed.is_empty()
+    }
+}
+
+fn compare(before: &Graph, after: &Graph) -> GraphDiff {
+    let (edges_before, edges_after) = (edge_keys(before), edge_keys(after));
+    GraphDiff {
+        nodes_added: after.nodes.keys().filter(|id| !before.nodes.contains_key(*id)).cloned().collect(),
+        nodes_removed: before.nodes.keys().filter(|id| !after.nodes.contains_key(*id)).cloned().collect(),
+        edges_added: edges_after.difference(&edges_before).cloned().collect(),
+        edges_removed: edges_before.difference(&edges_after).cloned().collect(),
+        object_types: count_deltas(per_type(before), per_type(after)),
+        relations: count_deltas(per_relation(before), per_relation(after)),
+    }
+}
+
+pub fn run(diff_args: &OcdgDiff) -> Result<(), Box<dyn Error>> {
+    debug!("Importing graphs: {:?} and {:?}", diff_args.before, diff_args.after);
+    let before = Graph::import(&diff_args.before)?;
+    let after = Graph::import(&diff_args.after)?;
+
+    let diff = compare(&before, &after);
+    match diff_args.format.unwrap_or(ReportFormat::Table) {
+        ReportFormat::Table => print_text(&diff, diff_args.examples),
+        ReportFormat::Csv => print_csv(&diff)?,
+        ReportFormat::Json => print_json(&diff)?,
+    }
+
+    if diff_args.exit_code && !diff.is_empty() {
+        process::exit(exit::VALIDATION_FAILED);
+    }
+    Ok(())
+}
+
+fn print_text(diff: &GraphDiff, examples: usize) {
+    if diff.is_empty() {
+        println!("no differences");
+        return;
+    }
+    for (title, ids, style) in [("nodes added", &diff.nodes_added, Style::Good), ("nodes removed", &diff.nodes_removed, Style::Bad)] {
+        println!("{}: {}", title, paint(&ids.len().to_string(), style));
+        for id in ids.iter().take(examples) {
+            println!("    {}", id);
+        }
+    }
+    for (title, edges, style) in [("edges added", &diff.edges_added, Style::Good), ("edges removed", &diff.edges_removed, Style::Bad)] {
+        println!("{}: {}", title, paint(&edges.len().to_string(), style));
+        for (source, target, relations) in edges.iter().take(examples) {
+            println!("    {} - {} ({})", source, target, relations);
+        }
+    }
+    for (title, column, counts) in [("nodes per object type", "object type", &diff.object_types), ("edges per relation", "relation", &diff.relations)] {
+        if counts.is_empty() {
+            continue;
+        }
+        println!("{}:", title);
+        let mut table = Table::new(&[column, "before", "after", "change"]).right(&[1, 2, 3]).indent(4);
+        for (key, (b, a)) in counts {
+            let change = Cell::styled(format!("{:+}", *a as i64 - *b as i64), change_style(*b, *a));
+            table.row(vec![key.as_str().into(), (*b).into(), (*a).into(), change]);
+        }
+        table.print();
+    }
+}
+
+/// One row per added or removed node and edge and per changed count of an object
+/// type or relation.
+fn print_csv(diff: &GraphDiff) -> Result<(), Box<dyn Error>> {
+    let mut writer = csv::Writer::from_writer(std::io::stdout());
+    writer.write_record(["change", "kind", "id", "source", "target", "relations", "before", "after"])?;
+    for (change, ids) in [("added", &diff.nodes_added), ("removed", &diff.nodes_removed)] {
+        for id in ids {
+            writer.write_record([change, "node", id.as_str(), "", "", "", "", ""])?;
+        }
+    }
+    for (change, edges) in [("added", &diff.edges_added), ("removed", &diff.edges_removed)] {
+        for (source, target, relations) in edges {
+            writer.write_record([change, "edge", "", source.as_str(), target.as_str(), relations.as_str(), "", ""])?;
+        }
+    }
+    for (kind, counts) in [("object_type", &diff.object_types), ("relation", &diff.relations)] {
+        for (key, (b, a)) in counts {
+            writer.write_record(["count", kind, key.as_str(), "", "", "", b.to_string().as_str(), a.to_string().as_str()])?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn print_json(diff: &GraphDiff) -> Result<(), Box<dyn Error>> {
+    let deltas = |counts: &BTreeMap<String, (usize, usize)>| -> Map<String, Value> {
+        counts.iter().map(|(key, (b, a))| (key.clone(), json!({"before": b, "after": a}))).collect()
+    };
+    let edges = |edges: &[EdgeKey]| -> Vec<Value> {
+        edges.iter().map(|(source, target, relations)| json!({"source": source, "target": target, "relations": relations})).collect()
+    };
+    let report = json!({
+        "identical": diff.is_empty(),
+        "nodes_added": diff.nodes_added,
+        "nodes_removed": diff.nodes_removed,
+        "edges_added": edges(&diff.edges_added),
+        "edges_removed": edges(&diff.edges_removed),
+        "object_types": deltas(&diff.object_types),
+        "relations": deltas(&diff.relations),
+    });
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
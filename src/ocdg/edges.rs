@@ -0,0 +1,46 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use log::debug;
+
+use super::graph::Graph;
+use crate::compression;
+
+#[derive(Args, Debug)]
+pub struct OcdgExportEdges {
+    /// Path to OCDG file
+    pub path: PathBuf,
+
+    /// Output CSV file
+    #[clap(short, long)]
+    pub output: PathBuf,
+}
+
+/// Writes one `source,target,relation,weight,first_seen,last_seen` row per relation
+/// of every edge. Edges without a known relation get an empty relation, edges
+/// without a time span empty `first_seen` and `last_seen`.
+pub fn run(export: &OcdgExportEdges) -> Result<(), Box<dyn Error>> {
+    debug!("Importing {:?}", export.path);
+    let graph = Graph::import(&export.path)?;
+
+    let mut writer = csv::Writer::from_writer(compression::create(&export.output)?);
+    writer.write_record(["source", "target", "relation", "weight", "first_seen", "last_seen"])?;
+    let mut rows = 0;
+    for edge in &graph.edges {
+        let weight = edge.weight.map(|w| w.to_string()).unwrap_or_default();
+        let first_seen = edge.start.as_deref().unwrap_or("");
+        let last_seen = edge.end.as_deref().unwrap_or("");
+        let mut relations = edge.relations();
+        if relations.is_empty() {
+            relations.push(String::new());
+        }
+        for relation in &relations {
+            writer.write_record([edge.source.as_str(), edge.target.as_str(), relation.as_str(), weight.as_str(), first_seen, last_seen])?;
+            rows += 1;
+        }
+    }
+    writer.flush()?;
+    status!("wrote {} rows for {} edges -> {}", rows, graph.edges.len(), export.output.display());
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use log::debug;
+
+use super::formats::{self, GraphFormat};
+use super::graph::{relation_names, Graph};
+
+#[derive(Args, Debug)]
+pub struct OcdgFilter {
+    /// Path to OCDG file
+    pub path: PathBuf,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// Graph format to write. Inferred from the output extension if unset, GEXF
+    /// otherwise
+    #[clap(long, value_enum)]
+    pub format: Option<GraphFormat>,
+
+    /// Only keep edges standing for one of these relations. May be repeated or comma
+    /// separated
+    #[clap(long, value_name = "RELATION", use_value_delimiter = true, multiple_occurrences = true)]
+    pub relation: Vec<String>,
+
+    /// Drop nodes of these object types together with their edges
+    #[clap(long, value_name = "TYPE", use_value_delimiter = true, multiple_occurrences = true)]
+    pub drop_object_type: Vec<String>,
+
+    /// Drop edges with a weight below this value. Edges without a weight count as 1
+    #[clap(long, value_name = "WEIGHT")]
+    pub min_weight: Option<f64>,
+
+    /// Drop nodes left without any edge
+    #[clap(long)]
+    pub drop_isolated: bool,
+}
+
+pub fn run(filter: &OcdgFilter) -> Result<(), Box<dyn Error>> {
+    let known = relation_names();
+    if let Some(unknown) = filter.relation.iter().find(|name| !known.iter().any(|k| k.eq_ignore_ascii_case(name))) {
+        return Err(format!("unknown relation {:?}. Known relations: {}", unknown, known.join(", ")).into());
+    }
+
+    debug!("Importing {:?}", filter.path);
+    let mut graph = Graph::import(&filter.path)?;
+    let (nodes, edges) = (graph.nodes.len(), graph.edges.len());
+
+    if !filter.drop_object_type.is_empty() {
+        graph.retain_nodes(|_, node| !matches!(node.obj_type(), Some(t) if filter.drop_object_type.iter().any(|d| d == t)));
+    }
+    if !filter.relation.is_empty() {
+        graph.edges.retain(|edge| edge.relations().iter().any(|r| filter.relation.iter().any(|k| k.eq_ignore_ascii_case(r))));
+    }
+    if let Some(min) = filter.min_weight {
+        graph.edges.retain(|edge| edge.weight.unwrap_or(1.0) >= min);
+    }
+    if filter.drop_isolated {
+        let degrees = graph.degrees();
+        let connected: HashSet<String> =
+            degrees.iter().filter(|(_, (i, o))| i + o > 0).map(|(id, _)| id.to_string()).collect();
+        graph.retain_nodes(|id, _| connected.contains(id));
+    }
+
+    let format = GraphFormat::resolve(filter.format, &filter.output);
+    debug!("Writing the filtered graph to {:?} as {:?}", filter.output, format);
+    formats::write(&graph, &filter.output, format)?;
+    status!(
+        "kept {} of {} nodes and {} of {} edges -> {}",
+        graph.nodes.len(),
+        nodes,
+        graph.edges.len(),
+        edges,
+        filter.output.display()
+    );
+    Ok(())
+}
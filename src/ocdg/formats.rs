@@ -0,0 +1,220 @@
+//! Export of a [`Graph`] to formats other tools read: DOT for Graphviz, GraphML for
+//! yEd and Gephi, a JSON node/edge document and a CSV edge list. pmrs itself only
+//! writes GEXF, so its export is staged in a temporary file and converted.
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::Path;
+
+use clap::ValueEnum;
+use indexmap::IndexMap;
+use serde_json::{json, Map, Value};
+
+use super::gexf;
+use super::graph::{Edge, Graph};
+use crate::compression::{self, Compression};
+use crate::stdio;
+use crate::xml::escape;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+    Gexf,
+    Dot,
+    Graphml,
+    Json,
+    Csv,
+}
+
+impl GraphFormat {
+    /// The format implied by the extension of `path`, looking through `.gz` and `.zst`.
+    pub fn from_path(path: &Path) -> Option<GraphFormat> {
+        match compression::inner_path(path).extension()?.to_str()? {
+            "gexf" | "gexfocdg" => Some(GraphFormat::Gexf),
+            "dot" | "gv" => Some(GraphFormat::Dot),
+            "graphml" => Some(GraphFormat::Graphml),
+            "json" => Some(GraphFormat::Json),
+            "csv" => Some(GraphFormat::Csv),
+            _ => None,
+        }
+    }
+
+    /// `explicit` if given, otherwise inferred from `path`, falling back to GEXF.
+    pub fn resolve(explicit: Option<GraphFormat>, path: &Path) -> GraphFormat {
+        explicit.or_else(|| GraphFormat::from_path(path)).unwrap_or(GraphFormat::Gexf)
+    }
+}
+
+/// A temporary GEXF file for pmrs to export into when `path` cannot be written by
+/// pmrs directly, i.e. it is compressed, standard output, in another format or
+/// `reshape` is set because the graph is changed afterwards. `None` if pmrs can
+/// write `path` itself.
+pub fn staging_file(path: &Path, format: GraphFormat, reshape: bool) -> io::Result<Option<tempfile::TempPath>> {
+    if writes_directly(path, format) && !reshape {
+        return Ok(None);
+    }
+    Ok(Some(tempfile::Builder::new().suffix(".gexf").tempfile()?.into_temp_path()))
+}
+
+/// Whether pmrs can export to `path` in `format` itself: uncompressed GEXF in a file.
+pub fn writes_directly(path: &Path, format: GraphFormat) -> bool {
+    format == GraphFormat::Gexf && Compression::from_path(path) == Compression::None && !stdio::is_stdio(path)
+}
+
+/// Rewrites the GEXF file `source` as `path` in `format`.
+pub fn convert(source: &Path, path: &Path, format: GraphFormat) -> Result<(), Box<dyn Error>> {
+    let graph = Graph::import(source)?;
+    write(&graph, path, format)
+}
+
+pub fn write(graph: &Graph, path: &Path, format: GraphFormat) -> Result<(), Box<dyn Error>> {
+    let mut out = compression::create(path)?;
+    write_to(graph, format, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+pub fn write_to<W: Write>(graph: &Graph, format: GraphFormat, out: &mut W) -> Result<(), Box<dyn Error>> {
+    match format {
+        GraphFormat::Gexf => gexf::write_to(graph, out),
+        GraphFormat::Dot => write_dot(graph, out),
+        GraphFormat::Graphml => write_graphml(graph, out),
+        GraphFormat::Json => write_json(graph, out),
+        GraphFormat::Csv => write_csv(graph, out),
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Nodes are labelled with their label, edges with their `label` attribute or else
+/// their relations.
+fn write_dot<W: Write>(graph: &Graph, out: &mut W) -> Result<(), Box<dyn Error>> {
+    let (keyword, arrow) = if graph.directed { ("digraph", "->") } else { ("graph", "--") };
+    writeln!(out, "{} ocdg {{", keyword)?;
+    for (id, node) in &graph.nodes {
+        write!(out, "  {} [label={}", quote(id), quote(&node.label))?;
+        if let Some(color) = node.color {
+            write!(out, ", color=\"#{:02x}{:02x}{:02x}\"", color.r, color.g, color.b)?;
+        }
+        writeln!(out, "];")?;
+    }
+    for edge in &graph.edges {
+        write!(out, "  {} {} {} [label={}", quote(&edge.source), arrow, quote(&edge.target), quote(&edge_label(edge)))?;
+        if let Some(weight) = edge.weight {
+            write!(out, ", weight={}", weight)?;
+        }
+        if let Some(color) = edge.color {
+            write!(out, ", color=\"#{:02x}{:02x}{:02x}\"", color.r, color.g, color.b)?;
+        }
+        writeln!(out, "];")?;
+    }
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+fn edge_label(edge: &Edge) -> String {
+    match edge.attributes.get("label") {
+        Some(label) => label.clone(),
+        None => edge.relations().join(","),
+    }
+}
+
+fn graphml_type(gexf_type: &str) -> &'static str {
+    match gexf_type {
+        "integer" => "long",
+        "double" => "double",
+        "boolean" => "boolean",
+        _ => "string",
+    }
+}
+
+fn write_graphml<W: Write>(graph: &Graph, out: &mut W) -> Result<(), Box<dyn Error>> {
+    let node_attrs = gexf::declare(graph.nodes.values().map(|n| &n.attributes));
+    let edge_attrs = gexf::declare(graph.edges.iter().map(|e| &e.attributes));
+
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#)?;
+    writeln!(out, r#"  <key id="label" for="node" attr.name="label" attr.type="string"/>"#)?;
+    writeln!(out, r#"  <key id="weight" for="edge" attr.name="weight" attr.type="double"/>"#)?;
+    for (index, (name, kind)) in node_attrs.iter().enumerate() {
+        writeln!(out, r#"  <key id="n{}" for="node" attr.name="{}" attr.type="{}"/>"#, index, escape(name), graphml_type(kind))?;
+    }
+    for (index, (name, kind)) in edge_attrs.iter().enumerate() {
+        writeln!(out, r#"  <key id="e{}" for="edge" attr.name="{}" attr.type="{}"/>"#, index, escape(name), graphml_type(kind))?;
+    }
+    let edge_type = if graph.directed { "directed" } else { "undirected" };
+    writeln!(out, r#"  <graph id="ocdg" edgedefault="{}">"#, edge_type)?;
+
+    for (id, node) in &graph.nodes {
+        writeln!(out, r#"    <node id="{}">"#, escape(id))?;
+        writeln!(out, r#"      <data key="label">{}</data>"#, escape(&node.label))?;
+        for (key, value) in &node.attributes {
+            if let Some(index) = node_attrs.get_index_of(key) {
+                writeln!(out, r#"      <data key="n{}">{}</data>"#, index, escape(value))?;
+            }
+        }
+        writeln!(out, "    </node>")?;
+    }
+    for edge in &graph.edges {
+        writeln!(out, r#"    <edge id="{}" source="{}" target="{}">"#, escape(&edge.id), escape(&edge.source), escape(&edge.target))?;
+        if let Some(weight) = edge.weight {
+            writeln!(out, r#"      <data key="weight">{}</data>"#, weight)?;
+        }
+        for (key, value) in &edge.attributes {
+            if let Some(index) = edge_attrs.get_index_of(key) {
+                writeln!(out, r#"      <data key="e{}">{}</data>"#, index, escape(value))?;
+            }
+        }
+        writeln!(out, "    </edge>")?;
+    }
+    writeln!(out, "  </graph>")?;
+    writeln!(out, "</graphml>")?;
+    Ok(())
+}
+
+fn attribute_map(attributes: &IndexMap<String, String>) -> Map<String, Value> {
+    attributes.iter().map(|(key, value)| (key.clone(), Value::from(value.as_str()))).collect()
+}
+
+/// `{"directed": .., "nodes": [{"id", "label", "attributes"}], "edges": [{"id",
+/// "source", "target", "relations", "weight", "attributes"}]}`
+fn write_json<W: Write>(graph: &Graph, out: &mut W) -> Result<(), Box<dyn Error>> {
+    let nodes: Vec<Value> = graph
+        .nodes
+        .iter()
+        .map(|(id, node)| json!({"id": id, "label": node.label, "attributes": attribute_map(&node.attributes)}))
+        .collect();
+    let edges: Vec<Value> = graph
+        .edges
+        .iter()
+        .map(|edge| {
+            json!({
+                "id": edge.id,
+                "source": edge.source,
+                "target": edge.target,
+                "relations": edge.relations(),
+                "weight": edge.weight,
+                "attributes": attribute_map(&edge.attributes),
+            })
+        })
+        .collect();
+    let mut document = json!({"directed": graph.directed, "nodes": nodes, "edges": edges});
+    if let Some(provenance) = &graph.provenance {
+        document["provenance"] = serde_json::to_value(provenance)?;
+    }
+    serde_json::to_writer_pretty(&mut *out, &document)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// One `source,target,relations,weight` row per edge, relations separated by `;`.
+fn write_csv<W: Write>(graph: &Graph, out: &mut W) -> Result<(), Box<dyn Error>> {
+    let mut writer = csv::Writer::from_writer(out);
+    writer.write_record(["source", "target", "relations", "weight"])?;
+    for edge in &graph.edges {
+        let weight = edge.weight.map(|w| w.to_string()).unwrap_or_default();
+        writer.write_record([edge.source.as_str(), edge.target.as_str(), edge.relations().join(";").as_str(), weight.as_str()])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
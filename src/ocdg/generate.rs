@@ -0,0 +1,763 @@
+use std::collections::BTreeMap;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use log::debug;
+use pmrs::objects::ocdg::exporter::export_ocdg;
+use pmrs::objects::ocdg::{generate_ocdg, Relations};
+use pmrs::objects::ocel::importer::import_ocel;
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use strum::IntoEnumIterator;
+
+use super::cache;
+use super::formats::{self, GraphFormat};
+use super::gexf::{self, Written};
+use super::graph::{Color, Edge, Graph, RELATION_KEY};
+use super::palette;
+use super::provenance::Provenance;
+use super::relations::parse_relations;
+use crate::compression;
+use crate::config::{Config, Defaults, Profile};
+use crate::ocel::model::OcelLog;
+use crate::ocel::window::TimeWindow;
+use crate::plan;
+use crate::limits;
+use crate::progress::Progress;
+use crate::stdio;
+
+#[derive(Args, Debug, Clone)]
+pub struct OcdgGeneration {
+    /// Path to OCEL file
+    pub path: String,
+
+    #[clap(flatten)]
+    pub window: TimeWindow,
+
+    /// Output file name and location. Default: output.gexf in the configured output
+    /// directory
+    #[clap(short, long, conflicts_with = "output-multiple")]
+    pub output: Option<String>,
+
+    /// Graph format to write. Inferred from the output extension if unset, GEXF
+    /// otherwise
+    #[clap(long, value_enum)]
+    pub format: Option<GraphFormat>,
+
+    /// Write one graph per relation into this directory, all generated from a single
+    /// import of the log
+    #[clap(long, value_name = "DIR")]
+    pub output_multiple: Option<PathBuf>,
+
+    /// File name pattern for --output-multiple, `{relation}` is replaced by the
+    /// lowercase relation name
+    #[clap(long, default_value = "{relation}.gexf", requires = "output-multiple")]
+    pub output_template: String,
+
+    /// Only keep nodes of these two object types and the edges between them,
+    /// tagging every node with a `mode` attribute
+    #[clap(long, number_of_values = 2, value_names = &["TYPE_A", "TYPE_B"])]
+    pub two_mode: Option<Vec<String>>,
+
+    /// JSON file mapping relation names to hex edge colors. Relations without an
+    /// entry use the default palette
+    #[clap(long, value_name = "PATH")]
+    pub relation_color_map: Option<PathBuf>,
+
+    /// Remove edges between objects of these type pairs, e.g. `order:order`. Pairs
+    /// match in either direction and may be repeated or comma separated
+    #[clap(long, value_name = "TYPE:TYPE", value_parser = parse_type_pair, use_value_delimiter = true)]
+    pub suppress_relations_with_types: Vec<(String, String)>,
+
+    /// Abort instead of writing an output larger than this many megabytes. pmrs
+    /// writes its export in one go, so it goes to a temporary file first and is only
+    /// copied to the output if it fits. Outputs pmrs-cli writes itself, because of
+    /// another format, compression or post-processing, are cut off at the limit and
+    /// removed. The temporary file is not limited
+    #[clap(long, value_name = "MB")]
+    pub max_output_size: Option<u64>,
+
+    /// Only keep edges whose numeric attribute satisfies a comparison, e.g.
+    /// `duration gt 3600`. OP is one of gt, ge, lt, le, eq; the key `weight` also
+    /// matches the edge weight
+    #[clap(long, number_of_values = 3, value_names = &["KEY", "OP", "VALUE"])]
+    pub attribute_edge_threshold: Option<Vec<String>>,
+
+    /// Relabel nodes and order nodes and edges canonically so that structurally
+    /// identical graphs export identically. Original ids are kept as node labels
+    #[clap(long)]
+    pub canonicalize: bool,
+
+    /// Generate the relations on this many threads, one relation per thread, and
+    /// union the results. 0 uses one thread per core. Default: 1
+    #[clap(long, value_name = "N")]
+    pub threads: Option<usize>,
+
+    /// Leave the provenance block (tool version, command line, input hash, time and
+    /// relations) out of GEXF and JSON outputs. GEXF files exported by pmrs get the
+    /// block inserted as text, the rest of the file is not touched either way
+    #[clap(long)]
+    pub no_provenance: bool,
+
+    /// Keep generated graphs in this directory, keyed by a hash of the log, the
+    /// relations and the pmrs version, and reuse them instead of generating again.
+    /// Not used with --output-multiple or a log on standard input
+    #[clap(long, value_name = "DIR")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Take defaults from the named `[profile.<name>]` table of the configuration.
+    /// Flags given on the command line still take precedence
+    #[clap(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Generate again whenever the log changes, until interrupted
+    #[clap(long)]
+    pub watch: bool,
+
+    /// Import the log and report the expected graph size and the files that would be
+    /// written, without generating or writing anything
+    #[clap(long, conflicts_with = "watch")]
+    pub dry_run: bool,
+
+    /// Relations to generate, e.g. `descendants,cobirth,interaction`. All relations
+    /// are generated if unset. Names are case-insensitive
+    #[clap(long, value_name = "RELATION", use_value_delimiter = true, multiple_occurrences = true)]
+    pub relations: Option<Vec<String>>,
+
+    /// Relations to leave out of the otherwise selected ones
+    #[clap(long, value_name = "RELATION", use_value_delimiter = true, multiple_occurrences = true)]
+    pub exclude: Vec<String>,
+}
+
+fn parse_type_pair(raw: &str) -> Result<(String, String), String> {
+    match raw.split_once(':') {
+        Some((a, b)) if !a.is_empty() && !b.is_empty() => Ok((a.to_string(), b.to_string())),
+        _ => Err(format!("expected a pair of object types like `order:item`, got {:?}", raw)),
+    }
+}
+
+impl OcdgGeneration {
+    /// Fills every option that was not given on the command line from `profile`.
+    fn with_profile(&self, profile: &Profile) -> Result<OcdgGeneration, String> {
+        let mut merged = self.clone();
+        merged.relations = merged.relations.or_else(|| profile.relations.clone());
+        merged.output = merged.output.or_else(|| profile.output.clone());
+        merged.two_mode = merged.two_mode.or_else(|| profile.two_mode.clone());
+        merged.relation_color_map = merged.relation_color_map.or_else(|| profile.relation_color_map.clone());
+        merged.max_output_size = merged.max_output_size.or(profile.max_output_size);
+        if merged.suppress_relations_with_types.is_empty() {
+            if let Some(pairs) = &profile.suppress_relations_with_types {
+                merged.suppress_relations_with_types = pairs.iter().map(|p| parse_type_pair(p)).collect::<Result<_, _>>()?;
+            }
+        }
+        if matches!(&merged.two_mode, Some(types) if types.len() != 2) {
+            return Err("two-mode in a profile needs exactly two object types".to_string());
+        }
+        Ok(merged)
+    }
+
+    /// Fills the options still unset after the profile from the `[defaults]` table.
+    fn with_defaults(mut self, defaults: &Defaults) -> OcdgGeneration {
+        self.relations = self.relations.or_else(|| defaults.relations.clone());
+        self.threads = self.threads.or(defaults.threads);
+        self.cache_dir = self.cache_dir.or_else(|| defaults.cache_dir.clone());
+        if self.output.is_none() && self.output_multiple.is_none() {
+            self.output = Some(defaults.output_path("output.gexf").to_string_lossy().into_owned());
+        }
+        if let Some(output) = &self.output {
+            self.format = defaults.graph_format(self.format, Path::new(output));
+        }
+        self
+    }
+
+    fn needs_postprocessing(&self) -> bool {
+        self.two_mode.is_some()
+            || self.relation_color_map.is_some()
+            || !self.suppress_relations_with_types.is_empty()
+            || self.attribute_edge_threshold.is_some()
+            || self.canonicalize
+    }
+
+    /// Whether pmrs exports to a temporary file even when it could write the output
+    /// itself, so that the size limit is checked before the output is touched.
+    fn stages(&self) -> bool {
+        self.needs_postprocessing() || self.max_output_size.is_some()
+    }
+}
+
+pub fn run(generation: &OcdgGeneration, config: &Config) -> Result<(), Box<dyn Error>> {
+    let resolved = match &generation.profile {
+        Some(name) => {
+            debug!("Applying profile {:?}", name);
+            generation.with_profile(config.profile(name)?)?
+        }
+        None => generation.clone(),
+    };
+    let generation = &resolved.with_defaults(&config.defaults);
+    let threads = generation.threads.unwrap_or(1);
+
+    let mut output_path = Path::new("output.gexf");
+
+    if let Some(custom_name) = &generation.output {
+        debug!("Setting custom output path to {:?}", custom_name);
+        output_path = Path::new(custom_name);
+    }
+
+    if let Some(types) = &generation.two_mode {
+        if types[0] == types[1] {
+            return Err(format!("--two-mode needs two different object types, got {:?} twice", types[0]).into());
+        }
+    }
+
+    let threshold = match &generation.attribute_edge_threshold {
+        Some(args) => Some(EdgeThreshold::parse(args)?),
+        None => None,
+    };
+
+    let colors = match &generation.relation_color_map {
+        Some(path) => Some(palette::load_color_map(path)?),
+        None => None,
+    };
+
+    let relations = selected_relations(generation)?;
+    generation.window.check()?;
+    if generation.dry_run {
+        return dry_run(generation, &relations, output_path);
+    }
+    let provenance = if generation.no_provenance {
+        None
+    } else {
+        Some(Provenance::record(Path::new(&generation.path), &relations)?)
+    };
+    let cache_entry = match &generation.cache_dir {
+        Some(dir) if generation.output_multiple.is_none() && !stdio::is_stdio(Path::new(&generation.path)) => {
+            Some(cache::entry(dir, Path::new(&generation.path), &relations, &generation.window)?)
+        }
+        _ => None,
+    };
+    if let Some(entry) = cache_entry.as_deref().filter(|entry| entry.is_file()) {
+        debug!("Reusing the cached OCDG {:?}", entry);
+        let format = GraphFormat::resolve(generation.format, output_path);
+        deliver(generation, entry, output_path, format, colors.as_ref(), threshold.as_ref(), provenance.as_ref())?;
+        status!("reused the cached OCDG {} -> {}", entry.display(), output_path.display());
+        return Ok(());
+    }
+    let parallel = threads != 1 && relations.len() > 1;
+    // the import, every relation and, for a single output, its export
+    let steps = 1 + relations.len() + usize::from(generation.output_multiple.is_none());
+    let progress = Progress::new("ocdg generate", steps as u64);
+
+    debug!("Importing log: {:?}", &generation.path);
+    progress.step(format!("importing {}", generation.path));
+    let source = generation.window.as_jsonocel(Path::new(&generation.path))?;
+    let log = import_ocel(&source.to_string_lossy())?;
+    progress.inc();
+
+    if let Some(dir) = &generation.output_multiple {
+        fs::create_dir_all(dir)?;
+        // errors are turned into strings so that they can cross threads
+        let export_relation = |relation: Relations| -> Result<(), String> {
+            let name = format!("{:?}", relation).to_lowercase();
+            let path = dir.join(generation.output_template.replace("{relation}", &name));
+            let format = GraphFormat::resolve(generation.format, &path);
+            debug!("Generating OCDG on relation: {:?}", relation);
+            progress.step(format!("generating {}", name));
+            let relation_provenance = provenance.clone().map(|p| p.with_relations(std::slice::from_ref(&relation)));
+            let ocdg = generate_ocdg(&log, &vec![relation]);
+            debug!("Exporting the generated OCDG to {:?}", path);
+            let _writing = limits::writing(&path);
+            let staging = formats::staging_file(&path, format, generation.stages()).map_err(|e| e.to_string())?;
+            export_ocdg(&ocdg, &staging.as_deref().unwrap_or(&path).to_string_lossy()).map_err(|e| e.to_string())?;
+            match staging {
+                Some(staging) => deliver(generation, &staging, &path, format, colors.as_ref(), threshold.as_ref(), relation_provenance.as_ref()),
+                None => stamp(&path, relation_provenance.as_ref()),
+            }
+            .map_err(|e| e.to_string())?;
+            progress.inc();
+            Ok(())
+        };
+        if parallel {
+            let pool = ThreadPoolBuilder::new().num_threads(threads).build()?;
+            pool.install(|| relations.into_par_iter().try_for_each(export_relation))?;
+        } else {
+            relations.into_iter().try_for_each(export_relation)?;
+        }
+        progress.finish();
+        return Ok(());
+    }
+
+    if parallel {
+        let pool = ThreadPoolBuilder::new().num_threads(threads).build()?;
+        debug!("Generating OCDG on relations {:?} with {} threads", relations, pool.current_num_threads());
+        let parts = pool.install(|| {
+            relations
+                .into_par_iter()
+                .map(|relation| -> Result<Graph, String> {
+                    progress.step(format!("generating {:?}", relation).to_lowercase());
+                    let ocdg = generate_ocdg(&log, &vec![relation]);
+                    let staging = tempfile::Builder::new().suffix(".gexf").tempfile().map_err(|e| e.to_string())?.into_temp_path();
+                    export_ocdg(&ocdg, &staging.to_string_lossy()).map_err(|e| e.to_string())?;
+                    let part = Graph::import(&staging).map_err(|e| e.to_string());
+                    progress.inc();
+                    part
+                })
+                .collect::<Result<Vec<Graph>, String>>()
+        })?;
+        let format = GraphFormat::resolve(generation.format, output_path);
+        progress.step(format!("exporting {}", output_path.display()));
+        let _writing = limits::writing(output_path);
+        let union = union_relations(parts);
+        if let Some(entry) = &cache_entry {
+            let exported = tempfile::Builder::new().suffix(".gexf").tempfile()?.into_temp_path();
+            formats::write(&union, &exported, GraphFormat::Gexf)?;
+            cache::store(entry, &exported)?;
+        }
+        finish(generation, union, output_path, format, colors.as_ref(), threshold.as_ref(), provenance.as_ref())?;
+        progress.inc();
+        progress.finish();
+        debug!("Successfully exported the OCDG to: {:?}", output_path);
+        return Ok(());
+    }
+
+    debug!("Generating OCDG on relations: {:?}", relations);
+    // pmrs generates all relations in one call, so they complete together
+    progress.step(format!("generating {} relations", relations.len()));
+    let ocdg = generate_ocdg(&log, &relations);
+    for _ in &relations {
+        progress.inc();
+    }
+    debug!("Exporting the generated OCDG.");
+    progress.step(format!("exporting {}", output_path.display()));
+    let _writing = limits::writing(output_path);
+    let format = GraphFormat::resolve(generation.format, output_path);
+    let staging = formats::staging_file(output_path, format, generation.stages())?;
+    export_ocdg(&ocdg, &staging.as_deref().unwrap_or(output_path).to_string_lossy())?;
+    if let Some(entry) = &cache_entry {
+        cache::store(entry, staging.as_deref().unwrap_or(output_path))?;
+    }
+    match staging {
+        Some(staging) => deliver(generation, &staging, output_path, format, colors.as_ref(), threshold.as_ref(), provenance.as_ref())?,
+        None => stamp(output_path, provenance.as_ref())?,
+    }
+    progress.inc();
+    progress.finish();
+    debug!("Successfully exported the OCDG to: {:?}", output_path);
+    Ok(())
+}
+
+/// Reports what `run` would produce. Every object becomes a node, and the edges are
+/// bounded by the object pairs sharing an event, which the interaction relation
+/// connects; the other relations are usually sparser.
+fn dry_run(generation: &OcdgGeneration, relations: &[Relations], output_path: &Path) -> Result<(), Box<dyn Error>> {
+    debug!("Importing log: {:?}", &generation.path);
+    let log = generation.window.import(Path::new(&generation.path))?;
+    let kept = |oid: &String| match (&generation.two_mode, log.objects.get(oid)) {
+        (Some(types), Some(object)) => types.contains(&object.obj_type),
+        (Some(_), None) => false,
+        (None, _) => true,
+    };
+    let nodes = log.objects.keys().filter(|oid| kept(oid)).count();
+    let mut pairs: HashSet<(&str, &str)> = HashSet::new();
+    for event in log.events.values() {
+        let objects: Vec<&str> = event.omap.iter().filter(|oid| kept(oid)).map(String::as_str).collect();
+        for (i, &a) in objects.iter().enumerate() {
+            for &b in &objects[i + 1..] {
+                if a != b {
+                    pairs.insert(if a < b { (a, b) } else { (b, a) });
+                }
+            }
+        }
+    }
+
+    let names: Vec<String> = relations.iter().map(|relation| format!("{:?}", relation).to_lowercase()).collect();
+    println!("relations: {}", names.join(", "));
+    match &generation.output_multiple {
+        Some(dir) => {
+            println!("estimated per graph: {} nodes, up to {} edges", nodes, pairs.len());
+            for name in &names {
+                plan::output(&dir.join(generation.output_template.replace("{relation}", name)));
+            }
+        }
+        None => {
+            println!("estimated: {} nodes, up to {} edges", nodes, pairs.len());
+            println!("format: {:?}", GraphFormat::resolve(generation.format, output_path));
+            plan::output(output_path);
+        }
+    }
+    Ok(())
+}
+
+fn selected_relations(generation: &OcdgGeneration) -> Result<Vec<Relations>, String> {
+    let mut relations = match &generation.relations {
+        Some(names) => parse_relations(names)?,
+        None => Relations::iter().collect(),
+    };
+    if !generation.exclude.is_empty() {
+        let excluded: Vec<String> = parse_relations(&generation.exclude)?.iter().map(|r| format!("{:?}", r)).collect();
+        relations.retain(|relation| !excluded.contains(&format!("{:?}", relation)));
+    }
+    if relations.is_empty() {
+        return Err("no relations left to generate after --exclude".to_string());
+    }
+    Ok(relations)
+}
+
+/// Adds the provenance to a GEXF file pmrs exported itself, without reading the
+/// graph back. Everything pmrs wrote is kept as it is.
+fn stamp(path: &Path, provenance: Option<&Provenance>) -> Result<(), Box<dyn Error>> {
+    match provenance {
+        Some(provenance) => gexf::stamp(path, provenance),
+        None => Ok(()),
+    }
+}
+
+/// Writes the OCDG pmrs has exported to `staging` to `path` in `format`. It is
+/// copied as it is if nothing changes the graph and the format matches, once it is
+/// known to fit `--max-output-size`, and post-processed otherwise.
+fn deliver(
+    generation: &OcdgGeneration,
+    staging: &Path,
+    path: &Path,
+    format: GraphFormat,
+    colors: Option<&HashMap<String, Color>>,
+    threshold: Option<&EdgeThreshold>,
+    provenance: Option<&Provenance>,
+) -> Result<(), Box<dyn Error>> {
+    if !formats::writes_directly(path, format) || generation.needs_postprocessing() {
+        return postprocess(generation, staging, path, format, colors, threshold, provenance);
+    }
+    if let Some(limit_mb) = generation.max_output_size {
+        let size = fs::metadata(staging)?.len();
+        if size > limit_mb * 1024 * 1024 {
+            return Err(format!(
+                "the output would take {:.1} MB, more than the {} MB allowed, nothing was written to {:?}",
+                size as f64 / (1024.0 * 1024.0),
+                limit_mb,
+                path
+            )
+            .into());
+        }
+    }
+    fs::copy(staging, path)?;
+    stamp(path, provenance)
+}
+
+/// Applies the graph-level options to the OCDG pmrs has exported to `staging` and
+/// writes the result to `path` in `format`.
+fn postprocess(
+    generation: &OcdgGeneration,
+    staging: &Path,
+    path: &Path,
+    format: GraphFormat,
+    colors: Option<&HashMap<String, Color>>,
+    threshold: Option<&EdgeThreshold>,
+    provenance: Option<&Provenance>,
+) -> Result<(), Box<dyn Error>> {
+    finish(generation, Graph::import(staging)?, path, format, colors, threshold, provenance)
+}
+
+/// Applies the graph-level options to `graph` and writes it to `path` in `format`.
+fn finish(
+    generation: &OcdgGeneration,
+    mut graph: Graph,
+    path: &Path,
+    format: GraphFormat,
+    colors: Option<&HashMap<String, Color>>,
+    threshold: Option<&EdgeThreshold>,
+    provenance: Option<&Provenance>,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(types) = &generation.two_mode {
+        two_mode(&mut graph, &types[0], &types[1]);
+    }
+    if !generation.suppress_relations_with_types.is_empty() {
+        let removed = suppress_type_pairs(&mut graph, &generation.suppress_relations_with_types);
+        status!("removed {} edges between suppressed type pairs", removed);
+    }
+    if let Some(threshold) = threshold {
+        let removed = threshold.apply(&mut graph)?;
+        status!("removed {} edges not satisfying {} {} {}", removed, threshold.key, threshold.op, threshold.value);
+    }
+    if let Some(colors) = colors {
+        palette::apply(&mut graph, colors);
+    }
+    if generation.canonicalize {
+        graph.canonicalize();
+    }
+    if let Some(provenance) = provenance {
+        graph.provenance = Some(provenance.clone());
+    }
+    match generation.max_output_size {
+        Some(limit) => export_limited(&graph, path, format, limit),
+        None => formats::write(&graph, path, format),
+    }
+}
+
+/// Unions graphs generated for one relation each into the graph pmrs would have
+/// generated for all of them: edges between the same objects are combined, their
+/// relation attributes joined and their weights added.
+fn union_relations(parts: Vec<Graph>) -> Graph {
+    let mut parts = parts.into_iter();
+    let mut union = parts.next().unwrap_or_default();
+    let key = |edge: &Edge, directed: bool| {
+        if !directed && edge.source > edge.target {
+            (edge.target.clone(), edge.source.clone())
+        } else {
+            (edge.source.clone(), edge.target.clone())
+        }
+    };
+    let mut index: HashMap<(String, String), usize> = HashMap::new();
+    for (i, edge) in union.edges.iter().enumerate() {
+        index.entry(key(edge, union.directed)).or_insert(i);
+    }
+
+    for part in parts {
+        for (id, node) in part.nodes {
+            match union.nodes.get_mut(&id) {
+                Some(existing) => {
+                    for (name, value) in node.attributes {
+                        existing.attributes.entry(name).or_insert(value);
+                    }
+                    existing.color = existing.color.or(node.color);
+                }
+                None => {
+                    union.nodes.insert(id, node);
+                }
+            }
+        }
+        for edge in part.edges {
+            let edge_key = key(&edge, union.directed);
+            let existing = match index.get(&edge_key).copied() {
+                Some(i) => &mut union.edges[i],
+                None => {
+                    index.insert(edge_key, union.edges.len());
+                    union.edges.push(edge);
+                    continue;
+                }
+            };
+            for (name, value) in edge.attributes {
+                match existing.attributes.get_mut(&name) {
+                    Some(current) if name == RELATION_KEY && current != &value => {
+                        current.push(',');
+                        current.push_str(&value);
+                    }
+                    Some(_) => {}
+                    None => {
+                        existing.attributes.insert(name, value);
+                    }
+                }
+            }
+            existing.weight = match (existing.weight, edge.weight) {
+                (Some(a), Some(b)) => Some(a + b),
+                (a, b) => a.or(b),
+            };
+        }
+    }
+    for (i, edge) in union.edges.iter_mut().enumerate() {
+        edge.id = i.to_string();
+    }
+    union
+}
+
+struct EdgeThreshold {
+    key: String,
+    op: String,
+    value: f64,
+}
+
+impl EdgeThreshold {
+    fn parse(args: &[String]) -> Result<EdgeThreshold, String> {
+        let op = args[1].to_lowercase();
+        if !matches!(op.as_str(), "gt" | "ge" | "lt" | "le" | "eq") {
+            return Err(format!("unknown comparison {:?}, expected one of gt, ge, lt, le, eq", args[1]));
+        }
+        let value = args[2].parse().map_err(|_| format!("threshold {:?} is not a number", args[2]))?;
+        Ok(EdgeThreshold { key: args[0].clone(), op, value })
+    }
+
+    fn holds(&self, x: f64) -> bool {
+        match self.op.as_str() {
+            "gt" => x > self.value,
+            "ge" => x >= self.value,
+            "lt" => x < self.value,
+            "le" => x <= self.value,
+            _ => x == self.value,
+        }
+    }
+
+    fn lookup(&self, edge: &Edge) -> Option<f64> {
+        match edge.attributes.get(&self.key) {
+            Some(value) => value.parse().ok(),
+            None if self.key == "weight" => edge.weight,
+            None => None,
+        }
+    }
+
+    /// Drops the edges failing the comparison, including those without the
+    /// attribute, and returns how many were removed.
+    fn apply(&self, graph: &mut Graph) -> Result<usize, String> {
+        if !graph.edges.iter().any(|edge| self.lookup(edge).is_some()) {
+            return Err(format!("no edge carries a numeric {:?} attribute", self.key));
+        }
+        let before = graph.edges.len();
+        graph.edges.retain(|edge| self.lookup(edge).map_or(false, |x| self.holds(x)));
+        Ok(before - graph.edges.len())
+    }
+}
+
+/// A writer that refuses to write more than `limit` bytes in total.
+struct LimitedWriter<W: Write> {
+    inner: W,
+    written: u64,
+    limit: u64,
+    exceeded: bool,
+}
+
+impl<W: Write> Write for LimitedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written + buf.len() as u64 > self.limit {
+            self.exceeded = true;
+            return Err(io::Error::new(io::ErrorKind::Other, "output size limit reached"));
+        }
+        let n = self.inner.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn export_limited(graph: &Graph, path: &Path, format: GraphFormat, limit_mb: u64) -> Result<(), Box<dyn Error>> {
+    let mut out = LimitedWriter {
+        inner: compression::create(path)?,
+        written: 0,
+        limit: limit_mb * 1024 * 1024,
+        exceeded: false,
+    };
+    let mut written = Written::default();
+    let result = match format {
+        GraphFormat::Gexf => gexf::write_counted(graph, &mut out, &mut written),
+        other => formats::write_to(graph, other, &mut out),
+    }
+    .and_then(|_| Ok(out.flush()?));
+    if out.exceeded {
+        drop(out);
+        fs::remove_file(path)?;
+        if format != GraphFormat::Gexf {
+            return Err(format!("the output would exceed {} MB, removed {:?}", limit_mb, path).into());
+        }
+        return Err(format!(
+            "the output would exceed {} MB, removed {:?}. Reached {} of {} nodes and {} of {} edges before the cutoff",
+            limit_mb, path, written.nodes, graph.nodes.len(), written.edges, graph.edges.len()
+        )
+        .into());
+    }
+    result
+}
+
+/// Reduces the graph to a bipartite graph between `type_a` and `type_b` objects.
+fn two_mode(graph: &mut Graph, type_a: &str, type_b: &str) {
+    graph.retain_nodes(|_, node| matches!(node.obj_type(), Some(t) if t == type_a || t == type_b));
+    let nodes = &graph.nodes;
+    graph.edges.retain(|edge| nodes.get(&edge.source).and_then(|n| n.obj_type()) != nodes.get(&edge.target).and_then(|n| n.obj_type()));
+
+    let mut per_mode: BTreeMap<String, usize> = BTreeMap::new();
+    for node in graph.nodes.values_mut() {
+        let mode = node.obj_type().unwrap_or_default().to_string();
+        node.attributes.insert("mode".to_string(), mode.clone());
+        *per_mode.entry(mode).or_default() += 1;
+    }
+    for mode in [type_a, type_b] {
+        status!("{}: {} nodes", mode, per_mode.get(mode).copied().unwrap_or(0));
+    }
+    status!("edges between modes: {}", graph.edges.len());
+}
+
+/// Drops the edges whose endpoint types match one of `pairs` in either direction and
+/// returns how many were removed.
+fn suppress_type_pairs(graph: &mut Graph, pairs: &[(String, String)]) -> usize {
+    let before = graph.edges.len();
+    let nodes = &graph.nodes;
+    graph.edges.retain(|edge| {
+        let source = nodes.get(&edge.source).and_then(|n| n.obj_type()).unwrap_or_default();
+        let target = nodes.get(&edge.target).and_then(|n| n.obj_type()).unwrap_or_default();
+        !pairs.iter().any(|(a, b)| (a == source && b == target) || (a == target && b == source))
+    });
+    before - graph.edges.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocdg::graph::{Node, TYPE_KEY};
+
+    fn graph(nodes: &[(&str, &str)], edges: &[(&str, &str, &str, f64)]) -> Graph {
+        let mut graph = Graph::default();
+        for (id, obj_type) in nodes {
+            let mut node = Node::default();
+            node.attributes.insert(TYPE_KEY.to_string(), obj_type.to_string());
+            graph.nodes.insert(id.to_string(), node);
+        }
+        for (i, (source, target, relation, weight)) in edges.iter().enumerate() {
+            let mut edge =
+                Edge { id: i.to_string(), source: source.to_string(), target: target.to_string(), weight: Some(*weight), ..Edge::default() };
+            edge.attributes.insert(RELATION_KEY.to_string(), relation.to_string());
+            graph.edges.push(edge);
+        }
+        graph
+    }
+
+    fn ends(graph: &Graph) -> Vec<(&str, &str)> {
+        graph.edges.iter().map(|edge| (edge.source.as_str(), edge.target.as_str())).collect()
+    }
+
+    const NODES: [(&str, &str); 4] = [("o1", "order"), ("o2", "order"), ("i1", "item"), ("c1", "customer")];
+
+    #[test]
+    fn thresholds_drop_failing_edges() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let mut graph = graph(&NODES, &[("o1", "i1", "a", 1.0), ("o2", "i1", "a", 2.0), ("o1", "c1", "a", 3.0)]);
+        let threshold = EdgeThreshold::parse(&args(&["weight", "GE", "2"])).unwrap();
+        assert_eq!(threshold.apply(&mut graph), Ok(1));
+        assert_eq!(ends(&graph), [("o2", "i1"), ("o1", "c1")]);
+
+        assert!(EdgeThreshold::parse(&args(&["weight", "above", "2"])).is_err());
+        assert!(EdgeThreshold::parse(&args(&["weight", "gt", "many"])).is_err());
+        let missing = EdgeThreshold::parse(&args(&["frequency", "gt", "0"])).unwrap();
+        assert!(missing.apply(&mut graph).is_err());
+    }
+
+    #[test]
+    fn unions_edges_of_several_relations() {
+        let first = graph(&NODES[..3], &[("o1", "i1", "a", 1.0)]);
+        let second = graph(&NODES, &[("i1", "o1", "b", 2.0), ("o1", "c1", "b", 1.0)]);
+        let union = union_relations(vec![first, second]);
+        assert_eq!(union.nodes.len(), 4);
+        assert_eq!(ends(&union), [("o1", "i1"), ("o1", "c1")]);
+        assert_eq!(union.edges[0].attributes[RELATION_KEY], "a,b");
+        assert_eq!(union.edges[0].weight, Some(3.0));
+        assert_eq!(union.edges[1].id, "1");
+    }
+
+    #[test]
+    fn suppresses_type_pairs_in_both_directions() {
+        let mut graph = graph(&NODES, &[("o1", "i1", "a", 1.0), ("i1", "o2", "a", 1.0), ("o1", "c1", "a", 1.0)]);
+        assert_eq!(suppress_type_pairs(&mut graph, &[("item".to_string(), "order".to_string())]), 2);
+        assert_eq!(ends(&graph), [("o1", "c1")]);
+        assert!(parse_type_pair("order:").is_err());
+    }
+
+    #[test]
+    fn projects_onto_two_modes() {
+        let mut graph = graph(&NODES, &[("o1", "i1", "a", 1.0), ("o1", "o2", "a", 1.0), ("o1", "c1", "a", 1.0)]);
+        two_mode(&mut graph, "order", "item");
+        assert_eq!(graph.nodes.keys().collect::<Vec<_>>(), ["o1", "o2", "i1"]);
+        assert_eq!(ends(&graph), [("o1", "i1")]);
+        assert_eq!(graph.nodes["i1"].attributes["mode"], "item");
+    }
+}
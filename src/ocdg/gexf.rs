@@ -0,0 +1,418 @@
+//! GEXF reading and writing for [`Graph`].
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use indexmap::IndexMap;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use super::graph::{widen, Color, Edge, Graph, Node};
+use super::provenance::Provenance;
+use crate::compression;
+use crate::xml::{attributes, escape};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Class {
+    Node,
+    Edge,
+}
+
+enum Element {
+    Node(String),
+    Edge(usize),
+}
+
+pub fn read(path: &Path) -> Result<Graph, Box<dyn Error>> {
+    parse(compression::open(path)?)
+}
+
+/// The provenance recorded in the `<meta>` of the file at `path`, reading no further
+/// than the start of the graph.
+pub fn read_provenance(path: &Path) -> Result<Option<Provenance>, Box<dyn Error>> {
+    let mut reader = Reader::from_reader(compression::open(path)?);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(tag) if tag.local_name().as_ref() == b"meta" => return read_meta(&mut reader),
+            Event::Start(tag) | Event::Empty(tag) if tag.local_name().as_ref() == b"graph" => return Ok(None),
+            Event::Eof => return Ok(None),
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+/// Reads up to the end of a `<meta>` element and parses its description as
+/// provenance. Descriptions written by other tools are ignored.
+fn read_meta<R: BufRead>(reader: &mut Reader<R>) -> Result<Option<Provenance>, Box<dyn Error>> {
+    let mut buf = Vec::new();
+    let mut in_description = false;
+    let mut provenance = None;
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(tag) => in_description = tag.local_name().as_ref() == b"description",
+            Event::Text(text) if in_description => provenance = serde_json::from_str(&text.unescape()?).ok(),
+            Event::End(tag) if tag.local_name().as_ref() == b"meta" => return Ok(provenance),
+            Event::End(_) => in_description = false,
+            Event::Eof => return Err("unexpected end of file in <meta>".into()),
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+pub fn parse<R: BufRead>(input: R) -> Result<Graph, Box<dyn Error>> {
+    let mut reader = Reader::from_reader(input);
+    reader.trim_text(true);
+
+    let mut graph = Graph { directed: true, ..Graph::default() };
+    let mut titles: HashMap<(Class, String), String> = HashMap::new();
+    let mut class = Class::Node;
+    let mut current: Option<Element> = None;
+    let mut buf = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(tag) if tag.local_name().as_ref() == b"meta" => {
+                graph.provenance = read_meta(&mut reader)?;
+            }
+            Event::Start(tag) | Event::Empty(tag) => {
+                let attrs = attributes(&tag)?;
+                let get = |key: &str| attrs.get(key).cloned().unwrap_or_default();
+                match tag.local_name().as_ref() {
+                    b"graph" => {
+                        graph.directed = attrs.get("defaultedgetype").map_or(true, |t| t != "undirected");
+                    }
+                    b"attributes" => {
+                        class = if get("class") == "edge" { Class::Edge } else { Class::Node };
+                    }
+                    b"attribute" => {
+                        titles.insert((class, get("id")), get("title"));
+                    }
+                    b"node" => {
+                        let id = get("id");
+                        let label = attrs.get("label").cloned().unwrap_or_else(|| id.clone());
+                        graph.nodes.insert(id.clone(), Node { label, ..Node::default() });
+                        current = Some(Element::Node(id));
+                    }
+                    b"edge" => {
+                        graph.edges.push(Edge {
+                            id: attrs.get("id").cloned().unwrap_or_else(|| graph.edges.len().to_string()),
+                            source: get("source"),
+                            target: get("target"),
+                            weight: attrs.get("weight").and_then(|w| w.parse().ok()),
+                            ..Edge::default()
+                        });
+                        current = Some(Element::Edge(graph.edges.len() - 1));
+                        widen_span(&mut graph, &current, &attrs);
+                    }
+                    b"spell" => widen_span(&mut graph, &current, &attrs),
+                    b"attvalue" => {
+                        let key = get("for");
+                        let value = get("value");
+                        match &current {
+                            Some(Element::Node(id)) => {
+                                let title = titles.get(&(Class::Node, key.clone())).cloned().unwrap_or(key);
+                                if let Some(node) = graph.nodes.get_mut(id) {
+                                    node.attributes.insert(title, value);
+                                }
+                            }
+                            Some(Element::Edge(index)) => {
+                                let title = titles.get(&(Class::Edge, key.clone())).cloned().unwrap_or(key);
+                                graph.edges[*index].attributes.insert(title, value);
+                            }
+                            None => {}
+                        }
+                        widen_span(&mut graph, &current, &attrs);
+                    }
+                    b"color" => {
+                        let color = Color {
+                            r: get("r").parse().unwrap_or(0),
+                            g: get("g").parse().unwrap_or(0),
+                            b: get("b").parse().unwrap_or(0),
+                        };
+                        match &current {
+                            Some(Element::Node(id)) => {
+                                if let Some(node) = graph.nodes.get_mut(id) {
+                                    node.color = Some(color);
+                                }
+                            }
+                            Some(Element::Edge(index)) => graph.edges[*index].color = Some(color),
+                            None => {}
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::End(tag) => {
+                if matches!(tag.local_name().as_ref(), b"node" | b"edge") {
+                    current = None;
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(graph)
+}
+
+/// Extends the time span of the current edge by the `start` and `end` of a tag.
+fn widen_span(graph: &mut Graph, current: &Option<Element>, attrs: &HashMap<String, String>) {
+    if let Some(Element::Edge(index)) = current {
+        let edge = &mut graph.edges[*index];
+        if let Some(start) = attrs.get("start") {
+            widen(&mut edge.start, start, true);
+        }
+        if let Some(end) = attrs.get("end") {
+            widen(&mut edge.end, end, false);
+        }
+    }
+}
+
+pub fn write(graph: &Graph, path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut out = compression::create(path)?;
+    write_to(graph, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Nodes and edges fully written so far, used to report how far an aborted export got.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Written {
+    pub nodes: usize,
+    pub edges: usize,
+}
+
+fn write_meta<W: Write>(out: &mut W, provenance: &Provenance) -> Result<(), Box<dyn Error>> {
+    // the date part of the RFC 3339 timestamp
+    writeln!(out, r#"  <meta lastmodifieddate="{}">"#, escape(provenance.created.get(..10).unwrap_or_default()))?;
+    writeln!(out, "    <creator>{}</creator>", escape(&provenance.tool))?;
+    writeln!(out, "    <description>{}</description>", escape(&serde_json::to_string(provenance)?))?;
+    writeln!(out, "  </meta>")?;
+    Ok(())
+}
+
+/// The `<meta>` and `<graph>` elements open within this many bytes of any GEXF file
+/// pmrs writes.
+const PROLOG_LIMIT: usize = 64 * 1024;
+
+/// Adds `provenance` to the uncompressed GEXF file at `path` as text, leaving the
+/// rest of the file byte for byte as it is. An existing `<meta>` is replaced, as a
+/// graph has at most one. The file is rewritten next to itself and renamed over it.
+pub fn stamp(path: &Path, provenance: &Provenance) -> Result<(), Box<dyn Error>> {
+    let mut input = BufReader::new(File::open(path)?);
+    let mut prolog = Vec::new();
+    (&mut input).take(PROLOG_LIMIT as u64).read_to_end(&mut prolog)?;
+    let (before, after) = match find_element(&prolog, b"meta") {
+        Some(start) => {
+            let end = find(&prolog[start..], b"</meta>").map(|end| start + end + b"</meta>".len());
+            (start, end.ok_or("the <meta> of the graph does not end near the start of the file")?)
+        }
+        None => {
+            let start = find_element(&prolog, b"graph").ok_or("no <graph> element near the start of the file")?;
+            (start, start)
+        }
+    };
+
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+    let staged = tempfile::Builder::new().suffix(".partial").tempfile_in(dir)?;
+    {
+        let mut out = BufWriter::new(staged.as_file());
+        out.write_all(&prolog[..before])?;
+        write_meta(&mut out, provenance)?;
+        out.write_all(&prolog[after..])?;
+        io::copy(&mut input, &mut out)?;
+        out.flush()?;
+    }
+    staged.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Offset of the `<` opening the first element named `name`.
+fn find_element(text: &[u8], name: &[u8]) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(found) = find(&text[offset..], b"<") {
+        let start = offset + found;
+        let rest = &text[start + 1..];
+        if rest.starts_with(name) && rest.get(name.len()).map_or(false, |b| b.is_ascii_whitespace() || *b == b'>' || *b == b'/') {
+            return Some(start);
+        }
+        offset = start + 1;
+    }
+    None
+}
+
+fn find(text: &[u8], needle: &[u8]) -> Option<usize> {
+    text.windows(needle.len()).position(|window| window == needle)
+}
+
+pub fn write_to<W: Write>(graph: &Graph, out: &mut W) -> Result<(), Box<dyn Error>> {
+    write_counted(graph, out, &mut Written::default())
+}
+
+pub fn write_counted<W: Write>(graph: &Graph, out: &mut W, written: &mut Written) -> Result<(), Box<dyn Error>> {
+    let node_attrs = declare(graph.nodes.values().map(|n| &n.attributes));
+    let edge_attrs = declare(graph.edges.iter().map(|e| &e.attributes));
+
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<gexf xmlns="http://gexf.net/1.3" xmlns:viz="http://gexf.net/1.3/viz" version="1.3">"#)?;
+    if let Some(provenance) = &graph.provenance {
+        write_meta(out, provenance)?;
+    }
+    let edge_type = if graph.directed { "directed" } else { "undirected" };
+    let mode = if graph.edges.iter().any(|e| e.start.is_some() || e.end.is_some()) { "dynamic" } else { "static" };
+    writeln!(out, r#"  <graph defaultedgetype="{}" mode="{}">"#, edge_type, mode)?;
+    write_declarations(out, "node", &node_attrs)?;
+    write_declarations(out, "edge", &edge_attrs)?;
+
+    writeln!(out, "    <nodes>")?;
+    for (id, node) in &graph.nodes {
+        writeln!(out, r#"      <node id="{}" label="{}">"#, escape(id), escape(&node.label))?;
+        write_attvalues(out, &node_attrs, &node.attributes)?;
+        write_color(out, node.color)?;
+        writeln!(out, "      </node>")?;
+        written.nodes += 1;
+    }
+    writeln!(out, "    </nodes>")?;
+
+    writeln!(out, "    <edges>")?;
+    for edge in &graph.edges {
+        write!(out, r#"      <edge id="{}" source="{}" target="{}""#, escape(&edge.id), escape(&edge.source), escape(&edge.target))?;
+        if let Some(weight) = edge.weight {
+            write!(out, r#" weight="{}""#, weight)?;
+        }
+        if let Some(start) = &edge.start {
+            write!(out, r#" start="{}""#, escape(start))?;
+        }
+        if let Some(end) = &edge.end {
+            write!(out, r#" end="{}""#, escape(end))?;
+        }
+        writeln!(out, ">")?;
+        write_attvalues(out, &edge_attrs, &edge.attributes)?;
+        write_color(out, edge.color)?;
+        writeln!(out, "      </edge>")?;
+        written.edges += 1;
+    }
+    writeln!(out, "    </edges>")?;
+    writeln!(out, "  </graph>")?;
+    writeln!(out, "</gexf>")?;
+    Ok(())
+}
+
+/// Assigns every attribute key an id and the narrowest GEXF type that fits all of its values.
+pub fn declare<'a>(maps: impl Iterator<Item = &'a IndexMap<String, String>>) -> IndexMap<String, &'static str> {
+    let mut declared: IndexMap<String, &'static str> = IndexMap::new();
+    for map in maps {
+        for (key, value) in map {
+            let found = value_type(value);
+            let entry = declared.entry(key.clone()).or_insert(found);
+            *entry = match (*entry, found) {
+                (a, b) if a == b => a,
+                ("integer", "double") | ("double", "integer") => "double",
+                _ => "string",
+            };
+        }
+    }
+    declared
+}
+
+fn value_type(value: &str) -> &'static str {
+    if value.parse::<i64>().is_ok() {
+        "integer"
+    } else if value.parse::<f64>().is_ok() {
+        "double"
+    } else if value == "true" || value == "false" {
+        "boolean"
+    } else {
+        "string"
+    }
+}
+
+fn write_declarations<W: Write>(out: &mut W, class: &str, declared: &IndexMap<String, &'static str>) -> Result<(), Box<dyn Error>> {
+    if declared.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, r#"    <attributes class="{}">"#, class)?;
+    for (index, (title, kind)) in declared.iter().enumerate() {
+        writeln!(out, r#"      <attribute id="{}" title="{}" type="{}"/>"#, index, escape(title), kind)?;
+    }
+    writeln!(out, "    </attributes>")?;
+    Ok(())
+}
+
+fn write_attvalues<W: Write>(out: &mut W, declared: &IndexMap<String, &'static str>, values: &IndexMap<String, String>) -> Result<(), Box<dyn Error>> {
+    if values.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "        <attvalues>")?;
+    for (key, value) in values {
+        if let Some(index) = declared.get_index_of(key) {
+            writeln!(out, r#"          <attvalue for="{}" value="{}"/>"#, index, escape(value))?;
+        }
+    }
+    writeln!(out, "        </attvalues>")?;
+    Ok(())
+}
+
+fn write_color<W: Write>(out: &mut W, color: Option<Color>) -> Result<(), Box<dyn Error>> {
+    if let Some(Color { r, g, b }) = color {
+        writeln!(out, r#"        <viz:color r="{}" g="{}" b="{}"/>"#, r, g, b)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORTED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gexf xmlns="http://gexf.net/1.3" xmlns:viz="http://gexf.net/1.3/viz" version="1.3">
+  <graph defaultedgetype="undirected">
+    <nodes>
+      <node id="o1" label="o1"><viz:color r="1" g="2" b="3"/><spells><spell start="1"/></spells></node>
+    </nodes>
+  </graph>
+</gexf>
+"#;
+
+    fn provenance() -> Provenance {
+        Provenance {
+            tool: "pmrs-cli test".to_string(),
+            pmrs_version: "0".to_string(),
+            command: vec!["pmrs-cli".to_string()],
+            input: "log.jsonocel".to_string(),
+            input_sha256: "00".to_string(),
+            created: "2023-01-01T00:00:00+00:00".to_string(),
+            relations: vec!["interaction".to_string()],
+        }
+    }
+
+    #[test]
+    fn stamp_keeps_the_pmrs_export() {
+        let file = tempfile::Builder::new().suffix(".gexf").tempfile().unwrap();
+        std::fs::write(file.path(), EXPORTED).unwrap();
+        stamp(file.path(), &provenance()).unwrap();
+
+        let stamped = std::fs::read_to_string(file.path()).unwrap();
+        let graph_at = EXPORTED.find("<graph").unwrap();
+        assert!(stamped.starts_with(&EXPORTED[..graph_at]));
+        assert!(stamped.ends_with(&EXPORTED[graph_at..]));
+        assert_eq!(read_provenance(file.path()).unwrap(), Some(provenance()));
+    }
+
+    #[test]
+    fn stamp_replaces_an_existing_meta() {
+        let file = tempfile::Builder::new().suffix(".gexf").tempfile().unwrap();
+        std::fs::write(file.path(), EXPORTED.replace("  <graph", "  <meta><creator>pmrs</creator></meta>\n  <graph")).unwrap();
+        stamp(file.path(), &provenance()).unwrap();
+
+        let stamped = std::fs::read_to_string(file.path()).unwrap();
+        assert_eq!(stamped.matches("<meta").count(), 1);
+        assert_eq!(read_provenance(file.path()).unwrap(), Some(provenance()));
+    }
+}
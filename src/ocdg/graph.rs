@@ -0,0 +1,395 @@
+//! A plain, serialization-oriented view of an OCDG. pmrs only reads and writes GEXF,
+//! so every command that inspects or reshapes an existing graph works on this model
+//! instead and writes it back out itself.
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::path::Path;
+
+use indexmap::IndexMap;
+use log::warn;
+use pmrs::objects::ocdg::Relations;
+use strum::IntoEnumIterator;
+
+use super::formats::{self, GraphFormat};
+use super::gexf;
+use super::provenance::Provenance;
+
+/// Node attribute holding the object type.
+pub const TYPE_KEY: &str = "type";
+/// Edge attribute holding a comma separated list of relation names.
+pub const RELATION_KEY: &str = "relation";
+
+#[derive(Debug, Clone, Default)]
+pub struct Graph {
+    pub directed: bool,
+    pub nodes: IndexMap<String, Node>,
+    pub edges: Vec<Edge>,
+    /// How the graph was produced, if the file records it
+    pub provenance: Option<Provenance>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Node {
+    pub label: String,
+    pub attributes: IndexMap<String, String>,
+    pub color: Option<Color>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Edge {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    pub weight: Option<f64>,
+    pub attributes: IndexMap<String, String>,
+    pub color: Option<Color>,
+    /// Earliest and latest time the edge is present, from GEXF `start` and `end`
+    /// on the edge, its spells or its attribute values
+    pub start: Option<String>,
+    pub end: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Node {
+    pub fn obj_type(&self) -> Option<&str> {
+        self.attributes.get(TYPE_KEY).map(String::as_str)
+    }
+}
+
+impl Edge {
+    /// The pmrs relations this edge stands for, in `Relations` order. They are read
+    /// from a `relation` attribute as well as from attributes named after a relation.
+    pub fn relations(&self) -> Vec<String> {
+        relation_names()
+            .into_iter()
+            .filter(|name| {
+                self.attributes.iter().any(|(key, value)| {
+                    (key.eq_ignore_ascii_case(name) && !value.is_empty())
+                        || (key == RELATION_KEY && value.split(',').any(|v| v.trim().eq_ignore_ascii_case(name)))
+                })
+            })
+            .collect()
+    }
+}
+
+/// Widens a time bound to include `time`, keeping the earlier one if `earliest` is
+/// set and the later one otherwise. Times compare numerically if both are numbers.
+pub fn widen(bound: &mut Option<String>, time: &str, earliest: bool) {
+    let replace = match bound.as_deref() {
+        None => true,
+        Some(current) => {
+            let ordering = match (current.parse::<f64>(), time.parse::<f64>()) {
+                (Ok(a), Ok(b)) => b.partial_cmp(&a).unwrap_or(Ordering::Equal),
+                _ => time.cmp(current),
+            };
+            ordering == if earliest { Ordering::Less } else { Ordering::Greater }
+        }
+    };
+    if replace {
+        *bound = Some(time.to_string());
+    }
+}
+
+impl Color {
+    /// Parses `#rrggbb` or `rrggbb`.
+    pub fn from_hex(hex: &str) -> Option<Color> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).ok();
+        Some(Color { r: channel(0)?, g: channel(2)?, b: channel(4)? })
+    }
+}
+
+/// Names of all pmrs relations as spelled by their `Debug` representation.
+pub fn relation_names() -> Vec<String> {
+    Relations::iter().map(|relation| format!("{:?}", relation)).collect()
+}
+
+impl Graph {
+    pub fn import(path: &Path) -> Result<Graph, Box<dyn Error>> {
+        let graph = gexf::read(path)?;
+        if graph.untyped() {
+            warn!("No node of {:?} has a {:?} attribute, the object types are unknown.", path, TYPE_KEY);
+        }
+        Ok(graph)
+    }
+
+    /// Writes the graph in the format implied by the extension of `path`, GEXF if
+    /// there is none.
+    pub fn export(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        formats::write(self, path, GraphFormat::resolve(None, path))
+    }
+
+    pub fn obj_type(&self, node: &str) -> Option<&str> {
+        self.nodes.get(node).and_then(Node::obj_type)
+    }
+
+    /// Whether the graph has nodes but none of them has an object type.
+    pub fn untyped(&self) -> bool {
+        !self.nodes.is_empty() && self.nodes.values().all(|node| node.obj_type().is_none())
+    }
+
+    /// Number of edges entering and leaving each node, as `(in, out)`.
+    pub fn degrees(&self) -> HashMap<&str, (usize, usize)> {
+        let mut degrees: HashMap<&str, (usize, usize)> =
+            self.nodes.keys().map(|id| (id.as_str(), (0, 0))).collect();
+        for edge in &self.edges {
+            degrees.entry(&edge.source).or_default().1 += 1;
+            degrees.entry(&edge.target).or_default().0 += 1;
+        }
+        degrees
+    }
+
+    /// Keeps the nodes matching `keep` and drops every edge touching a removed node.
+    pub fn retain_nodes(&mut self, mut keep: impl FnMut(&str, &Node) -> bool) {
+        self.nodes.retain(|id, node| keep(id, node));
+        let nodes = &self.nodes;
+        self.edges.retain(|edge| nodes.contains_key(&edge.source) && nodes.contains_key(&edge.target));
+    }
+
+    /// Outgoing neighbours of every node together with the index of the connecting
+    /// edge. Edges are traversed both ways unless the graph is directed and
+    /// `follow_direction` is set.
+    pub fn adjacency(&self, follow_direction: bool) -> HashMap<&str, Vec<(&str, usize)>> {
+        let mut adjacency: HashMap<&str, Vec<(&str, usize)>> = HashMap::new();
+        for (index, edge) in self.edges.iter().enumerate() {
+            adjacency.entry(&edge.source).or_default().push((&edge.target, index));
+            if !(self.directed && follow_direction) {
+                adjacency.entry(&edge.target).or_default().push((&edge.source, index));
+            }
+        }
+        adjacency
+    }
+
+    /// Hop distance from the nearest seed for every node within `max_hops`, ignoring
+    /// edge direction.
+    pub fn distances_from(&self, seeds: &[&str], max_hops: usize) -> HashMap<String, usize> {
+        let adjacency = self.adjacency(false);
+        let mut distances: HashMap<String, usize> = HashMap::new();
+        let mut queue: VecDeque<(&str, usize)> = VecDeque::new();
+        for seed in seeds {
+            if self.nodes.contains_key(*seed) && !distances.contains_key(*seed) {
+                distances.insert(seed.to_string(), 0);
+                queue.push_back((*seed, 0));
+            }
+        }
+        while let Some((node, distance)) = queue.pop_front() {
+            if distance == max_hops {
+                continue;
+            }
+            for &(next, _) in adjacency.get(node).into_iter().flatten() {
+                if !distances.contains_key(next) {
+                    distances.insert(next.to_string(), distance + 1);
+                    queue.push_back((next, distance + 1));
+                }
+            }
+        }
+        distances
+    }
+
+    /// Weakly connected components, largest first. Isolated nodes form components
+    /// of their own.
+    pub fn components(&self) -> Vec<Vec<&str>> {
+        let adjacency = self.adjacency(false);
+        let mut seen: HashSet<&str> = HashSet::new();
+        let mut components = Vec::new();
+        for start in self.nodes.keys() {
+            if !seen.insert(start) {
+                continue;
+            }
+            let mut component = Vec::new();
+            let mut stack = vec![start.as_str()];
+            while let Some(node) = stack.pop() {
+                component.push(node);
+                for &(next, _) in adjacency.get(node).into_iter().flatten() {
+                    if seen.insert(next) {
+                        stack.push(next);
+                    }
+                }
+            }
+            components.push(component);
+        }
+        components.sort_by(|a, b| b.len().cmp(&a.len()));
+        components
+    }
+
+    /// Strongly connected components after Tarjan, largest first. Undirected graphs
+    /// have the same strong and weak components.
+    pub fn strong_components(&self) -> Vec<Vec<&str>> {
+        if !self.directed {
+            return self.components();
+        }
+        let adjacency = self.adjacency(true);
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        let mut lowlink: HashMap<&str, usize> = HashMap::new();
+        let mut on_stack: HashSet<&str> = HashSet::new();
+        let mut stack: Vec<&str> = Vec::new();
+        let mut components = Vec::new();
+
+        for root in self.nodes.keys() {
+            if index.contains_key(root.as_str()) {
+                continue;
+            }
+            // explicit call stack of (node, position in its adjacency list)
+            let mut calls: Vec<(&str, usize)> = vec![(root.as_str(), 0)];
+            while let Some(&mut (node, ref mut position)) = calls.last_mut() {
+                if *position == 0 && !index.contains_key(node) {
+                    index.insert(node, index.len());
+                    lowlink.insert(node, index[node]);
+                    stack.push(node);
+                    on_stack.insert(node);
+                }
+                let neighbours = adjacency.get(node).map_or(&[][..], Vec::as_slice);
+                if let Some(&(next, _)) = neighbours.get(*position) {
+                    *position += 1;
+                    if !index.contains_key(next) {
+                        calls.push((next, 0));
+                    } else if on_stack.contains(next) {
+                        let low = lowlink[node].min(index[next]);
+                        lowlink.insert(node, low);
+                    }
+                    continue;
+                }
+                calls.pop();
+                if let Some(&(parent, _)) = calls.last() {
+                    let low = lowlink[parent].min(lowlink[node]);
+                    lowlink.insert(parent, low);
+                }
+                if lowlink[node] == index[node] {
+                    let mut component = Vec::new();
+                    while let Some(member) = stack.pop() {
+                        on_stack.remove(member);
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+        components.sort_by(|a, b| b.len().cmp(&a.len()));
+        components
+    }
+
+    /// Relabels the graph deterministically: nodes are ordered by object type, then
+    /// descending degree, then original id, and renamed `n0, n1, ...` with the
+    /// original id kept as label. Edges are ordered by their renamed endpoints and
+    /// renamed `e0, e1, ...`. Structurally identical graphs thus export identically.
+    pub fn canonicalize(&mut self) {
+        let degrees = self.degrees();
+        let mut order: Vec<String> = self.nodes.keys().cloned().collect();
+        order.sort_by(|a, b| {
+            let degree = |id: &str| degrees.get(id).map_or(0, |(i, o)| i + o);
+            self.obj_type(a)
+                .cmp(&self.obj_type(b))
+                .then_with(|| degree(b).cmp(&degree(a)))
+                .then_with(|| a.cmp(b))
+        });
+
+        let renamed: HashMap<String, String> =
+            order.iter().enumerate().map(|(i, id)| (id.clone(), format!("n{}", i))).collect();
+        let mut old_nodes = std::mem::take(&mut self.nodes);
+        for id in &order {
+            if let Some(mut node) = old_nodes.remove(id) {
+                node.label = id.clone();
+                self.nodes.insert(renamed[id].clone(), node);
+            }
+        }
+
+        for edge in &mut self.edges {
+            if let Some(source) = renamed.get(&edge.source) {
+                edge.source = source.clone();
+            }
+            if let Some(target) = renamed.get(&edge.target) {
+                edge.target = target.clone();
+            }
+        }
+        let position = |id: &str| self.nodes.get_index_of(id).unwrap_or(usize::MAX);
+        let mut edges = std::mem::take(&mut self.edges);
+        edges.sort_by_cached_key(|edge| (position(&edge.source), position(&edge.target), edge.relations()));
+        for (i, edge) in edges.iter_mut().enumerate() {
+            edge.id = format!("e{}", i);
+        }
+        self.edges = edges;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(obj_type: Option<&str>) -> Node {
+        let mut node = Node::default();
+        if let Some(obj_type) = obj_type {
+            node.attributes.insert(TYPE_KEY.to_string(), obj_type.to_string());
+        }
+        node
+    }
+
+    #[test]
+    fn reads_relations_from_the_relation_attribute() {
+        let names = relation_names();
+        let mut edge = Edge::default();
+        edge.attributes.insert(RELATION_KEY.to_string(), format!("{}, {}", names[1].to_lowercase(), names[0]));
+        assert_eq!(edge.relations(), [names[0].clone(), names[1].clone()]);
+
+        // the node type key does not name relations on edges
+        let mut typed = Edge::default();
+        typed.attributes.insert(TYPE_KEY.to_string(), names[0].clone());
+        assert!(typed.relations().is_empty());
+    }
+
+    #[test]
+    fn canonicalizes_differently_ordered_graphs_alike() {
+        let build = |nodes: &[(&str, &str)], edges: &[(&str, &str, &str)]| {
+            let mut graph = Graph::default();
+            for (id, obj_type) in nodes {
+                graph.nodes.insert(id.to_string(), node(Some(obj_type)));
+            }
+            for (id, source, target) in edges {
+                graph.edges.push(Edge { id: id.to_string(), source: source.to_string(), target: target.to_string(), ..Edge::default() });
+            }
+            graph
+        };
+        let mut first = build(&[("o1", "order"), ("i1", "item"), ("i2", "item")], &[("x", "o1", "i1"), ("y", "o1", "i2")]);
+        let mut second = build(&[("i2", "item"), ("o1", "order"), ("i1", "item")], &[("b", "o1", "i2"), ("a", "o1", "i1")]);
+        first.canonicalize();
+        second.canonicalize();
+
+        let layout = |graph: &Graph| {
+            let nodes: Vec<(String, String)> = graph.nodes.iter().map(|(id, node)| (id.clone(), node.label.clone())).collect();
+            let edges: Vec<(String, String, String)> =
+                graph.edges.iter().map(|edge| (edge.id.clone(), edge.source.clone(), edge.target.clone())).collect();
+            (nodes, edges)
+        };
+        assert_eq!(layout(&first), layout(&second));
+        // items come before orders, ties by original id
+        assert_eq!(first.nodes.values().map(|node| node.label.as_str()).collect::<Vec<_>>(), ["i1", "i2", "o1"]);
+    }
+
+    #[test]
+    fn imports_untyped_graphs() {
+        let mut graph = Graph::default();
+        graph.nodes.insert("o1".to_string(), node(None));
+        graph.nodes.insert("o2".to_string(), node(None));
+        let file = tempfile::Builder::new().suffix(".gexf").tempfile().unwrap();
+        graph.export(file.path()).unwrap();
+        let imported = Graph::import(file.path()).unwrap();
+        assert_eq!(imported.nodes.len(), 2);
+        assert!(imported.untyped());
+
+        graph.nodes.insert("o3".to_string(), node(Some("order")));
+        assert!(!graph.untyped());
+        assert!(!Graph::default().untyped());
+    }
+}
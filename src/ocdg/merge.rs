@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use log::{debug, warn};
+
+use super::formats::{self, GraphFormat};
+use super::graph::{Edge, Graph};
+
+#[derive(Args, Debug)]
+pub struct OcdgMerge {
+    /// Paths to the OCDG files to merge
+    #[clap(required = true, min_values = 2)]
+    pub paths: Vec<PathBuf>,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// Graph format to write. Inferred from the output extension if unset, GEXF
+    /// otherwise
+    #[clap(long, value_enum)]
+    pub format: Option<GraphFormat>,
+
+    /// How the weights of an edge present in several graphs are combined
+    #[clap(long, value_enum, default_value_t = WeightMerge::Sum)]
+    pub weights: WeightMerge,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WeightMerge {
+    /// Add the weights up
+    Sum,
+    /// Keep the largest weight
+    Max,
+}
+
+/// Edges are the same if they connect the same nodes through the same relations.
+/// Endpoints of undirected edges are ordered so that both orientations match.
+fn edge_key(edge: &Edge, directed: bool) -> (String, String, Vec<String>) {
+    let (source, target) = if !directed && edge.source > edge.target {
+        (edge.target.clone(), edge.source.clone())
+    } else {
+        (edge.source.clone(), edge.target.clone())
+    };
+    (source, target, edge.relations())
+}
+
+pub fn run(merge: &OcdgMerge) -> Result<(), Box<dyn Error>> {
+    let (first, rest) = merge.paths.split_first().ok_or("no graphs to merge")?;
+    debug!("Importing {:?}", first);
+    let mut merged = Graph::import(first)?;
+    let mut edge_index: HashMap<(String, String, Vec<String>), usize> = HashMap::new();
+    for (index, edge) in merged.edges.iter().enumerate() {
+        edge_index.entry(edge_key(edge, merged.directed)).or_insert(index);
+    }
+
+    for path in rest {
+        debug!("Importing {:?}", path);
+        let graph = Graph::import(path)?;
+        if graph.directed != merged.directed {
+            let kind = if merged.directed { "directed" } else { "undirected" };
+            warn!("{:?} differs in edge direction from the first graph, its edges are merged as {}", path, kind);
+        }
+
+        let (mut nodes_added, mut edges_added) = (0, 0);
+        for (id, node) in graph.nodes {
+            match merged.nodes.get_mut(&id) {
+                Some(existing) => {
+                    for (key, value) in node.attributes {
+                        existing.attributes.entry(key).or_insert(value);
+                    }
+                    existing.color = existing.color.or(node.color);
+                }
+                None => {
+                    merged.nodes.insert(id, node);
+                    nodes_added += 1;
+                }
+            }
+        }
+        for edge in graph.edges {
+            let key = edge_key(&edge, merged.directed);
+            match edge_index.get(&key) {
+                Some(&index) => {
+                    let existing = &mut merged.edges[index];
+                    existing.weight = match (existing.weight, edge.weight) {
+                        (Some(a), Some(b)) if merge.weights == WeightMerge::Sum => Some(a + b),
+                        (Some(a), Some(b)) => Some(a.max(b)),
+                        (a, b) => a.or(b),
+                    };
+                }
+                None => {
+                    edge_index.insert(key, merged.edges.len());
+                    merged.edges.push(edge);
+                    edges_added += 1;
+                }
+            }
+        }
+        status!("{}: {} new nodes, {} new edges", path.display(), nodes_added, edges_added);
+    }
+
+    // edge ids of different graphs collide, so they are renumbered
+    for (index, edge) in merged.edges.iter_mut().enumerate() {
+        edge.id = index.to_string();
+    }
+
+    let format = GraphFormat::resolve(merge.format, &merge.output);
+    debug!("Writing the merged graph to {:?} as {:?}", merge.output, format);
+    formats::write(&merged, &merge.output, format)?;
+    status!("merged {} nodes and {} edges -> {}", merged.nodes.len(), merged.edges.len(), merge.output.display());
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use clap::Args;
+use log::debug;
+
+use super::centrality::{self, Centrality};
+use super::graph::Graph;
+use crate::compression;
+
+#[derive(Args, Debug)]
+pub struct OcdgMetrics {
+    /// Path to OCDG file
+    pub path: PathBuf,
+
+    /// Write the CSV to this file instead of standard output
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Leave out betweenness, which takes time quadratic in the graph size
+    #[clap(long)]
+    pub skip_betweenness: bool,
+}
+
+pub fn run(metrics: &OcdgMetrics) -> Result<(), Box<dyn Error>> {
+    debug!("Importing {:?}", metrics.path);
+    let graph = Graph::import(&metrics.path)?;
+
+    let degrees = graph.degrees();
+    debug!("Computing PageRank");
+    let pagerank = centrality::scores(&graph, Centrality::Pagerank);
+    let betweenness = if metrics.skip_betweenness {
+        HashMap::new()
+    } else {
+        debug!("Computing betweenness");
+        centrality::scores(&graph, Centrality::Betweenness)
+    };
+    let clustering = centrality::clustering(&graph);
+    let mut component_size: HashMap<&str, usize> = HashMap::new();
+    for component in graph.components() {
+        for id in &component {
+            component_size.insert(*id, component.len());
+        }
+    }
+
+    let out: Box<dyn Write> = match &metrics.output {
+        Some(path) => compression::create(path)?,
+        None => Box::new(io::stdout()),
+    };
+    let mut writer = csv::Writer::from_writer(out);
+    let mut header = vec!["id", "type", "degree", "in_degree", "out_degree", "pagerank", "clustering", "component_size"];
+    if !metrics.skip_betweenness {
+        header.insert(5, "betweenness");
+    }
+    writer.write_record(&header)?;
+    for (id, node) in &graph.nodes {
+        let (incoming, outgoing) = degrees.get(id.as_str()).copied().unwrap_or_default();
+        let mut record = vec![
+            id.clone(),
+            node.obj_type().unwrap_or("").to_string(),
+            (incoming + outgoing).to_string(),
+            incoming.to_string(),
+            outgoing.to_string(),
+            pagerank.get(id.as_str()).copied().unwrap_or_default().to_string(),
+            clustering.get(id.as_str()).copied().unwrap_or_default().to_string(),
+            component_size.get(id.as_str()).copied().unwrap_or(1).to_string(),
+        ];
+        if !metrics.skip_betweenness {
+            record.insert(5, betweenness.get(id.as_str()).copied().unwrap_or_default().to_string());
+        }
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
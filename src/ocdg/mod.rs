@@ -0,0 +1,23 @@
+pub mod cache;
+pub mod centrality;
+pub mod components;
+pub mod convert;
+pub mod decompose;
+pub mod diff;
+pub mod edges;
+pub mod filter;
+pub mod formats;
+pub mod generate;
+pub mod gexf;
+pub mod graph;
+pub mod merge;
+pub mod metrics;
+pub mod neighbors;
+pub mod palette;
+pub mod path;
+pub mod provenance;
+pub mod relations;
+pub mod render;
+pub mod stats;
+pub mod subgraph;
+pub mod verify;
@@ -0,0 +1,90 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use log::debug;
+
+use super::graph::Graph;
+
+#[derive(Args, Debug)]
+pub struct OcdgNeighbors {
+    /// Path to OCDG file
+    pub path: PathBuf,
+
+    /// Id of the focal object
+    #[clap(long)]
+    pub object: String,
+
+    /// Include objects up to this many hops away
+    #[clap(long, alias = "depth", default_value_t = 1)]
+    pub hops: usize,
+
+    /// Write the neighbourhood, flagging the focal node with `focal=true` and
+    /// storing each node's hop distance as `ring`. The format follows the extension
+    #[clap(long, value_name = "PATH")]
+    pub export: Option<PathBuf>,
+}
+
+pub fn run(neighbors: &OcdgNeighbors) -> Result<(), Box<dyn Error>> {
+    debug!("Importing {:?}", neighbors.path);
+    let graph = Graph::import(&neighbors.path)?;
+    if !graph.nodes.contains_key(&neighbors.object) {
+        return Err(format!("object {:?} is not part of the graph", neighbors.object).into());
+    }
+
+    let rings = graph.distances_from(&[neighbors.object.as_str()], neighbors.hops);
+    let mut subgraph = graph.clone();
+    subgraph.retain_nodes(|id, _| rings.contains_key(id));
+
+    match &neighbors.export {
+        Some(path) => {
+            for (id, node) in subgraph.nodes.iter_mut() {
+                node.attributes.insert("focal".to_string(), (*id == neighbors.object).to_string());
+                node.attributes.insert("ring".to_string(), rings[id].to_string());
+            }
+            debug!("Exporting the neighbourhood to {:?}", path);
+            subgraph.export(path)?;
+            status!("exported {} nodes and {} edges to {:?}", subgraph.nodes.len(), subgraph.edges.len(), path);
+        }
+        None => print_summary(&subgraph, &neighbors.object, &rings),
+    }
+    Ok(())
+}
+
+fn print_summary(subgraph: &Graph, focal: &str, rings: &HashMap<String, usize>) {
+    let mut by_ring: BTreeMap<usize, Vec<&str>> = BTreeMap::new();
+    for id in subgraph.nodes.keys() {
+        by_ring.entry(rings[id]).or_default().push(id);
+    }
+    println!("{} ({})", focal, subgraph.obj_type(focal).unwrap_or("unknown"));
+    for (ring, ids) in by_ring.iter().skip(1) {
+        println!("  ring {}: {} objects", ring, ids.len());
+        for id in ids {
+            let via = inward_relations(subgraph, id, rings);
+            println!("    {} ({}) via {}", id, subgraph.obj_type(id).unwrap_or("unknown"), via.join(", "));
+        }
+    }
+    println!("  edges within the neighbourhood: {}", subgraph.edges.len());
+}
+
+/// Relations of the edges connecting `id` to nodes one ring closer to the focal node.
+fn inward_relations(subgraph: &Graph, id: &str, rings: &HashMap<String, usize>) -> Vec<String> {
+    let inner = rings[id] - 1;
+    let mut relations: BTreeSet<String> = BTreeSet::new();
+    for edge in &subgraph.edges {
+        let other = match (edge.source == id, edge.target == id) {
+            (true, _) => &edge.target,
+            (_, true) => &edge.source,
+            _ => continue,
+        };
+        if rings.get(other) == Some(&inner) {
+            let found = edge.relations();
+            if found.is_empty() {
+                relations.insert("unknown".to_string());
+            }
+            relations.extend(found);
+        }
+    }
+    relations.into_iter().collect()
+}
@@ -0,0 +1,47 @@
+//! Edge colors per relation, so the same relation keeps the same color across graphs.
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use super::graph::{relation_names, Color, Graph};
+
+/// Colors for relations without an explicit mapping, assigned in `Relations` order.
+const DEFAULT_PALETTE: [&str; 12] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b",
+    "#e377c2", "#7f7f7f", "#bcbd22", "#17becf", "#393b79", "#637939",
+];
+
+/// Reads a JSON object mapping relation names to hex colors, e.g.
+/// `{"DESCENDANTS": "#1f77b4"}`. Unknown relations and malformed colors are errors.
+pub fn load_color_map(path: &Path) -> Result<HashMap<String, Color>, Box<dyn Error>> {
+    let raw: HashMap<String, String> = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    let known = relation_names();
+    let mut colors = HashMap::new();
+    for (relation, hex) in raw {
+        let name = known
+            .iter()
+            .find(|name| name.eq_ignore_ascii_case(&relation))
+            .ok_or_else(|| format!("unknown relation {:?} in color map. Known relations: {}", relation, known.join(", ")))?;
+        let color = Color::from_hex(&hex).ok_or_else(|| format!("invalid color {:?} for relation {}", hex, relation))?;
+        colors.insert(name.clone(), color);
+    }
+    Ok(colors)
+}
+
+/// The default palette color of the `index`-th category, repeating after twelve.
+pub fn nth(index: usize) -> Color {
+    Color::from_hex(DEFAULT_PALETTE[index % DEFAULT_PALETTE.len()]).expect("the default palette is valid")
+}
+
+/// Colors every edge after its first relation, falling back to the default palette.
+pub fn apply(graph: &mut Graph, colors: &HashMap<String, Color>) {
+    let names = relation_names();
+    for edge in &mut graph.edges {
+        if let Some(relation) = edge.relations().into_iter().next() {
+            let default = names.iter().position(|name| *name == relation).map(nth);
+            edge.color = colors.get(&relation).copied().or(default);
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use log::debug;
+
+use super::graph::Graph;
+
+#[derive(Args, Debug)]
+pub struct OcdgPath {
+    /// Path to OCDG file
+    pub path: PathBuf,
+
+    /// Object id to start from
+    pub from: String,
+
+    /// Object id to reach
+    pub to: String,
+
+    /// Find the cheapest path by edge weight (Dijkstra) instead of the one with the
+    /// fewest hops (BFS). Edges without a weight count as weight 1
+    #[clap(long)]
+    pub weighted: bool,
+
+    /// How edge weights translate into costs with --weighted
+    #[clap(long, value_enum, default_value_t = CostMode::Weight, requires = "weighted")]
+    pub cost_mode: CostMode,
+
+    /// Traverse edges in both directions even in a directed graph
+    #[clap(long)]
+    pub undirected: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CostMode {
+    /// The weight is the cost of an edge: the path minimizes the summed weights
+    Weight,
+    /// The cost of an edge is 1/weight: the path follows the strongest relations
+    Inverse,
+}
+
+pub fn run(args: &OcdgPath) -> Result<(), Box<dyn Error>> {
+    debug!("Importing {:?}", args.path);
+    let graph = Graph::import(&args.path)?;
+    for id in [&args.from, &args.to] {
+        if !graph.nodes.contains_key(id) {
+            return Err(format!("object {:?} is not part of the graph", id).into());
+        }
+    }
+
+    let adjacency = graph.adjacency(!args.undirected);
+    let found = if args.weighted {
+        dijkstra(&graph, &adjacency, &args.from, &args.to, args.cost_mode)
+    } else {
+        bfs(&adjacency, &args.from, &args.to)
+    };
+
+    let hops = match found {
+        Some(hops) => hops,
+        None => {
+            println!("no path from {} to {}", args.from, args.to);
+            return Ok(());
+        }
+    };
+
+    println!("{}", args.from);
+    let mut cost = 0.0;
+    for (node, edge) in &hops {
+        let edge = &graph.edges[*edge];
+        cost += edge_cost(edge.weight, args.cost_mode);
+        println!("  {}", hop_line(&graph, node, *edge));
+    }
+    if args.weighted {
+        println!("hops: {}, cost: {}", hops.len(), cost);
+    } else {
+        println!("hops: {}", hops.len());
+    }
+    Ok(())
+}
+
+/// One hop to `node` over edge `edge`, with the arrow against the direction of the
+/// edge if a directed graph was walked from its target to its source.
+fn hop_line(graph: &Graph, node: &str, edge: usize) -> String {
+    let edge = &graph.edges[edge];
+    let relations = edge.relations().join(",");
+    if graph.directed && edge.target != node {
+        format!("<-[{}]- {}", relations, node)
+    } else {
+        format!("-[{}]-> {}", relations, node)
+    }
+}
+
+fn edge_cost(weight: Option<f64>, mode: CostMode) -> f64 {
+    let weight = weight.unwrap_or(1.0);
+    match mode {
+        CostMode::Weight => weight,
+        CostMode::Inverse if weight > 0.0 => 1.0 / weight,
+        CostMode::Inverse => f64::INFINITY,
+    }
+}
+
+type Hops<'a> = Vec<(&'a str, usize)>;
+
+/// Walks the predecessor map back from `to`, returning `(node, edge)` hops from `from`.
+fn unwind<'a>(previous: &HashMap<&'a str, (&'a str, usize)>, from: &str, to: &'a str) -> Hops<'a> {
+    let mut hops = Vec::new();
+    let mut current = to;
+    while current != from {
+        let (prev, edge) = previous[current];
+        hops.push((current, edge));
+        current = prev;
+    }
+    hops.reverse();
+    hops
+}
+
+fn bfs<'a>(adjacency: &HashMap<&'a str, Hops<'a>>, from: &'a str, to: &'a str) -> Option<Hops<'a>> {
+    let mut previous: HashMap<&str, (&str, usize)> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    while let Some(node) = queue.pop_front() {
+        if node == to {
+            return Some(unwind(&previous, from, to));
+        }
+        for &(next, edge) in adjacency.get(node).into_iter().flatten() {
+            if next != from && !previous.contains_key(next) {
+                previous.insert(next, (node, edge));
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+#[derive(PartialEq)]
+struct Candidate<'a> {
+    cost: f64,
+    node: &'a str,
+}
+
+impl Eq for Candidate<'_> {}
+
+impl Ord for Candidate<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed so the BinaryHeap pops the cheapest candidate first
+        other.cost.total_cmp(&self.cost).then_with(|| other.node.cmp(self.node))
+    }
+}
+
+impl PartialOrd for Candidate<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn dijkstra<'a>(graph: &Graph, adjacency: &HashMap<&'a str, Hops<'a>>, from: &'a str, to: &'a str, mode: CostMode) -> Option<Hops<'a>> {
+    let mut best: HashMap<&str, f64> = HashMap::from([(from, 0.0)]);
+    let mut previous: HashMap<&str, (&str, usize)> = HashMap::new();
+    let mut heap = BinaryHeap::from([Candidate { cost: 0.0, node: from }]);
+
+    while let Some(Candidate { cost, node }) = heap.pop() {
+        if node == to {
+            return Some(unwind(&previous, from, to));
+        }
+        if cost > best.get(node).copied().unwrap_or(f64::INFINITY) {
+            continue;
+        }
+        for &(next, edge) in adjacency.get(node).into_iter().flatten() {
+            let next_cost = cost + edge_cost(graph.edges[edge].weight, mode);
+            if next_cost < best.get(next).copied().unwrap_or(f64::INFINITY) {
+                best.insert(next, next_cost);
+                previous.insert(next, (node, edge));
+                heap.push(Candidate { cost: next_cost, node: next });
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocdg::graph::{Edge, Node};
+
+    /// a -> b <- c and the detour a -> d -> e -> c, weighted 5 on the short way.
+    fn graph() -> Graph {
+        let mut graph = Graph { directed: true, ..Graph::default() };
+        for id in ["a", "b", "c", "d", "e"] {
+            graph.nodes.insert(id.to_string(), Node::default());
+        }
+        for (source, target, weight) in [("a", "b", 5.0), ("c", "b", 5.0), ("a", "d", 1.0), ("d", "e", 1.0), ("e", "c", 1.0)] {
+            graph.edges.push(Edge { source: source.to_string(), target: target.to_string(), weight: Some(weight), ..Edge::default() });
+        }
+        graph
+    }
+
+    fn nodes<'a>(hops: &[(&'a str, usize)]) -> Vec<&'a str> {
+        hops.iter().map(|(node, _)| *node).collect()
+    }
+
+    #[test]
+    fn finds_the_fewest_hops() {
+        let graph = graph();
+        let directed = graph.adjacency(true);
+        assert_eq!(nodes(&bfs(&directed, "a", "c").unwrap()), ["d", "e", "c"]);
+        assert_eq!(bfs(&directed, "c", "a"), None);
+
+        let undirected = graph.adjacency(false);
+        let hops = bfs(&undirected, "a", "c").unwrap();
+        assert_eq!(nodes(&hops), ["b", "c"]);
+        let lines: Vec<String> = hops.iter().map(|(node, edge)| hop_line(&graph, node, *edge)).collect();
+        assert_eq!(lines, ["-[]-> b", "<-[]- c"]);
+    }
+
+    #[test]
+    fn finds_the_cheapest_path() {
+        let graph = graph();
+        let undirected = graph.adjacency(false);
+        assert_eq!(nodes(&dijkstra(&graph, &undirected, "a", "c", CostMode::Weight).unwrap()), ["d", "e", "c"]);
+        // strong edges are cheap when costs are inverted
+        assert_eq!(nodes(&dijkstra(&graph, &undirected, "a", "c", CostMode::Inverse).unwrap()), ["b", "c"]);
+        assert_eq!(edge_cost(Some(0.0), CostMode::Inverse), f64::INFINITY);
+    }
+}
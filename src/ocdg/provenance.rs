@@ -0,0 +1,54 @@
+//! How a graph file was produced, embedded in the GEXF `<meta>` description and
+//! under `provenance` in JSON so that `pmrs-cli inspect` can read it back.
+use std::env;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use chrono::Utc;
+use pmrs::objects::ocdg::Relations;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::stdio;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Provenance {
+    /// `pmrs-cli` with its version
+    pub tool: String,
+    pub pmrs_version: String,
+    /// The command line, starting with the program
+    pub command: Vec<String>,
+    pub input: String,
+    pub input_sha256: String,
+    /// RFC 3339 time of generation, in UTC
+    pub created: String,
+    pub relations: Vec<String>,
+}
+
+impl Provenance {
+    /// The provenance of generating `relations` from the log at `input` in this run.
+    pub fn record(input: &Path, relations: &[Relations]) -> io::Result<Provenance> {
+        let mut hasher = Sha256::new();
+        if stdio::is_stdio(input) {
+            io::copy(&mut stdio::stdin()?, &mut hasher)?;
+        } else {
+            io::copy(&mut File::open(input)?, &mut hasher)?;
+        }
+        Ok(Provenance {
+            tool: format!("pmrs-cli {}", env!("CARGO_PKG_VERSION")),
+            pmrs_version: env!("PMRS_VERSION").to_string(),
+            command: env::args().collect(),
+            input: input.to_string_lossy().into_owned(),
+            input_sha256: hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect(),
+            created: Utc::now().to_rfc3339(),
+            relations: Vec::new(),
+        }
+        .with_relations(relations))
+    }
+
+    pub fn with_relations(mut self, relations: &[Relations]) -> Provenance {
+        self.relations = relations.iter().map(|relation| format!("{:?}", relation).to_lowercase()).collect();
+        self
+    }
+}
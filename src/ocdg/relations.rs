@@ -0,0 +1,15 @@
+use pmrs::objects::ocdg::Relations;
+use strum::IntoEnumIterator;
+
+use super::graph::relation_names;
+
+/// Resolves relation names case-insensitively to `Relations`, keeping the enum order.
+pub fn parse_relations(names: &[String]) -> Result<Vec<Relations>, String> {
+    let known = relation_names();
+    if let Some(unknown) = names.iter().find(|name| !known.iter().any(|k| k.eq_ignore_ascii_case(name))) {
+        return Err(format!("unknown relation {:?}. Known relations: {}", unknown, known.join(", ")));
+    }
+    Ok(Relations::iter()
+        .filter(|relation| names.iter().any(|name| name.eq_ignore_ascii_case(&format!("{:?}", relation))))
+        .collect())
+}
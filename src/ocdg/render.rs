@@ -0,0 +1,92 @@
+//! Rendering of an OCDG to an image by piping its DOT serialization through
+//! Graphviz, which has to be installed and on the `PATH`.
+use std::error::Error;
+use std::ffi::OsStr;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use clap::{Args, ValueEnum};
+use log::{debug, warn};
+
+use super::formats::{self, GraphFormat};
+use super::graph::Graph;
+
+/// Above this many nodes Graphviz layouts take very long and the image is unreadable.
+const LARGE_GRAPH: usize = 5000;
+
+#[derive(Args, Debug)]
+pub struct OcdgRender {
+    /// Path to OCDG file
+    pub path: PathBuf,
+
+    /// Image file to write. Its extension selects the image format unless --format
+    /// is given
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// Image format, e.g. svg, png or pdf, as understood by Graphviz
+    #[clap(long)]
+    pub format: Option<String>,
+
+    /// Graphviz layout engine
+    #[clap(long, value_enum, default_value_t = Layout::Sfdp)]
+    pub layout: Layout,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    /// Hierarchical, for directed graphs
+    Dot,
+    /// Spring model
+    Neato,
+    /// Force directed
+    Fdp,
+    /// Force directed, scales to large graphs
+    Sfdp,
+    /// Circular
+    Circo,
+    /// Radial
+    Twopi,
+}
+
+pub fn run(render: &OcdgRender) -> Result<(), Box<dyn Error>> {
+    let image_format = match &render.format {
+        Some(format) => format.clone(),
+        None => match render.output.extension().and_then(OsStr::to_str) {
+            Some(ext) => ext.to_lowercase(),
+            None => return Err(format!("cannot tell the image format of {:?}, pass --format", render.output).into()),
+        },
+    };
+
+    debug!("Importing {:?}", render.path);
+    let graph = Graph::import(&render.path)?;
+    if graph.nodes.len() > LARGE_GRAPH {
+        warn!("The graph has {} nodes, consider rendering an `ocdg subgraph` instead.", graph.nodes.len());
+    }
+
+    let layout = format!("{:?}", render.layout).to_lowercase();
+    debug!("Rendering {:?} as {} with the {} layout", render.output, image_format, layout);
+    let mut child = Command::new("dot")
+        .arg(format!("-K{}", layout))
+        .arg(format!("-T{}", image_format))
+        .arg("-o")
+        .arg(&render.output)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => "Graphviz is not installed, `dot` was not found on the PATH".into(),
+            _ => Box::<dyn Error>::from(e),
+        })?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        formats::write_to(&graph, GraphFormat::Dot, &mut stdin)?;
+        stdin.flush()?;
+    }
+    let exit = child.wait()?;
+    if !exit.success() {
+        return Err(format!("Graphviz failed with {}", exit).into());
+    }
+    status!("rendered {} nodes and {} edges -> {}", graph.nodes.len(), graph.edges.len(), render.output.display());
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use log::debug;
+use serde_json::{json, Map, Value};
+
+use super::centrality::{self, Centrality};
+use super::graph::Graph;
+use crate::compression;
+use crate::ocel::performance::Stats;
+use crate::table::{Cell, Table};
+
+/// Degree percentiles reported besides the minimum, maximum and mean.
+const QUANTILES: [(&str, f64); 6] = [("p25", 0.25), ("p50", 0.5), ("p75", 0.75), ("p90", 0.9), ("p95", 0.95), ("p99", 0.99)];
+
+#[derive(Args, Debug)]
+pub struct OcdgStats {
+    /// Path to OCDG file
+    pub path: PathBuf,
+
+    /// Write the N most central nodes to the CSV file given by --export
+    #[clap(long, value_name = "N", requires = "export")]
+    pub export_top_nodes: Option<usize>,
+
+    /// Output CSV path for --export-top-nodes
+    #[clap(long, requires = "export-top-nodes")]
+    pub export: Option<PathBuf>,
+
+    /// Centrality used to rank nodes
+    #[clap(long, value_enum, default_value_t = Centrality::Degree)]
+    pub centrality: Centrality,
+
+    /// Print the summary as a JSON object instead of text
+    #[clap(long)]
+    pub json: bool,
+}
+
+pub fn run(stats: &OcdgStats) -> Result<(), Box<dyn Error>> {
+    debug!("Importing {:?}", stats.path);
+    let graph = Graph::import(&stats.path)?;
+
+    let mut per_type: BTreeMap<&str, usize> = BTreeMap::new();
+    for node in graph.nodes.values() {
+        *per_type.entry(node.obj_type().unwrap_or("unknown")).or_default() += 1;
+    }
+
+    let mut per_relation: BTreeMap<String, usize> = BTreeMap::new();
+    for edge in &graph.edges {
+        let relations = edge.relations();
+        if relations.is_empty() {
+            *per_relation.entry("unknown".to_string()).or_default() += 1;
+        }
+        for relation in relations {
+            *per_relation.entry(relation).or_default() += 1;
+        }
+    }
+
+    let degrees: Vec<f64> = graph.degrees().values().map(|(i, o)| (i + o) as f64).collect();
+    let degree = Stats::of(&degrees);
+    let fractions: Vec<f64> = QUANTILES.iter().map(|(_, q)| *q).collect();
+    let quantiles = Stats::quantiles(&degrees, &fractions);
+    let components = graph.components();
+    let largest = components.first().map_or(0, Vec::len);
+
+    if stats.json {
+        // nearest-rank percentiles are degrees of actual nodes, so they stay integers
+        let degree = degree.map(|stats| {
+            let mut summary = Map::new();
+            summary.insert("min".to_string(), json!(stats.min as usize));
+            for ((name, _), value) in QUANTILES.iter().zip(&quantiles) {
+                summary.insert(name.to_string(), json!(*value as usize));
+            }
+            summary.insert("max".to_string(), json!(stats.max as usize));
+            summary.insert("mean".to_string(), json!(stats.mean));
+            Value::Object(summary)
+        });
+        let summary = json!({
+            "nodes": graph.nodes.len(),
+            "edges": graph.edges.len(),
+            "nodes_per_type": per_type,
+            "edges_per_relation": per_relation,
+            "degree": degree,
+            "density": density(&graph),
+            "components": components.len(),
+            "largest_component": largest,
+        });
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else {
+        let mut overview = Table::new(&["metric", "value"]).right(&[1]);
+        overview.row(vec!["nodes".into(), graph.nodes.len().into()]);
+        overview.row(vec!["edges".into(), graph.edges.len().into()]);
+        overview.row(vec!["density".into(), format!("{:.6}", density(&graph)).into()]);
+        overview.row(vec!["weakly connected components".into(), components.len().into()]);
+        overview.row(vec!["largest component (nodes)".into(), largest.into()]);
+        overview.print();
+        println!();
+        let mut types = Table::new(&["object type", "nodes"]).right(&[1]);
+        for (obj_type, count) in &per_type {
+            types.row(vec![(*obj_type).into(), (*count).into()]);
+        }
+        types.print();
+        println!();
+        let mut relations = Table::new(&["relation", "edges"]).right(&[1]);
+        for (relation, count) in &per_relation {
+            relations.row(vec![relation.as_str().into(), (*count).into()]);
+        }
+        relations.print();
+        if let Some(stats) = degree {
+            println!();
+            let mut header = vec!["", "min"];
+            header.extend(QUANTILES.iter().map(|(name, _)| *name));
+            header.extend(["max", "mean"]);
+            let mut table = Table::new(&header).right(&(1..header.len()).collect::<Vec<_>>());
+            let mut row = vec![Cell::from("degree"), Cell::from(stats.min as usize)];
+            row.extend(quantiles.iter().map(|value| Cell::from(*value as usize)));
+            row.push(Cell::from(stats.max as usize));
+            row.push(Cell::from(format!("{:.2}", stats.mean)));
+            table.row(row);
+            table.print();
+        }
+    }
+
+    if let (Some(n), Some(path)) = (stats.export_top_nodes, &stats.export) {
+        let scores = centrality::scores(&graph, stats.centrality);
+        let top = centrality::top_nodes(&scores, n);
+        debug!("Writing top {} nodes by {:?} to {:?}", top.len(), stats.centrality, path);
+        let mut writer = csv::Writer::from_writer(compression::create(path)?);
+        writer.write_record(["id", "type", "score"])?;
+        for (id, score) in top {
+            writer.write_record([id, graph.obj_type(id).unwrap_or(""), score.to_string().as_str()])?;
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Edges over the possible number of node pairs, ordered if the graph is directed.
+fn density(graph: &Graph) -> f64 {
+    let n = graph.nodes.len() as f64;
+    if n < 2.0 {
+        return 0.0;
+    }
+    let pairs = if graph.directed { n * (n - 1.0) } else { n * (n - 1.0) / 2.0 };
+    graph.edges.len() as f64 / pairs
+}
@@ -0,0 +1,67 @@
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Args;
+use log::debug;
+
+use super::formats::{self, GraphFormat};
+use super::graph::Graph;
+
+#[derive(Args, Debug)]
+pub struct OcdgSubgraph {
+    /// Path to OCDG file
+    pub path: PathBuf,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// Graph format to write. Inferred from the output extension if unset, GEXF
+    /// otherwise
+    #[clap(long, value_enum)]
+    pub format: Option<GraphFormat>,
+
+    /// Seed object ids. May be repeated or comma separated
+    #[clap(long, value_name = "ID", use_value_delimiter = true, multiple_occurrences = true)]
+    pub object: Vec<String>,
+
+    /// File with one seed object id per line
+    #[clap(long, value_name = "PATH")]
+    pub objects_file: Option<PathBuf>,
+
+    /// Include objects up to this many hops away from any seed
+    #[clap(long, default_value_t = 1)]
+    pub hops: usize,
+}
+
+pub fn run(subgraph: &OcdgSubgraph) -> Result<(), Box<dyn Error>> {
+    let mut seeds = subgraph.object.clone();
+    if let Some(path) = &subgraph.objects_file {
+        let text = fs::read_to_string(path)?;
+        seeds.extend(text.lines().map(str::trim).filter(|line| !line.is_empty()).map(String::from));
+    }
+    if seeds.is_empty() {
+        return Err("no seed objects given, use --object or --objects-file".into());
+    }
+
+    debug!("Importing {:?}", subgraph.path);
+    let mut graph = Graph::import(&subgraph.path)?;
+    let missing: Vec<&str> = seeds.iter().filter(|id| !graph.nodes.contains_key(*id)).map(String::as_str).collect();
+    if missing.len() == seeds.len() {
+        return Err(format!("none of the seed objects is part of the graph: {}", missing.join(", ")).into());
+    }
+    for id in &missing {
+        status!("seed {} is not part of the graph", id);
+    }
+
+    let seeds: Vec<&str> = seeds.iter().map(String::as_str).collect();
+    let distances = graph.distances_from(&seeds, subgraph.hops);
+    graph.retain_nodes(|id, _| distances.contains_key(id));
+
+    let format = GraphFormat::resolve(subgraph.format, &subgraph.output);
+    debug!("Writing the subgraph to {:?} as {:?}", subgraph.output, format);
+    formats::write(&graph, &subgraph.output, format)?;
+    status!("extracted {} nodes and {} edges -> {}", graph.nodes.len(), graph.edges.len(), subgraph.output.display());
+    Ok(())
+}
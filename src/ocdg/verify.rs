@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use clap::Args;
+use log::debug;
+use pmrs::objects::ocdg::importer::import_ocdg;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use crate::compression;
+use crate::exit;
+use crate::xml::attributes;
+
+#[derive(Args, Debug)]
+pub struct OcdgVerify {
+    /// Path to OCDG file
+    pub path: PathBuf,
+
+    /// Only run the structural GEXF check, without importing the graph through
+    /// pmrs
+    #[clap(long)]
+    pub structure_only: bool,
+
+    /// Print the result as a JSON object instead of text
+    #[clap(long)]
+    pub json: bool,
+}
+
+pub fn run(verify: &OcdgVerify) -> Result<(), Box<dyn Error>> {
+    debug!("Checking the GEXF structure of {:?}", verify.path);
+    let mut issues = check_structure(&verify.path)?;
+
+    if !verify.structure_only {
+        debug!("Importing {:?}", verify.path);
+        let copy = compression::decompressed_copy(&verify.path)?;
+        let plain: &Path = copy.as_deref().unwrap_or(&verify.path);
+        if let Err(e) = import_ocdg(&plain.to_string_lossy()) {
+            issues.push(format!("pmrs could not import the graph: {:?}", e));
+        }
+    }
+
+    if verify.json {
+        let result = serde_json::json!({ "path": verify.path, "valid": issues.is_empty(), "errors": issues });
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else {
+        for (i, issue) in issues.iter().enumerate() {
+            println!("Error {}: {}", i + 1, issue);
+        }
+        println!("{}: {}", verify.path.display(), issues.is_empty());
+    }
+    if !issues.is_empty() {
+        process::exit(exit::VALIDATION_FAILED);
+    }
+    Ok(())
+}
+
+/// Elements allowed directly below each GEXF element. Elements of the `viz`
+/// namespace are checked by their local name.
+fn allowed_children(parent: &str) -> &'static [&'static str] {
+    match parent {
+        "" => &["gexf"],
+        "gexf" => &["meta", "graph"],
+        "meta" => &["creator", "description", "keywords"],
+        "graph" => &["attributes", "nodes", "edges"],
+        "attributes" => &["attribute"],
+        "attribute" => &["default", "options"],
+        "nodes" => &["node"],
+        "node" => &["attvalues", "spells", "nodes", "edges", "parents", "color", "position", "size", "shape"],
+        "edges" => &["edge"],
+        "edge" => &["attvalues", "spells", "color", "thickness", "shape"],
+        "attvalues" => &["attvalue"],
+        "spells" => &["spell"],
+        "parents" => &["parent"],
+        _ => &[],
+    }
+}
+
+const ATTRIBUTE_TYPES: [&str; 18] = [
+    "integer", "long", "double", "float", "boolean", "string", "liststring", "anyURI",
+    "listinteger", "listlong", "listdouble", "listfloat", "listboolean", "byte", "short",
+    "char", "date", "bigdecimal",
+];
+
+fn conforms(kind: &str, value: &str) -> bool {
+    match kind {
+        "integer" | "long" | "byte" | "short" => value.parse::<i64>().is_ok(),
+        "double" | "float" | "bigdecimal" => value.parse::<f64>().is_ok(),
+        "boolean" => matches!(value, "true" | "false" | "1" | "0"),
+        "char" => value.chars().count() == 1,
+        _ => true,
+    }
+}
+
+/// A structural check modelled on the GEXF 1.3 schema, not a validation against its
+/// XSD: the nesting of the elements, their required attributes, the declared
+/// attribute types and values, duplicate node ids and edges between unknown nodes.
+pub fn check_structure(path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut reader = Reader::from_reader(compression::open(path)?);
+    reader.trim_text(true);
+
+    let mut issues = Vec::new();
+    let mut stack: Vec<String> = Vec::new();
+    let mut class = String::from("node");
+    let mut declared: HashMap<(String, String), String> = HashMap::new();
+    let mut node_ids: HashSet<String> = HashSet::new();
+    let mut edge_ends: Vec<(u64, String, String)> = Vec::new();
+    let mut seen_root = false;
+    let mut buf = Vec::new();
+
+    loop {
+        let position = reader.buffer_position() as u64;
+        let event = match reader.read_event_into(&mut buf) {
+            Ok(event) => event,
+            Err(e) => {
+                issues.push(format!("malformed XML at byte {}: {}", position, e));
+                break;
+            }
+        };
+        match event {
+            Event::Start(ref tag) | Event::Empty(ref tag) => {
+                let name = String::from_utf8_lossy(tag.local_name().as_ref()).into_owned();
+                let parent = stack.last().map(String::as_str).unwrap_or("");
+                if !allowed_children(parent).contains(&name.as_str()) {
+                    issues.push(format!("<{}> is not allowed inside <{}> at byte {}", name, parent, position));
+                }
+                let attrs = attributes(tag)?;
+                let mut require = |key: &str| {
+                    if !attrs.contains_key(key) {
+                        issues.push(format!("<{}> is missing the required `{}` attribute at byte {}", name, key, position));
+                    }
+                };
+                match name.as_str() {
+                    "gexf" => {
+                        seen_root = true;
+                        require("version");
+                    }
+                    "node" => require("id"),
+                    "edge" => {
+                        require("source");
+                        require("target");
+                    }
+                    "attribute" => {
+                        require("id");
+                        require("title");
+                        require("type");
+                    }
+                    "attvalue" => {
+                        require("for");
+                        require("value");
+                    }
+                    _ => {}
+                }
+                let get = |key: &str| attrs.get(key).map(String::as_str);
+                match name.as_str() {
+                    "graph" => {
+                        if let Some(kind) = get("defaultedgetype") {
+                            if !matches!(kind, "directed" | "undirected" | "mutual") {
+                                issues.push(format!("invalid defaultedgetype {:?} at byte {}", kind, position));
+                            }
+                        }
+                        if let Some(mode) = get("mode") {
+                            if !matches!(mode, "static" | "dynamic") {
+                                issues.push(format!("invalid graph mode {:?} at byte {}", mode, position));
+                            }
+                        }
+                    }
+                    "attributes" => class = get("class").unwrap_or("node").to_string(),
+                    "attribute" => {
+                        if let Some(kind) = get("type") {
+                            if !ATTRIBUTE_TYPES.contains(&kind) {
+                                issues.push(format!("unknown attribute type {:?} at byte {}", kind, position));
+                            }
+                        }
+                        if let (Some(id), Some(kind)) = (get("id"), get("type")) {
+                            declared.insert((class.clone(), id.to_string()), kind.to_string());
+                        }
+                    }
+                    "node" if parent == "nodes" => {
+                        if let Some(id) = get("id") {
+                            if !node_ids.insert(id.to_string()) {
+                                issues.push(format!("duplicate node id {:?} at byte {}", id, position));
+                            }
+                        }
+                    }
+                    "edge" => {
+                        if let Some(weight) = get("weight") {
+                            if weight.parse::<f64>().is_err() {
+                                issues.push(format!("edge weight {:?} is not a number at byte {}", weight, position));
+                            }
+                        }
+                        if let (Some(source), Some(target)) = (get("source"), get("target")) {
+                            edge_ends.push((position, source.to_string(), target.to_string()));
+                        }
+                    }
+                    "attvalue" => {
+                        let owner = stack.iter().rev().find(|e| *e == "node" || *e == "edge").cloned().unwrap_or_default();
+                        if let (Some(key), Some(value)) = (get("for"), get("value")) {
+                            match declared.get(&(owner.clone(), key.to_string())) {
+                                Some(kind) if !conforms(kind, value) => {
+                                    issues.push(format!("value {:?} does not match attribute type {} at byte {}", value, kind, position));
+                                }
+                                Some(_) => {}
+                                None => issues.push(format!("attvalue refers to undeclared {} attribute {:?} at byte {}", owner, key, position)),
+                            }
+                        }
+                    }
+                    "color" => {
+                        for channel in ["r", "g", "b"] {
+                            if get(channel).map_or(true, |v| v.parse::<u8>().is_err()) {
+                                issues.push(format!("color channel `{}` must be an integer in 0..=255 at byte {}", channel, position));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                if matches!(event, Event::Start(_)) {
+                    stack.push(name);
+                }
+            }
+            Event::End(_) => {
+                stack.pop();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    if !seen_root {
+        issues.push("the document has no <gexf> root element".to_string());
+    }
+    for (position, source, target) in edge_ends {
+        for end in [source, target] {
+            if !node_ids.contains(&end) {
+                issues.push(format!("edge endpoint {:?} is not a declared node at byte {}", end, position));
+            }
+        }
+    }
+    Ok(issues)
+}
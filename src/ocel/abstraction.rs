@@ -0,0 +1,163 @@
+//! Event abstraction: bursts of low-level events, e.g. the readings of a sensor,
+//! collapsed into one event each. A burst is a run of events on the same set of
+//! objects that no other event on those objects interrupts, optionally bounded by
+//! the time between its events. With a mapping only the mapped events are
+//! abstracted, and only events mapped to the same activity form a burst.
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Duration, FixedOffset};
+use clap::Args;
+use indexmap::IndexMap;
+use log::debug;
+use serde_json::Value;
+
+use super::mapping;
+use super::model::{Event, OcelLog};
+use super::time::parse_duration;
+
+#[derive(Args, Debug)]
+pub struct OcelAbstract {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// End a burst when the next event follows more than this long after the last
+    /// one (e.g. `30s`, `5m`)
+    #[clap(long, value_name = "DURATION", value_parser = parse_duration, required_unless_present = "mapping")]
+    pub max_gap: Option<Duration>,
+
+    /// CSV file with a header row mapping low-level activities in the first column to
+    /// the activity of their abstracted events in the second. Events of other
+    /// activities are kept as they are
+    #[clap(long, value_name = "PATH")]
+    pub mapping: Option<PathBuf>,
+}
+
+/// An abstracted event in the making.
+struct Burst<'a> {
+    id: &'a str,
+    first: &'a Event,
+    /// Distinct activities in order, named after when there is no mapping
+    activities: Vec<&'a str>,
+    label: Option<&'a str>,
+    events: usize,
+    end: DateTime<FixedOffset>,
+    /// Position of the last event, to tell whether another one touched the objects since
+    last: usize,
+}
+
+impl Burst<'_> {
+    fn event(&self) -> Event {
+        let mut event = self.first.clone();
+        event.activity = match self.label {
+            Some(label) => label.to_string(),
+            None => self.activities.join("+"),
+        };
+        if self.events > 1 {
+            event.vmap.insert("abstracted_events".to_string(), Value::from(self.events));
+            event.vmap.insert("abstracted_end".to_string(), Value::from(self.end.to_rfc3339()));
+        }
+        event
+    }
+}
+
+/// An event of the output, in the order of the first event it stands for.
+enum Item {
+    Kept(usize),
+    Burst(usize),
+}
+
+pub fn run(abstraction: &OcelAbstract) -> Result<(), Box<dyn Error>> {
+    let renames = match &abstraction.mapping {
+        Some(path) => Some(mapping::read(path)?),
+        None => None,
+    };
+    debug!("Importing log: {:?}", abstraction.path);
+    let mut log = OcelLog::import(&abstraction.path)?;
+
+    let mut events: Vec<(&String, &Event)> = log.events.iter().collect();
+    events.sort_by_key(|(_, event)| event.timestamp);
+
+    let mut items: Vec<Item> = Vec::new();
+    let mut bursts: Vec<Burst> = Vec::new();
+    // the open burst of every object set and mapped activity
+    let mut open: HashMap<(BTreeSet<&str>, Option<&str>), usize> = HashMap::new();
+    // position of the last event referencing every object
+    let mut touched: HashMap<&str, usize> = HashMap::new();
+    for (position, &(id, event)) in events.iter().enumerate() {
+        let objects: BTreeSet<&str> = event.omap.iter().map(String::as_str).collect();
+        let label = match &renames {
+            Some(renames) => renames.get(&event.activity).map(String::as_str),
+            None => None,
+        };
+        let abstracted = !objects.is_empty() && (renames.is_none() || label.is_some());
+        if !abstracted {
+            items.push(Item::Kept(position));
+        } else {
+            let key = (objects.clone(), label);
+            let continues = open.get(&key).filter(|&&index| {
+                let burst = &bursts[index];
+                abstraction.max_gap.map_or(true, |gap| event.timestamp - burst.end <= gap)
+                    && objects.iter().all(|oid| touched.get(oid) == Some(&burst.last))
+            });
+            match continues.copied() {
+                Some(index) => {
+                    let burst = &mut bursts[index];
+                    if !burst.activities.contains(&event.activity.as_str()) {
+                        burst.activities.push(event.activity.as_str());
+                    }
+                    burst.events += 1;
+                    burst.end = event.timestamp;
+                    burst.last = position;
+                }
+                None => {
+                    open.insert(key, bursts.len());
+                    items.push(Item::Burst(bursts.len()));
+                    bursts.push(Burst {
+                        id,
+                        first: event,
+                        activities: vec![event.activity.as_str()],
+                        label,
+                        events: 1,
+                        end: event.timestamp,
+                        last: position,
+                    });
+                }
+            }
+        }
+        for oid in objects {
+            touched.insert(oid, position);
+        }
+    }
+
+    let merged: Vec<&Burst> = bursts.iter().filter(|burst| burst.events > 1).collect();
+    let absorbed: usize = merged.iter().map(|burst| burst.events).sum();
+    debug!("{} bursts of several events, the longest has {}", merged.len(), merged.iter().map(|burst| burst.events).max().unwrap_or(0));
+    let abstracted: IndexMap<String, Event> = items
+        .iter()
+        .map(|item| match item {
+            Item::Kept(position) => (events[*position].0.clone(), events[*position].1.clone()),
+            Item::Burst(index) => (bursts[*index].id.to_string(), bursts[*index].event()),
+        })
+        .collect();
+    let (collapsed_into, before) = (merged.len(), log.events.len());
+    log.events = abstracted;
+    log.refresh_globals();
+
+    debug!("Exporting abstracted log to {:?}", abstraction.output);
+    log.export(&abstraction.output)?;
+    status!(
+        "collapsed {} events into {} abstracted events, {} of {} events remain -> {}",
+        absorbed,
+        collapsed_into,
+        log.events.len(),
+        before,
+        abstraction.output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,188 @@
+//! Pseudonymization of identifiers and attribute values.
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use log::{debug, warn};
+use rand::Rng;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use super::model::{display_value, numeric, OcelLog};
+
+#[derive(Args, Debug)]
+pub struct OcelAnonymize {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// How to treat an event or object attribute, as `KEY=STRATEGY` with the
+    /// strategies `hash`, `random`, `suppress` and `bucket:<WIDTH>`. Can be given
+    /// several times; attributes without a rule are kept as they are
+    #[clap(long, multiple_occurrences = true, value_name = "KEY=STRATEGY", value_parser = parse_rule)]
+    pub rule: Vec<(String, Strategy)>,
+
+    /// Keep event and object ids instead of replacing them with pseudonyms
+    #[clap(long)]
+    pub keep_ids: bool,
+
+    /// Salt for the hashed pseudonyms. Default: a random salt, so pseudonyms differ
+    /// between runs
+    #[clap(long)]
+    pub salt: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Strategy {
+    /// Salted hash of the value
+    Hash,
+    /// Random token, the same for every occurrence of a value
+    Random,
+    /// Remove the attribute
+    Suppress,
+    /// Replace numbers by the bucket of the given width they fall into
+    Bucket(f64),
+}
+
+fn parse_rule(raw: &str) -> Result<(String, Strategy), String> {
+    let (key, strategy) = raw.split_once('=').ok_or_else(|| format!("invalid rule {:?}, expected KEY=STRATEGY", raw))?;
+    let strategy = match strategy.split_once(':') {
+        None if strategy == "hash" => Strategy::Hash,
+        None if strategy == "random" => Strategy::Random,
+        None if strategy == "suppress" => Strategy::Suppress,
+        Some(("bucket", width)) => match width.parse::<f64>() {
+            Ok(width) if width > 0.0 => Strategy::Bucket(width),
+            _ => return Err(format!("invalid bucket width {:?}, expected a positive number", width)),
+        },
+        _ => return Err(format!("unknown strategy {:?}, expected hash, random, suppress or bucket:<WIDTH>", strategy)),
+    };
+    Ok((key.to_string(), strategy))
+}
+
+/// Deterministic pseudonym for `value`: the first 16 hex digits of
+/// `sha256(salt || value)`. The same input and salt always map to the same
+/// pseudonym, so references stay consistent.
+pub fn pseudonym(value: &str, salt: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(value.as_bytes());
+    hasher.finalize().iter().take(8).map(|b| format!("{:02x}", b)).collect()
+}
+
+fn random_token() -> String {
+    let bytes: [u8; 8] = rand::thread_rng().gen();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+struct Anonymizer<'a> {
+    rules: HashMap<&'a str, &'a Strategy>,
+    salt: String,
+    /// Random tokens handed out so far, per attribute and original value
+    tokens: HashMap<(String, String), String>,
+}
+
+impl Anonymizer<'_> {
+    fn value(&mut self, key: &str, value: &Value) -> Result<Option<Value>, String> {
+        let strategy = match self.rules.get(key) {
+            Some(strategy) => *strategy,
+            None => return Ok(Some(value.clone())),
+        };
+        Ok(match strategy {
+            Strategy::Hash => Some(Value::String(pseudonym(&display_value(value), &self.salt))),
+            Strategy::Random => {
+                let token = self.tokens.entry((key.to_string(), display_value(value))).or_insert_with(random_token);
+                Some(Value::String(token.clone()))
+            }
+            Strategy::Suppress => None,
+            Strategy::Bucket(width) => {
+                let number = numeric(value).ok_or_else(|| format!("cannot bucket the non-numeric value of {}", key))?;
+                let low = (number / width).floor() * width;
+                Some(Value::String(format!("{}-{}", low, low + width)))
+            }
+        })
+    }
+
+    fn map(&mut self, map: &Map<String, Value>) -> Result<Map<String, Value>, String> {
+        let mut anonymized = Map::new();
+        for (key, value) in map {
+            if let Some(value) = self.value(key, value)? {
+                anonymized.insert(key.clone(), value);
+            }
+        }
+        Ok(anonymized)
+    }
+
+    fn id(&self, id: &str, keep: bool) -> String {
+        if keep {
+            id.to_string()
+        } else {
+            pseudonym(id, &self.salt)
+        }
+    }
+}
+
+pub fn run(anonymize: &OcelAnonymize) -> Result<(), Box<dyn Error>> {
+    let salt = match &anonymize.salt {
+        Some(salt) => salt.clone(),
+        None => {
+            warn!("No --salt given, using a random one. Pseudonyms will not match other runs.");
+            random_token()
+        }
+    };
+    let mut anonymizer = Anonymizer {
+        rules: anonymize.rule.iter().map(|(key, strategy)| (key.as_str(), strategy)).collect(),
+        salt,
+        tokens: HashMap::new(),
+    };
+
+    debug!("Importing log: {:?}", anonymize.path);
+    let log = OcelLog::import(&anonymize.path)?;
+    for key in anonymizer.rules.keys() {
+        let known = log.events.values().any(|e| e.vmap.contains_key(*key)) || log.objects.values().any(|o| o.ovmap.contains_key(*key));
+        if !known {
+            warn!("No event or object has an attribute {:?}, its rule has no effect.", key);
+        }
+    }
+
+    let mut anonymized = OcelLog {
+        global_log: log.global_log.clone(),
+        global_event: log.global_event.clone(),
+        global_object: log.global_object.clone(),
+        ..OcelLog::default()
+    };
+    for (id, object) in &log.objects {
+        let mut object = object.clone();
+        object.ovmap = anonymizer.map(&object.ovmap)?;
+        let mut changes = Vec::new();
+        for mut change in object.attribute_changes {
+            if let Some(value) = anonymizer.value(&change.name, &change.value)? {
+                change.value = value;
+                changes.push(change);
+            }
+        }
+        object.attribute_changes = changes;
+        for relationship in &mut object.relationships {
+            relationship.object_id = anonymizer.id(&relationship.object_id, anonymize.keep_ids);
+        }
+        anonymized.objects.insert(anonymizer.id(id, anonymize.keep_ids), object);
+    }
+    for (id, event) in &log.events {
+        let mut event = event.clone();
+        event.vmap = anonymizer.map(&event.vmap)?;
+        event.omap = event.omap.iter().map(|oid| anonymizer.id(oid, anonymize.keep_ids)).collect();
+        for relationship in &mut event.relationships {
+            relationship.object_id = anonymizer.id(&relationship.object_id, anonymize.keep_ids);
+        }
+        anonymized.events.insert(anonymizer.id(id, anonymize.keep_ids), event);
+    }
+    anonymized.refresh_globals();
+
+    debug!("Exporting anonymized log to {:?}", anonymize.output);
+    anonymized.export(&anonymize.output)?;
+    status!("anonymized {} events and {} objects -> {}", anonymized.events.len(), anonymized.objects.len(), anonymize.output.display());
+    Ok(())
+}
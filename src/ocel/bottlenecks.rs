@@ -0,0 +1,162 @@
+//! Bottleneck report: the transitions between activities per object type, ranked by
+//! the waiting time accumulated over all objects. The transitions that together
+//! make up most of the waiting of a type are flagged.
+use std::error::Error;
+use std::io::Write;
+use std::path::PathBuf;
+
+use clap::Args;
+use log::debug;
+use serde_json::{json, Value};
+
+use super::model::OcelLog;
+use super::performance::{measure, Stats};
+use super::time::format_seconds;
+use super::{report_writer, ReportFormat};
+use crate::table::{self, Cell, Style, Table};
+
+#[derive(Args, Debug)]
+pub struct OcelBottlenecks {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Only rank the transitions of these object types. May be repeated or comma
+    /// separated
+    #[clap(long, value_name = "TYPE", use_value_delimiter = true, multiple_occurrences = true)]
+    pub object_type: Vec<String>,
+
+    /// Number of transitions listed per object type, 0 for all
+    #[clap(long, default_value_t = 10)]
+    pub top: usize,
+
+    /// Flag the highest ranked transitions until they account for this share of the
+    /// waiting time of their object type
+    #[clap(long, value_name = "SHARE", default_value_t = 0.8)]
+    pub flag_share: f64,
+
+    /// Output format, table by default. Table durations are rounded, CSV and JSON
+    /// ones are in seconds
+    #[clap(long, value_enum)]
+    pub format: Option<ReportFormat>,
+
+    /// Write the report to this file instead of stdout
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+struct Bottleneck<'a> {
+    obj_type: &'a str,
+    rank: usize,
+    from: &'a str,
+    to: &'a str,
+    stats: Stats,
+    share: f64,
+    flagged: bool,
+}
+
+pub fn run(args: &OcelBottlenecks) -> Result<(), Box<dyn Error>> {
+    if !(0.0..=1.0).contains(&args.flag_share) {
+        return Err(format!("--flag-share must be between 0 and 1, not {}", args.flag_share).into());
+    }
+    debug!("Importing log: {:?}", args.path);
+    let log = OcelLog::import(&args.path)?;
+    let types = log.object_types();
+    if let Some(unknown) = args.object_type.iter().find(|t| !types.contains(&t.as_str())) {
+        return Err(format!("no object is of type {:?}, available types: {}", unknown, types.join(", ")).into());
+    }
+
+    let top = if args.top == 0 { usize::MAX } else { args.top };
+    let mut bottlenecks = Vec::new();
+    for (obj_type, performance) in measure(&log, &args.object_type) {
+        let mut ranked: Vec<((&str, &str), Stats)> =
+            performance.waiting.iter().filter_map(|(key, durations)| Stats::of(durations).map(|stats| (*key, stats))).collect();
+        ranked.sort_by(|(_, a), (_, b)| b.total.partial_cmp(&a.total).unwrap_or(std::cmp::Ordering::Equal));
+        let waiting: f64 = ranked.iter().map(|(_, stats)| stats.total).sum();
+
+        let mut covered = 0.0;
+        for (index, ((from, to), stats)) in ranked.into_iter().take(top).enumerate() {
+            let share = if waiting > 0.0 { stats.total / waiting } else { 0.0 };
+            // a transition is flagged while the ones above it fall short of the share
+            let flagged = share > 0.0 && covered < args.flag_share;
+            covered += share;
+            bottlenecks.push(Bottleneck { obj_type, rank: index + 1, from, to, stats, share, flagged });
+        }
+    }
+
+    let mut out = report_writer(args.output.as_deref())?;
+    match args.format.unwrap_or(ReportFormat::Table) {
+        ReportFormat::Table => {
+            let color = args.output.is_none() && table::color();
+            let mut types: Vec<&str> = bottlenecks.iter().map(|b| b.obj_type).collect();
+            types.dedup();
+            for (index, obj_type) in types.into_iter().enumerate() {
+                if index > 0 {
+                    writeln!(out)?;
+                }
+                writeln!(out, "{}", obj_type)?;
+                let mut table = Table::new(&["", "rank", "transition", "count", "total", "share", "mean", "p95"]).right(&[1, 3, 4, 5, 6, 7]);
+                for b in bottlenecks.iter().filter(|b| b.obj_type == obj_type) {
+                    table.row(vec![
+                        if b.flagged { Cell::styled("*", Style::Warn) } else { "".into() },
+                        b.rank.into(),
+                        format!("{} -> {}", b.from, b.to).into(),
+                        b.stats.count.into(),
+                        format_seconds(b.stats.total).into(),
+                        format!("{:.1}%", 100.0 * b.share).into(),
+                        format_seconds(b.stats.mean).into(),
+                        format_seconds(b.stats.p95).into(),
+                    ]);
+                }
+                table.write(&mut out, color)?;
+            }
+            if bottlenecks.iter().any(|b| b.flagged) {
+                writeln!(out)?;
+                writeln!(out, "* together account for {:.0}% of the waiting time of their object type", 100.0 * args.flag_share)?;
+            }
+        }
+        ReportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(["object_type", "rank", "from", "to", "count", "total", "share", "mean", "median", "p95", "flagged"])?;
+            for b in &bottlenecks {
+                writer.write_record([
+                    b.obj_type.to_string(),
+                    b.rank.to_string(),
+                    b.from.to_string(),
+                    b.to.to_string(),
+                    b.stats.count.to_string(),
+                    b.stats.total.to_string(),
+                    b.share.to_string(),
+                    b.stats.mean.to_string(),
+                    b.stats.median.to_string(),
+                    b.stats.p95.to_string(),
+                    b.flagged.to_string(),
+                ])?;
+            }
+            writer.flush()?;
+            return Ok(());
+        }
+        ReportFormat::Json => {
+            let report: Vec<Value> = bottlenecks
+                .iter()
+                .map(|b| {
+                    json!({
+                        "object_type": b.obj_type,
+                        "rank": b.rank,
+                        "from": b.from,
+                        "to": b.to,
+                        "count": b.stats.count,
+                        "total": b.stats.total,
+                        "share": b.share,
+                        "mean": b.stats.mean,
+                        "median": b.stats.median,
+                        "p95": b.stats.p95,
+                        "flagged": b.flagged,
+                    })
+                })
+                .collect();
+            writeln!(out, "{}", serde_json::to_string_pretty(&report)?)?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
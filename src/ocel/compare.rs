@@ -0,0 +1,103 @@
+//! Structural comparison of two logs, ignoring differences that only stem from how
+//! they were serialized (key order, omap order, timestamp offsets, number formats).
+use std::collections::BTreeSet;
+
+use serde_json::{Map, Value};
+
+use super::model::{display_value, Event, Object, OcelLog};
+
+#[derive(Debug, Default)]
+pub struct LogDiff {
+    pub events_added: Vec<String>,
+    pub events_removed: Vec<String>,
+    pub events_changed: Vec<String>,
+    pub objects_added: Vec<String>,
+    pub objects_removed: Vec<String>,
+    pub objects_changed: Vec<String>,
+}
+
+impl LogDiff {
+    pub fn is_empty(&self) -> bool {
+        self.events_added.is_empty()
+            && self.events_removed.is_empty()
+            && self.events_changed.is_empty()
+            && self.objects_added.is_empty()
+            && self.objects_removed.is_empty()
+            && self.objects_changed.is_empty()
+    }
+
+    /// Prints one count line per category followed by up to `examples` ids.
+    pub fn print_summary(&self, examples: usize) {
+        let sections = [
+            ("events added", &self.events_added),
+            ("events removed", &self.events_removed),
+            ("events changed", &self.events_changed),
+            ("objects added", &self.objects_added),
+            ("objects removed", &self.objects_removed),
+            ("objects changed", &self.objects_changed),
+        ];
+        for (name, ids) in sections {
+            if ids.is_empty() {
+                continue;
+            }
+            let shown: Vec<&str> = ids.iter().take(examples).map(String::as_str).collect();
+            let more = if ids.len() > examples { ", ..." } else { "" };
+            println!("{}: {} ({}{})", name, ids.len(), shown.join(", "), more);
+        }
+    }
+}
+
+/// Attribute values are equivalent if they are equal, numerically equal or render to
+/// the same text, so `1`, `1.0` and `"1.0"` all match.
+pub fn values_equivalent(a: &Value, b: &Value) -> bool {
+    if a == b {
+        return true;
+    }
+    let numeric = |v: &Value| match v {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse::<f64>().ok(),
+        _ => None,
+    };
+    match (numeric(a), numeric(b)) {
+        (Some(x), Some(y)) => x == y,
+        _ => display_value(a) == display_value(b),
+    }
+}
+
+pub fn maps_equivalent(a: &Map<String, Value>, b: &Map<String, Value>) -> bool {
+    a.len() == b.len() && a.iter().all(|(key, value)| b.get(key).map_or(false, |other| values_equivalent(value, other)))
+}
+
+pub fn events_equivalent(a: &Event, b: &Event) -> bool {
+    a.activity == b.activity
+        && a.timestamp == b.timestamp
+        && a.omap.iter().collect::<BTreeSet<_>>() == b.omap.iter().collect::<BTreeSet<_>>()
+        && maps_equivalent(&a.vmap, &b.vmap)
+}
+
+pub fn objects_equivalent(a: &Object, b: &Object) -> bool {
+    a.obj_type == b.obj_type && maps_equivalent(&a.ovmap, &b.ovmap)
+}
+
+/// Differences going from `before` to `after`, matching events and objects by id.
+pub fn compare(before: &OcelLog, after: &OcelLog) -> LogDiff {
+    let mut diff = LogDiff::default();
+    for (id, event) in &before.events {
+        match after.events.get(id) {
+            Some(other) if events_equivalent(event, other) => {}
+            Some(_) => diff.events_changed.push(id.clone()),
+            None => diff.events_removed.push(id.clone()),
+        }
+    }
+    diff.events_added = after.events.keys().filter(|id| !before.events.contains_key(*id)).cloned().collect();
+
+    for (id, object) in &before.objects {
+        match after.objects.get(id) {
+            Some(other) if objects_equivalent(object, other) => {}
+            Some(_) => diff.objects_changed.push(id.clone()),
+            None => diff.objects_removed.push(id.clone()),
+        }
+    }
+    diff.objects_added = after.objects.keys().filter(|id| !before.objects.contains_key(*id)).cloned().collect();
+    diff
+}
@@ -0,0 +1,62 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use log::debug;
+
+use super::compare::compare;
+use super::model::{Format, OcelLog};
+use crate::stdio;
+
+#[derive(Args, Debug)]
+pub struct OcelConvert {
+    /// Path to the OCEL file to convert
+    pub path: PathBuf,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// Format of the input, instead of inferring it from the extension
+    #[clap(long, value_enum)]
+    pub from: Option<Format>,
+
+    /// Format of the output, instead of inferring it from the extension
+    #[clap(long, value_enum)]
+    pub to: Option<Format>,
+
+    /// Re-import the written file and fail if it differs structurally from the input
+    #[clap(long)]
+    pub verify: bool,
+}
+
+pub fn run(convert: &OcelConvert) -> Result<(), Box<dyn Error>> {
+    if convert.verify && stdio::is_stdio(&convert.output) {
+        return Err("--verify cannot re-read a log written to standard output".into());
+    }
+    let from = match convert.from {
+        Some(format) => format,
+        None => Format::from_path(&convert.path)?,
+    };
+    let to = match convert.to {
+        Some(format) => format,
+        None => Format::from_path(&convert.output)?,
+    };
+
+    debug!("Importing log: {:?} as {:?}", convert.path, from);
+    let log = OcelLog::import_as(&convert.path, from)?;
+    debug!("Exporting log to {:?} as {:?}", convert.output, to);
+    log.export_as(&convert.output, to)?;
+
+    if convert.verify {
+        debug!("Verifying {:?} against {:?}", convert.output, convert.path);
+        let written = OcelLog::import_as(&convert.output, to)?;
+        let diff = compare(&log, &written);
+        if !diff.is_empty() {
+            diff.print_summary(5);
+            return Err(format!("{:?} does not preserve the contents of {:?}", convert.output, convert.path).into());
+        }
+        status!("{:?}: verified, {} events and {} objects preserved", convert.output, log.events.len(), log.objects.len());
+    }
+    Ok(())
+}
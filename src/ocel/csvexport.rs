@@ -0,0 +1,87 @@
+//! Export of a log as normalized CSV tables.
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Args;
+use indexmap::IndexSet;
+use log::debug;
+
+use super::model::{display_value, OcelLog};
+
+#[derive(Args, Debug)]
+pub struct OcelToCsv {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Directory the tables are written to. Default: the working directory
+    #[clap(short, long)]
+    pub output_dir: Option<PathBuf>,
+}
+
+pub fn run(export: &OcelToCsv) -> Result<(), Box<dyn Error>> {
+    debug!("Importing log: {:?}", export.path);
+    let log = OcelLog::import(&export.path)?;
+    let dir = export.output_dir.clone().unwrap_or_else(|| PathBuf::from("."));
+    fs::create_dir_all(&dir)?;
+
+    // events.csv: one row per event, one column per event attribute
+    let keys: IndexSet<&str> = log.events.values().flat_map(|e| e.vmap.keys().map(String::as_str)).collect();
+    let mut events = csv::Writer::from_path(dir.join("events.csv"))?;
+    let mut header = vec!["id", "activity", "timestamp"];
+    header.extend(keys.iter().copied());
+    events.write_record(&header)?;
+    for (id, event) in &log.events {
+        let mut row = vec![id.clone(), event.activity.clone(), event.timestamp.to_rfc3339()];
+        row.extend(keys.iter().map(|key| event.vmap.get(*key).map(display_value).unwrap_or_default()));
+        events.write_record(&row)?;
+    }
+    events.flush()?;
+
+    let mut objects = csv::Writer::from_path(dir.join("objects.csv"))?;
+    objects.write_record(["id", "type"])?;
+    for (id, object) in &log.objects {
+        objects.write_record([id.as_str(), object.obj_type.as_str()])?;
+    }
+    objects.flush()?;
+
+    let mut event_objects = csv::Writer::from_path(dir.join("event_objects.csv"))?;
+    event_objects.write_record(["event_id", "object_id", "qualifier"])?;
+    let mut references = 0;
+    for (id, event) in &log.events {
+        for oid in &event.omap {
+            let qualifier =
+                event.relationships.iter().find(|r| &r.object_id == oid).map(|r| r.qualifier.as_str()).unwrap_or("");
+            event_objects.write_record([id.as_str(), oid.as_str(), qualifier])?;
+            references += 1;
+        }
+    }
+    event_objects.flush()?;
+
+    // object_attributes.csv: static attributes have no time, OCEL 2.0 changes do
+    let mut object_attributes = csv::Writer::from_path(dir.join("object_attributes.csv"))?;
+    object_attributes.write_record(["object_id", "attribute", "value", "time"])?;
+    let mut attributes = 0;
+    for (id, object) in &log.objects {
+        for (key, value) in &object.ovmap {
+            object_attributes.write_record([id.as_str(), key.as_str(), display_value(value).as_str(), ""])?;
+            attributes += 1;
+        }
+        for change in &object.attribute_changes {
+            let (value, time) = (display_value(&change.value), change.time.to_rfc3339());
+            object_attributes.write_record([id.as_str(), change.name.as_str(), value.as_str(), time.as_str()])?;
+            attributes += 1;
+        }
+    }
+    object_attributes.flush()?;
+
+    println!(
+        "wrote {} events, {} objects, {} event-object references and {} object attributes to {}",
+        log.events.len(),
+        log.objects.len(),
+        references,
+        attributes,
+        dir.display()
+    );
+    Ok(())
+}
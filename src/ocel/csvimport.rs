@@ -0,0 +1,151 @@
+//! Building an OCEL from a flat event table, one event per row.
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use log::{debug, warn};
+use serde_json::{Map, Number, Value};
+
+use super::model::{Event, Object, OcelLog};
+use super::time::parse_instant;
+use crate::compression;
+
+#[derive(Args, Debug)]
+pub struct OcelFromCsv {
+    /// Path to the CSV file
+    pub path: PathBuf,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// Column holding the event id. Default: the row number
+    #[clap(long, value_name = "COLUMN")]
+    pub id_col: Option<String>,
+
+    /// Column holding the activity
+    #[clap(long, value_name = "COLUMN")]
+    pub activity_col: String,
+
+    /// Column holding the timestamp
+    #[clap(long, value_name = "COLUMN")]
+    pub timestamp_col: String,
+
+    /// Comma separated columns holding object ids. The column name is used as the
+    /// object type
+    #[clap(long, value_name = "COLUMNS", value_delimiter = ',', required = true)]
+    pub object_cols: Vec<String>,
+
+    /// Comma separated columns to keep as event attributes. Default: every column
+    /// not used otherwise
+    #[clap(long, value_name = "COLUMNS", value_delimiter = ',')]
+    pub attribute_cols: Option<Vec<String>>,
+
+    /// Separator between several object ids in one cell
+    #[clap(long, default_value = ";")]
+    pub value_separator: String,
+
+    /// Field delimiter of the CSV file
+    #[clap(long, default_value_t = ',')]
+    pub delimiter: char,
+}
+
+/// Reads a cell as a number or boolean where possible, otherwise as a string.
+fn cell_value(raw: &str) -> Value {
+    if let Ok(n) = raw.parse::<i64>() {
+        return Value::from(n);
+    }
+    if let Some(n) = raw.parse::<f64>().ok().and_then(Number::from_f64) {
+        return Value::Number(n);
+    }
+    match raw {
+        "true" | "TRUE" | "True" => Value::Bool(true),
+        "false" | "FALSE" | "False" => Value::Bool(false),
+        _ => Value::String(raw.to_string()),
+    }
+}
+
+pub fn run(import: &OcelFromCsv) -> Result<(), Box<dyn Error>> {
+    if !import.delimiter.is_ascii() {
+        return Err(format!("the delimiter {:?} is not an ASCII character", import.delimiter).into());
+    }
+
+    debug!("Reading table: {:?}", import.path);
+    let mut reader = csv::ReaderBuilder::new().delimiter(import.delimiter as u8).from_reader(compression::open(&import.path)?);
+    let headers: Vec<String> = reader.headers()?.iter().map(str::to_string).collect();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h == name)
+            .ok_or_else(|| format!("the table has no column {:?}, columns: {}", name, headers.join(", ")))
+    };
+
+    let id_col = import.id_col.as_deref().map(column).transpose()?;
+    let activity_col = column(&import.activity_col)?;
+    let timestamp_col = column(&import.timestamp_col)?;
+    let object_cols = import.object_cols.iter().map(|c| column(c).map(|i| (i, c.as_str()))).collect::<Result<Vec<_>, _>>()?;
+    let attribute_cols: Vec<usize> = match &import.attribute_cols {
+        Some(names) => names.iter().map(|c| column(c)).collect::<Result<_, _>>()?,
+        None => (0..headers.len())
+            .filter(|i| Some(*i) != id_col && *i != activity_col && *i != timestamp_col && !object_cols.iter().any(|(c, _)| c == i))
+            .collect(),
+    };
+
+    let mut log = OcelLog::default();
+    for (row, record) in reader.records().enumerate() {
+        let record = record?;
+        let line = row + 2;
+        let field = |i: usize| record.get(i).unwrap_or("").trim();
+
+        let id = match id_col {
+            Some(i) => field(i).to_string(),
+            None => (row + 1).to_string(),
+        };
+        if id.is_empty() {
+            return Err(format!("line {}: the event id is empty", line).into());
+        }
+        if log.events.contains_key(&id) {
+            return Err(format!("line {}: duplicate event id {:?}", line, id).into());
+        }
+        let timestamp = parse_instant(field(timestamp_col)).map_err(|e| format!("line {}: {}", line, e))?;
+
+        let mut omap = Vec::new();
+        for (i, obj_type) in &object_cols {
+            for oid in field(*i).split(import.value_separator.as_str()).map(str::trim).filter(|s| !s.is_empty()) {
+                match log.objects.get(oid) {
+                    Some(object) if object.obj_type != *obj_type => {
+                        warn!("line {}: object {:?} appears as {} and {}, keeping {}", line, oid, object.obj_type, obj_type, object.obj_type);
+                    }
+                    Some(_) => {}
+                    None => {
+                        let object = Object {
+                            obj_type: obj_type.to_string(),
+                            ovmap: Map::new(),
+                            relationships: Vec::new(),
+                            attribute_changes: Vec::new(),
+                        };
+                        log.objects.insert(oid.to_string(), object);
+                    }
+                }
+                if !omap.iter().any(|o| o == oid) {
+                    omap.push(oid.to_string());
+                }
+            }
+        }
+
+        let vmap: Map<String, Value> = attribute_cols
+            .iter()
+            .filter(|i| !field(**i).is_empty())
+            .map(|i| (headers[*i].clone(), cell_value(field(*i))))
+            .collect();
+
+        let event = Event { activity: field(activity_col).to_string(), timestamp, omap, vmap, relationships: Vec::new() };
+        log.events.insert(id, event);
+    }
+    log.refresh_globals();
+
+    debug!("Exporting log to {:?}", import.output);
+    log.export(&import.output)?;
+    status!("built {} events and {} objects -> {}", log.events.len(), log.objects.len(), import.output.display());
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::path::PathBuf;
+
+use chrono::{DateTime, FixedOffset};
+use clap::{Args, ValueEnum};
+use log::debug;
+
+use super::model::{Event, OcelLog};
+
+#[derive(Args, Debug)]
+pub struct OcelDedupe {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// What two events have to share to count as duplicates
+    #[clap(long, value_enum, default_value_t = EqualityKey::ActivityTimestampOmap)]
+    pub key: EqualityKey,
+
+    /// Also merge objects of the same type with identical, non-empty attributes,
+    /// pointing every reference to the first of them
+    #[clap(long)]
+    pub objects: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EqualityKey {
+    /// Activity, timestamp and the set of referenced objects
+    ActivityTimestampOmap,
+    /// Like activity-timestamp-omap, but the attributes have to match as well
+    All,
+}
+
+type EventKey<'a> = (&'a str, DateTime<FixedOffset>, BTreeSet<&'a str>, BTreeMap<&'a str, String>);
+
+fn event_key(event: &Event, key: EqualityKey) -> EventKey<'_> {
+    let vmap = match key {
+        EqualityKey::ActivityTimestampOmap => BTreeMap::new(),
+        EqualityKey::All => event.vmap.iter().map(|(k, v)| (k.as_str(), v.to_string())).collect(),
+    };
+    (event.activity.as_str(), event.timestamp, event.omap.iter().map(String::as_str).collect(), vmap)
+}
+
+pub fn run(dedupe: &OcelDedupe) -> Result<(), Box<dyn Error>> {
+    debug!("Importing log: {:?}", dedupe.path);
+    let mut log = OcelLog::import(&dedupe.path)?;
+
+    let mut dropped_objects = 0;
+    if dedupe.objects {
+        let mut first: HashMap<(String, BTreeMap<String, String>), String> = HashMap::new();
+        let mut replaced: HashMap<String, String> = HashMap::new();
+        for (id, object) in &log.objects {
+            if object.ovmap.is_empty() {
+                continue;
+            }
+            let attributes = object.ovmap.iter().map(|(k, v)| (k.clone(), v.to_string())).collect();
+            let signature = (object.obj_type.clone(), attributes);
+            match first.get(&signature) {
+                Some(kept) => {
+                    replaced.insert(id.clone(), kept.clone());
+                }
+                None => {
+                    first.insert(signature, id.clone());
+                }
+            }
+        }
+        dropped_objects = replaced.len();
+        log.objects.retain(|id, _| !replaced.contains_key(id));
+        for event in log.events.values_mut() {
+            let mut seen = HashSet::new();
+            event.omap = event
+                .omap
+                .drain(..)
+                .map(|oid| replaced.get(&oid).cloned().unwrap_or(oid))
+                .filter(|oid| seen.insert(oid.clone()))
+                .collect();
+            for relationship in &mut event.relationships {
+                if let Some(kept) = replaced.get(&relationship.object_id) {
+                    relationship.object_id = kept.clone();
+                }
+            }
+        }
+    }
+
+    let duplicates: HashSet<String> = {
+        let mut seen = HashSet::new();
+        log.events
+            .iter()
+            .filter(|(_, event)| !seen.insert(event_key(event, dedupe.key)))
+            .map(|(id, _)| id.clone())
+            .collect()
+    };
+    log.events.retain(|id, _| !duplicates.contains(id));
+    log.refresh_globals();
+
+    debug!("Exporting deduplicated log to {:?}", dedupe.output);
+    log.export(&dedupe.output)?;
+    status!(
+        "dropped {} duplicate events and {} duplicate objects, {} events and {} objects remain -> {}",
+        duplicates.len(),
+        dropped_objects,
+        log.events.len(),
+        log.objects.len(),
+        dedupe.output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use indexmap::{IndexMap, IndexSet};
+use log::debug;
+use serde_json::{Map, Value};
+
+use super::anonymize::pseudonym;
+use super::model::{display_value, value_type, OcelLog};
+
+#[derive(Args, Debug)]
+pub struct OcelDescribe {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Print up to N distinct example values per attribute
+    #[clap(long, value_name = "N", default_value_t = 0)]
+    pub sample_values: usize,
+
+    /// Pseudonymize the example values
+    #[clap(long)]
+    pub anonymize: bool,
+
+    /// Salt for --anonymize
+    #[clap(long, default_value = "", requires = "anonymize")]
+    pub salt: String,
+}
+
+#[derive(Default)]
+struct AttributeProfile {
+    types: IndexSet<&'static str>,
+    samples: IndexSet<String>,
+}
+
+#[derive(Default)]
+struct ClassProfile {
+    count: usize,
+    attributes: IndexMap<String, AttributeProfile>,
+}
+
+impl ClassProfile {
+    fn add(&mut self, map: &Map<String, Value>, samples: usize) {
+        self.count += 1;
+        for (key, value) in map {
+            let profile = self.attributes.entry(key.clone()).or_default();
+            profile.types.insert(value_type(value));
+            if profile.samples.len() < samples {
+                profile.samples.insert(display_value(value));
+            }
+        }
+    }
+}
+
+pub fn run(describe: &OcelDescribe) -> Result<(), Box<dyn Error>> {
+    debug!("Importing log: {:?}", describe.path);
+    let log = OcelLog::import(&describe.path)?;
+
+    let mut activities: IndexMap<&str, ClassProfile> = IndexMap::new();
+    for event in log.events.values() {
+        activities.entry(&event.activity).or_default().add(&event.vmap, describe.sample_values);
+    }
+    let mut obj_types: IndexMap<&str, ClassProfile> = IndexMap::new();
+    for object in log.objects.values() {
+        obj_types.entry(&object.obj_type).or_default().add(&object.ovmap, describe.sample_values);
+    }
+
+    println!("events: {}", log.events.len());
+    for (activity, profile) in &activities {
+        println!("  activity {:?} ({} events)", activity, profile.count);
+        print_attributes(describe, profile);
+    }
+    println!("objects: {}", log.objects.len());
+    for (obj_type, profile) in &obj_types {
+        println!("  type {:?} ({} objects)", obj_type, profile.count);
+        print_attributes(describe, profile);
+    }
+    Ok(())
+}
+
+fn print_attributes(describe: &OcelDescribe, profile: &ClassProfile) {
+    for (key, attribute) in &profile.attributes {
+        let types: Vec<&str> = attribute.types.iter().copied().collect();
+        if attribute.samples.is_empty() {
+            println!("    {}: {}", key, types.join("|"));
+            continue;
+        }
+        let samples: Vec<String> = attribute
+            .samples
+            .iter()
+            .map(|v| if describe.anonymize { pseudonym(v, &describe.salt) } else { v.clone() })
+            .collect();
+        println!("    {}: {} e.g. {}", key, types.join("|"), samples.join(", "));
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::PathBuf;
+use std::process;
+
+use clap::Args;
+use log::debug;
+use serde_json::{json, Map, Value};
+
+use super::compare::{compare, values_equivalent, LogDiff};
+use super::model::{display_value, OcelLog};
+use super::ReportFormat;
+use crate::exit;
+use crate::table::{change_style, Cell, Table};
+
+#[derive(Args, Debug)]
+pub struct OcelDiff {
+    /// Path to the original OCEL file
+    pub before: PathBuf,
+
+    /// Path to the OCEL file to compare against it
+    pub after: PathBuf,
+
+    /// Number of changed events and objects whose attribute differences are shown
+    #[clap(long, default_value_t = 10)]
+    pub examples: usize,
+
+    /// Output format. Default: table
+    #[clap(long, value_enum)]
+    pub format: Option<ReportFormat>,
+
+    /// Exit with status 1 if the logs differ, like `diff`
+    #[clap(long)]
+    pub exit_code: bool,
+}
+
+/// An attribute with its value before and after. Missing values are `None`.
+type Difference = (String, Option<Value>, Option<Value>);
+
+/// Attributes that differ on one event or object, after its kind and id.
+type Change = (&'static str, String, Vec<Difference>);
+
+fn changed(key: &str, before: Value, after: Value) -> Difference {
+    (key.to_string(), Some(before), Some(after))
+}
+
+/// Keys whose values differ between two attribute maps.
+fn attribute_differences(before: &Map<String, Value>, after: &Map<String, Value>) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    for (key, value) in before {
+        match after.get(key) {
+            Some(other) if values_equivalent(value, other) => {}
+            other => differences.push((key.clone(), Some(value.clone()), other.cloned())),
+        }
+    }
+    for (key, value) in after {
+        if !before.contains_key(key) {
+            differences.push((key.clone(), None, Some(value.clone())));
+        }
+    }
+    differences
+}
+
+/// Per-key counts before and after, for every key present in either.
+fn count_deltas<'a>(
+    before: impl Iterator<Item = &'a str>,
+    after: impl Iterator<Item = &'a str>,
+) -> BTreeMap<&'a str, (usize, usize)> {
+    let mut counts: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    for key in before {
+        counts.entry(key).or_default().0 += 1;
+    }
+    for key in after {
+        counts.entry(key).or_default().1 += 1;
+    }
+    counts.retain(|_, (b, a)| b != a);
+    counts
+}
+
+pub fn run(diff_args: &OcelDiff) -> Result<(), Box<dyn Error>> {
+    debug!("Importing logs: {:?} and {:?}", diff_args.before, diff_args.after);
+    let before = OcelLog::import(&diff_args.before)?;
+    let after = OcelLog::import(&diff_args.after)?;
+
+    let diff = compare(&before, &after);
+    let activities = count_deltas(
+        before.events.values().map(|e| e.activity.as_str()),
+        after.events.values().map(|e| e.activity.as_str()),
+    );
+    let object_types = count_deltas(
+        before.objects.values().map(|o| o.obj_type.as_str()),
+        after.objects.values().map(|o| o.obj_type.as_str()),
+    );
+
+    let mut changes: Vec<Change> = Vec::new();
+    for id in diff.events_changed.iter().take(diff_args.examples) {
+        let (a, b) = (&before.events[id], &after.events[id]);
+        let mut differences = attribute_differences(&a.vmap, &b.vmap);
+        if a.activity != b.activity {
+            differences.push(changed("ocel:activity", Value::from(a.activity.as_str()), Value::from(b.activity.as_str())));
+        }
+        if a.timestamp != b.timestamp {
+            differences.push(changed("ocel:timestamp", Value::from(a.timestamp.to_rfc3339()), Value::from(b.timestamp.to_rfc3339())));
+        }
+        let mut omap_a = a.omap.clone();
+        let mut omap_b = b.omap.clone();
+        omap_a.sort();
+        omap_b.sort();
+        if omap_a != omap_b {
+            differences.push(changed("ocel:omap", Value::from(omap_a), Value::from(omap_b)));
+        }
+        changes.push(("event", id.clone(), differences));
+    }
+    for id in diff.objects_changed.iter().take(diff_args.examples) {
+        let (a, b) = (&before.objects[id], &after.objects[id]);
+        let mut differences = attribute_differences(&a.ovmap, &b.ovmap);
+        if a.obj_type != b.obj_type {
+            differences.push(changed("ocel:type", Value::from(a.obj_type.as_str()), Value::from(b.obj_type.as_str())));
+        }
+        changes.push(("object", id.clone(), differences));
+    }
+
+    match diff_args.format.unwrap_or(ReportFormat::Table) {
+        ReportFormat::Table => print_text(&diff, &activities, &object_types, &changes, diff_args.examples),
+        ReportFormat::Csv => print_csv(&diff, &activities, &object_types, &changes)?,
+        ReportFormat::Json => print_json(&diff, &activities, &object_types, &changes)?,
+    }
+
+    if diff_args.exit_code && !diff.is_empty() {
+        process::exit(exit::VALIDATION_FAILED);
+    }
+    Ok(())
+}
+
+fn print_text(
+    diff: &LogDiff,
+    activities: &BTreeMap<&str, (usize, usize)>,
+    object_types: &BTreeMap<&str, (usize, usize)>,
+    changes: &[Change],
+    examples: usize,
+) {
+    if diff.is_empty() {
+        println!("no differences");
+        return;
+    }
+    diff.print_summary(examples);
+    for (title, column, counts) in [("events per activity", "activity", activities), ("objects per type", "object type", object_types)] {
+        if counts.is_empty() {
+            continue;
+        }
+        println!("{}:", title);
+        let mut table = Table::new(&[column, "before", "after", "change"]).right(&[1, 2, 3]).indent(4);
+        for (key, (b, a)) in counts {
+            let change = Cell::styled(format!("{:+}", *a as i64 - *b as i64), change_style(*b, *a));
+            table.row(vec![(*key).into(), (*b).into(), (*a).into(), change]);
+        }
+        table.print();
+    }
+    let show = |v: &Option<Value>| v.as_ref().map(display_value).unwrap_or_else(|| "(missing)".to_string());
+    for (kind, id, differences) in changes {
+        println!("{} {}:", kind, id);
+        for (key, b, a) in differences {
+            println!("    {}: {} -> {}", key, show(b), show(a));
+        }
+    }
+}
+
+/// One row per added, removed or changed event or object, per listed attribute
+/// difference and per changed count of an activity or object type.
+fn print_csv(
+    diff: &LogDiff,
+    activities: &BTreeMap<&str, (usize, usize)>,
+    object_types: &BTreeMap<&str, (usize, usize)>,
+    changes: &[Change],
+) -> Result<(), Box<dyn Error>> {
+    let mut writer = csv::Writer::from_writer(std::io::stdout());
+    writer.write_record(["change", "kind", "id", "attribute", "before", "after"])?;
+    let sections = [
+        ("added", "event", &diff.events_added),
+        ("removed", "event", &diff.events_removed),
+        ("added", "object", &diff.objects_added),
+        ("removed", "object", &diff.objects_removed),
+    ];
+    for (change, kind, ids) in sections {
+        for id in ids {
+            writer.write_record([change, kind, id.as_str(), "", "", ""])?;
+        }
+    }
+    let show = |v: &Option<Value>| v.as_ref().map(display_value).unwrap_or_default();
+    for (kind, ids) in [("event", &diff.events_changed), ("object", &diff.objects_changed)] {
+        for id in ids {
+            match changes.iter().find(|(k, changed, _)| *k == kind && changed == id) {
+                Some((_, _, differences)) => {
+                    for (key, b, a) in differences {
+                        writer.write_record(["changed", kind, id.as_str(), key.as_str(), show(b).as_str(), show(a).as_str()])?;
+                    }
+                }
+                // beyond --examples only the id is listed
+                None => writer.write_record(["changed", kind, id.as_str(), "", "", ""])?,
+            }
+        }
+    }
+    for (kind, counts) in [("activity", activities), ("object_type", object_types)] {
+        for (key, (b, a)) in counts {
+            writer.write_record(["count", kind, *key, "", b.to_string().as_str(), a.to_string().as_str()])?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn print_json(
+    diff: &LogDiff,
+    activities: &BTreeMap<&str, (usize, usize)>,
+    object_types: &BTreeMap<&str, (usize, usize)>,
+    changes: &[Change],
+) -> Result<(), Box<dyn Error>> {
+    let deltas = |counts: &BTreeMap<&str, (usize, usize)>| -> Map<String, Value> {
+        counts.iter().map(|(key, (b, a))| (key.to_string(), json!({"before": b, "after": a}))).collect()
+    };
+    let changes: Map<String, Value> = changes
+        .iter()
+        .map(|(kind, id, differences)| {
+            let attributes: Map<String, Value> =
+                differences.iter().map(|(key, b, a)| (key.clone(), json!({"before": b, "after": a}))).collect();
+            (format!("{} {}", kind, id), Value::Object(attributes))
+        })
+        .collect();
+    let report = json!({
+        "identical": diff.is_empty(),
+        "events_added": diff.events_added,
+        "events_removed": diff.events_removed,
+        "events_changed": diff.events_changed,
+        "objects_added": diff.objects_added,
+        "objects_removed": diff.objects_removed,
+        "objects_changed": diff.objects_changed,
+        "activities": deltas(activities),
+        "object_types": deltas(object_types),
+        "changes": changes,
+    });
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
@@ -0,0 +1,68 @@
+//! Detection of text that was decoded with the wrong character set somewhere
+//! between the source system and the log.
+use serde_json::Value;
+
+use super::model::OcelLog;
+
+pub struct EncodingIssue {
+    /// `event <id>` or `object <id>`
+    pub location: String,
+    pub attribute: String,
+    pub reason: &'static str,
+}
+
+/// Why `text` looks mis-encoded, if it does.
+pub fn suspicious(text: &str) -> Option<&'static str> {
+    if text.contains('\u{FFFD}') {
+        return Some("contains the U+FFFD replacement character");
+    }
+    if text.chars().any(|c| ('\u{80}'..='\u{9F}').contains(&c)) {
+        return Some("contains C1 control characters");
+    }
+    // UTF-8 multi-byte sequences read as Latin-1/Windows-1252 start with Ã, Â or â€
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next = chars.peek().copied();
+        match (c, next) {
+            ('Ã' | 'Â', Some(n)) if ('\u{A0}'..='\u{BF}').contains(&n) || "€‚ƒ„…†‡ˆ‰Š‹ŒŽ‘’“”•–—˜™š›œžŸ".contains(n) => {
+                return Some("looks like UTF-8 decoded as Latin-1 (mojibake)");
+            }
+            ('â', Some('€')) => return Some("looks like UTF-8 decoded as Windows-1252 (mojibake)"),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn scan_value(value: &Value, location: &str, attribute: &str, issues: &mut Vec<EncodingIssue>) {
+    match value {
+        Value::String(s) => {
+            if let Some(reason) = suspicious(s) {
+                issues.push(EncodingIssue { location: location.to_string(), attribute: attribute.to_string(), reason });
+            }
+        }
+        Value::Array(values) => values.iter().for_each(|v| scan_value(v, location, attribute, issues)),
+        Value::Object(map) => map.iter().for_each(|(k, v)| scan_value(v, location, &format!("{}.{}", attribute, k), issues)),
+        _ => {}
+    }
+}
+
+/// Scans activities, object types and all string attribute values of `log`.
+pub fn check(log: &OcelLog) -> Vec<EncodingIssue> {
+    let mut issues = Vec::new();
+    for (id, event) in &log.events {
+        let location = format!("event {}", id);
+        scan_value(&Value::String(event.activity.clone()), &location, "ocel:activity", &mut issues);
+        for (key, value) in &event.vmap {
+            scan_value(value, &location, key, &mut issues);
+        }
+    }
+    for (id, object) in &log.objects {
+        let location = format!("object {}", id);
+        scan_value(&Value::String(object.obj_type.clone()), &location, "ocel:type", &mut issues);
+        for (key, value) in &object.ovmap {
+            scan_value(value, &location, key, &mut issues);
+        }
+    }
+    issues
+}
@@ -0,0 +1,180 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use log::debug;
+
+use super::model::{Event, OcelLog};
+use super::window::TimeWindow;
+use crate::plan;
+
+#[derive(Args, Debug)]
+pub struct OcelFilter {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// Match events of this activity. Can be given several times
+    #[clap(long, multiple_occurrences = true, value_name = "ACTIVITY")]
+    pub activity: Vec<String>,
+
+    /// Match events referencing an object of this type. Can be given several times
+    #[clap(long, multiple_occurrences = true, value_name = "TYPE")]
+    pub object_type: Vec<String>,
+
+    #[clap(flatten)]
+    pub window: TimeWindow,
+
+    /// Keep the matching events (the default)
+    #[clap(long, conflicts_with = "drop")]
+    pub keep: bool,
+
+    /// Drop the matching events and keep everything else
+    #[clap(long)]
+    pub drop: bool,
+
+    /// Import the log and report what would be written, without writing anything
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+impl OcelFilter {
+    /// An event matches if it satisfies every predicate that was given.
+    fn matches(&self, log: &OcelLog, event: &Event) -> bool {
+        if !self.activity.is_empty() && !self.activity.contains(&event.activity) {
+            return false;
+        }
+        if !self.object_type.is_empty()
+            && !event
+                .omap
+                .iter()
+                .filter_map(|oid| log.objects.get(oid))
+                .any(|o| self.object_type.contains(&o.obj_type))
+        {
+            return false;
+        }
+        self.window.contains(event.timestamp)
+    }
+
+    /// Ids of the events that stay in the log: the matching ones, or with `--drop`
+    /// the others.
+    fn selected<'a>(&self, log: &'a OcelLog) -> HashSet<&'a str> {
+        log.events.iter().filter(|(_, event)| self.matches(log, event) != self.drop).map(|(id, _)| id.as_str()).collect()
+    }
+}
+
+pub fn run(filter: &OcelFilter) -> Result<(), Box<dyn Error>> {
+    filter.window.check()?;
+
+    debug!("Importing log: {:?}", filter.path);
+    let log = OcelLog::import(&filter.path)?;
+
+    let selected = filter.selected(&log);
+    let filtered = log.subset(&selected, &HashSet::new());
+    if filter.dry_run {
+        println!(
+            "would keep {} of {} events and {} of {} objects",
+            filtered.events.len(),
+            log.events.len(),
+            filtered.objects.len(),
+            log.objects.len()
+        );
+        plan::output(&filter.output);
+        return Ok(());
+    }
+
+    debug!("Exporting filtered log to {:?}", filter.output);
+    filtered.export(&filter.output)?;
+    status!(
+        "kept {} of {} events and {} of {} objects -> {}",
+        filtered.events.len(),
+        log.events.len(),
+        filtered.objects.len(),
+        log.objects.len(),
+        filter.output.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocel::time::parse_instant;
+
+    fn log() -> OcelLog {
+        serde_json::from_str(
+            r#"{"ocel:events": {
+                    "e1": {"ocel:activity": "place", "ocel:timestamp": "2023-01-01T00:00:00Z",
+                           "ocel:omap": ["o1"], "ocel:vmap": {}},
+                    "e2": {"ocel:activity": "pick", "ocel:timestamp": "2023-01-02T00:00:00Z",
+                           "ocel:omap": ["i1"], "ocel:vmap": {}},
+                    "e3": {"ocel:activity": "place", "ocel:timestamp": "2023-01-03T00:00:00Z",
+                           "ocel:omap": ["o2", "i1"], "ocel:vmap": {}}},
+                "ocel:objects": {
+                    "o1": {"ocel:type": "order", "ocel:ovmap": {}},
+                    "o2": {"ocel:type": "order", "ocel:ovmap": {}},
+                    "i1": {"ocel:type": "item", "ocel:ovmap": {}}}}"#,
+        )
+        .unwrap()
+    }
+
+    fn filter() -> OcelFilter {
+        OcelFilter {
+            path: PathBuf::new(),
+            output: PathBuf::new(),
+            activity: Vec::new(),
+            object_type: Vec::new(),
+            window: TimeWindow::default(),
+            keep: false,
+            drop: false,
+            dry_run: false,
+        }
+    }
+
+    fn selected(filter: &OcelFilter) -> Vec<String> {
+        let log = log();
+        let mut ids: Vec<String> = filter.selected(&log).into_iter().map(str::to_string).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn keeps_or_drops_events_of_an_activity() {
+        let mut by_activity = OcelFilter { activity: vec!["place".to_string()], ..filter() };
+        assert_eq!(selected(&by_activity), ["e1", "e3"]);
+        by_activity.drop = true;
+        assert_eq!(selected(&by_activity), ["e2"]);
+    }
+
+    #[test]
+    fn keeps_or_drops_events_of_an_object_type() {
+        let mut by_type = OcelFilter { object_type: vec!["item".to_string()], ..filter() };
+        assert_eq!(selected(&by_type), ["e2", "e3"]);
+        by_type.drop = true;
+        assert_eq!(selected(&by_type), ["e1"]);
+    }
+
+    #[test]
+    fn keeps_or_drops_events_inside_the_window() {
+        let window = TimeWindow { from: Some(parse_instant("2023-01-02").unwrap()), to: Some(parse_instant("2023-01-03").unwrap()) };
+        let mut by_time = OcelFilter { window, ..filter() };
+        assert_eq!(selected(&by_time), ["e2"]);
+        by_time.drop = true;
+        assert_eq!(selected(&by_time), ["e1", "e3"]);
+
+        // all given predicates have to match
+        let combined = OcelFilter { activity: vec!["place".to_string()], object_type: vec!["item".to_string()], ..filter() };
+        assert_eq!(selected(&combined), ["e3"]);
+    }
+
+    #[test]
+    fn rejects_an_empty_window() {
+        let instant = parse_instant("2023-01-02").unwrap();
+        let window = TimeWindow { from: Some(instant), to: Some(instant) };
+        assert!(OcelFilter { window, ..filter() }.window.check().is_err());
+    }
+}
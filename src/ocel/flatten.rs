@@ -0,0 +1,113 @@
+//! Flattening of an object-centric log into a classic XES event log for one case
+//! notion. Every object of the chosen type becomes a trace; an event is copied into
+//! the trace of each such object it references and dropped if it references none.
+use std::error::Error;
+use std::io::Write;
+use std::path::PathBuf;
+
+use clap::Args;
+use log::debug;
+use serde_json::Value;
+
+use super::model::{parse_timestamp, OcelLog};
+use crate::compression;
+use crate::xml::escape;
+
+#[derive(Args, Debug)]
+pub struct OcelFlatten {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Object type used as the case notion
+    #[clap(long, value_name = "TYPE")]
+    pub object_type: String,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+}
+
+pub fn run(flatten: &OcelFlatten) -> Result<(), Box<dyn Error>> {
+    debug!("Importing log: {:?}", flatten.path);
+    let log = OcelLog::import(&flatten.path)?;
+    if !log.objects.values().any(|o| o.obj_type == flatten.object_type) {
+        let types = log.object_types().join(", ");
+        return Err(format!("no object is of type {:?}, available types: {}", flatten.object_type, types).into());
+    }
+
+    debug!("Flattening on {:?} to {:?}", flatten.object_type, flatten.output);
+    let mut out = compression::create(&flatten.output)?;
+    let (traces, events) = write_xes(&log, &flatten.object_type, &mut out)?;
+    out.flush()?;
+
+    let dropped = log
+        .events
+        .values()
+        .filter(|e| !e.omap.iter().any(|oid| log.objects.get(oid).map(|o| o.obj_type.as_str()) == Some(flatten.object_type.as_str())))
+        .count();
+    status!(
+        "{} traces with {} events, dropped {} events without a {} -> {}",
+        traces,
+        events,
+        dropped,
+        flatten.object_type,
+        flatten.output.display()
+    );
+    Ok(())
+}
+
+/// Writes the flattened log and returns the number of traces and events written.
+fn write_xes<W: Write>(log: &OcelLog, object_type: &str, out: &mut W) -> Result<(usize, usize), Box<dyn Error>> {
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<log xes.version="1.0" xes.features="nested-attributes">"#)?;
+    writeln!(out, r#"  <extension name="Concept" prefix="concept" uri="http://www.xes-standard.org/concept.xesext"/>"#)?;
+    writeln!(out, r#"  <extension name="Time" prefix="time" uri="http://www.xes-standard.org/time.xesext"/>"#)?;
+
+    let (mut traces, mut events) = (0, 0);
+    for (oid, trace) in log.object_traces() {
+        let object = match log.objects.get(oid) {
+            Some(object) if object.obj_type == object_type => object,
+            _ => continue,
+        };
+        traces += 1;
+        writeln!(out, "  <trace>")?;
+        writeln!(out, r#"    <string key="concept:name" value="{}"/>"#, escape(oid))?;
+        for (key, value) in &object.ovmap {
+            write_attribute(out, 2, key, value)?;
+        }
+        for eid in trace {
+            let event = &log.events[eid];
+            events += 1;
+            writeln!(out, "    <event>")?;
+            writeln!(out, r#"      <string key="concept:name" value="{}"/>"#, escape(&event.activity))?;
+            writeln!(out, r#"      <date key="time:timestamp" value="{}"/>"#, event.timestamp.to_rfc3339())?;
+            writeln!(out, r#"      <string key="ocel:eid" value="{}"/>"#, escape(eid))?;
+            for (key, value) in &event.vmap {
+                write_attribute(out, 3, key, value)?;
+            }
+            writeln!(out, "    </event>")?;
+        }
+        writeln!(out, "  </trace>")?;
+    }
+    writeln!(out, "</log>")?;
+    Ok((traces, events))
+}
+
+/// Writes `value` as a typed XES attribute. Lists and maps have no faithful XES
+/// counterpart and are written as their JSON text.
+fn write_attribute<W: Write>(out: &mut W, depth: usize, key: &str, value: &Value) -> Result<(), Box<dyn Error>> {
+    let indent = "  ".repeat(depth);
+    let key = escape(key);
+    match value {
+        Value::Bool(b) => writeln!(out, r#"{}<boolean key="{}" value="{}"/>"#, indent, key, b)?,
+        Value::Number(n) if n.is_f64() => writeln!(out, r#"{}<float key="{}" value="{}"/>"#, indent, key, n)?,
+        Value::Number(n) => writeln!(out, r#"{}<int key="{}" value="{}"/>"#, indent, key, n)?,
+        Value::String(s) => match parse_timestamp(s) {
+            Some(ts) => writeln!(out, r#"{}<date key="{}" value="{}"/>"#, indent, key, ts.to_rfc3339())?,
+            None => writeln!(out, r#"{}<string key="{}" value="{}"/>"#, indent, key, escape(s))?,
+        },
+        Value::Null => writeln!(out, r#"{}<string key="{}" value=""/>"#, indent, key)?,
+        other => writeln!(out, r#"{}<string key="{}" value="{}"/>"#, indent, key, escape(&other.to_string()))?,
+    }
+    Ok(())
+}
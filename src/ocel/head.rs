@@ -0,0 +1,93 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use indexmap::IndexMap;
+use log::debug;
+
+use super::model::{display_value, Event, OcelLog};
+
+#[derive(Args, Debug)]
+pub struct OcelHead {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Number of events to print
+    #[clap(short = 'n', long, default_value_t = 10)]
+    pub count: usize,
+
+    /// Only print events of this activity
+    #[clap(short, long)]
+    pub activity: Option<String>,
+
+    /// Print the events as a JSON-OCEL events map
+    #[clap(long)]
+    pub json: bool,
+}
+
+pub fn run(head: &OcelHead) -> Result<(), Box<dyn Error>> {
+    preview(head, false)
+}
+
+/// `ocel tail`: the same as `head`, but from the end of the log.
+pub fn run_tail(tail: &OcelHead) -> Result<(), Box<dyn Error>> {
+    preview(tail, true)
+}
+
+fn preview(head: &OcelHead, from_end: bool) -> Result<(), Box<dyn Error>> {
+    debug!("Importing log: {:?}", head.path);
+    let log = OcelLog::import(&head.path)?;
+
+    if let Some(activity) = &head.activity {
+        if !log.events.values().any(|event| &event.activity == activity) {
+            return Err(format!(
+                "activity {:?} never occurs in {:?}. Available activities: {}",
+                activity,
+                head.path,
+                log.activities().join(", ")
+            )
+            .into());
+        }
+    }
+
+    let matching = log.events.iter().filter(|(_, event)| head.activity.as_ref().map_or(true, |a| &event.activity == a));
+    let events: IndexMap<&String, &Event> = if from_end {
+        let mut last: Vec<(&String, &Event)> = matching.rev().take(head.count).collect();
+        last.reverse();
+        last.into_iter().collect()
+    } else {
+        matching.take(head.count).collect()
+    };
+
+    if head.json {
+        println!("{}", serde_json::to_string_pretty(&events)?);
+    } else {
+        for (id, event) in events {
+            print_event(&log, id, event);
+        }
+    }
+    Ok(())
+}
+
+pub fn print_event(log: &OcelLog, id: &str, event: &Event) {
+    println!("{} | {} | {}", id, event.activity, event.timestamp.to_rfc3339());
+    for (key, value) in &event.vmap {
+        println!("    {}: {}", key, display_value(value));
+    }
+    println!("    objects:");
+    for oid in &event.omap {
+        let object = match log.objects.get(oid) {
+            Some(object) => object,
+            None => {
+                println!("        {} (missing)", oid);
+                continue;
+            }
+        };
+        let attributes: Vec<String> = object.ovmap.iter().map(|(k, v)| format!("{}={}", k, display_value(v))).collect();
+        if attributes.is_empty() {
+            println!("        {} ({})", oid, object.obj_type);
+        } else {
+            println!("        {} ({}) {}", oid, object.obj_type, attributes.join(", "));
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Object interaction networks: two objects interact when they take part in the same
+//! event. Unlike the OCDG relations, there is no distinction by kind of relation,
+//! only by how often objects, or object types, meet.
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use indexmap::IndexMap;
+use log::debug;
+
+use super::model::OcelLog;
+use crate::ocdg::formats::{self, GraphFormat};
+use crate::ocdg::graph::{Edge, Graph, Node};
+use crate::ocdg::palette;
+
+#[derive(Args, Debug)]
+pub struct OcelInteractions {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// Graph format to write. Inferred from the output extension if unset, GEXF
+    /// otherwise
+    #[clap(long, value_enum)]
+    pub format: Option<GraphFormat>,
+
+    /// Aggregate objects to one node per object type
+    #[clap(long)]
+    pub by_type: bool,
+
+    /// Drop interactions observed in fewer events than this
+    #[clap(long, default_value_t = 1)]
+    pub min_events: usize,
+}
+
+fn attribute(attributes: &mut IndexMap<String, String>, key: &str, value: impl ToString) {
+    attributes.insert(key.to_string(), value.to_string());
+}
+
+pub fn run(args: &OcelInteractions) -> Result<(), Box<dyn Error>> {
+    debug!("Importing log: {:?}", args.path);
+    let log = OcelLog::import(&args.path)?;
+    let types = log.object_types();
+    let color = |obj_type: &str| palette::nth(types.iter().position(|t| *t == obj_type).unwrap_or(0));
+
+    // events shared by each pair of nodes, and the distinct object pairs of a type pair
+    let mut shared: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    let mut pairs: HashMap<(&str, &str), BTreeSet<(&str, &str)>> = HashMap::new();
+    for event in log.events.values() {
+        let objects: BTreeSet<&str> = event.omap.iter().map(String::as_str).filter(|oid| log.objects.contains_key(*oid)).collect();
+        let objects: Vec<&str> = objects.into_iter().collect();
+        let mut keys: BTreeSet<(&str, &str)> = BTreeSet::new();
+        for (i, a) in objects.iter().enumerate() {
+            for b in &objects[i + 1..] {
+                if args.by_type {
+                    let (ta, tb) = (log.objects[*a].obj_type.as_str(), log.objects[*b].obj_type.as_str());
+                    let key = if ta <= tb { (ta, tb) } else { (tb, ta) };
+                    pairs.entry(key).or_default().insert((*a, *b));
+                    keys.insert(key);
+                } else {
+                    keys.insert((*a, *b));
+                }
+            }
+        }
+        // an event counts once per pair no matter how many of its objects meet in it
+        for key in keys {
+            *shared.entry(key).or_default() += 1;
+        }
+    }
+    shared.retain(|_, events| *events >= args.min_events);
+
+    let mut graph = Graph::default();
+    if args.by_type {
+        let mut objects: BTreeMap<&str, usize> = BTreeMap::new();
+        for object in log.objects.values() {
+            *objects.entry(&object.obj_type).or_default() += 1;
+        }
+        for (obj_type, count) in objects {
+            let mut node = Node { label: obj_type.to_string(), color: Some(color(obj_type)), ..Node::default() };
+            attribute(&mut node.attributes, "type", obj_type);
+            attribute(&mut node.attributes, "objects", count);
+            graph.nodes.insert(obj_type.to_string(), node);
+        }
+    } else {
+        let connected: BTreeSet<&str> = shared.keys().flat_map(|(a, b)| [*a, *b]).collect();
+        for (oid, object) in &log.objects {
+            if connected.contains(oid.as_str()) {
+                let mut node = Node { label: oid.clone(), color: Some(color(&object.obj_type)), ..Node::default() };
+                attribute(&mut node.attributes, "type", &object.obj_type);
+                graph.nodes.insert(oid.clone(), node);
+            }
+        }
+    }
+    for ((a, b), events) in &shared {
+        let mut edge = Edge {
+            id: graph.edges.len().to_string(),
+            source: a.to_string(),
+            target: b.to_string(),
+            weight: Some(*events as f64),
+            ..Edge::default()
+        };
+        attribute(&mut edge.attributes, "events", events);
+        if let Some(objects) = pairs.get(&(*a, *b)) {
+            attribute(&mut edge.attributes, "object_pairs", objects.len());
+        }
+        graph.edges.push(edge);
+    }
+
+    let format = GraphFormat::resolve(args.format, &args.output);
+    debug!("Writing the interaction network to {:?} as {:?}", args.output, format);
+    formats::write(&graph, &args.output, format)?;
+    status!(
+        "{} {} and {} interactions -> {}",
+        graph.nodes.len(),
+        if args.by_type { "object types" } else { "objects" },
+        graph.edges.len(),
+        args.output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,267 @@
+//! Object lifecycles: the first and last event, activity sequence and lifespan of
+//! every object, summarized per object type. The start and end activities show at a
+//! glance whether an extraction caught objects from beginning to end or cut them off.
+use std::error::Error;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use indexmap::IndexMap;
+use log::debug;
+use serde_json::{json, Value};
+
+use super::model::{Event, OcelLog};
+use super::performance::Stats;
+use super::time::format_seconds;
+use super::{report_writer, ReportFormat};
+use crate::compression;
+use crate::table::{self, Cell, Table};
+
+#[derive(Args, Debug)]
+pub struct OcelLifecycle {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Only inspect objects of these types. May be repeated or comma separated
+    #[clap(long, value_name = "TYPE", use_value_delimiter = true, multiple_occurrences = true)]
+    pub object_type: Vec<String>,
+
+    /// Number of start and end activities listed per object type, 0 for all
+    #[clap(long, default_value_t = 5)]
+    pub top: usize,
+
+    /// Output format of the summary, table by default. Table durations are rounded,
+    /// CSV and JSON ones are in seconds
+    #[clap(long, value_enum)]
+    pub format: Option<ReportFormat>,
+
+    /// Write the summary to this file instead of stdout
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Additionally write one CSV row per object to this file
+    #[clap(long, value_name = "PATH")]
+    pub objects_csv: Option<PathBuf>,
+}
+
+/// The lifecycle of one object. Objects without events have no first and last event.
+struct Lifecycle<'a> {
+    id: &'a str,
+    obj_type: &'a str,
+    first: Option<(&'a str, &'a Event)>,
+    last: Option<(&'a str, &'a Event)>,
+    activities: Vec<&'a str>,
+}
+
+impl Lifecycle<'_> {
+    fn lifespan(&self) -> Option<f64> {
+        let (first, last) = self.first.zip(self.last)?;
+        Some((last.1.timestamp - first.1.timestamp).num_milliseconds() as f64 / 1000.0)
+    }
+}
+
+/// The lifecycles of one object type.
+#[derive(Default)]
+struct TypeSummary<'a> {
+    objects: usize,
+    without_events: usize,
+    events: Vec<f64>,
+    lifespans: Vec<f64>,
+    starts: IndexMap<&'a str, usize>,
+    ends: IndexMap<&'a str, usize>,
+}
+
+impl TypeSummary<'_> {
+    fn with_events(&self) -> usize {
+        self.objects - self.without_events
+    }
+}
+
+fn lifecycles<'a>(log: &'a OcelLog, object_types: &[String]) -> Vec<Lifecycle<'a>> {
+    let mut lifecycles = Vec::new();
+    for (oid, trace) in log.object_traces() {
+        let obj_type = match log.objects.get(oid) {
+            Some(object) if object_types.is_empty() || object_types.contains(&object.obj_type) => object.obj_type.as_str(),
+            _ => continue,
+        };
+        let events: Vec<(&str, &Event)> = trace.iter().map(|eid| (*eid, &log.events[*eid])).collect();
+        lifecycles.push(Lifecycle {
+            id: oid,
+            obj_type,
+            first: events.first().copied(),
+            last: events.last().copied(),
+            activities: events.iter().map(|(_, event)| event.activity.as_str()).collect(),
+        });
+    }
+    lifecycles
+}
+
+fn summarize<'a>(lifecycles: &[Lifecycle<'a>]) -> IndexMap<&'a str, TypeSummary<'a>> {
+    let mut per_type: IndexMap<&str, TypeSummary> = IndexMap::new();
+    for lifecycle in lifecycles {
+        let summary = per_type.entry(lifecycle.obj_type).or_default();
+        summary.objects += 1;
+        summary.events.push(lifecycle.activities.len() as f64);
+        match (lifecycle.activities.first(), lifecycle.activities.last(), lifecycle.lifespan()) {
+            (Some(start), Some(end), Some(lifespan)) => {
+                *summary.starts.entry(*start).or_default() += 1;
+                *summary.ends.entry(*end).or_default() += 1;
+                summary.lifespans.push(lifespan);
+            }
+            _ => summary.without_events += 1,
+        }
+    }
+    per_type.sort_keys();
+    for summary in per_type.values_mut() {
+        summary.starts.sort_by(|_, a, _, b| b.cmp(a));
+        summary.ends.sort_by(|_, a, _, b| b.cmp(a));
+    }
+    per_type
+}
+
+pub fn run(args: &OcelLifecycle) -> Result<(), Box<dyn Error>> {
+    debug!("Importing log: {:?}", args.path);
+    let log = OcelLog::import(&args.path)?;
+    let types = log.object_types();
+    if let Some(unknown) = args.object_type.iter().find(|t| !types.contains(&t.as_str())) {
+        return Err(format!("no object is of type {:?}, available types: {}", unknown, types.join(", ")).into());
+    }
+
+    let lifecycles = lifecycles(&log, &args.object_type);
+    if let Some(path) = &args.objects_csv {
+        write_objects(path, &lifecycles)?;
+        debug!("{} object lifecycles -> {:?}", lifecycles.len(), path);
+    }
+    let per_type = summarize(&lifecycles);
+    let top = if args.top == 0 { usize::MAX } else { args.top };
+
+    let mut out = report_writer(args.output.as_deref())?;
+    match args.format.unwrap_or(ReportFormat::Table) {
+        ReportFormat::Table => {
+            let color = args.output.is_none() && table::color();
+            for (index, (obj_type, summary)) in per_type.iter().enumerate() {
+                if index > 0 {
+                    writeln!(out)?;
+                }
+                writeln!(out, "{} ({} objects, {} without events)", obj_type, summary.objects, summary.without_events)?;
+                let mut overview = Table::new(&["metric", "mean", "median", "p95", "min", "max"]).right(&[1, 2, 3, 4, 5]).indent(2);
+                if let Some(stats) = Stats::of(&summary.events) {
+                    let count = |value: f64| -> Cell { format!("{:.1}", value).into() };
+                    overview.row(vec!["events".into(), count(stats.mean), count(stats.median), count(stats.p95), count(stats.min), count(stats.max)]);
+                }
+                if let Some(stats) = Stats::of(&summary.lifespans) {
+                    let duration = |value: f64| -> Cell { format_seconds(value).into() };
+                    overview.row(vec![
+                        "lifespan".into(),
+                        duration(stats.mean),
+                        duration(stats.median),
+                        duration(stats.p95),
+                        duration(stats.min),
+                        duration(stats.max),
+                    ]);
+                }
+                overview.write(&mut out, color)?;
+                for (title, activities) in [("start activity", &summary.starts), ("end activity", &summary.ends)] {
+                    writeln!(out)?;
+                    let mut ranking = Table::new(&[title, "objects", "share"]).right(&[1, 2]).indent(2);
+                    for (activity, count) in activities.iter().take(top) {
+                        let share = *count as f64 / summary.with_events().max(1) as f64;
+                        ranking.row(vec![(*activity).into(), (*count).into(), format!("{:.1}%", share * 100.0).into()]);
+                    }
+                    ranking.write(&mut out, color)?;
+                }
+            }
+        }
+        ReportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record([
+                "object_type",
+                "objects",
+                "without_events",
+                "events_mean",
+                "lifespan_mean",
+                "lifespan_median",
+                "lifespan_p95",
+                "lifespan_max",
+                "start_activities",
+                "end_activities",
+            ])?;
+            for (obj_type, summary) in &per_type {
+                let events = Stats::of(&summary.events);
+                let lifespans = Stats::of(&summary.lifespans);
+                let field = |stats: Option<Stats>, pick: fn(Stats) -> f64| stats.map(|stats| pick(stats).to_string()).unwrap_or_default();
+                let counts = |activities: &IndexMap<&str, usize>| {
+                    activities.iter().take(top).map(|(activity, count)| format!("{}={}", activity, count)).collect::<Vec<_>>().join(";")
+                };
+                writer.write_record([
+                    obj_type.to_string(),
+                    summary.objects.to_string(),
+                    summary.without_events.to_string(),
+                    field(events, |stats| stats.mean),
+                    field(lifespans, |stats| stats.mean),
+                    field(lifespans, |stats| stats.median),
+                    field(lifespans, |stats| stats.p95),
+                    field(lifespans, |stats| stats.max),
+                    counts(&summary.starts),
+                    counts(&summary.ends),
+                ])?;
+            }
+            writer.flush()?;
+            return Ok(());
+        }
+        ReportFormat::Json => {
+            let stats_json = |stats: Option<Stats>| {
+                stats.map(|stats| {
+                    json!({
+                        "mean": stats.mean,
+                        "median": stats.median,
+                        "p95": stats.p95,
+                        "min": stats.min,
+                        "max": stats.max,
+                    })
+                })
+            };
+            let report: serde_json::Map<String, Value> = per_type
+                .iter()
+                .map(|(obj_type, summary)| {
+                    let value = json!({
+                        "objects": summary.objects,
+                        "without_events": summary.without_events,
+                        "events": stats_json(Stats::of(&summary.events)),
+                        "lifespan": stats_json(Stats::of(&summary.lifespans)),
+                        "start_activities": summary.starts.iter().take(top).collect::<IndexMap<_, _>>(),
+                        "end_activities": summary.ends.iter().take(top).collect::<IndexMap<_, _>>(),
+                    });
+                    (obj_type.to_string(), value)
+                })
+                .collect();
+            writeln!(out, "{}", serde_json::to_string_pretty(&report)?)?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// One row per object: id, type, first and last event, lifespan in seconds and the
+/// activities in order, separated by `;`.
+fn write_objects(path: &Path, lifecycles: &[Lifecycle]) -> Result<(), Box<dyn Error>> {
+    let timestamp = |event: Option<(&str, &Event)>| event.map(|(_, event)| event.timestamp.to_rfc3339()).unwrap_or_default();
+    let id = |event: Option<(&str, &Event)>| event.map(|(id, _)| id.to_string()).unwrap_or_default();
+    let mut writer = csv::Writer::from_writer(compression::create(path)?);
+    writer.write_record(["object_id", "object_type", "events", "first_event", "first_timestamp", "last_event", "last_timestamp", "lifespan", "activities"])?;
+    for lifecycle in lifecycles {
+        writer.write_record([
+            lifecycle.id.to_string(),
+            lifecycle.obj_type.to_string(),
+            lifecycle.activities.len().to_string(),
+            id(lifecycle.first),
+            timestamp(lifecycle.first),
+            id(lifecycle.last),
+            timestamp(lifecycle.last),
+            lifecycle.lifespan().map(|seconds| seconds.to_string()).unwrap_or_default(),
+            lifecycle.activities.join(";"),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
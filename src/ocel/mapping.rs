@@ -0,0 +1,41 @@
+//! Rename tables for the `remap` commands: a CSV file whose first column holds the
+//! old names and whose second column holds the new ones, below a header row.
+//! Several old names may map to the same new one, which merges them.
+use std::error::Error;
+use std::path::Path;
+
+use indexmap::IndexMap;
+
+use crate::compression;
+
+/// The renames of the table at `path`, in file order. An old name mapped twice
+/// to different new names is an error, as is an empty name.
+pub fn read(path: &Path) -> Result<IndexMap<String, String>, Box<dyn Error>> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(compression::open(path)?);
+    let mut renames: IndexMap<String, String> = IndexMap::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record?;
+        // the header is line 1
+        let line = index + 2;
+        let (old, new) = match (record.get(0), record.get(1)) {
+            (Some(old), Some(new)) => (old.trim(), new.trim()),
+            _ => return Err(format!("line {}: expected two columns, the old and the new name", line).into()),
+        };
+        if old.is_empty() || new.is_empty() {
+            return Err(format!("line {}: names must not be empty", line).into());
+        }
+        match renames.get(old) {
+            Some(earlier) if earlier != new => {
+                return Err(format!("line {}: {:?} is mapped to both {:?} and {:?}", line, old, earlier, new).into());
+            }
+            Some(_) => {}
+            None => {
+                renames.insert(old.to_string(), new.to_string());
+            }
+        }
+    }
+    if renames.is_empty() {
+        return Err("the mapping has no rows".into());
+    }
+    Ok(renames)
+}
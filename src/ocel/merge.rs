@@ -0,0 +1,202 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use log::debug;
+use serde_json::{Map, Value};
+
+use super::compare::values_equivalent;
+use super::model::{display_value, Object, OcelLog};
+
+#[derive(Args, Debug)]
+pub struct OcelMerge {
+    /// Paths to the OCEL files to merge, in order of precedence
+    #[clap(required = true, min_values = 2)]
+    pub paths: Vec<PathBuf>,
+
+    /// Output file name and location. Default: merged.jsonocel
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// How duplicate events across the inputs are detected and dropped
+    #[clap(long, value_enum, default_value_t = DedupEvents::ById)]
+    pub dedup_events: DedupEvents,
+
+    /// Which value wins when an object appears in several inputs with different
+    /// values for the same attribute
+    #[clap(long, value_enum, default_value_t = OnConflict::First)]
+    pub on_conflict: OnConflict,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnConflict {
+    /// Keep the value of the input listed first
+    First,
+    /// Take the value of the input listed last
+    Last,
+    /// Abort the merge
+    Fail,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DedupEvents {
+    /// Events with an id that was already merged are dropped
+    ById,
+    /// Events whose activity, timestamp, objects and attributes match an already
+    /// merged event are dropped, regardless of their id
+    ByContent,
+    /// Keep every event, renaming clashing ids
+    None,
+}
+
+pub fn run(merge: &OcelMerge) -> Result<(), Box<dyn Error>> {
+    let output_path = merge.output.clone().unwrap_or_else(|| PathBuf::from("merged.jsonocel"));
+
+    let mut merged = OcelLog::default();
+    let mut seen_content: HashSet<u64> = HashSet::new();
+    let mut dropped = 0;
+    let mut conflicts = 0;
+
+    for path in &merge.paths {
+        debug!("Importing log: {:?}", path);
+        let log = OcelLog::import(path)?;
+
+        fill(&mut merged.global_log, log.global_log);
+        fill(&mut merged.global_event, log.global_event);
+        fill(&mut merged.global_object, log.global_object);
+
+        for (id, object) in log.objects {
+            match merged.objects.get_mut(&id) {
+                Some(existing) => conflicts += merge_object(&id, existing, object, merge.on_conflict)?,
+                None => {
+                    merged.objects.insert(id, object);
+                }
+            }
+        }
+
+        for (id, event) in log.events {
+            let duplicate = match merge.dedup_events {
+                DedupEvents::ById => merged.events.contains_key(&id),
+                DedupEvents::ByContent => !seen_content.insert(event.content_hash()),
+                DedupEvents::None => false,
+            };
+            if duplicate {
+                dropped += 1;
+                continue;
+            }
+            let id = merged.free_event_id(&id);
+            merged.events.insert(id, event);
+        }
+    }
+
+    // the inputs usually cover consecutive periods, but may overlap
+    merged.events.sort_by(|_, a, _, b| a.timestamp.cmp(&b.timestamp));
+    merged.refresh_globals();
+    debug!("Exporting merged log to {:?}", output_path);
+    merged.export(&output_path)?;
+    status!(
+        "merged {} events and {} objects, dropped {} duplicate events, resolved {} attribute conflicts -> {}",
+        merged.events.len(),
+        merged.objects.len(),
+        dropped,
+        conflicts,
+        output_path.display()
+    );
+    Ok(())
+}
+
+/// Copies the keys of `source` that `target` does not define yet.
+fn fill(target: &mut Map<String, Value>, source: Map<String, Value>) {
+    for (key, value) in source {
+        target.entry(key).or_insert(value);
+    }
+}
+
+/// Folds a later copy of an object into the merged one and returns the number of
+/// attributes whose values disagreed. Relationships and attribute changes are
+/// united, the changes stay ordered by time.
+fn merge_object(id: &str, existing: &mut Object, other: Object, on_conflict: OnConflict) -> Result<usize, String> {
+    if existing.obj_type != other.obj_type {
+        return Err(format!("object {} is of type {} and {} in different inputs", id, existing.obj_type, other.obj_type));
+    }
+    let mut conflicts = 0;
+    for (key, value) in other.ovmap {
+        match existing.ovmap.get_mut(&key) {
+            Some(current) if values_equivalent(current, &value) => {}
+            Some(current) => {
+                conflicts += 1;
+                match on_conflict {
+                    OnConflict::First => {}
+                    OnConflict::Last => *current = value,
+                    OnConflict::Fail => {
+                        return Err(format!(
+                            "object {} has conflicting values for {}: {} and {}",
+                            id,
+                            key,
+                            display_value(current),
+                            display_value(&value)
+                        ));
+                    }
+                }
+            }
+            None => {
+                existing.ovmap.insert(key, value);
+            }
+        }
+    }
+    for relationship in other.relationships {
+        if !existing.relationships.contains(&relationship) {
+            existing.relationships.push(relationship);
+        }
+    }
+    for change in other.attribute_changes {
+        let known = existing
+            .attribute_changes
+            .iter()
+            .any(|c| c.name == change.name && c.time == change.time && values_equivalent(&c.value, &change.value));
+        if !known {
+            existing.attribute_changes.push(change);
+        }
+    }
+    // stable, so changes at the same time keep the precedence of the inputs
+    existing.attribute_changes.sort_by_key(|change| change.time);
+    Ok(conflicts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocel::model::{parse_timestamp, AttributeChange, Relationship};
+
+    fn object(relationships: &[&str], changes: &[(&str, i64)]) -> Object {
+        Object {
+            obj_type: "order".to_string(),
+            ovmap: Map::new(),
+            relationships: relationships
+                .iter()
+                .map(|target| Relationship { object_id: target.to_string(), qualifier: "contains".to_string() })
+                .collect(),
+            attribute_changes: changes
+                .iter()
+                .map(|(time, value)| AttributeChange {
+                    name: "price".to_string(),
+                    time: parse_timestamp(time).unwrap(),
+                    value: Value::from(*value),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn unites_relationships_and_attribute_changes() {
+        let mut existing = object(&["i1"], &[("2023-01-01T00:00:00Z", 1), ("2023-01-03T00:00:00Z", 3)]);
+        let other = object(&["i1", "i2"], &[("2023-01-02T00:00:00Z", 2), ("2023-01-03T00:00:00Z", 3)]);
+        merge_object("o1", &mut existing, other, OnConflict::Fail).unwrap();
+
+        let targets: Vec<&str> = existing.relationships.iter().map(|r| r.object_id.as_str()).collect();
+        assert_eq!(targets, ["i1", "i2"]);
+        let values: Vec<&Value> = existing.attribute_changes.iter().map(|c| &c.value).collect();
+        assert_eq!(values, [&Value::from(1), &Value::from(2), &Value::from(3)]);
+    }
+}
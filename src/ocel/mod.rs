@@ -0,0 +1,113 @@
+pub mod abstraction;
+pub mod anonymize;
+pub mod bottlenecks;
+pub mod compare;
+pub mod convert;
+pub mod csvexport;
+pub mod csvimport;
+pub mod dedupe;
+pub mod describe;
+pub mod diff;
+pub mod encoding;
+pub mod filter;
+pub mod flatten;
+pub mod head;
+pub mod interactions;
+pub mod lifecycle;
+pub mod mapping;
+pub mod merge;
+pub mod model;
+pub mod ocel2;
+pub mod ocel2sqlite;
+pub mod ocel2xml;
+pub mod performance;
+pub mod project;
+pub mod query;
+pub mod remap;
+pub mod repair;
+pub mod sample;
+pub mod schema;
+pub mod sort;
+pub mod split;
+pub mod stats;
+pub mod streaming;
+pub mod time;
+pub mod timeline;
+pub mod validate;
+pub mod variants;
+pub mod window;
+pub mod xmlocel;
+
+use std::error::Error;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::compression::{self, Compression};
+use crate::stdio;
+use model::{Format, OcelLog};
+
+/// How analysis commands print their results.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Aligned columns for reading in a terminal
+    Table,
+    Csv,
+    Json,
+}
+
+/// The file at `path`, or stdout if there is none.
+pub fn report_writer(path: Option<&Path>) -> Result<Box<dyn Write>, Box<dyn Error>> {
+    Ok(match path {
+        Some(path) => compression::create(path)?,
+        None => Box::new(io::stdout()),
+    })
+}
+
+/// A JSON-OCEL file for the pmrs functions that only read JSON-OCEL from a path.
+/// Logs in other formats or compressed logs are converted into a temporary file,
+/// removed on drop.
+pub enum JsonOcelPath {
+    Original(PathBuf),
+    Temporary(tempfile::TempPath),
+}
+
+impl JsonOcelPath {
+    pub fn path(&self) -> &Path {
+        match self {
+            JsonOcelPath::Original(path) => path,
+            JsonOcelPath::Temporary(path) => path,
+        }
+    }
+
+    pub fn to_string_lossy(&self) -> String {
+        self.path().to_string_lossy().into_owned()
+    }
+}
+
+pub fn as_jsonocel(path: &Path) -> Result<JsonOcelPath, Box<dyn Error>> {
+    let plain = Compression::from_path(path) == Compression::None && !stdio::is_stdio(path);
+    let json_ocel = Format::from_path(path)? == Format::JsonOcel && !ocel2::is_ocel2(path)?;
+    if plain && json_ocel {
+        return Ok(JsonOcelPath::Original(path.to_path_buf()));
+    }
+    if json_ocel {
+        // compressed JSON-OCEL only needs decompressing, which takes no more memory
+        // than a buffer, while importing would hold the whole log
+        let mut file = tempfile::Builder::new().suffix(".jsonocel").tempfile()?;
+        io::copy(&mut compression::open(path)?, file.as_file_mut())?;
+        return Ok(JsonOcelPath::Temporary(file.into_temp_path()));
+    }
+    temporary_jsonocel(&OcelLog::import(path)?)
+}
+
+/// `log` written to a temporary JSON-OCEL file, removed on drop.
+pub fn temporary_jsonocel(log: &OcelLog) -> Result<JsonOcelPath, Box<dyn Error>> {
+    let file = tempfile::Builder::new().suffix(".jsonocel").tempfile()?;
+    let mut writer = BufWriter::new(file.as_file());
+    serde_json::to_writer(&mut writer, log)?;
+    writer.flush()?;
+    drop(writer);
+    Ok(JsonOcelPath::Temporary(file.into_temp_path()))
+}
@@ -0,0 +1,308 @@
+//! Owned, serde-backed representation of a JSON-OCEL document. Unlike the pmrs
+//! `Ocel`, it keeps the original string identifiers and key order so that logs can
+//! be inspected, rewritten and exported again without losing anything.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::error::Error;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::Path;
+
+use chrono::{DateTime, FixedOffset};
+use clap::ValueEnum;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::{ocel2, ocel2sqlite, ocel2xml, xmlocel};
+use crate::compression;
+use crate::stdio;
+
+/// Serializations of an OCEL log, usually inferred from the file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    #[clap(name = "jsonocel")]
+    JsonOcel,
+    #[clap(name = "xmlocel")]
+    XmlOcel,
+    /// OCEL 2.0 JSON; detected automatically when reading `.jsonocel`/`.json` files
+    #[clap(name = "ocel2-json")]
+    Ocel2Json,
+    /// OCEL 2.0 XML; detected automatically when reading `.xmlocel`/`.xml` files
+    #[clap(name = "ocel2-xml")]
+    Ocel2Xml,
+    /// OCEL 2.0 SQLite, the format of `.sqlite` files
+    #[clap(name = "ocel2-sqlite")]
+    Ocel2Sqlite,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Result<Format, String> {
+        if stdio::is_stdio(path) {
+            return Ok(Format::JsonOcel);
+        }
+        match compression::inner_path(path).extension().and_then(|ext| ext.to_str()) {
+            Some("jsonocel") | Some("json") => Ok(Format::JsonOcel),
+            Some("xmlocel") | Some("xml") => Ok(Format::XmlOcel),
+            Some("sqlite") => Ok(Format::Ocel2Sqlite),
+            _ => Err(format!("{:?}: file format is not supported, expected .jsonocel, .xmlocel or .sqlite", path)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OcelLog {
+    #[serde(rename = "ocel:global-log", default)]
+    pub global_log: Map<String, Value>,
+    #[serde(rename = "ocel:global-event", default)]
+    pub global_event: Map<String, Value>,
+    #[serde(rename = "ocel:global-object", default)]
+    pub global_object: Map<String, Value>,
+    #[serde(rename = "ocel:events", default)]
+    pub events: IndexMap<String, Event>,
+    #[serde(rename = "ocel:objects", default)]
+    pub objects: IndexMap<String, Object>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    #[serde(rename = "ocel:activity")]
+    pub activity: String,
+    #[serde(rename = "ocel:timestamp", with = "timestamp")]
+    pub timestamp: DateTime<FixedOffset>,
+    #[serde(rename = "ocel:omap", default)]
+    pub omap: Vec<String>,
+    #[serde(rename = "ocel:vmap", default)]
+    pub vmap: Map<String, Value>,
+    /// Qualified object references of OCEL 2.0 logs. JSON-OCEL has no place for
+    /// qualifiers, so these only survive OCEL 2.0 round trips.
+    #[serde(skip)]
+    pub relationships: Vec<Relationship>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Object {
+    #[serde(rename = "ocel:type")]
+    pub obj_type: String,
+    #[serde(rename = "ocel:ovmap", default)]
+    pub ovmap: Map<String, Value>,
+    /// Object-to-object relationships (OCEL 2.0 only).
+    #[serde(skip)]
+    pub relationships: Vec<Relationship>,
+    /// Attribute values that changed over the lifetime of the object, ordered by time
+    /// (OCEL 2.0 only).
+    #[serde(skip)]
+    pub attribute_changes: Vec<AttributeChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Relationship {
+    #[serde(rename = "objectId")]
+    pub object_id: String,
+    #[serde(default)]
+    pub qualifier: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeChange {
+    pub name: String,
+    pub time: DateTime<FixedOffset>,
+    pub value: Value,
+}
+
+impl OcelLog {
+    pub fn import(path: &Path) -> Result<OcelLog, Box<dyn Error>> {
+        OcelLog::import_as(path, Format::from_path(path)?)
+    }
+
+    pub fn import_as(path: &Path, format: Format) -> Result<OcelLog, Box<dyn Error>> {
+        match format {
+            Format::JsonOcel if ocel2::is_ocel2(path)? => ocel2::read(path),
+            Format::JsonOcel => {
+                let reader = compression::open(path)?;
+                Ok(serde_json::from_reader(reader)?)
+            }
+            Format::XmlOcel if ocel2xml::is_ocel2_xml(path)? => ocel2xml::read(path),
+            Format::XmlOcel => xmlocel::read(path),
+            Format::Ocel2Json => ocel2::read(path),
+            Format::Ocel2Xml => ocel2xml::read(path),
+            Format::Ocel2Sqlite => ocel2sqlite::read(path),
+        }
+    }
+
+    pub fn export(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        self.export_as(path, Format::from_path(path)?)
+    }
+
+    pub fn export_as(&self, path: &Path, format: Format) -> Result<(), Box<dyn Error>> {
+        match format {
+            Format::JsonOcel => {
+                let mut writer = compression::create(path)?;
+                serde_json::to_writer_pretty(&mut writer, self)?;
+                writer.flush()?;
+                Ok(())
+            }
+            Format::XmlOcel => xmlocel::write(self, path),
+            Format::Ocel2Json => ocel2::write(self, path),
+            Format::Ocel2Xml => ocel2xml::write(self, path),
+            Format::Ocel2Sqlite => ocel2sqlite::write(self, path),
+        }
+    }
+
+    /// Distinct activities in order of first occurrence.
+    pub fn activities(&self) -> Vec<&str> {
+        let mut activities: Vec<&str> = Vec::new();
+        for event in self.events.values() {
+            if !activities.contains(&event.activity.as_str()) {
+                activities.push(&event.activity);
+            }
+        }
+        activities
+    }
+
+    /// Distinct object types, sorted.
+    pub fn object_types(&self) -> Vec<&str> {
+        let types: BTreeSet<&str> = self.objects.values().map(|o| o.obj_type.as_str()).collect();
+        types.into_iter().collect()
+    }
+
+    /// The events of every object, ordered by timestamp with ties kept in log order.
+    /// Objects without events are included with an empty trace.
+    pub fn object_traces(&self) -> IndexMap<&str, Vec<&str>> {
+        let mut traces: IndexMap<&str, Vec<&str>> = self.objects.keys().map(|id| (id.as_str(), Vec::new())).collect();
+        let mut events: Vec<(&String, &Event)> = self.events.iter().collect();
+        events.sort_by_key(|(_, event)| event.timestamp);
+        for (id, event) in events {
+            for oid in &event.omap {
+                traces.entry(oid).or_default().push(id);
+            }
+        }
+        traces
+    }
+
+    /// A new log with the given events and every object they reference, plus the
+    /// given extra objects. Log order and the global attributes are kept.
+    pub fn subset(&self, events: &HashSet<&str>, extra_objects: &HashSet<&str>) -> OcelLog {
+        let mut referenced: HashSet<&str> = extra_objects.clone();
+        let mut subset = OcelLog {
+            global_log: self.global_log.clone(),
+            global_event: self.global_event.clone(),
+            global_object: self.global_object.clone(),
+            ..OcelLog::default()
+        };
+        for (id, event) in &self.events {
+            if events.contains(id.as_str()) {
+                referenced.extend(event.omap.iter().map(String::as_str));
+                subset.events.insert(id.clone(), event.clone());
+            }
+        }
+        for (id, object) in &self.objects {
+            if referenced.contains(id.as_str()) {
+                subset.objects.insert(id.clone(), object.clone());
+            }
+        }
+        subset.refresh_globals();
+        subset
+    }
+
+    /// Rewrites `ocel:object-types` and `ocel:attribute-names` in the global log so
+    /// they describe the events and objects actually present.
+    pub fn refresh_globals(&mut self) {
+        let obj_types: BTreeSet<&str> = self.objects.values().map(|o| o.obj_type.as_str()).collect();
+        let attribute_names: BTreeSet<&str> = self
+            .events
+            .values()
+            .flat_map(|e| e.vmap.keys())
+            .chain(self.objects.values().flat_map(|o| o.ovmap.keys()))
+            .map(String::as_str)
+            .collect();
+        let obj_types: Vec<Value> = obj_types.into_iter().map(Value::from).collect();
+        let attribute_names: Vec<Value> = attribute_names.into_iter().map(Value::from).collect();
+        self.global_log.insert("ocel:object-types".to_string(), Value::Array(obj_types));
+        self.global_log.insert("ocel:attribute-names".to_string(), Value::Array(attribute_names));
+    }
+
+    /// Returns `id` if it is not yet used by an event, otherwise the first free
+    /// `id-N` variant.
+    pub fn free_event_id(&self, id: &str) -> String {
+        let mut candidate = id.to_string();
+        let mut n = 1;
+        while self.events.contains_key(&candidate) {
+            n += 1;
+            candidate = format!("{}-{}", id, n);
+        }
+        candidate
+    }
+}
+
+impl Event {
+    /// Hash of everything except the event id. The object references and attributes
+    /// are hashed in sorted order so reserialized copies of an event collide.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.activity.hash(&mut hasher);
+        self.timestamp.hash(&mut hasher);
+        self.omap.iter().collect::<BTreeSet<_>>().hash(&mut hasher);
+        let vmap: BTreeMap<&String, String> = self.vmap.iter().map(|(k, v)| (k, v.to_string())).collect();
+        vmap.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Parses an OCEL timestamp. RFC 3339 is preferred, but offset-less timestamps are
+/// common in the wild and are interpreted as UTC.
+pub fn parse_timestamp(value: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(value).ok().or_else(|| {
+        ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+            .iter()
+            .find_map(|fmt| chrono::NaiveDateTime::parse_from_str(value, fmt).ok())
+            .map(|naive| DateTime::<chrono::Utc>::from_utc(naive, chrono::Utc).into())
+    })
+}
+
+mod timestamp {
+    use chrono::{DateTime, FixedOffset};
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(ts: &DateTime<FixedOffset>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&ts.to_rfc3339())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<FixedOffset>, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        super::parse_timestamp(&raw).ok_or_else(|| de::Error::custom(format!("invalid timestamp {:?}", raw)))
+    }
+}
+
+/// Inferred type name of an attribute value. Strings that parse as timestamps are
+/// reported as `timestamp`.
+pub fn value_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "float",
+        Value::Number(_) => "integer",
+        Value::String(s) if parse_timestamp(s).is_some() => "timestamp",
+        Value::String(_) => "string",
+        Value::Array(_) => "list",
+        Value::Object(_) => "map",
+    }
+}
+
+/// Renders an attribute value for terminal output, without the quotes JSON would add
+/// around strings.
+pub fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Numeric reading of an attribute value, accepting numbers written as strings.
+pub fn numeric(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
@@ -0,0 +1,351 @@
+//! OCEL 2.0 JSON reading and writing for [`OcelLog`], and the document model the
+//! XML and SQLite serializations of OCEL 2.0 share. Qualified event-to-object and
+//! object-to-object relationships are kept in `relationships`. Object attributes
+//! stamped with the Unix epoch are treated as static and land in the `ovmap`, every
+//! other timed value is kept as an attribute change.
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+
+use indexmap::IndexMap;
+use serde::de::{IgnoredAny, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Number, Value};
+
+use super::model::{parse_timestamp, value_type, AttributeChange, Event, Object, OcelLog, Relationship};
+use crate::compression;
+
+/// Timestamp OCEL 2.0 uses for attribute values that never change.
+pub(super) const STATIC_TIME: &str = "1970-01-01T00:00:00Z";
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct Document {
+    #[serde(default)]
+    pub(super) object_types: Vec<TypeDeclaration>,
+    #[serde(default)]
+    pub(super) event_types: Vec<TypeDeclaration>,
+    #[serde(default)]
+    pub(super) objects: Vec<Ocel2Object>,
+    #[serde(default)]
+    pub(super) events: Vec<Ocel2Event>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct TypeDeclaration {
+    pub(super) name: String,
+    #[serde(default)]
+    pub(super) attributes: Vec<AttributeDeclaration>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct AttributeDeclaration {
+    pub(super) name: String,
+    #[serde(rename = "type")]
+    pub(super) kind: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct Ocel2Object {
+    pub(super) id: String,
+    #[serde(rename = "type")]
+    pub(super) obj_type: String,
+    #[serde(default)]
+    pub(super) attributes: Vec<TimedAttribute>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(super) relationships: Vec<Relationship>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct TimedAttribute {
+    pub(super) name: String,
+    pub(super) time: String,
+    pub(super) value: Value,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct Ocel2Event {
+    pub(super) id: String,
+    #[serde(rename = "type")]
+    pub(super) activity: String,
+    pub(super) time: String,
+    #[serde(default)]
+    pub(super) attributes: Vec<EventAttribute>,
+    #[serde(default)]
+    pub(super) relationships: Vec<Relationship>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct EventAttribute {
+    pub(super) name: String,
+    pub(super) value: Value,
+}
+
+/// Walks the top-level keys until one tells the layouts apart and records the
+/// answer, values of other keys are skipped without being materialized.
+struct Probe<'a>(&'a mut Option<bool>);
+
+impl<'de> Visitor<'de> for Probe<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            if key.starts_with("ocel:") {
+                *self.0 = Some(false);
+            } else if matches!(key.as_str(), "objectTypes" | "eventTypes" | "objects" | "events") {
+                *self.0 = Some(true);
+            }
+            if self.0.is_some() {
+                // the rest of the document is left unread
+                return Ok(());
+            }
+            map.next_value::<IgnoredAny>()?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether a JSON log uses the OCEL 2.0 layout rather than JSON-OCEL 1.0. Reading
+/// stops at the first top-level key that belongs to either layout.
+pub fn is_ocel2(path: &Path) -> Result<bool, Box<dyn Error>> {
+    let mut layout = None;
+    let mut deserializer = serde_json::Deserializer::from_reader(compression::open(path)?);
+    // stopping early leaves the object unclosed, which only matters if no key decided
+    let result = (&mut deserializer).deserialize_map(Probe(&mut layout));
+    match layout {
+        Some(ocel2) => Ok(ocel2),
+        None => {
+            result?;
+            Ok(false)
+        }
+    }
+}
+
+pub fn read(path: &Path) -> Result<OcelLog, Box<dyn Error>> {
+    let document: Document = serde_json::from_reader(compression::open(path)?)?;
+    from_document(document)
+}
+
+fn declared_types(declarations: &[TypeDeclaration]) -> HashMap<(&str, &str), &str> {
+    declarations
+        .iter()
+        .flat_map(|t| t.attributes.iter().map(move |a| ((t.name.as_str(), a.name.as_str()), a.kind.as_str())))
+        .collect()
+}
+
+/// OCEL 2.0 exporters frequently write every value as a string, the declared
+/// attribute type tells what it was.
+fn typed_value(value: Value, kind: Option<&str>) -> Value {
+    let raw = match &value {
+        Value::String(raw) => raw,
+        _ => return value,
+    };
+    match kind {
+        Some("integer") => raw.parse::<i64>().map(Value::from).unwrap_or(value),
+        Some("float") => raw.parse::<f64>().ok().and_then(Number::from_f64).map(Value::Number).unwrap_or(value),
+        Some("boolean") => match raw.to_ascii_lowercase().as_str() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => value,
+        },
+        _ => value,
+    }
+}
+
+pub(super) fn from_document(document: Document) -> Result<OcelLog, Box<dyn Error>> {
+    let object_kinds = declared_types(&document.object_types);
+    let event_kinds = declared_types(&document.event_types);
+    let mut log = OcelLog::default();
+
+    for object in document.objects {
+        let mut ovmap = Map::new();
+        let mut attribute_changes = Vec::new();
+        for attribute in object.attributes {
+            let time = parse_timestamp(&attribute.time)
+                .ok_or_else(|| format!("object {} has an invalid attribute time {:?}", object.id, attribute.time))?;
+            let value = typed_value(attribute.value, object_kinds.get(&(object.obj_type.as_str(), attribute.name.as_str())).copied());
+            if time.timestamp() == 0 {
+                ovmap.insert(attribute.name, value);
+            } else {
+                attribute_changes.push(AttributeChange { name: attribute.name, time, value });
+            }
+        }
+        attribute_changes.sort_by_key(|change| change.time);
+        log.objects.insert(
+            object.id,
+            Object { obj_type: object.obj_type, ovmap, relationships: object.relationships, attribute_changes },
+        );
+    }
+
+    for event in document.events {
+        let timestamp =
+            parse_timestamp(&event.time).ok_or_else(|| format!("event {} has an invalid timestamp {:?}", event.id, event.time))?;
+        let mut omap: Vec<String> = Vec::new();
+        for relationship in &event.relationships {
+            if !omap.contains(&relationship.object_id) {
+                omap.push(relationship.object_id.clone());
+            }
+        }
+        let vmap = event
+            .attributes
+            .into_iter()
+            .map(|a| {
+                let kind = event_kinds.get(&(event.activity.as_str(), a.name.as_str())).copied();
+                (a.name, typed_value(a.value, kind))
+            })
+            .collect();
+        log.events.insert(
+            event.id,
+            Event { activity: event.activity, timestamp, omap, vmap, relationships: event.relationships },
+        );
+    }
+
+    log.refresh_globals();
+    Ok(log)
+}
+
+pub fn write(log: &OcelLog, path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut out = compression::create(path)?;
+    serde_json::to_writer_pretty(&mut out, &to_document(log))?;
+    out.flush()?;
+    Ok(())
+}
+
+fn declared_kind(value: &Value) -> &'static str {
+    match value_type(value) {
+        kind @ ("boolean" | "float" | "integer") => kind,
+        "timestamp" => "time",
+        _ => "string",
+    }
+}
+
+/// Declarations in order of first occurrence.
+fn declare<'a>(
+    declarations: &mut IndexMap<String, IndexMap<String, &'static str>>,
+    name: &str,
+    attributes: impl Iterator<Item = (&'a String, &'a Value)>,
+) {
+    let declared = declarations.entry(name.to_string()).or_default();
+    for (key, value) in attributes {
+        declared.entry(key.clone()).or_insert_with(|| declared_kind(value));
+    }
+}
+
+fn into_declarations(declarations: IndexMap<String, IndexMap<String, &'static str>>) -> Vec<TypeDeclaration> {
+    declarations
+        .into_iter()
+        .map(|(name, attributes)| TypeDeclaration {
+            name,
+            attributes: attributes.into_iter().map(|(name, kind)| AttributeDeclaration { name, kind: kind.to_string() }).collect(),
+        })
+        .collect()
+}
+
+pub(super) fn to_document(log: &OcelLog) -> Document {
+    let mut object_types = IndexMap::new();
+    let mut event_types = IndexMap::new();
+
+    let objects = log
+        .objects
+        .iter()
+        .map(|(id, object)| {
+            let changes = object.attribute_changes.iter().map(|c| (&c.name, &c.value));
+            declare(&mut object_types, &object.obj_type, object.ovmap.iter().chain(changes));
+            let mut attributes: Vec<TimedAttribute> = object
+                .ovmap
+                .iter()
+                .map(|(name, value)| TimedAttribute { name: name.clone(), time: STATIC_TIME.to_string(), value: value.clone() })
+                .collect();
+            attributes.extend(object.attribute_changes.iter().map(|c| TimedAttribute {
+                name: c.name.clone(),
+                time: c.time.to_rfc3339(),
+                value: c.value.clone(),
+            }));
+            Ocel2Object { id: id.clone(), obj_type: object.obj_type.clone(), attributes, relationships: object.relationships.clone() }
+        })
+        .collect();
+
+    let events = log
+        .events
+        .iter()
+        .map(|(id, event)| {
+            declare(&mut event_types, &event.activity, event.vmap.iter());
+            // omap entries without a qualified relationship are related with an empty qualifier
+            let mut relationships = event.relationships.clone();
+            for object_id in &event.omap {
+                if !relationships.iter().any(|r| &r.object_id == object_id) {
+                    relationships.push(Relationship { object_id: object_id.clone(), qualifier: String::new() });
+                }
+            }
+            Ocel2Event {
+                id: id.clone(),
+                activity: event.activity.clone(),
+                time: event.timestamp.to_rfc3339(),
+                attributes: event.vmap.iter().map(|(name, value)| EventAttribute { name: name.clone(), value: value.clone() }).collect(),
+                relationships,
+            }
+        })
+        .collect();
+
+    Document {
+        object_types: into_declarations(object_types),
+        event_types: into_declarations(event_types),
+        objects,
+        events,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn json_file(content: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::Builder::new().suffix(".jsonocel").tempfile().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn probe_decides_on_the_first_layout_key() {
+        // truncated after the deciding key, so reading further would fail
+        assert!(is_ocel2(json_file(r#"{"objectTypes": [], "events": [{"id": "#).path()).unwrap());
+        assert!(!is_ocel2(json_file(r#"{"ocel:global-log": {}, "ocel:events": {"e1": "#).path()).unwrap());
+    }
+
+    #[test]
+    fn probe_skips_unrelated_keys() {
+        assert!(is_ocel2(json_file(r#"{"meta": {"objectTypes": 1}, "eventTypes": []}"#).path()).unwrap());
+        assert!(!is_ocel2(json_file(r#"{"meta": []}"#).path()).unwrap());
+        assert!(is_ocel2(json_file("[]").path()).is_err());
+    }
+
+    #[test]
+    fn declared_types_apply_to_string_values() {
+        let file = json_file(
+            r#"{"objectTypes": [{"name": "order", "attributes": [{"name": "price", "type": "float"}]}],
+                "eventTypes": [{"name": "pay", "attributes": [{"name": "paid", "type": "boolean"}]}],
+                "objects": [{"id": "o1", "type": "order", "attributes": [
+                    {"name": "price", "time": "1970-01-01T00:00:00Z", "value": "9.5"},
+                    {"name": "price", "time": "2023-01-02T00:00:00Z", "value": "7.5"}]}],
+                "events": [{"id": "e1", "type": "pay", "time": "2023-01-03T00:00:00Z",
+                    "attributes": [{"name": "paid", "value": "true"}],
+                    "relationships": [{"objectId": "o1", "qualifier": "paid order"}]}]}"#,
+        );
+        let log = read(file.path()).unwrap();
+        let object = &log.objects["o1"];
+        assert_eq!(object.ovmap["price"], Value::from(9.5));
+        assert_eq!(object.attribute_changes[0].value, Value::from(7.5));
+        let event = &log.events["e1"];
+        assert_eq!(event.vmap["paid"], Value::Bool(true));
+        assert_eq!(event.omap, vec!["o1".to_string()]);
+        assert_eq!(event.relationships[0].qualifier, "paid order");
+    }
+}
@@ -0,0 +1,399 @@
+//! OCEL 2.0 SQLite reading and writing for [`OcelLog`], in the relational layout of
+//! the OCEL 2.0 specification: `event` and `object` list the ids with their types,
+//! `event_map_type` and `object_map_type` name one `event_<type>`/`object_<type>`
+//! table of attribute values per type, and `event_object` and `object_object` hold
+//! the qualified relationships. Object tables have one row for the initial values
+//! and one per later change, naming the attribute in `ocel_changed_field`.
+//!
+//! SQLite works on plain files, so compressed paths and standard input are rejected.
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use indexmap::IndexMap;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params_from_iter, Connection, OpenFlags};
+use serde_json::{Number, Value};
+
+use super::model::{OcelLog, Relationship};
+use super::ocel2::{
+    from_document, to_document, AttributeDeclaration, Document, EventAttribute, Ocel2Event, Ocel2Object, TimedAttribute,
+    TypeDeclaration, STATIC_TIME,
+};
+use crate::compression::Compression;
+use crate::stdio;
+
+fn check_plain(path: &Path) -> Result<(), String> {
+    if stdio::is_stdio(path) || Compression::from_path(path) != Compression::None {
+        return Err(format!("{:?}: OCEL 2.0 SQLite logs can only be read from and written to plain files", path));
+    }
+    Ok(())
+}
+
+/// Quotes a table or column name for use in a statement.
+fn quoted(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+pub fn read(path: &Path) -> Result<OcelLog, Box<dyn Error>> {
+    check_plain(path)?;
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    from_document(load(&connection)?)
+}
+
+/// The attribute type the declared SQL type of a column stands for.
+fn declared_kind(sql_type: &str) -> &'static str {
+    let sql_type = sql_type.to_ascii_uppercase();
+    if sql_type.contains("BOOL") {
+        "boolean"
+    } else if sql_type.contains("INT") {
+        "integer"
+    } else if ["REAL", "FLOA", "DOUB"].iter().any(|name| sql_type.contains(name)) {
+        "float"
+    } else if sql_type.contains("TIME") || sql_type.contains("DATE") {
+        "time"
+    } else {
+        "string"
+    }
+}
+
+fn json_value(value: ValueRef, kind: &str) -> Option<Value> {
+    match value {
+        ValueRef::Null => None,
+        ValueRef::Integer(i) if kind == "boolean" => Some(Value::Bool(i != 0)),
+        ValueRef::Integer(i) => Some(Value::from(i)),
+        ValueRef::Real(f) => Some(Number::from_f64(f).map_or(Value::Null, Value::Number)),
+        ValueRef::Text(text) | ValueRef::Blob(text) => Some(Value::String(String::from_utf8_lossy(text).into_owned())),
+    }
+}
+
+/// The types listed in the `<kind>_map_type` table with the table of each.
+fn type_tables(connection: &Connection, kind: &str) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let mut statement = connection.prepare(&format!("SELECT ocel_type, ocel_type_map FROM {}_map_type", kind))?;
+    let rows = statement.query_map([], |row| Ok((row.get(0)?, format!("{}_{}", kind, row.get::<_, String>(1)?))))?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// The columns of `table` other than the `ocel_` ones, with their attribute types.
+fn attribute_columns(connection: &Connection, table: &str) -> Result<Vec<AttributeDeclaration>, Box<dyn Error>> {
+    let mut statement = connection.prepare(&format!("PRAGMA table_info({})", quoted(table)))?;
+    let columns = statement.query_map([], |row| Ok((row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?)))?;
+    let mut declarations = Vec::new();
+    for column in columns {
+        let (name, sql_type) = column?;
+        if !name.starts_with("ocel_") {
+            declarations.push(AttributeDeclaration { name, kind: declared_kind(&sql_type.unwrap_or_default()).to_string() });
+        }
+    }
+    Ok(declarations)
+}
+
+/// The ids of the `event` or `object` table in order, with their types.
+fn ids(connection: &Connection, table: &str) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let mut statement = connection.prepare(&format!("SELECT ocel_id, ocel_type FROM {}", table))?;
+    let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// Calls `visit` with the id, time, changed field and attribute values of every row
+/// of a type table.
+fn type_rows(
+    connection: &Connection,
+    table: &str,
+    declarations: &[AttributeDeclaration],
+    changes: bool,
+    mut visit: impl FnMut(String, String, Option<String>, Vec<(String, Value)>) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let mut columns = vec!["ocel_id".to_string(), "ocel_time".to_string()];
+    if changes {
+        columns.push("ocel_changed_field".to_string());
+    }
+    let fixed = columns.len();
+    columns.extend(declarations.iter().map(|declaration| quoted(&declaration.name)));
+    let mut statement = connection.prepare(&format!("SELECT {} FROM {}", columns.join(", "), quoted(table)))?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let id: String = row.get(0)?;
+        let time: String = row.get(1)?;
+        let changed: Option<String> = if changes { row.get(2)? } else { None };
+        let mut values = Vec::new();
+        for (index, declaration) in declarations.iter().enumerate() {
+            if let Some(value) = json_value(row.get_ref(fixed + index)?, &declaration.kind) {
+                values.push((declaration.name.clone(), value));
+            }
+        }
+        visit(id, time, changed, values)?;
+    }
+    Ok(())
+}
+
+fn load(connection: &Connection) -> Result<Document, Box<dyn Error>> {
+    let mut document = Document { object_types: Vec::new(), event_types: Vec::new(), objects: Vec::new(), events: Vec::new() };
+
+    let mut events: IndexMap<String, Ocel2Event> = ids(connection, "event")?
+        .into_iter()
+        .map(|(id, activity)| {
+            let event =
+                Ocel2Event { id: id.clone(), activity, time: String::new(), attributes: Vec::new(), relationships: Vec::new() };
+            (id, event)
+        })
+        .collect();
+    for (name, table) in type_tables(connection, "event")? {
+        let attributes = attribute_columns(connection, &table)?;
+        type_rows(connection, &table, &attributes, false, |id, time, _, values| {
+            let event = events.get_mut(&id).ok_or_else(|| format!("{} has a row for the unknown event {}", table, id))?;
+            event.time = time;
+            event.attributes.extend(values.into_iter().map(|(name, value)| EventAttribute { name, value }));
+            Ok(())
+        })?;
+        document.event_types.push(TypeDeclaration { name, attributes });
+    }
+
+    let mut objects: IndexMap<String, Ocel2Object> = ids(connection, "object")?
+        .into_iter()
+        .map(|(id, obj_type)| (id.clone(), Ocel2Object { id, obj_type, attributes: Vec::new(), relationships: Vec::new() }))
+        .collect();
+    for (name, table) in type_tables(connection, "object")? {
+        let attributes = attribute_columns(connection, &table)?;
+        type_rows(connection, &table, &attributes, true, |id, time, changed, values| {
+            let object = objects.get_mut(&id).ok_or_else(|| format!("{} has a row for the unknown object {}", table, id))?;
+            for (name, value) in values {
+                // rows of later changes only carry the changed attribute
+                if changed.as_ref().map_or(true, |field| *field == name) {
+                    object.attributes.push(TimedAttribute { name, time: time.clone(), value });
+                }
+            }
+            Ok(())
+        })?;
+        document.object_types.push(TypeDeclaration { name, attributes });
+    }
+
+    let mut statement = connection.prepare("SELECT ocel_event_id, ocel_object_id, ocel_qualifier FROM event_object")?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let id: String = row.get(0)?;
+        let relationship =
+            Relationship { object_id: row.get(1)?, qualifier: row.get::<_, Option<String>>(2)?.unwrap_or_default() };
+        events
+            .get_mut(&id)
+            .ok_or_else(|| format!("event_object relates the unknown event {}", id))?
+            .relationships
+            .push(relationship);
+    }
+    let mut statement = connection.prepare("SELECT ocel_source_id, ocel_target_id, ocel_qualifier FROM object_object")?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let id: String = row.get(0)?;
+        let relationship =
+            Relationship { object_id: row.get(1)?, qualifier: row.get::<_, Option<String>>(2)?.unwrap_or_default() };
+        objects
+            .get_mut(&id)
+            .ok_or_else(|| format!("object_object relates the unknown object {}", id))?
+            .relationships
+            .push(relationship);
+    }
+
+    document.events = events.into_values().collect();
+    document.objects = objects.into_values().collect();
+    Ok(document)
+}
+
+fn sql_type(kind: &str) -> &'static str {
+    match kind {
+        "integer" => "INTEGER",
+        "float" => "REAL",
+        "boolean" => "BOOLEAN",
+        "time" => "TIMESTAMP",
+        _ => "TEXT",
+    }
+}
+
+fn sql_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+/// Table name suffixes for the declared types: the alphanumeric characters of the
+/// type name, numbered when two types reduce to the same name.
+fn table_names(declarations: &[TypeDeclaration]) -> Vec<String> {
+    let mut taken: HashMap<String, usize> = HashMap::new();
+    declarations
+        .iter()
+        .map(|declaration| {
+            let base: String = declaration.name.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+            let seen = taken.entry(base.clone()).or_default();
+            *seen += 1;
+            if *seen == 1 {
+                base
+            } else {
+                format!("{}{}", base, seen)
+            }
+        })
+        .collect()
+}
+
+/// Creates the `kind` table of every declared type and registers it in the map table.
+fn create_type_tables(
+    connection: &Connection,
+    kind: &str,
+    declarations: &[TypeDeclaration],
+    changes: bool,
+) -> Result<HashMap<String, (String, Vec<String>)>, Box<dyn Error>> {
+    let mut tables = HashMap::new();
+    for (declaration, suffix) in declarations.iter().zip(table_names(declarations)) {
+        let table = format!("{}_{}", kind, suffix);
+        let mut columns = vec!["ocel_id TEXT".to_string(), "ocel_time TIMESTAMP".to_string()];
+        if changes {
+            columns.push("ocel_changed_field TEXT".to_string());
+        }
+        columns.extend(declaration.attributes.iter().map(|a| format!("{} {}", quoted(&a.name), sql_type(&a.kind))));
+        connection.execute(&format!("CREATE TABLE {} ({})", quoted(&table), columns.join(", ")), [])?;
+        connection.execute(
+            &format!("INSERT INTO {}_map_type (ocel_type, ocel_type_map) VALUES (?1, ?2)", kind),
+            [&declaration.name, &suffix],
+        )?;
+        let names = declaration.attributes.iter().map(|a| a.name.clone()).collect();
+        tables.insert(declaration.name.clone(), (table, names));
+    }
+    Ok(tables)
+}
+
+/// Inserts a row of `table` with the given `ocel_` columns and the values of the
+/// attribute columns present in `values`.
+fn insert_row(
+    connection: &Connection,
+    table: &str,
+    fixed: Vec<(&str, SqlValue)>,
+    columns: &[String],
+    values: &HashMap<&str, &Value>,
+) -> Result<(), Box<dyn Error>> {
+    let (mut names, mut row): (Vec<String>, Vec<SqlValue>) =
+        fixed.into_iter().map(|(name, value)| (name.to_string(), value)).unzip();
+    for column in columns {
+        if let Some(value) = values.get(column.as_str()) {
+            names.push(quoted(column));
+            row.push(sql_value(value));
+        }
+    }
+    let placeholders: Vec<String> = (1..=names.len()).map(|i| format!("?{}", i)).collect();
+    let sql = format!("INSERT INTO {} ({}) VALUES ({})", quoted(table), names.join(", "), placeholders.join(", "));
+    connection.prepare_cached(&sql)?.execute(params_from_iter(row))?;
+    Ok(())
+}
+
+pub fn write(log: &OcelLog, path: &Path) -> Result<(), Box<dyn Error>> {
+    check_plain(path)?;
+    // the tables are created from scratch
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    let document = to_document(log);
+    let mut connection = Connection::open(path)?;
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(
+        "CREATE TABLE event (ocel_id TEXT PRIMARY KEY, ocel_type TEXT);
+         CREATE TABLE event_map_type (ocel_type TEXT PRIMARY KEY, ocel_type_map TEXT);
+         CREATE TABLE object (ocel_id TEXT PRIMARY KEY, ocel_type TEXT);
+         CREATE TABLE object_map_type (ocel_type TEXT PRIMARY KEY, ocel_type_map TEXT);
+         CREATE TABLE event_object (ocel_event_id TEXT, ocel_object_id TEXT, ocel_qualifier TEXT);
+         CREATE TABLE object_object (ocel_source_id TEXT, ocel_target_id TEXT, ocel_qualifier TEXT);",
+    )?;
+    let event_tables = create_type_tables(&transaction, "event", &document.event_types, false)?;
+    let object_tables = create_type_tables(&transaction, "object", &document.object_types, true)?;
+
+    for event in &document.events {
+        transaction
+            .prepare_cached("INSERT INTO event (ocel_id, ocel_type) VALUES (?1, ?2)")?
+            .execute([&event.id, &event.activity])?;
+        let (table, columns) = &event_tables[&event.activity];
+        let values = event.attributes.iter().map(|a| (a.name.as_str(), &a.value)).collect();
+        let fixed = vec![("ocel_id", SqlValue::Text(event.id.clone())), ("ocel_time", SqlValue::Text(event.time.clone()))];
+        insert_row(&transaction, table, fixed, columns, &values)?;
+        for relationship in &event.relationships {
+            transaction
+                .prepare_cached("INSERT INTO event_object (ocel_event_id, ocel_object_id, ocel_qualifier) VALUES (?1, ?2, ?3)")?
+                .execute([&event.id, &relationship.object_id, &relationship.qualifier])?;
+        }
+    }
+
+    for object in &document.objects {
+        transaction
+            .prepare_cached("INSERT INTO object (ocel_id, ocel_type) VALUES (?1, ?2)")?
+            .execute([&object.id, &object.obj_type])?;
+        let (table, columns) = &object_tables[&object.obj_type];
+        let fixed = |time: &str, changed: Option<&str>| {
+            vec![
+                ("ocel_id", SqlValue::Text(object.id.clone())),
+                ("ocel_time", SqlValue::Text(time.to_string())),
+                ("ocel_changed_field", changed.map_or(SqlValue::Null, |name| SqlValue::Text(name.to_string()))),
+            ]
+        };
+        let initial = object.attributes.iter().filter(|a| a.time == STATIC_TIME).map(|a| (a.name.as_str(), &a.value)).collect();
+        insert_row(&transaction, table, fixed(STATIC_TIME, None), columns, &initial)?;
+        for change in object.attributes.iter().filter(|a| a.time != STATIC_TIME) {
+            let values = HashMap::from([(change.name.as_str(), &change.value)]);
+            insert_row(&transaction, table, fixed(&change.time, Some(&change.name)), columns, &values)?;
+        }
+        for relationship in &object.relationships {
+            transaction
+                .prepare_cached("INSERT INTO object_object (ocel_source_id, ocel_target_id, ocel_qualifier) VALUES (?1, ?2, ?3)")?
+                .execute([&object.id, &relationship.object_id, &relationship.qualifier])?;
+        }
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::ocel::ocel2;
+
+    #[test]
+    fn round_trips() {
+        let mut source = tempfile::Builder::new().suffix(".jsonocel").tempfile().unwrap();
+        source
+            .write_all(
+                br#"{"objectTypes": [{"name": "order", "attributes": [{"name": "price", "type": "float"}, {"name": "rush", "type": "boolean"}]}],
+                    "eventTypes": [{"name": "place order", "attributes": [{"name": "items", "type": "integer"}]}],
+                    "objects": [
+                        {"id": "o1", "type": "order", "attributes": [
+                            {"name": "price", "time": "1970-01-01T00:00:00Z", "value": 9.5},
+                            {"name": "rush", "time": "1970-01-01T00:00:00Z", "value": false},
+                            {"name": "price", "time": "2023-01-02T00:00:00+00:00", "value": 7.5}],
+                         "relationships": [{"objectId": "o2", "qualifier": "follows"}]},
+                        {"id": "o2", "type": "order", "attributes": []}],
+                    "events": [{"id": "e1", "type": "place order", "time": "2023-01-01T10:00:00+00:00",
+                        "attributes": [{"name": "items", "value": 3}],
+                        "relationships": [{"objectId": "o1", "qualifier": "placed"}, {"objectId": "o2", "qualifier": ""}]}]}"#,
+            )
+            .unwrap();
+        let log = ocel2::read(source.path()).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.sqlite");
+        write(&log, &path).unwrap();
+        // writing again replaces the tables instead of failing on them
+        write(&log, &path).unwrap();
+        let again = read(&path).unwrap();
+        assert_eq!(again.events, log.events);
+        assert_eq!(again.objects, log.objects);
+        assert_eq!(again.objects["o1"].ovmap["rush"], Value::Bool(false));
+    }
+
+    #[test]
+    fn rejects_compressed_paths() {
+        assert!(read(Path::new("log.sqlite.gz")).is_err());
+    }
+}
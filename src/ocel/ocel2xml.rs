@@ -0,0 +1,368 @@
+//! OCEL 2.0 XML reading and writing for [`OcelLog`]. The document shares the model
+//! of the OCEL 2.0 JSON layout: `<object-types>` and `<event-types>` declare the
+//! attribute types, `<objects>` and `<events>` carry the values as text along with
+//! their `<relationship>` elements.
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use quick_xml::events::{BytesStart, Event as XmlEvent};
+use quick_xml::Reader;
+use serde_json::Value;
+
+use super::model::{OcelLog, Relationship};
+use super::ocel2::{
+    from_document, to_document, AttributeDeclaration, Document, EventAttribute, Ocel2Event, Ocel2Object, TimedAttribute,
+    TypeDeclaration,
+};
+use crate::compression;
+use crate::xml::{attributes, escape};
+
+/// Whether an XML log uses the OCEL 2.0 layout rather than XML-OCEL 1.0. Both have
+/// a `<log>` root, reading stops at the first element that belongs to either.
+pub fn is_ocel2_xml(path: &Path) -> Result<bool, Box<dyn Error>> {
+    let mut reader = Reader::from_reader(compression::open(path)?);
+    let mut buf = Vec::new();
+    loop {
+        let tag = match reader.read_event_into(&mut buf)? {
+            XmlEvent::Start(tag) | XmlEvent::Empty(tag) => tag,
+            XmlEvent::Eof => return Ok(false),
+            _ => {
+                buf.clear();
+                continue;
+            }
+        };
+        match tag.local_name().as_ref() {
+            b"object-types" | b"event-types" => return Ok(true),
+            b"global" => return Ok(false),
+            // XML-OCEL 1.0 keeps the id in a child element
+            b"event" | b"object" => return Ok(attributes(&tag)?.contains_key("id")),
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+pub fn read(path: &Path) -> Result<OcelLog, Box<dyn Error>> {
+    from_document(parse(compression::open(path)?)?)
+}
+
+/// An `<attribute>` of an object or event whose text is still being read.
+struct Pending {
+    name: String,
+    time: Option<String>,
+    value: String,
+}
+
+fn parse<R: BufRead>(input: R) -> Result<Document, Box<dyn Error>> {
+    let mut reader = Reader::from_reader(input);
+    reader.trim_text(true);
+
+    let mut document = Document { object_types: Vec::new(), event_types: Vec::new(), objects: Vec::new(), events: Vec::new() };
+    // open elements, the section is the one below the root: `<relationship>`s are
+    // grouped in nested `<objects>` elements too
+    let mut stack: Vec<Vec<u8>> = Vec::new();
+    let mut pending: Option<Pending> = None;
+    let mut buf = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            XmlEvent::Start(tag) => {
+                let section = stack.get(1).cloned().unwrap_or_default();
+                pending = element(&mut document, &section, &tag)?;
+                stack.push(tag.local_name().as_ref().to_vec());
+            }
+            XmlEvent::Empty(tag) => {
+                let section = stack.get(1).cloned().unwrap_or_default();
+                if let Some(attribute) = element(&mut document, &section, &tag)? {
+                    finish(&mut document, &section, attribute)?;
+                }
+            }
+            XmlEvent::Text(text) => {
+                if let Some(attribute) = pending.as_mut() {
+                    attribute.value.push_str(&text.unescape()?);
+                }
+            }
+            XmlEvent::CData(data) => {
+                if let Some(attribute) = pending.as_mut() {
+                    attribute.value.push_str(&String::from_utf8_lossy(&data.into_inner()));
+                }
+            }
+            XmlEvent::End(_) => {
+                stack.pop();
+                if let Some(attribute) = pending.take() {
+                    let section = stack.get(1).cloned().unwrap_or_default();
+                    finish(&mut document, &section, attribute)?;
+                }
+            }
+            XmlEvent::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(document)
+}
+
+/// Adds the declaration, object, event or relationship `tag` opens to the document.
+/// An `<attribute>` value is returned to collect its text.
+fn element(document: &mut Document, section: &[u8], tag: &BytesStart) -> Result<Option<Pending>, Box<dyn Error>> {
+    let mut attrs = attributes(tag)?;
+    match (section, tag.local_name().as_ref()) {
+        (b"object-types", b"object-type") => document
+            .object_types
+            .push(TypeDeclaration { name: required(&mut attrs, "name", "object-type")?, attributes: Vec::new() }),
+        (b"event-types", b"event-type") => document
+            .event_types
+            .push(TypeDeclaration { name: required(&mut attrs, "name", "event-type")?, attributes: Vec::new() }),
+        (b"object-types" | b"event-types", b"attribute") => {
+            let declarations = if section == b"object-types" { &mut document.object_types } else { &mut document.event_types };
+            let declaration = AttributeDeclaration {
+                name: required(&mut attrs, "name", "attribute")?,
+                kind: required(&mut attrs, "type", "attribute")?,
+            };
+            declarations.last_mut().ok_or("an <attribute> is declared outside of a type")?.attributes.push(declaration);
+        }
+        (b"objects", b"object") => document.objects.push(Ocel2Object {
+            id: required(&mut attrs, "id", "object")?,
+            obj_type: required(&mut attrs, "type", "object")?,
+            attributes: Vec::new(),
+            relationships: Vec::new(),
+        }),
+        (b"events", b"event") => document.events.push(Ocel2Event {
+            id: required(&mut attrs, "id", "event")?,
+            activity: required(&mut attrs, "type", "event")?,
+            time: required(&mut attrs, "time", "event")?,
+            attributes: Vec::new(),
+            relationships: Vec::new(),
+        }),
+        (b"objects" | b"events", b"relationship") => {
+            let relationship = Relationship {
+                object_id: required(&mut attrs, "object-id", "relationship")?,
+                qualifier: attrs.remove("qualifier").unwrap_or_default(),
+            };
+            match section {
+                b"objects" => document
+                    .objects
+                    .last_mut()
+                    .ok_or("a <relationship> is outside of an <object>")?
+                    .relationships
+                    .push(relationship),
+                _ => document
+                    .events
+                    .last_mut()
+                    .ok_or("a <relationship> is outside of an <event>")?
+                    .relationships
+                    .push(relationship),
+            }
+        }
+        (b"objects" | b"events", b"attribute") => {
+            return Ok(Some(Pending {
+                name: required(&mut attrs, "name", "attribute")?,
+                time: attrs.remove("time"),
+                value: String::new(),
+            }))
+        }
+        _ => {}
+    }
+    Ok(None)
+}
+
+fn required(attrs: &mut HashMap<String, String>, key: &str, element: &str) -> Result<String, String> {
+    attrs.remove(key).ok_or_else(|| format!("an <{}> has no {}", element, key))
+}
+
+fn finish(document: &mut Document, section: &[u8], attribute: Pending) -> Result<(), Box<dyn Error>> {
+    let value = Value::String(attribute.value);
+    if section == b"objects" {
+        let object = document.objects.last_mut().ok_or("an <attribute> is outside of an <object>")?;
+        let time = attribute.time.ok_or_else(|| format!("an attribute of object {} has no time", object.id))?;
+        object.attributes.push(TimedAttribute { name: attribute.name, time, value });
+    } else {
+        let event = document.events.last_mut().ok_or("an <attribute> is outside of an <event>")?;
+        event.attributes.push(EventAttribute { name: attribute.name, value });
+    }
+    Ok(())
+}
+
+pub fn write(log: &OcelLog, path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut out = compression::create(path)?;
+    write_to(log, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+pub fn write_to<W: Write>(log: &OcelLog, out: &mut W) -> Result<(), Box<dyn Error>> {
+    let document = to_document(log);
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, "<log>")?;
+    for (section, element, declarations) in
+        [("object-types", "object-type", &document.object_types), ("event-types", "event-type", &document.event_types)]
+    {
+        writeln!(out, "  <{}>", section)?;
+        for declaration in declarations {
+            writeln!(out, r#"    <{} name="{}">"#, element, escape(&declaration.name))?;
+            writeln!(out, "      <attributes>")?;
+            for attribute in &declaration.attributes {
+                writeln!(out, r#"        <attribute name="{}" type="{}"/>"#, escape(&attribute.name), escape(&attribute.kind))?;
+            }
+            writeln!(out, "      </attributes>")?;
+            writeln!(out, "    </{}>", element)?;
+        }
+        writeln!(out, "  </{}>", section)?;
+    }
+
+    writeln!(out, "  <objects>")?;
+    for object in &document.objects {
+        writeln!(out, r#"    <object id="{}" type="{}">"#, escape(&object.id), escape(&object.obj_type))?;
+        writeln!(out, "      <attributes>")?;
+        for attribute in &object.attributes {
+            writeln!(
+                out,
+                r#"        <attribute name="{}" time="{}">{}</attribute>"#,
+                escape(&attribute.name),
+                escape(&attribute.time),
+                escape(&text(&attribute.value))
+            )?;
+        }
+        writeln!(out, "      </attributes>")?;
+        write_relationships(out, &object.relationships)?;
+        writeln!(out, "    </object>")?;
+    }
+    writeln!(out, "  </objects>")?;
+
+    writeln!(out, "  <events>")?;
+    for event in &document.events {
+        writeln!(
+            out,
+            r#"    <event id="{}" type="{}" time="{}">"#,
+            escape(&event.id),
+            escape(&event.activity),
+            escape(&event.time)
+        )?;
+        writeln!(out, "      <attributes>")?;
+        for attribute in &event.attributes {
+            writeln!(
+                out,
+                r#"        <attribute name="{}">{}</attribute>"#,
+                escape(&attribute.name),
+                escape(&text(&attribute.value))
+            )?;
+        }
+        writeln!(out, "      </attributes>")?;
+        write_relationships(out, &event.relationships)?;
+        writeln!(out, "    </event>")?;
+    }
+    writeln!(out, "  </events>")?;
+    writeln!(out, "</log>")?;
+    Ok(())
+}
+
+fn write_relationships<W: Write>(out: &mut W, relationships: &[Relationship]) -> Result<(), Box<dyn Error>> {
+    writeln!(out, "      <objects>")?;
+    for relationship in relationships {
+        writeln!(
+            out,
+            r#"        <relationship object-id="{}" qualifier="{}"/>"#,
+            escape(&relationship.object_id),
+            escape(&relationship.qualifier)
+        )?;
+    }
+    writeln!(out, "      </objects>")?;
+    Ok(())
+}
+
+/// Attribute values are text, the declared type tells how to read them back.
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    const LOG: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<log>
+  <object-types>
+    <object-type name="order">
+      <attributes>
+        <attribute name="price" type="float"/>
+      </attributes>
+    </object-type>
+    <object-type name="item"/>
+  </object-types>
+  <event-types>
+    <event-type name="place order">
+      <attributes>
+        <attribute name="channel" type="string"/>
+      </attributes>
+    </event-type>
+  </event-types>
+  <objects>
+    <object id="o1" type="order">
+      <attributes>
+        <attribute name="price" time="1970-01-01T00:00:00Z">9.5</attribute>
+        <attribute name="price" time="2023-01-02T00:00:00Z">7.5</attribute>
+      </attributes>
+      <objects>
+        <relationship object-id="i1" qualifier="contains"/>
+      </objects>
+    </object>
+    <object id="i1" type="item"/>
+  </objects>
+  <events>
+    <event id="e1" type="place order" time="2023-01-01T10:00:00Z">
+      <attributes>
+        <attribute name="channel">web &amp; app</attribute>
+      </attributes>
+      <objects>
+        <relationship object-id="o1" qualifier="placed"/>
+        <relationship object-id="i1" qualifier="ordered"/>
+      </objects>
+    </event>
+  </events>
+</log>
+"#;
+
+    fn xml_file(content: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::Builder::new().suffix(".xmlocel").tempfile().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn reads_the_ocel2_layout() {
+        let log = read(xml_file(LOG).path()).unwrap();
+        let order = &log.objects["o1"];
+        assert_eq!(order.ovmap["price"], Value::from(9.5));
+        assert_eq!(order.attribute_changes.len(), 1);
+        assert_eq!(order.relationships, vec![Relationship { object_id: "i1".to_string(), qualifier: "contains".to_string() }]);
+        let event = &log.events["e1"];
+        assert_eq!(event.vmap["channel"], Value::from("web & app"));
+        assert_eq!(event.omap, vec!["o1".to_string(), "i1".to_string()]);
+        assert_eq!(event.relationships[1].qualifier, "ordered");
+    }
+
+    #[test]
+    fn round_trips() {
+        let log = read(xml_file(LOG).path()).unwrap();
+        let mut written = Vec::new();
+        write_to(&log, &mut written).unwrap();
+        let again = read(xml_file(&String::from_utf8(written).unwrap()).path()).unwrap();
+        assert_eq!(again.events, log.events);
+        assert_eq!(again.objects, log.objects);
+    }
+
+    #[test]
+    fn tells_the_xml_layouts_apart() {
+        assert!(is_ocel2_xml(xml_file(LOG).path()).unwrap());
+        let classic = r#"<log><global scope="event"/><events><event><string key="id" value="e1"/></event></events></log>"#;
+        assert!(!is_ocel2_xml(xml_file(classic).path()).unwrap());
+    }
+}
@@ -0,0 +1,243 @@
+//! Time-based performance per object type: how long objects live, how long they
+//! wait between two activities and how long each activity takes. OCEL events carry
+//! a single timestamp, so the time an activity takes is measured from the preceding
+//! event of the same object.
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::Write;
+use std::path::PathBuf;
+
+use clap::Args;
+use log::debug;
+use serde_json::{json, Value};
+
+use super::model::OcelLog;
+use super::time::format_seconds;
+use super::{report_writer, ReportFormat};
+use crate::table::{self, Table};
+
+#[derive(Args, Debug)]
+pub struct OcelPerformance {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Only measure objects of these types. May be repeated or comma separated
+    #[clap(long, value_name = "TYPE", use_value_delimiter = true, multiple_occurrences = true)]
+    pub object_type: Vec<String>,
+
+    /// Output format, table by default. Table durations are rounded, CSV and JSON
+    /// ones are in seconds
+    #[clap(long, value_enum)]
+    pub format: Option<ReportFormat>,
+
+    /// Write the report to this file instead of stdout
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+/// Summary statistics of a set of durations in seconds.
+#[derive(Debug, Clone, Copy)]
+pub struct Stats {
+    pub count: usize,
+    pub total: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub p95: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Stats {
+    /// `None` if there are no values. Percentiles are nearest-rank.
+    pub fn of(values: &[f64]) -> Option<Stats> {
+        if values.is_empty() {
+            return None;
+        }
+        let sorted = sorted(values);
+        let total: f64 = sorted.iter().sum();
+        Some(Stats {
+            count: sorted.len(),
+            total,
+            mean: total / sorted.len() as f64,
+            median: nearest_rank(&sorted, 0.5),
+            p95: nearest_rank(&sorted, 0.95),
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+        })
+    }
+
+    /// The nearest-rank quantile of `values` for every fraction in `quantiles`, like
+    /// the percentiles of [`Stats::of`]. Empty if there are no values.
+    pub fn quantiles(values: &[f64], quantiles: &[f64]) -> Vec<f64> {
+        if values.is_empty() {
+            return Vec::new();
+        }
+        let sorted = sorted(values);
+        quantiles.iter().map(|&q| nearest_rank(&sorted, q)).collect()
+    }
+}
+
+fn sorted(values: &[f64]) -> Vec<f64> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    sorted
+}
+
+/// The smallest value with at least the fraction `q` of `sorted` at or below it.
+fn nearest_rank(sorted: &[f64], q: f64) -> f64 {
+    sorted[((q * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len()) - 1]
+}
+
+/// Durations in seconds of the objects of one type.
+#[derive(Debug, Default)]
+pub struct TypePerformance<'a> {
+    pub objects: usize,
+    /// From the first to the last event of every object with events
+    pub lifecycle: Vec<f64>,
+    /// Between two directly following activities of an object
+    pub waiting: BTreeMap<(&'a str, &'a str), Vec<f64>>,
+    /// From the preceding event of the object to the activity
+    pub service: BTreeMap<&'a str, Vec<f64>>,
+}
+
+/// Performance of the given object types, all of them if `object_types` is empty.
+pub fn measure<'a>(log: &'a OcelLog, object_types: &[String]) -> BTreeMap<&'a str, TypePerformance<'a>> {
+    let mut per_type: BTreeMap<&str, TypePerformance> = BTreeMap::new();
+    for (oid, trace) in log.object_traces() {
+        let obj_type = match log.objects.get(oid) {
+            Some(object) if object_types.is_empty() || object_types.contains(&object.obj_type) => object.obj_type.as_str(),
+            _ => continue,
+        };
+        let performance = per_type.entry(obj_type).or_default();
+        performance.objects += 1;
+        let events: Vec<_> = trace.iter().map(|eid| &log.events[*eid]).collect();
+        if let (Some(first), Some(last)) = (events.first(), events.last()) {
+            performance.lifecycle.push(seconds(last.timestamp - first.timestamp));
+        }
+        for pair in events.windows(2) {
+            let duration = seconds(pair[1].timestamp - pair[0].timestamp);
+            performance.waiting.entry((pair[0].activity.as_str(), pair[1].activity.as_str())).or_default().push(duration);
+            performance.service.entry(pair[1].activity.as_str()).or_default().push(duration);
+        }
+    }
+    per_type
+}
+
+fn seconds(duration: chrono::Duration) -> f64 {
+    duration.num_milliseconds() as f64 / 1000.0
+}
+
+/// One output row: object type, metric, key and the statistics of its durations.
+type Row<'a> = (&'a str, &'static str, String, Stats);
+
+fn rows<'a>(per_type: &BTreeMap<&'a str, TypePerformance<'a>>) -> Vec<Row<'a>> {
+    let mut rows = Vec::new();
+    for (obj_type, performance) in per_type {
+        if let Some(stats) = Stats::of(&performance.lifecycle) {
+            rows.push((*obj_type, "lifecycle", String::new(), stats));
+        }
+        for ((from, to), durations) in &performance.waiting {
+            if let Some(stats) = Stats::of(durations) {
+                rows.push((*obj_type, "waiting", format!("{} -> {}", from, to), stats));
+            }
+        }
+        for (activity, durations) in &performance.service {
+            if let Some(stats) = Stats::of(durations) {
+                rows.push((*obj_type, "service", activity.to_string(), stats));
+            }
+        }
+    }
+    rows
+}
+
+pub fn run(args: &OcelPerformance) -> Result<(), Box<dyn Error>> {
+    debug!("Importing log: {:?}", args.path);
+    let log = OcelLog::import(&args.path)?;
+    let types = log.object_types();
+    if let Some(unknown) = args.object_type.iter().find(|t| !types.contains(&t.as_str())) {
+        return Err(format!("no object is of type {:?}, available types: {}", unknown, types.join(", ")).into());
+    }
+
+    let per_type = measure(&log, &args.object_type);
+    let rows = rows(&per_type);
+    let mut out = report_writer(args.output.as_deref())?;
+    match args.format.unwrap_or(ReportFormat::Table) {
+        ReportFormat::Table => {
+            let color = args.output.is_none() && table::color();
+            let measured = per_type.keys().filter(|obj_type| rows.iter().any(|(t, ..)| t == *obj_type));
+            for (index, obj_type) in measured.enumerate() {
+                if index > 0 {
+                    writeln!(out)?;
+                }
+                writeln!(out, "{} ({} objects)", obj_type, per_type[obj_type].objects)?;
+                let mut table = Table::new(&["metric", "key", "count", "mean", "median", "p95", "max"]).right(&[2, 3, 4, 5, 6]).indent(2);
+                for (_, metric, key, stats) in rows.iter().filter(|(t, ..)| t == obj_type) {
+                    table.row(vec![
+                        (*metric).into(),
+                        key.as_str().into(),
+                        stats.count.into(),
+                        format_seconds(stats.mean).into(),
+                        format_seconds(stats.median).into(),
+                        format_seconds(stats.p95).into(),
+                        format_seconds(stats.max).into(),
+                    ]);
+                }
+                table.write(&mut out, color)?;
+            }
+        }
+        ReportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(["object_type", "metric", "key", "count", "mean", "median", "p95", "min", "max"])?;
+            for (obj_type, metric, key, stats) in &rows {
+                writer.write_record([
+                    obj_type.to_string(),
+                    metric.to_string(),
+                    key.clone(),
+                    stats.count.to_string(),
+                    stats.mean.to_string(),
+                    stats.median.to_string(),
+                    stats.p95.to_string(),
+                    stats.min.to_string(),
+                    stats.max.to_string(),
+                ])?;
+            }
+            writer.flush()?;
+            return Ok(());
+        }
+        ReportFormat::Json => {
+            let report: Vec<Value> = rows
+                .iter()
+                .map(|(obj_type, metric, key, stats)| {
+                    json!({
+                        "object_type": obj_type,
+                        "metric": metric,
+                        "key": key,
+                        "count": stats.count,
+                        "mean": stats.mean,
+                        "median": stats.median,
+                        "p95": stats.p95,
+                        "min": stats.min,
+                        "max": stats.max,
+                    })
+                })
+                .collect();
+            writeln!(out, "{}", serde_json::to_string_pretty(&report)?)?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantiles_are_nearest_rank() {
+        let values: Vec<f64> = (1..=10).rev().map(f64::from).collect();
+        assert_eq!(Stats::quantiles(&values, &[0.0, 0.25, 0.5, 0.9, 0.99, 1.0]), [1.0, 3.0, 5.0, 9.0, 10.0, 10.0]);
+        assert!(Stats::quantiles(&[], &[0.5]).is_empty());
+        let stats = Stats::of(&values).unwrap();
+        assert_eq!((stats.min, stats.median, stats.p95, stats.max, stats.mean), (1.0, 5.0, 10.0, 10.0, 5.5));
+    }
+}
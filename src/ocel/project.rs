@@ -0,0 +1,108 @@
+//! Projecting a log onto some of its attributes, to slim raw exports down to what
+//! the analysis uses before every later step pays for the rest.
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use log::{debug, warn};
+use serde_json::{Map, Value};
+
+use super::model::OcelLog;
+
+#[derive(Args, Debug)]
+pub struct OcelProject {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// Only keep these event attributes. May be repeated or comma separated
+    #[clap(long, value_name = "KEY", use_value_delimiter = true, multiple_occurrences = true, conflicts_with = "drop-event-attrs")]
+    pub keep_event_attrs: Option<Vec<String>>,
+
+    /// Remove these event attributes. May be repeated or comma separated
+    #[clap(long, value_name = "KEY", use_value_delimiter = true, multiple_occurrences = true)]
+    pub drop_event_attrs: Option<Vec<String>>,
+
+    /// Only keep these object attributes. May be repeated or comma separated
+    #[clap(long, value_name = "KEY", use_value_delimiter = true, multiple_occurrences = true, conflicts_with = "drop-object-attrs")]
+    pub keep_object_attrs: Option<Vec<String>>,
+
+    /// Remove these object attributes. May be repeated or comma separated
+    #[clap(long, value_name = "KEY", use_value_delimiter = true, multiple_occurrences = true)]
+    pub drop_object_attrs: Option<Vec<String>>,
+}
+
+/// Which attributes of a kind survive. The whole map if neither list was given.
+enum Projection<'a> {
+    All,
+    Keep(HashSet<&'a str>),
+    Drop(HashSet<&'a str>),
+}
+
+impl<'a> Projection<'a> {
+    fn of(keep: &'a Option<Vec<String>>, drop: &'a Option<Vec<String>>) -> Projection<'a> {
+        let set = |keys: &'a Vec<String>| keys.iter().map(String::as_str).collect();
+        match (keep, drop) {
+            (Some(keys), _) => Projection::Keep(set(keys)),
+            (None, Some(keys)) => Projection::Drop(set(keys)),
+            (None, None) => Projection::All,
+        }
+    }
+
+    fn keeps(&self, key: &str) -> bool {
+        match self {
+            Projection::All => true,
+            Projection::Keep(keys) => keys.contains(key),
+            Projection::Drop(keys) => !keys.contains(key),
+        }
+    }
+
+    /// Warns about the named keys no map in `maps` carries, as they are likely typos.
+    fn check<'m>(&self, kind: &str, maps: impl Iterator<Item = &'m Map<String, Value>>) {
+        let keys = match self {
+            Projection::All => return,
+            Projection::Keep(keys) | Projection::Drop(keys) => keys,
+        };
+        let present: HashSet<&str> = maps.flat_map(|map| map.keys()).map(String::as_str).collect();
+        for key in keys.iter().filter(|key| !present.contains(**key)) {
+            warn!("No {} has an attribute {:?}, naming it has no effect.", kind, key);
+        }
+    }
+}
+
+pub fn run(project: &OcelProject) -> Result<(), Box<dyn Error>> {
+    let events = Projection::of(&project.keep_event_attrs, &project.drop_event_attrs);
+    let objects = Projection::of(&project.keep_object_attrs, &project.drop_object_attrs);
+    if let (Projection::All, Projection::All) = (&events, &objects) {
+        return Err("nothing to project, pass --keep-event-attrs, --drop-event-attrs, --keep-object-attrs or --drop-object-attrs".into());
+    }
+
+    debug!("Importing log: {:?}", project.path);
+    let mut log = OcelLog::import(&project.path)?;
+    events.check("event", log.events.values().map(|event| &event.vmap));
+    objects.check("object", log.objects.values().map(|object| &object.ovmap));
+
+    let mut removed = 0;
+    for event in log.events.values_mut() {
+        let before = event.vmap.len();
+        event.vmap.retain(|key, _| events.keeps(key));
+        removed += before - event.vmap.len();
+    }
+    for object in log.objects.values_mut() {
+        let before = object.ovmap.len();
+        object.ovmap.retain(|key, _| objects.keeps(key));
+        object.attribute_changes.retain(|change| objects.keeps(&change.name));
+        removed += before - object.ovmap.len();
+    }
+    log.refresh_globals();
+
+    debug!("Exporting projected log to {:?}", project.output);
+    log.export(&project.output)?;
+    let attributes = log.global_log.get("ocel:attribute-names").and_then(Value::as_array).map_or(0, Vec::len);
+    status!("removed {} attribute values, {} attribute names remain -> {}", removed, attributes, project.output.display());
+    Ok(())
+}
@@ -0,0 +1,434 @@
+//! A small selection language over events, e.g.
+//! `activity == "ship" and "item" in otypes and vmap.price > 100`.
+//!
+//! Fields are `id`, `activity`, `timestamp`, `omap` (list of object ids), `otypes`
+//! (list of referenced object types) and `vmap.<key>`. Literals are strings in
+//! double quotes, numbers, `true`, `false` and `null`. Comparisons (`==`, `!=`,
+//! `<`, `<=`, `>`, `>=`, `in`) combine with `and`, `or`, `not` and parentheses.
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use log::debug;
+use serde_json::Value;
+
+use super::head::print_event;
+use super::model::{Event, OcelLog};
+use super::time::parse_instant;
+
+#[derive(Args, Debug)]
+pub struct OcelQuery {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Selection expression, e.g. `activity == "ship" and vmap.price > 100`
+    pub expression: String,
+
+    /// Write the matching events and their objects to this file instead of
+    /// printing them
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Print at most this many matching events
+    #[clap(short = 'n', long, conflicts_with = "output")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Str(String),
+    Num(f64),
+    Ident(String),
+    Op(&'static str),
+    Open,
+    Close,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push(Token::Open);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                i += 1;
+            }
+            '"' => {
+                let mut literal = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        Some('"') => break,
+                        Some('\\') if i + 1 < chars.len() => {
+                            literal.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(c) => {
+                            literal.push(*c);
+                            i += 1;
+                        }
+                        None => return Err("unterminated string literal".to_string()),
+                    }
+                }
+                i += 1;
+                tokens.push(Token::Str(literal));
+            }
+            '=' | '!' | '<' | '>' => {
+                let next = chars.get(i + 1) == Some(&'=');
+                let op = match (c, next) {
+                    ('=', true) => "==",
+                    ('!', true) => "!=",
+                    ('<', true) => "<=",
+                    ('>', true) => ">=",
+                    ('<', false) => "<",
+                    ('>', false) => ">",
+                    _ => return Err(format!("unexpected {:?} at position {}", c, i)),
+                };
+                tokens.push(Token::Op(op));
+                i += op.len();
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let raw: String = chars[start..i].iter().collect();
+                tokens.push(Token::Num(raw.parse().map_err(|_| format!("invalid number {:?}", raw))?));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '.' | ':' | '-')) {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            }
+            c => return Err(format!("unexpected {:?} at position {}", c, i)),
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Literal(Value),
+    Field(String),
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Compare(Operand, &'static str, Operand),
+    /// A bare operand, true if the value is truthy
+    Test(Operand),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn keyword(&mut self, word: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(ident)) if ident == word) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.not()?;
+        while self.keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        if self.peek() == Some(&Token::Open) {
+            self.position += 1;
+            let expr = self.or()?;
+            if self.peek() != Some(&Token::Close) {
+                return Err("missing closing parenthesis".to_string());
+            }
+            self.position += 1;
+            return Ok(expr);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let left = self.operand()?;
+        let op = match self.peek() {
+            Some(Token::Op(op)) => *op,
+            Some(Token::Ident(ident)) if ident == "in" => "in",
+            _ => return Ok(Expr::Test(left)),
+        };
+        self.position += 1;
+        Ok(Expr::Compare(left, op, self.operand()?))
+    }
+
+    fn operand(&mut self) -> Result<Operand, String> {
+        let token = self.peek().cloned().ok_or("unexpected end of expression")?;
+        self.position += 1;
+        Ok(match token {
+            Token::Str(s) => Operand::Literal(Value::String(s)),
+            Token::Num(n) => Operand::Literal(Value::from(n)),
+            Token::Ident(ident) => match ident.as_str() {
+                "true" => Operand::Literal(Value::Bool(true)),
+                "false" => Operand::Literal(Value::Bool(false)),
+                "null" => Operand::Literal(Value::Null),
+                "and" | "or" | "not" | "in" => return Err(format!("expected a value, found {:?}", ident)),
+                "id" | "activity" | "timestamp" | "omap" | "otypes" => Operand::Field(ident),
+                field if field.starts_with("vmap.") => Operand::Field(ident),
+                _ => {
+                    return Err(format!(
+                        "unknown field {:?}, expected id, activity, timestamp, omap, otypes or vmap.<key>",
+                        ident
+                    ))
+                }
+            },
+            other => return Err(format!("expected a value, found {:?}", other)),
+        })
+    }
+}
+
+fn parse(input: &str) -> Result<Expr, String> {
+    let mut parser = Parser { tokens: tokenize(input)?, position: 0 };
+    let expr = parser.or()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(format!("unexpected {:?} after the end of the expression", token)),
+    }
+}
+
+struct Context<'a> {
+    log: &'a OcelLog,
+    id: &'a str,
+    event: &'a Event,
+}
+
+impl Context<'_> {
+    fn resolve(&self, operand: &Operand) -> Value {
+        let field = match operand {
+            Operand::Literal(value) => return value.clone(),
+            Operand::Field(field) => field.as_str(),
+        };
+        match field {
+            "id" => Value::from(self.id),
+            "activity" => Value::from(self.event.activity.as_str()),
+            "timestamp" => Value::from(self.event.timestamp.to_rfc3339()),
+            "omap" => Value::from(self.event.omap.clone()),
+            "otypes" => {
+                let mut types: Vec<&str> =
+                    self.event.omap.iter().filter_map(|oid| self.log.objects.get(oid)).map(|o| o.obj_type.as_str()).collect();
+                types.sort_unstable();
+                types.dedup();
+                Value::from(types)
+            }
+            _ => {
+                let key = field.strip_prefix("vmap.").unwrap_or(field);
+                self.event.vmap.get(key).cloned().unwrap_or(Value::Null)
+            }
+        }
+    }
+}
+
+/// Orders numbers numerically, timestamps chronologically and anything else by its
+/// text. Strings holding numbers count as numbers, so `"10"` sorts after `"9"`.
+/// Values of different kinds do not compare.
+fn order(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64()?.partial_cmp(&y.as_f64()?),
+        (Value::Number(x), Value::String(y)) => x.as_f64()?.partial_cmp(&y.parse::<f64>().ok()?),
+        (Value::String(x), Value::Number(y)) => x.parse::<f64>().ok()?.partial_cmp(&y.as_f64()?),
+        (Value::String(x), Value::String(y)) => {
+            if let (Ok(x), Ok(y)) = (x.parse::<f64>(), y.parse::<f64>()) {
+                return x.partial_cmp(&y);
+            }
+            match (parse_instant(x), parse_instant(y)) {
+                (Ok(x), Ok(y)) => Some(x.cmp(&y)),
+                _ => Some(x.cmp(y)),
+            }
+        }
+        (Value::Bool(x), Value::Bool(y)) => Some(x.cmp(y)),
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(values) => !values.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+fn evaluate(expr: &Expr, context: &Context) -> bool {
+    match expr {
+        Expr::Not(inner) => !evaluate(inner, context),
+        Expr::And(a, b) => evaluate(a, context) && evaluate(b, context),
+        Expr::Or(a, b) => evaluate(a, context) || evaluate(b, context),
+        Expr::Test(operand) => truthy(&context.resolve(operand)),
+        Expr::Compare(left, op, right) => {
+            let (a, b) = (context.resolve(left), context.resolve(right));
+            match *op {
+                "in" => match &b {
+                    Value::Array(values) => values.iter().any(|v| order(&a, v) == Some(Ordering::Equal)),
+                    Value::String(s) => a.as_str().map_or(false, |needle| s.contains(needle)),
+                    _ => false,
+                },
+                "==" => order(&a, &b) == Some(Ordering::Equal),
+                "!=" => order(&a, &b) != Some(Ordering::Equal),
+                "<" => order(&a, &b) == Some(Ordering::Less),
+                "<=" => matches!(order(&a, &b), Some(Ordering::Less | Ordering::Equal)),
+                ">" => order(&a, &b) == Some(Ordering::Greater),
+                _ => matches!(order(&a, &b), Some(Ordering::Greater | Ordering::Equal)),
+            }
+        }
+    }
+}
+
+/// Ids of the events of `log` that match `expression`, in log order.
+pub fn select<'a>(log: &'a OcelLog, expression: &str) -> Result<Vec<&'a str>, String> {
+    let expr = parse(expression).map_err(|e| format!("invalid expression: {}", e))?;
+    Ok(log.events.iter().filter(|(id, event)| evaluate(&expr, &Context { log, id, event })).map(|(id, _)| id.as_str()).collect())
+}
+
+pub fn run(query: &OcelQuery) -> Result<(), Box<dyn Error>> {
+    let expr = parse(&query.expression).map_err(|e| format!("invalid expression: {}", e))?;
+    debug!("Parsed expression: {:?}", expr);
+
+    debug!("Importing log: {:?}", query.path);
+    let log = OcelLog::import(&query.path)?;
+
+    let matching: Vec<(&String, &Event)> = log
+        .events
+        .iter()
+        .filter(|(id, event)| evaluate(&expr, &Context { log: &log, id, event }))
+        .collect();
+
+    if let Some(output) = &query.output {
+        let selected: HashSet<&str> = matching.iter().map(|(id, _)| id.as_str()).collect();
+        let subset = log.subset(&selected, &HashSet::new());
+        debug!("Exporting matching events to {:?}", output);
+        subset.export(output)?;
+        status!("{} of {} events match -> {}", subset.events.len(), log.events.len(), output.display());
+        return Ok(());
+    }
+
+    for (id, event) in matching.iter().take(query.limit.unwrap_or(usize::MAX)) {
+        print_event(&log, id, event);
+    }
+    println!("{} of {} events match", matching.len(), log.events.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log() -> OcelLog {
+        serde_json::from_str(
+            r#"{"ocel:events": {
+                    "e1": {"ocel:activity": "ship", "ocel:timestamp": "2023-01-02T00:00:00Z",
+                           "ocel:omap": ["o1", "i1"], "ocel:vmap": {"price": 120, "code": "10"}},
+                    "e2": {"ocel:activity": "pay", "ocel:timestamp": "2023-01-01T00:00:00Z",
+                           "ocel:omap": ["o1"], "ocel:vmap": {"price": 80, "code": "9"}}},
+                "ocel:objects": {
+                    "o1": {"ocel:type": "order", "ocel:ovmap": {}},
+                    "i1": {"ocel:type": "item", "ocel:ovmap": {}}}}"#,
+        )
+        .unwrap()
+    }
+
+    fn matching(expression: &str) -> Vec<String> {
+        let log = log();
+        select(&log, expression).unwrap().into_iter().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn tokenizes_literals_fields_and_operators() {
+        let tokens = tokenize(r#"vmap.price >= -1.5 and not (activity != "a \"b\"")"#).unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Ident("vmap.price".to_string()),
+                Token::Op(">="),
+                Token::Num(-1.5),
+                Token::Ident("and".to_string()),
+                Token::Ident("not".to_string()),
+                Token::Open,
+                Token::Ident("activity".to_string()),
+                Token::Op("!="),
+                Token::Str("a \"b\"".to_string()),
+                Token::Close,
+            ]
+        );
+        assert!(tokenize(r#"activity == "ship"#).is_err());
+        assert!(tokenize("activity = 1").is_err());
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        match parse("id == 1 or id == 2 and id == 3").unwrap() {
+            Expr::Or(_, right) => assert!(matches!(*right, Expr::And(_, _))),
+            other => panic!("expected an or, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for expression in ["(id == 1", "id ==", "id == 1 2", "price > 1", "and"] {
+            assert!(parse(expression).is_err(), "{} parsed", expression);
+        }
+    }
+
+    #[test]
+    fn evaluates_against_events() {
+        assert_eq!(matching(r#"activity == "ship""#), ["e1"]);
+        assert_eq!(matching(r#""item" in otypes"#), ["e1"]);
+        assert_eq!(matching(r#"not "i1" in omap"#), ["e2"]);
+        assert_eq!(matching("vmap.price > 100 or vmap.price < 90"), ["e1", "e2"]);
+        assert_eq!(matching(r#"timestamp < "2023-01-02""#), ["e2"]);
+        assert_eq!(matching("vmap.missing"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn numeric_strings_compare_as_numbers() {
+        assert_eq!(matching(r#"vmap.code > "9""#), ["e1"]);
+        assert_eq!(order(&Value::from("10"), &Value::from("9")), Some(Ordering::Greater));
+        assert_eq!(order(&Value::from("b"), &Value::from("a")), Some(Ordering::Greater));
+    }
+}
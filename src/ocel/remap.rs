@@ -0,0 +1,154 @@
+//! Renaming activities or object types from a mapping table, e.g. to replace the
+//! codes of a source system with readable names or to harmonize the types of
+//! several systems. Mapping several names to one merges them.
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use indexmap::IndexMap;
+use log::{debug, warn};
+
+use super::mapping;
+use super::model::OcelLog;
+
+#[derive(Args, Debug)]
+pub struct OcelRemapActivities {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// CSV file with a header row and the old and the new activity name in the first
+    /// two columns
+    #[clap(long, value_name = "PATH")]
+    pub mapping: PathBuf,
+
+    /// Fail instead of keeping activities the mapping has no row for
+    #[clap(long)]
+    pub strict: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct OcelRemapTypes {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// CSV file with a header row and the old and the new object type in the first
+    /// two columns
+    #[clap(long, value_name = "PATH")]
+    pub mapping: PathBuf,
+
+    /// Fail instead of keeping object types the mapping has no row for
+    #[clap(long)]
+    pub strict: bool,
+}
+
+/// What renaming the names of one kind changed.
+struct Remapped {
+    /// Events or objects that got a new name
+    renamed: usize,
+    /// Old names present in the log that the mapping covers
+    mapped: usize,
+    /// New names the mapped ones end up as
+    targets: usize,
+    unmatched: usize,
+}
+
+/// Renames every name in `names` that has a row in `renames`. `kind` is the plural
+/// of what is renamed, for the messages.
+fn remap<'a>(
+    names: impl Iterator<Item = &'a mut String>,
+    renames: &IndexMap<String, String>,
+    strict: bool,
+    kind: &str,
+) -> Result<Remapped, Box<dyn Error>> {
+    let mut names: Vec<&mut String> = names.collect();
+    let present: HashSet<String> = names.iter().map(|name| name.to_string()).collect();
+    // occurrences of the names the mapping does not cover, in log order
+    let mut unmatched: IndexMap<String, usize> = IndexMap::new();
+    for name in &names {
+        if !renames.contains_key(name.as_str()) {
+            *unmatched.entry(name.to_string()).or_default() += 1;
+        }
+    }
+    if strict && !unmatched.is_empty() {
+        let missing: Vec<&str> = unmatched.keys().map(String::as_str).collect();
+        return Err(format!("the mapping has no row for the {} {}", kind, missing.join(", ")).into());
+    }
+    for (name, count) in &unmatched {
+        warn!("The mapping has no row for {:?}, its {} occurrences keep the name.", name, count);
+    }
+
+    let mut renamed = 0;
+    for name in names.iter_mut() {
+        if let Some(new) = renames.get(name.as_str()) {
+            if **name != *new {
+                **name = new.clone();
+                renamed += 1;
+            }
+        }
+    }
+
+    let unused: Vec<&str> = renames.keys().filter(|old| !present.contains(*old)).map(String::as_str).collect();
+    if !unused.is_empty() {
+        debug!("The mapping rows for {} match none of the {} of the log", unused.join(", "), kind);
+    }
+    let mut sources: IndexMap<&str, usize> = IndexMap::new();
+    for (_, new) in renames.iter().filter(|(old, _)| present.contains(*old)) {
+        *sources.entry(new.as_str()).or_default() += 1;
+    }
+    for (new, count) in sources.iter().filter(|(_, count)| **count > 1) {
+        debug!("{} {} are merged into {:?}", count, kind, new);
+    }
+    Ok(Remapped { renamed, mapped: present.len() - unmatched.len(), targets: sources.len(), unmatched: unmatched.len() })
+}
+
+pub fn activities(remap_activities: &OcelRemapActivities) -> Result<(), Box<dyn Error>> {
+    let renames = mapping::read(&remap_activities.mapping)?;
+    debug!("Importing log: {:?}", remap_activities.path);
+    let mut log = OcelLog::import(&remap_activities.path)?;
+
+    let remapped = remap(log.events.values_mut().map(|event| &mut event.activity), &renames, remap_activities.strict, "activities")?;
+
+    debug!("Exporting remapped log to {:?}", remap_activities.output);
+    log.export(&remap_activities.output)?;
+    status!(
+        "renamed {} events, {} activities mapped to {} and {} unmatched activities kept -> {}",
+        remapped.renamed,
+        remapped.mapped,
+        remapped.targets,
+        remapped.unmatched,
+        remap_activities.output.display()
+    );
+    Ok(())
+}
+
+/// Object ids are kept, so the omaps of the events and the object-to-object
+/// relationships go on pointing at the same objects under their new type.
+pub fn types(remap_types: &OcelRemapTypes) -> Result<(), Box<dyn Error>> {
+    let renames = mapping::read(&remap_types.mapping)?;
+    debug!("Importing log: {:?}", remap_types.path);
+    let mut log = OcelLog::import(&remap_types.path)?;
+
+    let remapped = remap(log.objects.values_mut().map(|object| &mut object.obj_type), &renames, remap_types.strict, "object types")?;
+    log.refresh_globals();
+
+    debug!("Exporting remapped log to {:?}", remap_types.output);
+    log.export(&remap_types.output)?;
+    status!(
+        "retyped {} objects, {} object types mapped to {} and {} unmatched object types kept -> {}",
+        remapped.renamed,
+        remapped.mapped,
+        remapped.targets,
+        remapped.unmatched,
+        remap_types.output.display()
+    );
+    Ok(())
+}
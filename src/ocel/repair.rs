@@ -0,0 +1,145 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use log::debug;
+use serde::de::{Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::model::{Format, OcelLog};
+use crate::compression;
+
+#[derive(Args, Debug)]
+pub struct OcelRepair {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+}
+
+/// Keys of a JSON object in document order, including repeated ones.
+#[derive(Default)]
+struct Keys(Vec<String>);
+
+impl<'de> Deserialize<'de> for Keys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Keys, D::Error> {
+        struct KeysVisitor;
+
+        impl<'de> Visitor<'de> for KeysVisitor {
+            type Value = Keys;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Keys, A::Error> {
+                let mut keys = Vec::new();
+                while let Some((key, IgnoredAny)) = map.next_entry::<String, IgnoredAny>()? {
+                    keys.push(key);
+                }
+                Ok(Keys(keys))
+            }
+        }
+
+        deserializer.deserialize_map(KeysVisitor)
+    }
+}
+
+#[derive(Deserialize)]
+struct IdProbe {
+    #[serde(rename = "ocel:events", default)]
+    events: Keys,
+    #[serde(rename = "ocel:objects", default)]
+    objects: Keys,
+}
+
+fn duplicates(keys: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut repeated: Vec<String> = Vec::new();
+    for key in keys {
+        if !seen.insert(key.clone()) && !repeated.contains(&key) {
+            repeated.push(key);
+        }
+    }
+    repeated
+}
+
+/// Repeated event and object ids of a JSON-OCEL file. They are collapsed silently
+/// on import, so they have to be found on the raw document.
+fn duplicate_ids(path: &Path) -> Result<(Vec<String>, Vec<String>), Box<dyn Error>> {
+    let probe: IdProbe = serde_json::from_reader(compression::open(path)?)?;
+    Ok((duplicates(probe.events.0), duplicates(probe.objects.0)))
+}
+
+pub fn run(repair: &OcelRepair) -> Result<(), Box<dyn Error>> {
+    let mut report: Vec<String> = Vec::new();
+
+    if Format::from_path(&repair.path)? == Format::JsonOcel && !super::ocel2::is_ocel2(&repair.path)? {
+        let (events, objects) = duplicate_ids(&repair.path)?;
+        for id in events {
+            report.push(format!("duplicate event id {}: kept the last definition", id));
+        }
+        for id in objects {
+            report.push(format!("duplicate object id {}: kept the last definition", id));
+        }
+    }
+
+    debug!("Importing log: {:?}", repair.path);
+    let mut log = OcelLog::import(&repair.path)?;
+
+    let known: HashSet<String> = log.objects.keys().cloned().collect();
+    for (id, event) in log.events.iter_mut() {
+        let before = event.omap.len();
+        let mut seen = HashSet::new();
+        event.omap.retain(|oid| known.contains(oid) && seen.insert(oid.clone()));
+        event.relationships.retain(|r| known.contains(&r.object_id));
+        if event.omap.len() != before {
+            report.push(format!("event {}: removed {} dangling or repeated object references", id, before - event.omap.len()));
+        }
+    }
+
+    let sorted = log.events.values().zip(log.events.values().skip(1)).all(|(a, b)| a.timestamp <= b.timestamp);
+    if !sorted {
+        log.events.sort_by(|_, a, _, b| a.timestamp.cmp(&b.timestamp));
+        report.push("sorted the events by timestamp".to_string());
+    }
+
+    for (key, default) in [("ocel:version", "1.0"), ("ocel:ordering", "timestamp")] {
+        if !log.global_log.contains_key(key) {
+            log.global_log.insert(key.to_string(), Value::from(default));
+            report.push(format!("added missing global-log key {}", key));
+        }
+    }
+    if !log.global_event.contains_key("ocel:activity") {
+        log.global_event.insert("ocel:activity".to_string(), Value::from("__INVALID__"));
+        report.push("added missing global-event key ocel:activity".to_string());
+    }
+    if !log.global_object.contains_key("ocel:type") {
+        log.global_object.insert("ocel:type".to_string(), Value::from("__INVALID__"));
+        report.push("added missing global-object key ocel:type".to_string());
+    }
+    let declared = log.global_log.clone();
+    log.refresh_globals();
+    for key in ["ocel:object-types", "ocel:attribute-names"] {
+        if declared.get(key) != log.global_log.get(key) {
+            report.push(format!("rewrote global-log key {} to match the log", key));
+        }
+    }
+
+    debug!("Exporting repaired log to {:?}", repair.output);
+    log.export(&repair.output)?;
+    if report.is_empty() {
+        status!("nothing to repair -> {}", repair.output.display());
+    } else {
+        for line in &report {
+            status!("{}", line);
+        }
+        status!("{} repairs -> {}", report.len(), repair.output.display());
+    }
+    Ok(())
+}
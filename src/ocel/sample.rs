@@ -0,0 +1,87 @@
+use std::collections::{BTreeSet, HashSet};
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use indexmap::IndexMap;
+use log::debug;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use super::model::OcelLog;
+
+#[derive(Args, Debug)]
+pub struct OcelSample {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// Number of events to sample
+    #[clap(long, value_name = "N")]
+    pub events: usize,
+
+    /// Sample every activity in proportion to its share of the log
+    #[clap(long, conflicts_with = "per-object-type")]
+    pub per_activity: bool,
+
+    /// Sample every combination of referenced object types in proportion to its
+    /// share of the log
+    #[clap(long)]
+    pub per_object_type: bool,
+
+    /// Seed for the random number generator, for reproducible samples
+    #[clap(long)]
+    pub seed: Option<u64>,
+}
+
+pub fn run(sample: &OcelSample) -> Result<(), Box<dyn Error>> {
+    debug!("Importing log: {:?}", sample.path);
+    let log = OcelLog::import(&sample.path)?;
+
+    let mut rng = match sample.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    // events grouped into strata, a single one unless sampling is stratified
+    let mut strata: IndexMap<String, Vec<&str>> = IndexMap::new();
+    for (id, event) in &log.events {
+        let stratum = if sample.per_activity {
+            event.activity.clone()
+        } else if sample.per_object_type {
+            let types: BTreeSet<&str> =
+                event.omap.iter().filter_map(|oid| log.objects.get(oid)).map(|o| o.obj_type.as_str()).collect();
+            types.into_iter().collect::<Vec<_>>().join("+")
+        } else {
+            String::new()
+        };
+        strata.entry(stratum).or_default().push(id.as_str());
+    }
+
+    let total = log.events.len().max(1);
+    let mut selected: HashSet<&str> = HashSet::new();
+    for (name, ids) in &strata {
+        // proportional allocation, but never leave a stratum out entirely
+        let quota = ((sample.events as f64 * ids.len() as f64 / total as f64).round() as usize).clamp(1, ids.len());
+        let quota = quota.min(sample.events);
+        debug!("Sampling {} of {} events from stratum {:?}", quota, ids.len(), name);
+        selected.extend(ids.choose_multiple(&mut rng, quota).copied());
+    }
+
+    let sampled = log.subset(&selected, &HashSet::new());
+    debug!("Exporting sample to {:?}", sample.output);
+    sampled.export(&sample.output)?;
+    status!(
+        "sampled {} of {} events with {} of {} objects -> {}",
+        sampled.events.len(),
+        log.events.len(),
+        sampled.objects.len(),
+        log.objects.len(),
+        sample.output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,127 @@
+//! The implicit schema of a log: which attributes, with which value types, occur
+//! for every activity and object type.
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use log::debug;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use super::model::{display_value, value_type, OcelLog};
+
+#[derive(Args, Debug)]
+pub struct OcelSchema {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Print the schema as JSON
+    #[clap(long)]
+    pub json: bool,
+}
+
+/// Attribute name to the set of value types observed for it.
+pub type Attributes = BTreeMap<String, BTreeSet<&'static str>>;
+
+#[derive(Debug, Default, Serialize)]
+pub struct Schema {
+    pub activities: BTreeMap<String, Attributes>,
+    pub object_types: BTreeMap<String, Attributes>,
+    /// The declared `ocel:global-log`, `ocel:global-event` and `ocel:global-object`
+    /// maps. Informational only, they are not part of the differences.
+    pub globals: BTreeMap<&'static str, Map<String, Value>>,
+}
+
+fn record(attributes: &mut Attributes, map: &Map<String, Value>) {
+    for (key, value) in map {
+        attributes.entry(key.clone()).or_default().insert(value_type(value));
+    }
+}
+
+impl Schema {
+    pub fn infer(log: &OcelLog) -> Schema {
+        let mut schema = Schema::default();
+        for event in log.events.values() {
+            record(schema.activities.entry(event.activity.clone()).or_default(), &event.vmap);
+        }
+        for object in log.objects.values() {
+            record(schema.object_types.entry(object.obj_type.clone()).or_default(), &object.ovmap);
+        }
+        schema.globals.insert("log", log.global_log.clone());
+        schema.globals.insert("event", log.global_event.clone());
+        schema.globals.insert("object", log.global_object.clone());
+        schema
+    }
+
+    /// Human readable differences between `self` (the reference) and `other`.
+    pub fn differences(&self, other: &Schema) -> Vec<String> {
+        let mut differences = Vec::new();
+        compare_classes("activity", &self.activities, &other.activities, &mut differences);
+        compare_classes("object type", &self.object_types, &other.object_types, &mut differences);
+        differences
+    }
+}
+
+fn compare_classes(kind: &str, reference: &BTreeMap<String, Attributes>, other: &BTreeMap<String, Attributes>, differences: &mut Vec<String>) {
+    for (name, attributes) in reference {
+        let theirs = match other.get(name) {
+            Some(theirs) => theirs,
+            None => {
+                differences.push(format!("{} {:?} only exists in the reference", kind, name));
+                continue;
+            }
+        };
+        for (key, types) in attributes {
+            match theirs.get(key) {
+                None => differences.push(format!("{} {:?}: attribute {:?} only exists in the reference", kind, name, key)),
+                Some(their_types) if their_types != types => differences.push(format!(
+                    "{} {:?}: attribute {:?} has type {} in the reference but {}",
+                    kind,
+                    name,
+                    key,
+                    join(types),
+                    join(their_types)
+                )),
+                Some(_) => {}
+            }
+        }
+        for key in theirs.keys().filter(|key| !attributes.contains_key(*key)) {
+            differences.push(format!("{} {:?}: attribute {:?} is missing from the reference", kind, name, key));
+        }
+    }
+    for name in other.keys().filter(|name| !reference.contains_key(*name)) {
+        differences.push(format!("{} {:?} is missing from the reference", kind, name));
+    }
+}
+
+fn join(types: &BTreeSet<&'static str>) -> String {
+    types.iter().copied().collect::<Vec<_>>().join("|")
+}
+
+pub fn run(schema: &OcelSchema) -> Result<(), Box<dyn Error>> {
+    debug!("Importing log: {:?}", schema.path);
+    let log = OcelLog::import(&schema.path)?;
+    let inferred = Schema::infer(&log);
+
+    if schema.json {
+        println!("{}", serde_json::to_string_pretty(&inferred)?);
+        return Ok(());
+    }
+
+    for (kind, classes) in [("activity", &inferred.activities), ("object type", &inferred.object_types)] {
+        for (name, attributes) in classes {
+            println!("{} {}", kind, name);
+            for (key, types) in attributes {
+                println!("    {}: {}", key, join(types));
+            }
+        }
+    }
+    for (scope, map) in &inferred.globals {
+        println!("global {}", scope);
+        for (key, value) in map {
+            println!("    {}: {}", key, display_value(value));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Args;
+use log::debug;
+
+use super::model::OcelLog;
+
+#[derive(Args, Debug)]
+pub struct OcelSort {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Output file name and location
+    #[clap(short, long)]
+    pub output: PathBuf,
+
+    /// Replace the event ids by `<PREFIX><N>`, numbered in the new order
+    #[clap(long, value_name = "PREFIX", min_values = 0, default_missing_value = "e")]
+    pub renumber: Option<String>,
+}
+
+pub fn run(sort: &OcelSort) -> Result<(), Box<dyn Error>> {
+    debug!("Importing log: {:?}", sort.path);
+    let mut log = OcelLog::import(&sort.path)?;
+
+    let unordered = log.events.values().zip(log.events.values().skip(1)).filter(|(a, b)| a.timestamp > b.timestamp).count();
+
+    // ties on the timestamp are broken by activity and then id, so reruns agree
+    log.events.sort_by(|id_a, a, id_b, b| (a.timestamp, &a.activity, id_a).cmp(&(b.timestamp, &b.activity, id_b)));
+
+    if let Some(prefix) = &sort.renumber {
+        let events = std::mem::take(&mut log.events);
+        log.events = events.into_iter().enumerate().map(|(i, (_, event))| (format!("{}{}", prefix, i + 1), event)).collect();
+    }
+
+    debug!("Exporting sorted log to {:?}", sort.output);
+    log.export(&sort.output)?;
+    status!("sorted {} events, {} were out of order -> {}", log.events.len(), unordered, sort.output.display());
+    Ok(())
+}
@@ -0,0 +1,212 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use indexmap::IndexMap;
+use log::debug;
+
+use super::model::{display_value, Event, OcelLog};
+use crate::exit::{self, Failure};
+
+#[derive(Args, Debug)]
+pub struct OcelSplit {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// How to partition the log: `month` writes one log per calendar month,
+    /// `object-type` one per object type and `attribute <KEY>` one per distinct
+    /// value of an object attribute
+    #[clap(long, min_values = 1, max_values = 2, value_names = &["MODE", "KEY"], required_unless_present = "chunks")]
+    pub by: Vec<String>,
+
+    /// Split the events, in time order, into this many logs of about equal size
+    #[clap(long, value_name = "N", conflicts_with = "by")]
+    pub chunks: Option<usize>,
+
+    /// Directory the partitions are written to. Default: the working directory
+    #[clap(short, long)]
+    pub output_dir: Option<PathBuf>,
+}
+
+enum Partitioning<'a> {
+    Attribute(&'a str),
+    Month,
+    ObjectType,
+    Chunks(usize),
+}
+
+impl OcelSplit {
+    fn partitioning(&self) -> Result<Partitioning<'_>, String> {
+        if let Some(chunks) = self.chunks {
+            if chunks == 0 {
+                return Err("--chunks needs to be at least 1".to_string());
+            }
+            return Ok(Partitioning::Chunks(chunks));
+        }
+        let mode = self.by.first().ok_or("either --by or --chunks is required")?;
+        match (mode.as_str(), self.by.get(1)) {
+            ("attribute", Some(key)) => Ok(Partitioning::Attribute(key)),
+            ("attribute", None) => Err("--by attribute needs the attribute key, e.g. `--by attribute region`".to_string()),
+            ("month", None) => Ok(Partitioning::Month),
+            ("object-type", None) => Ok(Partitioning::ObjectType),
+            ("month" | "object-type", Some(_)) => Err(format!("--by {} takes no further value", mode)),
+            (mode, _) => Err(format!("unknown split mode {:?}, expected `month`, `object-type` or `attribute <KEY>`", mode)),
+        }
+    }
+}
+
+/// Name of the partition of the objects that lack the split attribute. It is kept
+/// apart from an attribute value of the same name, whose file would clash with it.
+const MISSING: &str = "_missing";
+
+pub fn run(split: &OcelSplit) -> Result<(), Box<dyn Error>> {
+    let partitioning = split.partitioning()?;
+    debug!("Importing log: {:?}", split.path);
+    let log = OcelLog::import(&split.path)?;
+
+    let partitions = match partitioning {
+        Partitioning::Attribute(key) => by_attribute(&log, key),
+        Partitioning::Month => by_month(&log),
+        Partitioning::ObjectType => by_object_type(&log),
+        Partitioning::Chunks(n) => in_chunks(&log, n),
+    };
+
+    let dir = split.output_dir.clone().unwrap_or_else(|| PathBuf::from("."));
+    let paths = partition_paths(&dir, &split.path, partitions.iter().map(|(name, _)| name.as_str()))?;
+    fs::create_dir_all(&dir)?;
+    for ((name, part), path) in partitions.into_iter().zip(paths) {
+        debug!("Exporting partition {:?} to {:?}", name, path);
+        part.export(&path)?;
+        status!("{}: {} events, {} objects -> {}", name, part.events.len(), part.objects.len(), path.display());
+    }
+    Ok(())
+}
+
+/// One partition per distinct value of object attribute `key`. An event belongs to
+/// every partition that one of its objects qualifies for.
+fn by_attribute(log: &OcelLog, key: &str) -> Vec<(String, OcelLog)> {
+    let mut members: IndexMap<Option<String>, HashSet<&str>> = IndexMap::new();
+    for (id, object) in &log.objects {
+        members.entry(object.ovmap.get(key).map(display_value)).or_default().insert(id);
+    }
+
+    members
+        .into_iter()
+        .map(|(value, objects)| {
+            let events: HashSet<&str> = log
+                .events
+                .iter()
+                .filter(|(_, event)| event.omap.iter().any(|oid| objects.contains(oid.as_str())))
+                .map(|(id, _)| id.as_str())
+                .collect();
+            (value.unwrap_or_else(|| MISSING.to_string()), log.subset(&events, &objects))
+        })
+        .collect()
+}
+
+/// One partition per calendar month (in the offset of the event timestamps).
+fn by_month(log: &OcelLog) -> Vec<(String, OcelLog)> {
+    let mut members: BTreeMap<String, HashSet<&str>> = BTreeMap::new();
+    for (id, event) in &log.events {
+        members.entry(event.timestamp.format("%Y-%m").to_string()).or_default().insert(id);
+    }
+    members.into_iter().map(|(month, events)| (month, log.subset(&events, &HashSet::new()))).collect()
+}
+
+/// One partition per object type with every event that references an object of
+/// that type. An event touching several types appears in several partitions.
+fn by_object_type(log: &OcelLog) -> Vec<(String, OcelLog)> {
+    let mut members: IndexMap<String, HashSet<&str>> = IndexMap::new();
+    for (id, object) in &log.objects {
+        members.entry(object.obj_type.clone()).or_default().insert(id);
+    }
+
+    members
+        .into_iter()
+        .map(|(obj_type, objects)| {
+            let events: HashSet<&str> = log
+                .events
+                .iter()
+                .filter(|(_, event)| event.omap.iter().any(|oid| objects.contains(oid.as_str())))
+                .map(|(id, _)| id.as_str())
+                .collect();
+            (obj_type, log.subset(&events, &objects))
+        })
+        .collect()
+}
+
+/// `n` partitions of consecutive events in time order, numbered from 1.
+fn in_chunks(log: &OcelLog, n: usize) -> Vec<(String, OcelLog)> {
+    let mut ordered: Vec<(&String, &Event)> = log.events.iter().collect();
+    ordered.sort_by_key(|(_, event)| event.timestamp);
+    let size = ((ordered.len() + n - 1) / n).max(1);
+    let width = n.to_string().len();
+
+    ordered
+        .chunks(size)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let events: HashSet<&str> = chunk.iter().map(|(id, _)| id.as_str()).collect();
+            (format!("{:0width$}", i + 1, width = width), log.subset(&events, &HashSet::new()))
+        })
+        .collect()
+}
+
+/// `<dir>/<input stem>-<partition>.jsonocel`, with the partition name reduced to
+/// file name safe characters.
+pub(super) fn partition_path(dir: &Path, input: &Path, name: &str) -> PathBuf {
+    let stem = input.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_else(|| "log".to_string());
+    let safe: String = name.chars().map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect();
+    dir.join(format!("{}-{}.jsonocel", stem, safe))
+}
+
+/// The [`partition_path`] of every partition, checked before anything is written so
+/// that no partition overwrites another one whose name reduces to the same file.
+fn partition_paths<'a>(dir: &Path, input: &Path, names: impl Iterator<Item = &'a str>) -> Result<Vec<PathBuf>, Failure> {
+    let mut seen: HashMap<PathBuf, &str> = HashMap::new();
+    let mut paths = Vec::new();
+    for name in names {
+        let path = partition_path(dir, input, name);
+        if let Some(other) = seen.insert(path.clone(), name) {
+            let message = format!("the partitions {:?} and {:?} would both be written to {}", other, name, path.display());
+            return Err(Failure::new(exit::USAGE, message));
+        }
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_objects_without_the_attribute_apart() {
+        let log: OcelLog = serde_json::from_str(
+            r#"{"ocel:events": {
+                    "e1": {"ocel:activity": "a", "ocel:timestamp": "2023-01-01T00:00:00Z", "ocel:omap": ["o1"], "ocel:vmap": {}},
+                    "e2": {"ocel:activity": "b", "ocel:timestamp": "2023-01-02T00:00:00Z", "ocel:omap": ["o2"], "ocel:vmap": {}}},
+                "ocel:objects": {
+                    "o1": {"ocel:type": "order", "ocel:ovmap": {"region": "unknown"}},
+                    "o2": {"ocel:type": "order", "ocel:ovmap": {}}}}"#,
+        )
+        .unwrap();
+        let partitions = by_attribute(&log, "region");
+        let names: Vec<&str> = partitions.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["unknown", MISSING]);
+        assert!(partitions.iter().all(|(_, part)| part.events.len() == 1 && part.objects.len() == 1));
+    }
+
+    #[test]
+    fn rejects_partitions_sharing_a_file() {
+        let (dir, input) = (Path::new("out"), Path::new("log.jsonocel"));
+        let paths = partition_paths(dir, input, ["north", "south"].into_iter()).unwrap();
+        assert_eq!(paths, [dir.join("log-north.jsonocel"), dir.join("log-south.jsonocel")]);
+
+        let clash = partition_paths(dir, input, ["a/b", "a?b"].into_iter()).unwrap_err();
+        assert_eq!(clash.code, exit::USAGE);
+        assert!(partition_paths(dir, input, [MISSING, "_missing"].into_iter()).is_err());
+    }
+}
@@ -0,0 +1,622 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use clap::{Args, ValueEnum};
+use indexmap::IndexMap;
+use log::debug;
+use serde_json::{json, Value};
+
+use super::model::{numeric, Event, Object, OcelLog};
+use super::streaming::{self, Entry};
+use super::time::parse_duration;
+use super::window::TimeWindow;
+use crate::table::{Cell, Style, Table};
+
+#[derive(Args, Debug)]
+pub struct OcelStats {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    #[clap(flatten)]
+    pub window: TimeWindow,
+
+    /// Pearson correlation of two numeric attributes over all events and objects
+    /// that carry both
+    #[clap(long, number_of_values = 2, value_names = &["KEY_A", "KEY_B"])]
+    pub attribute_correlation: Option<Vec<String>>,
+
+    /// Print a CSV matrix counting how often an event directly follows another one
+    /// on a shared object, between their activities or object types as chosen by
+    /// --handover-by
+    #[clap(long)]
+    pub handover_matrix: bool,
+
+    /// What the rows and columns of --handover-matrix are
+    #[clap(long, value_enum, default_value_t = HandoverBy::Activity, requires = "handover-matrix")]
+    pub handover_by: HandoverBy,
+
+    /// Restrict --handover-matrix to objects of this type
+    #[clap(long, value_name = "TYPE", requires = "handover-matrix")]
+    pub by_type: Option<String>,
+
+    /// List objects whose last event is older than this duration (e.g. `7d`, `12h`)
+    #[clap(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub idle_objects: Option<Duration>,
+
+    /// Point in time --idle-objects is measured from
+    #[clap(long, value_enum, default_value_t = RelativeTo::LogEnd, requires = "idle-objects")]
+    pub relative_to: RelativeTo,
+
+    /// Print the summary, --handover-matrix or --idle-objects report as JSON
+    #[clap(long)]
+    pub json: bool,
+
+    /// Add tables per activity and per object type: events per activity, objects and
+    /// average events per object of each type, and the attribute fill rates of both
+    #[clap(long, conflicts_with_all = &["attribute-correlation", "handover-matrix", "idle-objects"])]
+    pub breakdown: bool,
+
+    /// Read the log one event and object at a time instead of importing it, for logs
+    /// too large for memory. Only the summary is available this way
+    #[clap(long, conflicts_with_all = &["attribute-correlation", "handover-matrix", "idle-objects"])]
+    pub streaming: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HandoverBy {
+    /// The activities of the two events
+    Activity,
+    /// Every object type of the first event against every object type of the second
+    ObjectType,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RelativeTo {
+    /// The latest timestamp in the log
+    LogEnd,
+    /// The current time
+    Now,
+}
+
+pub fn run(stats: &OcelStats) -> Result<(), Box<dyn Error>> {
+    if stats.streaming {
+        debug!("Streaming log: {:?}", stats.path);
+        return streamed_summary(&stats.path, &stats.window, stats.breakdown, stats.json);
+    }
+    debug!("Importing log: {:?}", stats.path);
+    let log = stats.window.import(&stats.path)?;
+
+    if let Some(keys) = &stats.attribute_correlation {
+        return attribute_correlation(&log, &keys[0], &keys[1]);
+    }
+    if let Some(threshold) = stats.idle_objects {
+        return idle_objects(&log, threshold, stats.relative_to, stats.json);
+    }
+    if stats.handover_matrix {
+        return handover_matrix(&log, stats.handover_by, stats.by_type.as_deref(), stats.json);
+    }
+
+    print_summary(&Summary::of(&log, stats.breakdown), stats.json)
+}
+
+/// The figures of the default `ocel stats` output, gathered one event and object at
+/// a time so that a streamed log can be summarized without keeping it.
+#[derive(Default)]
+struct Summary {
+    events: usize,
+    objects: usize,
+    activities: HashSet<String>,
+    object_types: IndexMap<String, usize>,
+    first: Option<DateTime<FixedOffset>>,
+    last: Option<DateTime<FixedOffset>>,
+    references: usize,
+    /// Events (or objects) carrying each attribute key, in order of first occurrence
+    event_keys: IndexMap<String, usize>,
+    object_keys: IndexMap<String, usize>,
+    /// Only gathered with --breakdown
+    breakdown: Option<Breakdown>,
+}
+
+/// The figures of `ocel stats --breakdown`, per activity and per object type.
+#[derive(Default)]
+struct Breakdown {
+    activities: IndexMap<String, Group>,
+    object_types: IndexMap<String, Group>,
+    /// Events referencing each object id
+    references: HashMap<String, usize>,
+    types: HashMap<String, String>,
+}
+
+/// The members of an activity or object type and how many carry each attribute key.
+#[derive(Default)]
+struct Group {
+    count: usize,
+    keys: IndexMap<String, usize>,
+}
+
+impl Group {
+    fn add(&mut self, attributes: &serde_json::Map<String, Value>) {
+        self.count += 1;
+        count_keys(&mut self.keys, attributes);
+    }
+}
+
+impl Breakdown {
+    /// Average number of events per object of each type.
+    fn events_per_object(&self) -> IndexMap<&str, f64> {
+        let mut totals: HashMap<&str, usize> = HashMap::new();
+        for (oid, obj_type) in &self.types {
+            *totals.entry(obj_type.as_str()).or_default() += self.references.get(oid).copied().unwrap_or(0);
+        }
+        self.object_types
+            .iter()
+            .map(|(obj_type, group)| (obj_type.as_str(), totals.get(obj_type.as_str()).copied().unwrap_or(0) as f64 / group.count.max(1) as f64))
+            .collect()
+    }
+
+    fn to_json(&self) -> Value {
+        let events_per_object = self.events_per_object();
+        let activities: serde_json::Map<String, Value> = self
+            .activities
+            .iter()
+            .map(|(activity, group)| (activity.clone(), json!({ "events": group.count, "attribute_fill_rates": coverage(&group.keys, group.count) })))
+            .collect();
+        let object_types: serde_json::Map<String, Value> = self
+            .object_types
+            .iter()
+            .map(|(obj_type, group)| {
+                let value = json!({
+                    "objects": group.count,
+                    "events_per_object": events_per_object.get(obj_type.as_str()),
+                    "attribute_fill_rates": coverage(&group.keys, group.count),
+                });
+                (obj_type.clone(), value)
+            })
+            .collect();
+        json!({ "activities": activities, "object_types": object_types })
+    }
+
+    fn print(&self, events: usize) {
+        println!();
+        let mut activities = Table::new(&["activity", "events", "share"]).right(&[1, 2]);
+        for (activity, group) in &self.activities {
+            let share = group.count as f64 / events.max(1) as f64;
+            activities.row(vec![activity.as_str().into(), group.count.into(), format!("{:.1}%", share * 100.0).into()]);
+        }
+        activities.print();
+
+        println!();
+        let events_per_object = self.events_per_object();
+        let mut types = Table::new(&["object type", "objects", "events per object"]).right(&[1, 2]);
+        for (obj_type, group) in &self.object_types {
+            let average = events_per_object.get(obj_type.as_str()).copied().unwrap_or(0.0);
+            types.row(vec![obj_type.as_str().into(), group.count.into(), format!("{:.2}", average).into()]);
+        }
+        types.print();
+
+        for (kind, groups) in [("activity", &self.activities), ("object type", &self.object_types)] {
+            for (name, group) in groups.iter().filter(|(_, group)| !group.keys.is_empty()) {
+                println!();
+                println!("attribute fill rates of {} {}:", kind, name);
+                let mut table = Table::new(&["attribute", "filled"]).right(&[1]).indent(4);
+                for (key, share) in coverage(&group.keys, group.count) {
+                    let style = if share < 1.0 { Style::Warn } else { Style::Plain };
+                    table.row(vec![key.into(), Cell::styled(format!("{:.1}%", share * 100.0), style)]);
+                }
+                table.print();
+            }
+        }
+    }
+}
+
+impl Summary {
+    fn new(breakdown: bool) -> Summary {
+        Summary { breakdown: breakdown.then(Breakdown::default), ..Summary::default() }
+    }
+
+    fn of(log: &OcelLog, breakdown: bool) -> Summary {
+        let mut summary = Summary::new(breakdown);
+        for (id, object) in &log.objects {
+            summary.add_object(id, object);
+        }
+        for event in log.events.values() {
+            summary.add_event(event);
+        }
+        summary
+    }
+
+    fn add_event(&mut self, event: &Event) {
+        self.events += 1;
+        if !self.activities.contains(&event.activity) {
+            self.activities.insert(event.activity.clone());
+        }
+        self.first = Some(self.first.map_or(event.timestamp, |first| first.min(event.timestamp)));
+        self.last = Some(self.last.map_or(event.timestamp, |last| last.max(event.timestamp)));
+        self.references += event.omap.len();
+        count_keys(&mut self.event_keys, &event.vmap);
+        if let Some(breakdown) = &mut self.breakdown {
+            group(&mut breakdown.activities, &event.activity).add(&event.vmap);
+            for oid in &event.omap {
+                match breakdown.references.get_mut(oid) {
+                    Some(count) => *count += 1,
+                    None => {
+                        breakdown.references.insert(oid.clone(), 1);
+                    }
+                }
+            }
+        }
+    }
+
+    fn add_object(&mut self, id: &str, object: &Object) {
+        self.objects += 1;
+        match self.object_types.get_mut(&object.obj_type) {
+            Some(count) => *count += 1,
+            None => {
+                self.object_types.insert(object.obj_type.clone(), 1);
+            }
+        }
+        count_keys(&mut self.object_keys, &object.ovmap);
+        if let Some(breakdown) = &mut self.breakdown {
+            group(&mut breakdown.object_types, &object.obj_type).add(&object.ovmap);
+            breakdown.types.insert(id.to_string(), object.obj_type.clone());
+        }
+    }
+
+    fn objects_per_event(&self) -> f64 {
+        self.references as f64 / self.events.max(1) as f64
+    }
+
+    /// Share of events carrying each attribute key.
+    fn event_coverage(&self) -> IndexMap<&str, f64> {
+        coverage(&self.event_keys, self.events)
+    }
+
+    /// Share of objects carrying each attribute key.
+    fn object_coverage(&self) -> IndexMap<&str, f64> {
+        coverage(&self.object_keys, self.objects)
+    }
+
+    fn to_json(&self) -> Value {
+        let mut summary = json!({
+            "events": self.events,
+            "objects": self.objects,
+            "activities": self.activities.len(),
+            "object_types": self.object_types,
+            "first_event": self.first.map(|t| t.to_rfc3339()),
+            "last_event": self.last.map(|t| t.to_rfc3339()),
+            "span_seconds": self.first.zip(self.last).map(|(f, l)| (l - f).num_seconds()),
+            "objects_per_event": self.objects_per_event(),
+            "event_attribute_coverage": self.event_coverage(),
+            "object_attribute_coverage": self.object_coverage(),
+        });
+        if let Some(breakdown) = &self.breakdown {
+            summary["breakdown"] = breakdown.to_json();
+        }
+        summary
+    }
+
+    fn print(&self) {
+        let mut overview = Table::new(&["metric", "value"]).right(&[1]);
+        overview.row(vec!["events".into(), self.events.into()]);
+        overview.row(vec!["objects".into(), self.objects.into()]);
+        overview.row(vec!["activities".into(), self.activities.len().into()]);
+        overview.row(vec!["object types".into(), self.object_types.len().into()]);
+        overview.row(vec!["objects per event".into(), format!("{:.2}", self.objects_per_event()).into()]);
+        if let (Some(first), Some(last)) = (self.first, self.last) {
+            let span = last - first;
+            overview.row(vec!["first event".into(), first.to_rfc3339().into()]);
+            overview.row(vec!["last event".into(), last.to_rfc3339().into()]);
+            overview.row(vec!["time span".into(), format!("{}d {}h", span.num_days(), span.num_hours() % 24).into()]);
+        }
+        overview.print();
+
+        println!();
+        let mut types = Table::new(&["object type", "objects"]).right(&[1]);
+        for (obj_type, count) in &self.object_types {
+            types.row(vec![obj_type.as_str().into(), (*count).into()]);
+        }
+        types.print();
+        for (title, coverage) in [("event attribute", self.event_coverage()), ("object attribute", self.object_coverage())] {
+            println!();
+            let mut table = Table::new(&[title, "coverage"]).right(&[1]);
+            for (key, share) in coverage {
+                let style = if share < 1.0 { Style::Warn } else { Style::Plain };
+                table.row(vec![key.into(), Cell::styled(format!("{:.1}%", share * 100.0), style)]);
+            }
+            table.print();
+        }
+        if let Some(breakdown) = &self.breakdown {
+            breakdown.print(self.events);
+        }
+    }
+}
+
+/// The group of `name`, created on first use without cloning the name again.
+fn group<'a>(groups: &'a mut IndexMap<String, Group>, name: &str) -> &'a mut Group {
+    if !groups.contains_key(name) {
+        groups.insert(name.to_string(), Group::default());
+    }
+    &mut groups[name]
+}
+
+fn count_keys(counts: &mut IndexMap<String, usize>, map: &serde_json::Map<String, Value>) {
+    for key in map.keys() {
+        match counts.get_mut(key) {
+            Some(count) => *count += 1,
+            None => {
+                counts.insert(key.clone(), 1);
+            }
+        }
+    }
+}
+
+fn coverage(counts: &IndexMap<String, usize>, total: usize) -> IndexMap<&str, f64> {
+    counts.iter().map(|(key, n)| (key.as_str(), *n as f64 / total.max(1) as f64)).collect()
+}
+
+/// The summary as printed by `ocel stats --json`.
+pub fn summary_json(log: &OcelLog) -> Value {
+    Summary::of(log, false).to_json()
+}
+
+pub fn summary(log: &OcelLog, as_json: bool) -> Result<(), Box<dyn Error>> {
+    print_summary(&Summary::of(log, false), as_json)
+}
+
+fn print_summary(summary: &Summary, as_json: bool) -> Result<(), Box<dyn Error>> {
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&summary.to_json())?);
+    } else {
+        summary.print();
+    }
+    Ok(())
+}
+
+/// The summary of a JSON-OCEL file read entry by entry, holding one event or object
+/// at a time.
+fn streamed_summary(path: &Path, window: &TimeWindow, breakdown: bool, as_json: bool) -> Result<(), Box<dyn Error>> {
+    window.check()?;
+    let mut summary = Summary::new(breakdown);
+    // with a window only the objects referenced inside it count. Events and objects
+    // come as two arrays, so objects read before the events wait for them
+    let mut referenced: HashSet<String> = HashSet::new();
+    let mut pending: Vec<(String, Object)> = Vec::new();
+    let mut events_read = false;
+    streaming::read(path, |entry| match entry {
+        Entry::Event(_, event) => {
+            events_read = true;
+            if window.contains(event.timestamp) {
+                if window.is_set() {
+                    referenced.extend(event.omap.iter().cloned());
+                }
+                summary.add_event(&event);
+            }
+        }
+        Entry::Object(id, object) if !window.is_set() || referenced.contains(&id) => summary.add_object(&id, &object),
+        Entry::Object(id, object) if !events_read => pending.push((id, object)),
+        Entry::Object(..) => {}
+    })?;
+    for (id, object) in pending.iter().filter(|(id, _)| referenced.contains(id)) {
+        summary.add_object(id, object);
+    }
+    print_summary(&summary, as_json)
+}
+
+fn attribute_correlation(log: &OcelLog, key_a: &str, key_b: &str) -> Result<(), Box<dyn Error>> {
+    let maps = log.events.values().map(|e| &e.vmap).chain(log.objects.values().map(|o| &o.ovmap));
+
+    let mut pairs: Vec<(f64, f64)> = Vec::new();
+    let mut skipped = 0;
+    for map in maps {
+        let (a, b) = match (map.get(key_a), map.get(key_b)) {
+            (Some(a), Some(b)) => (a, b),
+            _ => continue,
+        };
+        match (numeric(a), numeric(b)) {
+            (Some(a), Some(b)) => pairs.push((a, b)),
+            _ => skipped += 1,
+        }
+    }
+
+    println!("n: {}", pairs.len());
+    println!("skipped (non-numeric): {}", skipped);
+    match pearson(&pairs) {
+        Some(r) => println!("pearson r({}, {}): {:.4}", key_a, key_b, r),
+        None => println!("pearson r({}, {}): undefined (needs at least two values with non-zero variance)", key_a, key_b),
+    }
+    Ok(())
+}
+
+fn pearson(pairs: &[(f64, f64)]) -> Option<f64> {
+    if pairs.len() < 2 {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let mean_a = pairs.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_b = pairs.iter().map(|p| p.1).sum::<f64>() / n;
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (a, b) in pairs {
+        cov += (a - mean_a) * (b - mean_b);
+        var_a += (a - mean_a).powi(2);
+        var_b += (b - mean_b).powi(2);
+    }
+    if var_a == 0.0 || var_b == 0.0 {
+        return None;
+    }
+    Some(cov / (var_a.sqrt() * var_b.sqrt()))
+}
+
+/// Counts directly-follows pairs of events over the per-object traces, by the labels
+/// of `by`, and returns the labels with the counts. A pair of events is counted once
+/// even if several objects share it.
+fn handover_counts<'a>(
+    log: &'a OcelLog,
+    by: HandoverBy,
+    by_type: Option<&str>,
+) -> (Vec<&'a str>, HashMap<(&'a str, &'a str), usize>) {
+    let labels = match by {
+        HandoverBy::Activity => log.activities(),
+        HandoverBy::ObjectType => log.object_types(),
+    };
+    let event_labels = |eid: &str| -> BTreeSet<&'a str> {
+        let event = &log.events[eid];
+        match by {
+            HandoverBy::Activity => BTreeSet::from([event.activity.as_str()]),
+            HandoverBy::ObjectType => event.omap.iter().filter_map(|oid| log.objects.get(oid)).map(|o| o.obj_type.as_str()).collect(),
+        }
+    };
+    let mut counts: HashMap<(&str, &str), usize> = HashMap::new();
+    let mut seen: HashSet<(&str, &str)> = HashSet::new();
+
+    for (oid, trace) in log.object_traces() {
+        if let Some(wanted) = by_type {
+            if log.objects.get(oid).map(|o| o.obj_type.as_str()) != Some(wanted) {
+                continue;
+            }
+        }
+        for pair in trace.windows(2) {
+            if seen.insert((pair[0], pair[1])) {
+                let to = event_labels(pair[1]);
+                for from in event_labels(pair[0]) {
+                    for to in &to {
+                        *counts.entry((from, *to)).or_default() += 1;
+                    }
+                }
+            }
+        }
+    }
+    (labels, counts)
+}
+
+fn handover_matrix(log: &OcelLog, by: HandoverBy, by_type: Option<&str>, as_json: bool) -> Result<(), Box<dyn Error>> {
+    let (labels, counts) = handover_counts(log, by, by_type);
+    let count = |from: &str, to: &str| counts.get(&(from, to)).copied().unwrap_or(0);
+
+    if as_json {
+        let matrix: serde_json::Map<String, Value> = labels
+            .iter()
+            .map(|from| (from.to_string(), Value::Object(labels.iter().map(|to| (to.to_string(), json!(count(*from, *to)))).collect())))
+            .collect();
+        let by = match by {
+            HandoverBy::Activity => "activity",
+            HandoverBy::ObjectType => "object-type",
+        };
+        println!("{}", serde_json::to_string_pretty(&json!({ "by": by, "labels": labels, "counts": matrix }))?);
+        return Ok(());
+    }
+
+    let mut writer = csv::Writer::from_writer(std::io::stdout());
+    let mut header = vec!["from/to"];
+    header.extend(labels.iter().copied());
+    writer.write_record(&header)?;
+    for from in &labels {
+        let mut row = vec![from.to_string()];
+        for to in &labels {
+            row.push(count(*from, *to).to_string());
+        }
+        writer.write_record(&row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+const IDLE_SAMPLE: usize = 5;
+
+/// Objects whose last event lies more than `threshold` before the reference time,
+/// grouped by type. Objects without any event are always idle.
+fn idle_objects(log: &OcelLog, threshold: Duration, relative_to: RelativeTo, as_json: bool) -> Result<(), Box<dyn Error>> {
+    let reference: DateTime<FixedOffset> = match relative_to {
+        RelativeTo::LogEnd => log.events.values().map(|e| e.timestamp).max().ok_or("the log has no events")?,
+        RelativeTo::Now => Utc::now().into(),
+    };
+    let cutoff = reference
+        .checked_sub_signed(threshold)
+        .ok_or_else(|| format!("--idle-objects reaches back before the earliest representable time from {}", reference.to_rfc3339()))?;
+
+    let mut last_seen: IndexMap<&str, Option<DateTime<FixedOffset>>> = log.objects.keys().map(|id| (id.as_str(), None)).collect();
+    for event in log.events.values() {
+        for oid in &event.omap {
+            if let Some(last) = last_seen.get_mut(oid.as_str()) {
+                *last = Some(last.map_or(event.timestamp, |t| t.max(event.timestamp)));
+            }
+        }
+    }
+
+    let mut idle: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (id, last) in &last_seen {
+        if last.map_or(true, |t| t < cutoff) {
+            let obj_type = log.objects.get(*id).map(|o| o.obj_type.as_str()).unwrap_or_default();
+            idle.entry(obj_type).or_default().push(id);
+        }
+    }
+
+    if as_json {
+        let per_type: serde_json::Map<String, Value> = idle
+            .iter()
+            .map(|(t, ids)| (t.to_string(), json!({"count": ids.len(), "sample": &ids[..ids.len().min(IDLE_SAMPLE)]})))
+            .collect();
+        let report = json!({
+            "reference": reference.to_rfc3339(),
+            "cutoff": cutoff.to_rfc3339(),
+            "idle": idle.values().map(Vec::len).sum::<usize>(),
+            "types": per_type,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    let anchor = match relative_to {
+        RelativeTo::LogEnd => "end of log",
+        RelativeTo::Now => "now",
+    };
+    println!("objects without events since {} ({}: {}):", cutoff.to_rfc3339(), anchor, reference.to_rfc3339());
+    for (obj_type, ids) in &idle {
+        let sample: Vec<&str> = ids.iter().take(IDLE_SAMPLE).copied().collect();
+        println!("    {}: {} (e.g. {})", obj_type, ids.len(), sample.join(", "));
+    }
+    println!("total idle: {}", idle.values().map(Vec::len).sum::<usize>());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log() -> OcelLog {
+        serde_json::from_str(
+            r#"{"ocel:events": {
+                    "e1": {"ocel:activity": "place", "ocel:timestamp": "2023-01-01T00:00:00Z",
+                           "ocel:omap": ["o1"], "ocel:vmap": {}},
+                    "e2": {"ocel:activity": "pick", "ocel:timestamp": "2023-01-02T00:00:00Z",
+                           "ocel:omap": ["o1", "i1"], "ocel:vmap": {}},
+                    "e3": {"ocel:activity": "ship", "ocel:timestamp": "2023-01-03T00:00:00Z",
+                           "ocel:omap": ["i1"], "ocel:vmap": {}}},
+                "ocel:objects": {
+                    "o1": {"ocel:type": "order", "ocel:ovmap": {}},
+                    "i1": {"ocel:type": "item", "ocel:ovmap": {}}}}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn counts_handovers_between_activities() {
+        let log = log();
+        let (labels, counts) = handover_counts(&log, HandoverBy::Activity, None);
+        assert_eq!(labels, ["place", "pick", "ship"]);
+        assert_eq!(counts, HashMap::from([(("place", "pick"), 1), (("pick", "ship"), 1)]));
+
+        let (_, items) = handover_counts(&log, HandoverBy::Activity, Some("item"));
+        assert_eq!(items, HashMap::from([(("pick", "ship"), 1)]));
+    }
+
+    #[test]
+    fn counts_handovers_between_object_types() {
+        let log = log();
+        let (labels, counts) = handover_counts(&log, HandoverBy::ObjectType, None);
+        assert_eq!(labels, ["item", "order"]);
+        // e1 -> e2 on o1 and e2 -> e3 on i1, e2 references both types
+        assert_eq!(counts, HashMap::from([(("order", "item"), 2), (("order", "order"), 1), (("item", "item"), 1)]));
+    }
+}
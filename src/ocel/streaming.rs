@@ -0,0 +1,375 @@
+//! Validation and reading of JSON-OCEL files without loading them. The document is
+//! walked entry by entry, so memory stays bounded by one event or object plus, when
+//! validating, the sets of ids needed for the referential checks.
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde_json::{Map, Value};
+
+use super::model::{parse_timestamp, Event, Object};
+use super::ocel2;
+use crate::compression;
+
+/// Files larger than this are validated in streaming mode even without `--streaming`.
+pub const AUTO_THRESHOLD: u64 = 1 << 30;
+
+/// At most this many issues are kept, the rest are only counted.
+const MAX_ISSUES: usize = 1000;
+
+const GLOBAL_LOG_KEYS: [&str; 3] = ["ocel:version", "ocel:attribute-names", "ocel:object-types"];
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub events: usize,
+    pub objects: usize,
+    /// `(message, location)` pairs, like `validate_ocel_verbose`
+    pub issues: Vec<(String, String)>,
+    pub total_issues: usize,
+}
+
+impl Report {
+    fn issue(&mut self, message: impl Into<String>, location: impl Into<String>) {
+        self.total_issues += 1;
+        if self.issues.len() < MAX_ISSUES {
+            self.issues.push((message.into(), location.into()));
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    report: Report,
+    event_ids: HashSet<String>,
+    object_ids: HashSet<String>,
+    /// Referenced object ids that were not defined yet when they were referenced
+    pending: HashSet<String>,
+    sections: HashSet<String>,
+    /// Events checked so far, for progress reports from another thread
+    progress: Arc<AtomicUsize>,
+}
+
+/// An event or object with its id, as read by [`read`].
+pub enum Entry {
+    Event(String, Event),
+    Object(String, Object),
+}
+
+/// Reads the JSON-OCEL 1.0 log at `path`, handing every event and object to `visit`
+/// in file order. The global sections are skipped.
+pub fn read(path: &Path, mut visit: impl FnMut(Entry)) -> Result<(), Box<dyn Error>> {
+    if ocel2::is_ocel2(path)? {
+        return Err(format!("{:?} is an OCEL 2.0 log, which can only be read whole", path).into());
+    }
+    let mut deserializer = serde_json::Deserializer::from_reader(compression::open(path)?);
+    ReadSeed(&mut visit).deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(())
+}
+
+struct ReadSeed<'a, F>(&'a mut F);
+
+impl<'de, F: FnMut(Entry)> DeserializeSeed<'de> for ReadSeed<'_, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, F: FnMut(Entry)> Visitor<'de> for ReadSeed<'_, F> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON-OCEL document")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "ocel:events" => map.next_value_seed(ReadEntries { visit: &mut *self.0, kind: Kind::Event })?,
+                "ocel:objects" => map.next_value_seed(ReadEntries { visit: &mut *self.0, kind: Kind::Object })?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+struct ReadEntries<'a, F> {
+    visit: &'a mut F,
+    kind: Kind,
+}
+
+impl<'de, F: FnMut(Entry)> DeserializeSeed<'de> for ReadEntries<'_, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, F: FnMut(Entry)> Visitor<'de> for ReadEntries<'_, F> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of ids to entries")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        match self.kind {
+            Kind::Event => {
+                while let Some((id, event)) = map.next_entry::<String, Event>()? {
+                    (self.visit)(Entry::Event(id, event));
+                }
+            }
+            Kind::Object => {
+                while let Some((id, object)) = map.next_entry::<String, Object>()? {
+                    (self.visit)(Entry::Object(id, object));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks the JSON-OCEL 1.0 log at `path`. OCEL 2.0 logs are rejected, their layout
+/// has none of the sections checked here. `progress` counts the events checked
+/// while the validation runs.
+pub fn validate(path: &Path, progress: Arc<AtomicUsize>) -> Result<Report, Box<dyn Error>> {
+    if ocel2::is_ocel2(path)? {
+        return Err(format!("{:?} is an OCEL 2.0 log, which cannot be validated entry by entry", path).into());
+    }
+    let mut state = State { progress, ..State::default() };
+    let mut deserializer = serde_json::Deserializer::from_reader(compression::open(path)?);
+    DocumentSeed(&mut state).deserialize(&mut deserializer)?;
+    deserializer.end()?;
+
+    for section in ["ocel:global-log", "ocel:global-event", "ocel:global-object", "ocel:events", "ocel:objects"] {
+        if !state.sections.contains(section) {
+            state.report.issue(format!("missing required key {}", section), "/");
+        }
+    }
+    let mut dangling: Vec<String> = state.pending.into_iter().filter(|oid| !state.object_ids.contains(oid)).collect();
+    dangling.sort();
+    for oid in dangling {
+        state.report.issue(format!("object {} is referenced but not defined", oid), "/ocel:events");
+    }
+    Ok(state.report)
+}
+
+struct DocumentSeed<'a>(&'a mut State);
+
+impl<'de> DeserializeSeed<'de> for DocumentSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DocumentSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON-OCEL document")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let state = self.0;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "ocel:global-log" => {
+                    let globals: Map<String, Value> = map.next_value()?;
+                    for required in GLOBAL_LOG_KEYS {
+                        if !globals.contains_key(required) {
+                            state.report.issue(format!("missing required key {}", required), "/ocel:global-log");
+                        }
+                    }
+                }
+                "ocel:global-event" | "ocel:global-object" => {
+                    map.next_value::<Map<String, Value>>()?;
+                }
+                "ocel:events" => map.next_value_seed(EntriesSeed { state: &mut *state, kind: Kind::Event })?,
+                "ocel:objects" => map.next_value_seed(EntriesSeed { state: &mut *state, kind: Kind::Object })?,
+                other => {
+                    state.report.issue(format!("unexpected key {}", other), "/");
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+            state.sections.insert(key);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Event,
+    Object,
+}
+
+struct EntriesSeed<'a> {
+    state: &'a mut State,
+    kind: Kind,
+}
+
+impl<'de> DeserializeSeed<'de> for EntriesSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for EntriesSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of ids to entries")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some((id, entry)) = map.next_entry::<String, Value>()? {
+            match self.kind {
+                Kind::Event => check_event(self.state, id, &entry),
+                Kind::Object => check_object(self.state, id, &entry),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_event(state: &mut State, id: String, entry: &Value) {
+    let location = format!("/ocel:events/{}", id);
+    state.report.events += 1;
+    state.progress.store(state.report.events, Ordering::Relaxed);
+    match entry.get("ocel:activity") {
+        Some(Value::String(_)) => {}
+        _ => state.report.issue("ocel:activity is missing or not a string", location.as_str()),
+    }
+    match entry.get("ocel:timestamp") {
+        Some(Value::String(ts)) if parse_timestamp(ts).is_some() => {}
+        _ => state.report.issue("ocel:timestamp is missing or not a timestamp", location.as_str()),
+    }
+    match entry.get("ocel:omap") {
+        Some(Value::Array(omap)) => {
+            for oid in omap {
+                match oid.as_str() {
+                    Some(oid) if !state.object_ids.contains(oid) => {
+                        state.pending.insert(oid.to_string());
+                    }
+                    Some(_) => {}
+                    None => state.report.issue("ocel:omap contains a non-string object id", location.as_str()),
+                }
+            }
+        }
+        _ => state.report.issue("ocel:omap is missing or not a list", location.as_str()),
+    }
+    if !matches!(entry.get("ocel:vmap"), Some(Value::Object(_))) {
+        state.report.issue("ocel:vmap is missing or not a map", location.as_str());
+    }
+    if !state.event_ids.insert(id) {
+        state.report.issue("duplicate event id", location);
+    }
+}
+
+fn check_object(state: &mut State, id: String, entry: &Value) {
+    let location = format!("/ocel:objects/{}", id);
+    state.report.objects += 1;
+    if !matches!(entry.get("ocel:type"), Some(Value::String(_))) {
+        state.report.issue("ocel:type is missing or not a string", location.as_str());
+    }
+    if !matches!(entry.get("ocel:ovmap"), Some(Value::Object(_))) {
+        state.report.issue("ocel:ovmap is missing or not a map", location.as_str());
+    }
+    state.pending.remove(&id);
+    if !state.object_ids.insert(id) {
+        state.report.issue("duplicate object id", location);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn log_file(content: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::Builder::new().suffix(".jsonocel").tempfile().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    const GLOBALS: &str = r#""ocel:global-log": {"ocel:version": "1.0", "ocel:attribute-names": [], "ocel:object-types": []},
+        "ocel:global-event": {}, "ocel:global-object": {}"#;
+
+    fn messages(report: &Report) -> Vec<&str> {
+        report.issues.iter().map(|(message, _)| message.as_str()).collect()
+    }
+
+    #[test]
+    fn accepts_a_valid_log() {
+        let file = log_file(&format!(
+            r#"{{{}, "ocel:events": {{"e1": {{"ocel:activity": "a", "ocel:timestamp": "2023-01-01T00:00:00Z",
+                "ocel:omap": ["o1"], "ocel:vmap": {{}}}}}},
+                "ocel:objects": {{"o1": {{"ocel:type": "order", "ocel:ovmap": {{}}}}}}}}"#,
+            GLOBALS
+        ));
+        let progress = Arc::new(AtomicUsize::new(0));
+        let report = validate(file.path(), Arc::clone(&progress)).unwrap();
+        assert_eq!((report.events, report.objects, report.total_issues), (1, 1, 0));
+        assert_eq!(progress.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn reports_broken_entries_and_references() {
+        let file = log_file(&format!(
+            r#"{{{}, "ocel:events": {{
+                    "e1": {{"ocel:activity": 1, "ocel:timestamp": "yesterday", "ocel:omap": ["o1", "o2"], "ocel:vmap": {{}}}},
+                    "e1": {{"ocel:activity": "a", "ocel:timestamp": "2023-01-01T00:00:00Z", "ocel:omap": [], "ocel:vmap": []}}}},
+                "ocel:objects": {{"o1": {{"ocel:type": "order"}}}}}}"#,
+            GLOBALS
+        ));
+        let report = validate(file.path(), Arc::default()).unwrap();
+        assert_eq!(report.events, 2);
+        assert_eq!(
+            messages(&report),
+            [
+                "ocel:activity is missing or not a string",
+                "ocel:timestamp is missing or not a timestamp",
+                "ocel:vmap is missing or not a map",
+                "duplicate event id",
+                "ocel:ovmap is missing or not a map",
+                "object o2 is referenced but not defined",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_missing_sections() {
+        let report = validate(log_file(r#"{"ocel:events": {}, "extra": 1}"#).path(), Arc::default()).unwrap();
+        assert_eq!(
+            messages(&report),
+            [
+                "unexpected key extra",
+                "missing required key ocel:global-log",
+                "missing required key ocel:global-event",
+                "missing required key ocel:global-object",
+                "missing required key ocel:objects",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_ocel2_and_malformed_json() {
+        assert!(validate(log_file(r#"{"objectTypes": [], "events": []}"#).path(), Arc::default()).is_err());
+        assert!(validate(log_file(r#"{"ocel:events": {"e1": "#).path(), Arc::default()).is_err());
+    }
+}
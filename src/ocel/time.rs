@@ -0,0 +1,105 @@
+//! Parsing of the time-related command line arguments.
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, Utc};
+
+use super::model::parse_timestamp;
+
+/// Parses durations like `90s`, `30m`, `12h`, `7d` or `2w`. A bare number is taken
+/// as seconds. Durations beyond what chrono can represent are an error.
+pub fn parse_duration(raw: &str) -> Result<Duration, String> {
+    let raw = raw.trim();
+    let split = raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len());
+    let (amount, unit) = raw.split_at(split);
+    let amount: i64 = amount.parse().map_err(|_| format!("invalid duration {:?}, expected e.g. `7d` or `12h`", raw))?;
+    let unit_seconds: i64 = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("invalid duration unit {:?} in {:?}, expected one of s, m, h, d, w", unit, raw)),
+    };
+    match amount.checked_mul(unit_seconds) {
+        // chrono keeps durations in milliseconds
+        Some(seconds) if seconds <= Duration::max_value().num_seconds() => Ok(Duration::seconds(seconds)),
+        _ => Err(format!("duration {:?} is too long", raw)),
+    }
+}
+
+/// [`parse_duration`] as a standard duration, for limits on the run time.
+pub fn parse_timeout(raw: &str) -> Result<std::time::Duration, String> {
+    parse_duration(raw)?.to_std().map_err(|_| format!("invalid duration {:?}", raw))
+}
+
+/// Formats a number of seconds compactly with its two largest units, e.g. `3d 4h`,
+/// `12m 5s` or `0.4s`.
+pub fn format_seconds(seconds: f64) -> String {
+    if seconds < 60.0 {
+        let rounded = format!("{:.1}", seconds);
+        return format!("{}s", rounded.trim_end_matches(".0"));
+    }
+    let total = seconds.round() as i64;
+    let units = [("d", 86400), ("h", 3600), ("m", 60), ("s", 1)];
+    let largest = units.iter().position(|(_, size)| total >= *size).unwrap_or(units.len() - 1);
+    let (major, major_size) = units[largest];
+    let mut text = format!("{}{}", total / major_size, major);
+    if let Some((minor, minor_size)) = units.get(largest + 1) {
+        let rest = total % major_size / minor_size;
+        if rest > 0 {
+            text.push_str(&format!(" {}{}", rest, minor));
+        }
+    }
+    text
+}
+
+/// Parses a point in time given as a full timestamp or as a bare date like
+/// `2023-01-01`, which stands for midnight UTC.
+pub fn parse_instant(raw: &str) -> Result<DateTime<FixedOffset>, String> {
+    if let Some(timestamp) = parse_timestamp(raw.trim()) {
+        return Ok(timestamp);
+    }
+    NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d")
+        .map(|date| DateTime::<Utc>::from_utc(date.and_hms(0, 0, 0), Utc).into())
+        .map_err(|_| format!("invalid point in time {:?}, expected e.g. `2023-01-01` or `2023-01-01T12:00:00Z`", raw))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations_with_units() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::seconds(90));
+        assert_eq!(parse_duration(" 30m ").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_duration("12h").unwrap(), Duration::hours(12));
+        assert_eq!(parse_duration("7d").unwrap(), Duration::days(7));
+        assert_eq!(parse_duration("2w").unwrap(), Duration::weeks(2));
+        for invalid in ["", "d", "5y", "-5s", "1.5h"] {
+            assert!(parse_duration(invalid).is_err(), "{:?} parsed", invalid);
+        }
+    }
+
+    #[test]
+    fn parses_dates_and_timestamps() {
+        assert_eq!(parse_instant("2023-01-01").unwrap().to_rfc3339(), "2023-01-01T00:00:00+00:00");
+        assert_eq!(parse_instant("2023-01-01T12:00:00+02:00").unwrap().to_rfc3339(), "2023-01-01T12:00:00+02:00");
+        assert_eq!(parse_instant("2023-01-01 12:00:00").unwrap().to_rfc3339(), "2023-01-01T12:00:00+00:00");
+        assert!(parse_instant("2023-13-01").is_err());
+        assert!(parse_instant("yesterday").is_err());
+    }
+
+    #[test]
+    fn formats_seconds_with_two_units() {
+        assert_eq!(format_seconds(0.4), "0.4s");
+        assert_eq!(format_seconds(12.0), "12s");
+        assert_eq!(format_seconds(725.0), "12m 5s");
+        assert_eq!(format_seconds(3.0 * 86400.0 + 4.0 * 3600.0 + 59.0), "3d 4h");
+    }
+
+    #[test]
+    fn overlong_durations_are_errors() {
+        assert!(parse_duration("99999999999999999w").is_err());
+        assert!(parse_duration(&format!("{}s", i64::MAX)).is_err());
+        assert!(parse_timeout("9223372036854775807d").is_err());
+        assert_eq!(parse_duration(&format!("{}s", Duration::max_value().num_seconds())).unwrap().num_seconds(), i64::MAX / 1000);
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::{Datelike, Duration, NaiveDate};
+use clap::{Args, ValueEnum};
+use log::debug;
+use serde_json::{json, Value};
+
+use super::model::OcelLog;
+use super::report_writer;
+
+#[derive(Args, Debug)]
+pub struct OcelTimeline {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Width of the time buckets
+    #[clap(long, value_enum, default_value_t = Granularity::Day)]
+    pub granularity: Granularity,
+
+    /// Write the report to this file instead of stdout
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Write the report as JSON instead of CSV
+    #[clap(long)]
+    pub json: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Granularity {
+    Day,
+    /// Weeks starting on Monday
+    Week,
+    Month,
+}
+
+impl Granularity {
+    /// First day of the bucket `date` falls into.
+    fn bucket(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Granularity::Day => date,
+            Granularity::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            Granularity::Month => NaiveDate::from_ymd(date.year(), date.month(), 1),
+        }
+    }
+
+    fn next(self, bucket: NaiveDate) -> NaiveDate {
+        match self {
+            Granularity::Day => bucket + Duration::days(1),
+            Granularity::Week => bucket + Duration::weeks(1),
+            Granularity::Month if bucket.month() == 12 => NaiveDate::from_ymd(bucket.year() + 1, 1, 1),
+            Granularity::Month => NaiveDate::from_ymd(bucket.year(), bucket.month() + 1, 1),
+        }
+    }
+}
+
+/// Event counts of one bucket.
+#[derive(Default)]
+struct Bucket<'a> {
+    events: usize,
+    activities: BTreeMap<&'a str, usize>,
+    object_types: BTreeMap<&'a str, usize>,
+}
+
+pub fn run(timeline: &OcelTimeline) -> Result<(), Box<dyn Error>> {
+    debug!("Importing log: {:?}", timeline.path);
+    let log = OcelLog::import(&timeline.path)?;
+
+    let mut buckets: BTreeMap<NaiveDate, Bucket> = BTreeMap::new();
+    for event in log.events.values() {
+        let date = event.timestamp.naive_utc().date();
+        let bucket = buckets.entry(timeline.granularity.bucket(date)).or_default();
+        bucket.events += 1;
+        *bucket.activities.entry(event.activity.as_str()).or_default() += 1;
+        let types: BTreeSet<&str> =
+            event.omap.iter().filter_map(|oid| log.objects.get(oid)).map(|o| o.obj_type.as_str()).collect();
+        for obj_type in types {
+            *bucket.object_types.entry(obj_type).or_default() += 1;
+        }
+    }
+
+    // empty buckets are kept so that gaps in the extraction stand out
+    if let (Some(first), Some(last)) = (buckets.keys().next().copied(), buckets.keys().next_back().copied()) {
+        let mut bucket = first;
+        while bucket < last {
+            buckets.entry(bucket).or_default();
+            bucket = timeline.granularity.next(bucket);
+        }
+    }
+
+    let mut out = report_writer(timeline.output.as_deref())?;
+
+    if timeline.json {
+        let report: Vec<Value> = buckets
+            .iter()
+            .map(|(start, bucket)| {
+                json!({
+                    "bucket": start.to_string(),
+                    "events": bucket.events,
+                    "activities": bucket.activities,
+                    "object_types": bucket.object_types,
+                })
+            })
+            .collect();
+        writeln!(out, "{}", serde_json::to_string_pretty(&report)?)?;
+        return Ok(());
+    }
+
+    let mut writer = csv::Writer::from_writer(out);
+    writer.write_record(["bucket", "dimension", "key", "events"])?;
+    for (start, bucket) in &buckets {
+        let start = start.to_string();
+        writer.write_record([start.as_str(), "all", "", bucket.events.to_string().as_str()])?;
+        for (activity, count) in &bucket.activities {
+            writer.write_record([start.as_str(), "activity", *activity, count.to_string().as_str()])?;
+        }
+        for (obj_type, count) in &bucket.object_types {
+            writer.write_record([start.as_str(), "object_type", *obj_type, count.to_string().as_str()])?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,405 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clap::Args;
+use log::{error, warn};
+use pmrs::objects::ocel::validator::{validate_ocel, validate_ocel_verbose};
+use serde::Serialize;
+
+use super::as_jsonocel;
+use super::encoding;
+use super::model::{Format, OcelLog};
+use super::ocel2;
+use super::schema::Schema;
+use super::streaming;
+use crate::exit::{self, ErrorCode, ErrorReport};
+use crate::table::{self, Cell, Style, Table};
+
+#[derive(Args, Debug)]
+pub struct Validate {
+    /// OCEL files or directories of OCEL files to validate
+    #[clap(required = true, min_values = 1)]
+    pub paths: Vec<String>,
+
+    /// Set by the global -v
+    #[clap(skip)]
+    pub verbose: bool,
+
+    /// Also validate the logs in subdirectories of the given directories
+    #[clap(short, long)]
+    pub recursive: bool,
+
+    /// Check the structure and references of JSON-OCEL 1.0 files entry by entry
+    /// instead of loading them. Used automatically for files larger than 1 GiB.
+    /// OCEL 2.0 logs are always validated whole
+    #[clap(long)]
+    pub streaming: bool,
+
+    /// Print a heartbeat line to stderr every N seconds while validating. Streaming
+    /// validation reports the events checked so far, pmrs validates a file in a
+    /// single call, so its heartbeat reports elapsed time only.
+    #[clap(long, value_name = "SECONDS")]
+    pub progress_interval: Option<u64>,
+
+    /// Additionally warn about string values that look mis-encoded (replacement
+    /// characters, mojibake)
+    #[clap(long)]
+    pub encoding_check: bool,
+
+    /// Check that the log has the same activities, object types and attributes as
+    /// a reference log, exiting with a non-zero code if it does not
+    #[clap(long, value_name = "REFERENCE")]
+    pub compare_schema: Option<PathBuf>,
+
+    /// Print one JSON document with the outcome of every file instead of text
+    #[clap(long)]
+    pub json: bool,
+
+    /// Validate again whenever one of the files changes, until interrupted
+    #[clap(long)]
+    pub watch: bool,
+}
+
+/// Outcome of validating one file, as printed by --json.
+#[derive(Serialize, Default)]
+struct FileReport {
+    path: String,
+    /// `None` if the file could not be validated at all
+    valid: Option<bool>,
+    /// Why the file could not be validated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ErrorReport>,
+    /// Validation errors, only collected with the global -v
+    errors: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encoding_warnings: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema_differences: Option<Vec<String>>,
+}
+
+/// Prints a line of the text output, which --json replaces.
+macro_rules! text {
+    ($validate:expr, $($arg:tt)*) => {
+        if !$validate.json {
+            println!($($arg)*);
+        }
+    };
+}
+
+pub fn run(validate: &Validate) {
+    let code = check(validate);
+    if code != 0 {
+        process::exit(code);
+    }
+}
+
+/// Validates every file and returns the exit code of the run.
+pub fn check(validate: &Validate) -> i32 {
+    let mut files: Vec<String> = Vec::new();
+    let mut unreadable = false;
+    for path in &validate.paths {
+        if Path::new(path).is_dir() {
+            if let Err(e) = collect_logs(Path::new(path), validate.recursive, &mut files) {
+                error!("{}", ErrorReport::of("Could not read the directory", Some(Path::new(path)), &e));
+                unreadable = true;
+            }
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let mut results: Vec<(&str, Option<bool>)> = Vec::new();
+    let mut reports = Vec::new();
+    let mut schema_mismatch = false;
+    for path in &files {
+        let mut report = FileReport { path: path.clone(), ..FileReport::default() };
+        let mut passed = validate_file(validate, path, &mut report);
+        if validate.encoding_check {
+            encoding_check(validate, path, &mut report);
+        }
+        if let Some(reference) = &validate.compare_schema {
+            if !compare_schema(validate, path, reference, &mut report) {
+                schema_mismatch = true;
+                passed = passed.map(|_| false);
+            }
+        }
+        // a log that could not be read for one of the checks counts as not validated
+        if report.error.is_some() {
+            passed = None;
+        }
+        report.valid = passed;
+        results.push((path, passed));
+        reports.push(report);
+    }
+
+    if validate.json {
+        match serde_json::to_string_pretty(&reports) {
+            Ok(document) => println!("{}", document),
+            Err(e) => {
+                error!("{}", ErrorReport::of("Could not serialize the results", None, &e));
+                unreadable = true;
+            }
+        }
+    } else if files.len() > 1 {
+        println!();
+        let mut summary = Table::new(&["result", "file"]);
+        for (path, passed) in &results {
+            let result = match passed {
+                Some(true) => Cell::styled("pass", Style::Good),
+                Some(false) => Cell::styled("FAIL", Style::Bad),
+                None => Cell::styled("ERROR", Style::Bad),
+            };
+            summary.row(vec![result, (*path).into()]);
+        }
+        summary.print();
+        let count = |wanted: Option<bool>, style: Style| {
+            let n = results.iter().filter(|(_, passed)| *passed == wanted).count();
+            table::paint(&n.to_string(), if n > 0 { style } else { Style::Plain })
+        };
+        println!(
+            "{} files: {} passed, {} failed, {} errors",
+            files.len(),
+            count(Some(true), Style::Good),
+            count(Some(false), Style::Bad),
+            count(None, Style::Bad)
+        );
+    }
+    if let Some((_, report)) = results.iter().zip(&reports).find(|((_, passed), _)| passed.is_none()) {
+        return report.error.as_ref().map_or(exit::IMPORT_ERROR, |failure| failure.exit_code);
+    }
+    if unreadable {
+        return exit::IO_ERROR;
+    }
+    if schema_mismatch || results.iter().any(|(_, passed)| *passed == Some(false)) {
+        return exit::VALIDATION_FAILED;
+    }
+    0
+}
+
+/// Adds the OCEL files of `dir` to `files` in name order, descending into
+/// subdirectories if `recursive` is set.
+fn collect_logs(dir: &Path, recursive: bool, files: &mut Vec<String>) -> std::io::Result<()> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?.map(|entry| entry.map(|e| e.path())).collect::<Result<_, _>>()?;
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() {
+            if recursive {
+                collect_logs(&entry, recursive, files)?;
+            }
+        } else if Format::from_path(&entry).is_ok() {
+            files.push(entry.to_string_lossy().into_owned());
+        }
+    }
+    Ok(())
+}
+
+/// Whether the file at `path` goes through the streaming validator: JSON-OCEL 1.0
+/// files when `requested` or when they are large. OCEL 2.0 logs share the file
+/// extension but not the layout, so they are always validated whole.
+fn streams(path: &Path, requested: bool) -> bool {
+    let large = fs::metadata(path).map_or(false, |m| m.len() > streaming::AUTO_THRESHOLD);
+    if !(requested || large) || Format::from_path(path).ok() != Some(Format::JsonOcel) {
+        return false;
+    }
+    match ocel2::is_ocel2(path) {
+        Ok(true) => {
+            if requested {
+                warn!("{:?} is an OCEL 2.0 log, which cannot be streamed; validating it whole", path);
+            }
+            false
+        }
+        // an unreadable file is reported by the streaming validator
+        _ => true,
+    }
+}
+
+/// Validates one file with pmrs and prints the result. `None` if it could not be
+/// validated at all.
+fn validate_file(validate: &Validate, path: &str, report: &mut FileReport) -> Option<bool> {
+    if streams(Path::new(path), validate.streaming) {
+        return validate_streaming(validate, path, report);
+    }
+
+    // pmrs validates JSON-OCEL only, other formats are checked through a converted copy
+    let source = match as_jsonocel(Path::new(path)) {
+        Ok(source) => source,
+        Err(e) => {
+            let failure = ErrorReport::of("Could not read", Some(Path::new(path)), &*e);
+            error!("{}", failure);
+            report.error = Some(failure);
+            return None;
+        }
+    };
+    let json_path = source.to_string_lossy();
+
+    with_heartbeat(path, validate.progress_interval, None, || {
+        if validate.verbose {
+            match validate_ocel_verbose(&json_path) {
+                Ok(v) => {
+                    for (i, error) in v.iter().enumerate() {
+                        text!(validate, "Error {}: {} at {}", i+1, error.0, error.1);
+                        report.errors.push(format!("{} at {}", error.0, error.1));
+                    }
+
+                    text!(validate, "{}: {}", path, v.is_empty());
+                    Some(v.is_empty())
+                }
+                Err(e) => {
+                    let failure = ErrorReport::with_code(ErrorCode::Parse, "Could not validate", Some(Path::new(path)), e);
+                    text!(validate, "{}", failure);
+                    report.error = Some(failure);
+                    None
+                }
+            }
+        } else {
+            match validate_ocel(&json_path) {
+                Ok(v) => {
+                    text!(validate, "{}: {}", path, v);
+                    Some(v)
+                }
+                Err(e) => {
+                    let failure = ErrorReport::with_code(ErrorCode::Parse, "Could not validate", Some(Path::new(path)), e);
+                    text!(validate, "{}", failure);
+                    report.error = Some(failure);
+                    None
+                }
+            }
+        }
+    })
+}
+
+fn validate_streaming(validate: &Validate, path: &str, file_report: &mut FileReport) -> Option<bool> {
+    let events = Arc::new(AtomicUsize::new(0));
+    let result = with_heartbeat(path, validate.progress_interval, Some(&*events), || {
+        streaming::validate(Path::new(path), Arc::clone(&events))
+    });
+    match result {
+        Ok(report) => {
+            if validate.verbose {
+                for (i, error) in report.issues.iter().enumerate() {
+                    text!(validate, "Error {}: {} at {}", i+1, error.0, error.1);
+                    file_report.errors.push(format!("{} at {}", error.0, error.1));
+                }
+                if report.total_issues > report.issues.len() {
+                    text!(validate, "... and {} more errors", report.total_issues - report.issues.len());
+                }
+            }
+            text!(validate, "{}: {}", path, report.total_issues == 0);
+            Some(report.total_issues == 0)
+        }
+        Err(e) => {
+            let failure = ErrorReport::of("Could not validate", Some(Path::new(path)), &*e);
+            text!(validate, "{}", failure);
+            file_report.error = Some(failure);
+            None
+        }
+    }
+}
+
+fn encoding_check(validate: &Validate, path: &str, report: &mut FileReport) {
+    match OcelLog::import(Path::new(path)) {
+        Ok(log) => {
+            let issues = encoding::check(&log);
+            let mut warnings = Vec::new();
+            for issue in &issues {
+                text!(validate, "Warning: {} attribute {:?} {}", issue.location, issue.attribute, issue.reason);
+                warnings.push(format!("{} attribute {:?} {}", issue.location, issue.attribute, issue.reason));
+            }
+            text!(validate, "{}: {} encoding warnings", path, issues.len());
+            report.encoding_warnings = Some(warnings);
+        }
+        Err(e) => {
+            let failure = ErrorReport::of("Could not check the encoding of", Some(Path::new(path)), &*e);
+            text!(validate, "{}", failure);
+            report.error.get_or_insert(failure);
+        }
+    }
+}
+
+/// Prints the schema differences to `reference` and returns whether there are none.
+fn compare_schema(validate: &Validate, path: &str, reference: &Path, report: &mut FileReport) -> bool {
+    let logs = OcelLog::import(reference).and_then(|r| Ok((r, OcelLog::import(Path::new(path))?)));
+    match logs {
+        Ok((reference_log, log)) => {
+            let differences = Schema::infer(&reference_log).differences(&Schema::infer(&log));
+            for (i, difference) in differences.iter().enumerate() {
+                text!(validate, "Schema difference {}: {}", i + 1, difference);
+            }
+            text!(validate, "{}: compatible with {}: {}", path, reference.display(), differences.is_empty());
+            let compatible = differences.is_empty();
+            report.schema_differences = Some(differences);
+            compatible
+        }
+        Err(e) => {
+            let failure = ErrorReport::of("Could not compare the schema of", Some(Path::new(path)), &*e);
+            text!(validate, "{}", failure);
+            report.error.get_or_insert(failure);
+            // not a mismatch, the run fails with the error's code instead
+            true
+        }
+    }
+}
+
+/// Runs `task` on the current thread while a scoped thread prints a heartbeat to
+/// stderr every `interval` seconds until the task returns, with the count in `events`
+/// if the task keeps one.
+fn with_heartbeat<T>(path: &str, interval: Option<u64>, events: Option<&AtomicUsize>, task: impl FnOnce() -> T) -> T {
+    let secs = match interval {
+        Some(secs) if secs > 0 => secs,
+        _ => return task(),
+    };
+
+    let (done, finished) = mpsc::channel::<()>();
+    let started = Instant::now();
+    thread::scope(|scope| {
+        scope.spawn(move || loop {
+            match finished.recv_timeout(Duration::from_secs(secs)) {
+                Err(RecvTimeoutError::Timeout) => {
+                    let elapsed = started.elapsed().as_secs();
+                    match events {
+                        Some(events) => {
+                            eprintln!("still validating {} ({} events, {}s elapsed)", path, events.load(Ordering::Relaxed), elapsed)
+                        }
+                        None => eprintln!("still validating {} ({}s elapsed)", path, elapsed),
+                    }
+                }
+                _ => break,
+            }
+        });
+        let result = task();
+        drop(done);
+        result
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn log_file(content: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::Builder::new().suffix(".jsonocel").tempfile().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn streams_json_ocel_when_requested() {
+        let file = log_file(r#"{"ocel:global-log": {}, "ocel:events": {}, "ocel:objects": {}}"#);
+        assert!(streams(file.path(), true));
+        assert!(!streams(file.path(), false));
+    }
+
+    #[test]
+    fn never_streams_ocel2() {
+        let file = log_file(r#"{"objectTypes": [], "eventTypes": [], "objects": [], "events": []}"#);
+        assert!(!streams(file.path(), true));
+    }
+}
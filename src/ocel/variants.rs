@@ -0,0 +1,150 @@
+//! Trace variants per object type: the log is flattened on each type and objects
+//! whose traces have the same sequence of activities share a variant.
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::Write;
+use std::path::PathBuf;
+
+use clap::Args;
+use log::debug;
+use serde_json::{json, Value};
+
+use super::model::OcelLog;
+use super::{report_writer, ReportFormat};
+use crate::table::{self, Table};
+
+#[derive(Args, Debug)]
+pub struct OcelVariants {
+    /// Path to OCEL file
+    pub path: PathBuf,
+
+    /// Only compute the variants of these object types. May be repeated or comma
+    /// separated
+    #[clap(long, value_name = "TYPE", use_value_delimiter = true, multiple_occurrences = true)]
+    pub object_type: Vec<String>,
+
+    /// Number of most frequent variants listed per object type, 0 for all
+    #[clap(long, default_value_t = 10)]
+    pub top: usize,
+
+    /// Output format. Default: table
+    #[clap(long, value_enum)]
+    pub format: Option<ReportFormat>,
+
+    /// Write the report to this file instead of stdout
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+/// The variants of one object type, most frequent first.
+pub struct TypeVariants<'a> {
+    pub obj_type: &'a str,
+    pub objects: usize,
+    pub variants: Vec<(Vec<&'a str>, usize)>,
+}
+
+/// Variants of the given object types, all of them if `object_types` is empty.
+/// Ties in frequency are ordered by the activity sequences.
+pub fn variants<'a>(log: &'a OcelLog, object_types: &[String]) -> Vec<TypeVariants<'a>> {
+    let mut counts: HashMap<&str, HashMap<Vec<&str>, usize>> = HashMap::new();
+    for (oid, trace) in log.object_traces() {
+        let obj_type = match log.objects.get(oid) {
+            Some(object) if object_types.is_empty() || object_types.contains(&object.obj_type) => object.obj_type.as_str(),
+            _ => continue,
+        };
+        let activities: Vec<&str> = trace.iter().map(|eid| log.events[*eid].activity.as_str()).collect();
+        *counts.entry(obj_type).or_default().entry(activities).or_default() += 1;
+    }
+
+    let mut per_type: Vec<TypeVariants> = counts
+        .into_iter()
+        .map(|(obj_type, variants)| {
+            let mut variants: Vec<(Vec<&str>, usize)> = variants.into_iter().collect();
+            variants.sort_by(|(a, count_a), (b, count_b)| count_b.cmp(count_a).then_with(|| a.cmp(b)));
+            TypeVariants { obj_type, objects: variants.iter().map(|(_, count)| count).sum(), variants }
+        })
+        .collect();
+    per_type.sort_by_key(|t| t.obj_type);
+    per_type
+}
+
+fn describe(activities: &[&str]) -> String {
+    if activities.is_empty() {
+        "(no events)".to_string()
+    } else {
+        activities.join(" -> ")
+    }
+}
+
+pub fn run(args: &OcelVariants) -> Result<(), Box<dyn Error>> {
+    debug!("Importing log: {:?}", args.path);
+    let log = OcelLog::import(&args.path)?;
+    let types = log.object_types();
+    if let Some(unknown) = args.object_type.iter().find(|t| !types.contains(&t.as_str())) {
+        return Err(format!("no object is of type {:?}, available types: {}", unknown, types.join(", ")).into());
+    }
+
+    let per_type = variants(&log, &args.object_type);
+    let top = if args.top == 0 { usize::MAX } else { args.top };
+    let mut out = report_writer(args.output.as_deref())?;
+    match args.format.unwrap_or(ReportFormat::Table) {
+        ReportFormat::Table => {
+            let color = args.output.is_none() && table::color();
+            for (index, t) in per_type.iter().enumerate() {
+                if index > 0 {
+                    writeln!(out)?;
+                }
+                writeln!(out, "{}: {} objects, {} variants", t.obj_type, t.objects, t.variants.len())?;
+                let mut table = Table::new(&["rank", "objects", "share", "variant"]).right(&[0, 1, 2]).indent(2);
+                for (rank, (activities, count)) in t.variants.iter().take(top).enumerate() {
+                    let share = 100.0 * *count as f64 / t.objects as f64;
+                    table.row(vec![(rank + 1).into(), (*count).into(), format!("{:.1}%", share).into(), describe(activities).into()]);
+                }
+                table.write(&mut out, color)?;
+            }
+        }
+        ReportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(["object_type", "rank", "count", "share", "length", "activities"])?;
+            for t in &per_type {
+                for (rank, (activities, count)) in t.variants.iter().take(top).enumerate() {
+                    writer.write_record([
+                        t.obj_type.to_string(),
+                        (rank + 1).to_string(),
+                        count.to_string(),
+                        format!("{:.4}", *count as f64 / t.objects as f64),
+                        activities.len().to_string(),
+                        activities.join(","),
+                    ])?;
+                }
+            }
+            writer.flush()?;
+            return Ok(());
+        }
+        ReportFormat::Json => {
+            let report: Vec<Value> = per_type
+                .iter()
+                .map(|t| {
+                    let listed: Vec<Value> = t
+                        .variants
+                        .iter()
+                        .take(top)
+                        .enumerate()
+                        .map(|(rank, (activities, count))| {
+                            json!({
+                                "rank": rank + 1,
+                                "count": count,
+                                "share": *count as f64 / t.objects as f64,
+                                "activities": activities,
+                            })
+                        })
+                        .collect();
+                    json!({"object_type": t.obj_type, "objects": t.objects, "variants": t.variants.len(), "top": listed})
+                })
+                .collect();
+            writeln!(out, "{}", serde_json::to_string_pretty(&report)?)?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
@@ -0,0 +1,78 @@
+//! `--from` and `--to`: the time window of the analysis commands. The window is
+//! applied right after the import, so a slice of a large log needs no filtered copy
+//! on disk first.
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+use chrono::{DateTime, FixedOffset};
+use clap::Args;
+use log::debug;
+
+use super::model::OcelLog;
+use super::time::parse_instant;
+use super::{as_jsonocel, temporary_jsonocel, JsonOcelPath};
+
+#[derive(Args, Debug, Clone, Copy, Default)]
+pub struct TimeWindow {
+    /// Only use the events at or after this point in time (e.g. `2023-01-01`) and
+    /// the objects they reference
+    #[clap(long, value_name = "TIME", value_parser = parse_instant)]
+    pub from: Option<DateTime<FixedOffset>>,
+
+    /// Only use the events before this point in time and the objects they reference
+    #[clap(long, value_name = "TIME", value_parser = parse_instant)]
+    pub to: Option<DateTime<FixedOffset>>,
+}
+
+impl TimeWindow {
+    pub fn is_set(&self) -> bool {
+        self.from.is_some() || self.to.is_some()
+    }
+
+    pub fn check(&self) -> Result<(), String> {
+        match (self.from, self.to) {
+            (Some(from), Some(to)) if from >= to => Err(format!("--from {} is not before --to {}", from.to_rfc3339(), to.to_rfc3339())),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn contains(&self, timestamp: DateTime<FixedOffset>) -> bool {
+        self.from.map_or(true, |from| timestamp >= from) && self.to.map_or(true, |to| timestamp < to)
+    }
+
+    /// The events inside the window and the objects they reference. Objects that no
+    /// event inside the window references are left out.
+    pub fn trim(&self, log: OcelLog) -> OcelLog {
+        if !self.is_set() {
+            return log;
+        }
+        let inside: HashSet<&str> =
+            log.events.iter().filter(|(_, event)| self.contains(event.timestamp)).map(|(id, _)| id.as_str()).collect();
+        debug!("{} of {} events are inside {}", inside.len(), log.events.len(), self);
+        log.subset(&inside, &HashSet::new())
+    }
+
+    /// Imports the log at `path` and trims it to the window.
+    pub fn import(&self, path: &Path) -> Result<OcelLog, Box<dyn Error>> {
+        self.check()?;
+        Ok(self.trim(OcelLog::import(path)?))
+    }
+
+    /// Like [`as_jsonocel`], for the pmrs functions that read the log themselves: a
+    /// trimmed log is written to a temporary file.
+    pub fn as_jsonocel(&self, path: &Path) -> Result<JsonOcelPath, Box<dyn Error>> {
+        if !self.is_set() {
+            return as_jsonocel(path);
+        }
+        temporary_jsonocel(&self.import(path)?)
+    }
+}
+
+impl fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bound = |bound: Option<DateTime<FixedOffset>>| bound.map_or_else(|| "..".to_string(), |t| t.to_rfc3339());
+        write!(f, "[{}, {})", bound(self.from), bound(self.to))
+    }
+}
@@ -0,0 +1,248 @@
+//! XML-OCEL reading and writing for [`OcelLog`], following the layout written by
+//! pm4py: typed `<string>`/`<int>`/`<float>`/`<boolean>`/`<date>`/`<list>` elements
+//! with `key` and `value` attributes below `<global>`, `<event>` and `<object>`.
+use std::error::Error;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use quick_xml::events::Event as XmlEvent;
+use quick_xml::Reader;
+use serde_json::{Map, Number, Value};
+
+use super::model::{parse_timestamp, Event, Object, OcelLog};
+use crate::compression;
+use crate::xml::{attributes, escape};
+
+/// Lists whose children are keyed attributes rather than plain values.
+const MAP_LISTS: [&str; 2] = ["vmap", "ovmap"];
+
+struct Frame {
+    tag: String,
+    key: String,
+    scope: String,
+    value: String,
+    items: Vec<(String, Value)>,
+}
+
+pub fn read(path: &Path) -> Result<OcelLog, Box<dyn Error>> {
+    parse(compression::open(path)?)
+}
+
+pub fn parse<R: BufRead>(input: R) -> Result<OcelLog, Box<dyn Error>> {
+    let mut reader = Reader::from_reader(input);
+    reader.trim_text(true);
+
+    let mut log = OcelLog::default();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut buf = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            XmlEvent::Start(tag) => {
+                if matches!(tag.local_name().as_ref(), b"object-types" | b"event-types") {
+                    return Err("this is an OCEL 2.0 XML log, read it as ocel2-xml".into());
+                }
+                let attrs = attributes(&tag)?;
+                stack.push(Frame {
+                    tag: String::from_utf8_lossy(tag.local_name().as_ref()).into_owned(),
+                    key: attrs.get("key").cloned().unwrap_or_default(),
+                    scope: attrs.get("scope").cloned().unwrap_or_default(),
+                    value: attrs.get("value").cloned().unwrap_or_default(),
+                    items: Vec::new(),
+                });
+            }
+            XmlEvent::Empty(tag) => {
+                let attrs = attributes(&tag)?;
+                let kind = String::from_utf8_lossy(tag.local_name().as_ref()).into_owned();
+                let key = attrs.get("key").cloned().unwrap_or_default();
+                let raw = attrs.get("value").cloned().unwrap_or_default();
+                let value = typed_value(&kind, &raw)?;
+                if let Some(parent) = stack.last_mut() {
+                    parent.items.push((key, value));
+                }
+            }
+            XmlEvent::End(_) => {
+                let frame = match stack.pop() {
+                    Some(frame) => frame,
+                    None => continue,
+                };
+                match frame.tag.as_str() {
+                    "list" => {
+                        let value = if MAP_LISTS.contains(&frame.key.as_str()) {
+                            Value::Object(frame.items.into_iter().collect())
+                        } else {
+                            Value::Array(frame.items.into_iter().map(|(_, v)| v).collect())
+                        };
+                        if let Some(parent) = stack.last_mut() {
+                            parent.items.push((frame.key, value));
+                        }
+                    }
+                    "global" => {
+                        let target = match frame.scope.as_str() {
+                            "event" => &mut log.global_event,
+                            "object" => &mut log.global_object,
+                            _ => &mut log.global_log,
+                        };
+                        for (key, value) in frame.items {
+                            target.insert(format!("ocel:{}", key), value);
+                        }
+                    }
+                    "event" => {
+                        let (id, event) = to_event(frame.items)?;
+                        log.events.insert(id, event);
+                    }
+                    "object" => {
+                        let (id, object) = to_object(frame.items)?;
+                        log.objects.insert(id, object);
+                    }
+                    "log" | "events" | "objects" => {}
+                    kind => {
+                        // a typed element written as <string ...></string>
+                        let value = typed_value(kind, &frame.value)?;
+                        if let Some(parent) = stack.last_mut() {
+                            parent.items.push((frame.key, value));
+                        }
+                    }
+                }
+            }
+            XmlEvent::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(log)
+}
+
+fn typed_value(kind: &str, raw: &str) -> Result<Value, Box<dyn Error>> {
+    Ok(match kind {
+        "int" => Value::from(raw.parse::<i64>().map_err(|_| format!("invalid int value {:?}", raw))?),
+        "float" => {
+            let parsed = raw.parse::<f64>().map_err(|_| format!("invalid float value {:?}", raw))?;
+            Number::from_f64(parsed).map(Value::Number).unwrap_or(Value::Null)
+        }
+        "boolean" => Value::Bool(raw.eq_ignore_ascii_case("true")),
+        _ => Value::String(raw.to_string()),
+    })
+}
+
+fn take_string(items: &mut Vec<(String, Value)>, key: &str) -> Option<String> {
+    let index = items.iter().position(|(k, _)| k == key)?;
+    match items.remove(index).1 {
+        Value::String(s) => Some(s),
+        other => Some(other.to_string()),
+    }
+}
+
+fn take_map(items: &mut Vec<(String, Value)>, key: &str) -> Map<String, Value> {
+    match items.iter().position(|(k, _)| k == key).map(|i| items.remove(i).1) {
+        Some(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+fn to_event(mut items: Vec<(String, Value)>) -> Result<(String, Event), Box<dyn Error>> {
+    let id = take_string(&mut items, "id").ok_or("an <event> has no id")?;
+    let activity = take_string(&mut items, "activity").ok_or_else(|| format!("event {} has no activity", id))?;
+    let raw_timestamp = take_string(&mut items, "timestamp").ok_or_else(|| format!("event {} has no timestamp", id))?;
+    let timestamp = parse_timestamp(&raw_timestamp).ok_or_else(|| format!("event {} has an invalid timestamp {:?}", id, raw_timestamp))?;
+    let omap = match items.iter().position(|(k, _)| k == "omap").map(|i| items.remove(i).1) {
+        Some(Value::Array(values)) => values
+            .into_iter()
+            .map(|v| match v {
+                Value::String(s) => s,
+                other => other.to_string(),
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let vmap = take_map(&mut items, "vmap");
+    Ok((id, Event { activity, timestamp, omap, vmap, relationships: Vec::new() }))
+}
+
+fn to_object(mut items: Vec<(String, Value)>) -> Result<(String, Object), Box<dyn Error>> {
+    let id = take_string(&mut items, "id").ok_or("an <object> has no id")?;
+    let obj_type = take_string(&mut items, "type").ok_or_else(|| format!("object {} has no type", id))?;
+    let ovmap = take_map(&mut items, "ovmap");
+    Ok((id, Object { obj_type, ovmap, relationships: Vec::new(), attribute_changes: Vec::new() }))
+}
+
+pub fn write(log: &OcelLog, path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut out = compression::create(path)?;
+    write_to(log, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+pub fn write_to<W: Write>(log: &OcelLog, out: &mut W) -> Result<(), Box<dyn Error>> {
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, "<log>")?;
+    for (scope, map) in [("event", &log.global_event), ("object", &log.global_object), ("log", &log.global_log)] {
+        writeln!(out, r#"  <global scope="{}">"#, scope)?;
+        for (key, value) in map {
+            let key = key.strip_prefix("ocel:").unwrap_or(key);
+            let child_key = match key {
+                "attribute-names" => "attribute-name",
+                "object-types" => "object-type",
+                _ => "item",
+            };
+            write_value(out, 2, key, value, child_key)?;
+        }
+        writeln!(out, "  </global>")?;
+    }
+
+    writeln!(out, "  <events>")?;
+    for (id, event) in &log.events {
+        writeln!(out, "    <event>")?;
+        write_value(out, 3, "id", &Value::from(id.as_str()), "item")?;
+        write_value(out, 3, "activity", &Value::from(event.activity.as_str()), "item")?;
+        writeln!(out, r#"      <date key="timestamp" value="{}"/>"#, event.timestamp.to_rfc3339())?;
+        let omap = Value::Array(event.omap.iter().map(|o| Value::from(o.as_str())).collect());
+        write_value(out, 3, "omap", &omap, "object-id")?;
+        write_value(out, 3, "vmap", &Value::Object(event.vmap.clone()), "item")?;
+        writeln!(out, "    </event>")?;
+    }
+    writeln!(out, "  </events>")?;
+
+    writeln!(out, "  <objects>")?;
+    for (id, object) in &log.objects {
+        writeln!(out, "    <object>")?;
+        write_value(out, 3, "id", &Value::from(id.as_str()), "item")?;
+        write_value(out, 3, "type", &Value::from(object.obj_type.as_str()), "item")?;
+        write_value(out, 3, "ovmap", &Value::Object(object.ovmap.clone()), "item")?;
+        writeln!(out, "    </object>")?;
+    }
+    writeln!(out, "  </objects>")?;
+    writeln!(out, "</log>")?;
+    Ok(())
+}
+
+/// Writes `value` as a typed element. Array entries are keyed `child_key`.
+fn write_value<W: Write>(out: &mut W, depth: usize, key: &str, value: &Value, child_key: &str) -> Result<(), Box<dyn Error>> {
+    let indent = "  ".repeat(depth);
+    let key = escape(key);
+    match value {
+        Value::Array(values) => {
+            writeln!(out, r#"{}<list key="{}">"#, indent, key)?;
+            for v in values {
+                write_value(out, depth + 1, child_key, v, "item")?;
+            }
+            writeln!(out, "{}</list>", indent)?;
+        }
+        Value::Object(map) => {
+            writeln!(out, r#"{}<list key="{}">"#, indent, key)?;
+            for (k, v) in map {
+                write_value(out, depth + 1, k, v, "item")?;
+            }
+            writeln!(out, "{}</list>", indent)?;
+        }
+        Value::Bool(b) => writeln!(out, r#"{}<boolean key="{}" value="{}"/>"#, indent, key, b)?,
+        Value::Number(n) if n.is_f64() => writeln!(out, r#"{}<float key="{}" value="{}"/>"#, indent, key, n)?,
+        Value::Number(n) => writeln!(out, r#"{}<int key="{}" value="{}"/>"#, indent, key, n)?,
+        Value::String(s) if parse_timestamp(s).is_some() => {
+            writeln!(out, r#"{}<date key="{}" value="{}"/>"#, indent, key, escape(s))?
+        }
+        Value::String(s) => writeln!(out, r#"{}<string key="{}" value="{}"/>"#, indent, key, escape(s))?,
+        Value::Null => writeln!(out, r#"{}<string key="{}" value=""/>"#, indent, key)?,
+    }
+    Ok(())
+}
@@ -0,0 +1,15 @@
+//! `--dry-run`: the files a command would write, printed instead of writing them.
+use std::path::Path;
+
+use crate::stdio;
+
+/// Prints that `path` would be written, noting when that replaces an existing file.
+pub fn output(path: &Path) {
+    if stdio::is_stdio(path) {
+        println!("would write: standard output");
+    } else if path.exists() {
+        println!("would write: {} (overwrites the existing file)", path.display());
+    } else {
+        println!("would write: {}", path.display());
+    }
+}
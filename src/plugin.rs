@@ -0,0 +1,63 @@
+//! External subcommands: `pmrs-cli <name> ARGS` runs `pmrs-cli-<name> ARGS` from the
+//! PATH, the way cargo and git do. The plugin learns about the invocation from
+//! environment variables:
+//!
+//! - `PMRS_CLI_VERSION`: version of the calling pmrs-cli
+//! - `PMRS_CLI_OUTPUT_FORMAT`: `text` or `json`, from `--output-format`
+//! - `PMRS_CLI_LOG_LEVEL`: the effective log level, e.g. `warn`
+//! - `PMRS_CLI_CONFIG`: the configuration file given with `--config`, if any
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const PREFIX: &str = "pmrs-cli-";
+
+/// What the plugin is told about the invocation.
+pub struct Context<'a> {
+    pub output_format: &'a str,
+    pub config: Option<&'a Path>,
+}
+
+/// Runs the plugin named by the first of `args` with the others and returns its exit
+/// code. A missing plugin is an error of kind [`io::ErrorKind::NotFound`].
+pub fn run(args: &[String], context: &Context) -> io::Result<i32> {
+    let (name, rest) = args.split_first().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no command given"))?;
+    let mut command = Command::new(format!("{}{}", PREFIX, name));
+    command
+        .args(rest)
+        .env("PMRS_CLI_VERSION", env!("CARGO_PKG_VERSION"))
+        .env("PMRS_CLI_OUTPUT_FORMAT", context.output_format)
+        .env("PMRS_CLI_LOG_LEVEL", log::max_level().to_string().to_lowercase());
+    if let Some(config) = context.config {
+        command.env("PMRS_CLI_CONFIG", config);
+    }
+    let status = command.status()?;
+    // a plugin killed by a signal has no exit code
+    Ok(status.code().unwrap_or(1))
+}
+
+/// The plugins on the PATH by name. Earlier PATH entries shadow later ones.
+pub fn discover() -> BTreeMap<String, PathBuf> {
+    let mut plugins = BTreeMap::new();
+    let path = env::var_os("PATH").unwrap_or_default();
+    for dir in env::split_paths(&path) {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let name = match file_name.strip_prefix(PREFIX) {
+                Some(name) => name.strip_suffix(".exe").unwrap_or(name).to_string(),
+                None => continue,
+            };
+            if !name.is_empty() && entry.path().is_file() {
+                plugins.entry(name).or_insert_with(|| entry.path());
+            }
+        }
+    }
+    plugins
+}
@@ -0,0 +1,89 @@
+//! Progress of long-running commands: a bar with an ETA on stderr when run in a
+//! terminal, an `info` log line per step otherwise, e.g. in batch jobs.
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use indicatif::{ProgressBar, ProgressStyle};
+use log::info;
+
+use crate::ocel::time::format_seconds;
+use crate::stdio;
+
+/// The step announced last by any task, for reports on aborted runs.
+static CURRENT: Mutex<Option<String>> = Mutex::new(None);
+
+/// The step running at the moment, e.g. `ocdg generate [2/7] generating cobirth`.
+pub fn current() -> Option<String> {
+    CURRENT.lock().ok().and_then(|current| current.clone())
+}
+
+pub struct Progress {
+    bar: Option<ProgressBar>,
+    task: &'static str,
+    total: u64,
+    done: AtomicU64,
+    started: Instant,
+}
+
+impl Progress {
+    /// Progress of `task` over `total` steps. The bar is only drawn while both stdout
+    /// and stderr are terminals and no data is piped.
+    pub fn new(task: &'static str, total: u64) -> Progress {
+        let interactive = atty::is(atty::Stream::Stdout) && atty::is(atty::Stream::Stderr) && !stdio::piped();
+        let bar = interactive.then(|| {
+            let bar = ProgressBar::new(total);
+            if let Ok(style) = ProgressStyle::with_template("{spinner} {prefix} [{bar:30}] {pos}/{len} {msg} ({elapsed}, ETA {eta})") {
+                bar.set_style(style.progress_chars("=> "));
+            }
+            bar.set_prefix(task);
+            bar.enable_steady_tick(Duration::from_millis(120));
+            bar
+        });
+        Progress { bar, task, total, done: AtomicU64::new(0), started: Instant::now() }
+    }
+
+    /// Announces the step that is starting, e.g. `importing log.jsonocel`.
+    pub fn step(&self, message: impl Into<String>) {
+        let message = message.into();
+        if let Ok(mut current) = CURRENT.lock() {
+            *current = Some(format!("{} [{}/{}] {}", self.task, self.done.load(Ordering::Relaxed), self.total, message));
+        }
+        match &self.bar {
+            Some(bar) => bar.set_message(message),
+            None => info!("{} [{}/{}] {}", self.task, self.done.load(Ordering::Relaxed), self.total, message),
+        }
+    }
+
+    /// Marks one step as done. Safe to call from several threads.
+    pub fn inc(&self) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        match &self.bar {
+            Some(bar) => bar.inc(1),
+            None if done < self.total => {
+                let elapsed = self.started.elapsed().as_secs_f64();
+                let eta = elapsed / done as f64 * (self.total - done) as f64;
+                info!("{} [{}/{}] {} elapsed, ETA {}", self.task, done, self.total, format_seconds(elapsed), format_seconds(eta));
+            }
+            None => {}
+        }
+    }
+
+    pub fn finish(&self) {
+        match &self.bar {
+            Some(bar) => bar.finish_and_clear(),
+            None => info!("{} finished in {}", self.task, format_seconds(self.started.elapsed().as_secs_f64())),
+        }
+    }
+}
+
+impl Drop for Progress {
+    /// Clears the bar if the command stopped early, e.g. on an error.
+    fn drop(&mut self) {
+        if let Some(bar) = &self.bar {
+            if !bar.is_finished() {
+                bar.finish_and_clear();
+            }
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! `pmrs-cli serve`: the core operations over HTTP. Every endpoint takes the log as
+//! the request body and options as query parameters:
+//!
+//! - `POST /validate` answers `{"valid": bool, "errors": [...]}`
+//! - `POST /stats` answers the `ocel stats --json` summary
+//! - `POST /filter?expression=EXPR` answers the matching events as JSON-OCEL
+//! - `POST /ocdg/generate?relations=A,B&format=FORMAT` answers the graph file
+//!
+//! `format` selects the upload format of the log (`jsonocel` by default) on every
+//! endpoint but `/ocdg/generate`, where `log-format` does. Errors are answered as
+//! `{"error": message}` with a 4xx or 5xx status. A fixed number of workers handles
+//! the requests, further ones wait until a worker is free.
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::thread;
+
+use clap::{Args, ValueEnum};
+use log::{debug, error, info};
+use pmrs::objects::ocdg::exporter::export_ocdg;
+use pmrs::objects::ocdg::{generate_ocdg, Relations};
+use pmrs::objects::ocel::importer::import_ocel;
+use pmrs::objects::ocel::validator::validate_ocel_verbose;
+use serde_json::{json, Value};
+use strum::IntoEnumIterator;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::ocdg::formats::{self, GraphFormat};
+use crate::ocdg::relations::parse_relations;
+use crate::ocel::model::{Format, OcelLog};
+use crate::ocel::{as_jsonocel, query, stats};
+
+#[derive(Args, Debug)]
+pub struct Serve {
+    /// Port to listen on
+    #[clap(long, default_value_t = 8080)]
+    pub port: u16,
+
+    /// Address to bind to. Use 0.0.0.0 to accept connections from other hosts
+    #[clap(long, default_value = "127.0.0.1")]
+    pub host: String,
+
+    /// Reject uploads larger than this many megabytes
+    #[clap(long, value_name = "MB", default_value_t = 1024)]
+    pub max_upload: u64,
+
+    /// Number of requests handled at the same time, each holding up to --max-upload
+    /// in memory. 0 uses one per core
+    #[clap(long, default_value_t = 4)]
+    pub workers: usize,
+}
+
+/// A failed request: the status code and the message returned to the client.
+struct Failure(u16, String);
+
+impl<E: Error> From<E> for Failure {
+    fn from(e: E) -> Failure {
+        Failure(500, e.to_string())
+    }
+}
+
+fn bad_request(message: impl Into<String>) -> Failure {
+    Failure(400, message.into())
+}
+
+/// The body of a successful request and its content type.
+enum Answer {
+    Json(Value),
+    File(Vec<u8>, &'static str),
+}
+
+pub fn run(serve: &Serve) -> Result<(), Box<dyn Error>> {
+    let address = format!("{}:{}", serve.host, serve.port);
+    let server = Server::http(&address).map_err(|e| format!("could not listen on {}: {}", address, e))?;
+    let workers = match serve.workers {
+        0 => thread::available_parallelism().map_or(1, |cores| cores.get()),
+        workers => workers,
+    };
+    status!("listening on http://{} with {} workers", address, workers);
+    let limit = serve.max_upload * 1024 * 1024;
+    let server = &server;
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(move || loop {
+                match server.recv() {
+                    Ok(request) => handle(request, limit),
+                    Err(e) => {
+                        error!("Could not receive a request: {}", e);
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+fn handle(mut request: Request, limit: u64) {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let params = parse_query(query);
+    info!("{} {}", request.method(), url);
+
+    let result = if *request.method() != Method::Post {
+        Err(Failure(405, "endpoints only accept POST with the log as the body".to_string()))
+    } else {
+        match path {
+            "/validate" | "/stats" | "/filter" | "/ocdg/generate" => {
+                read_body(&mut request, limit).and_then(|body| dispatch(path, &params, &body))
+            }
+            _ => Err(Failure(404, format!("unknown endpoint {}", path))),
+        }
+    };
+
+    let response = match result {
+        Ok(Answer::Json(value)) => Response::from_string(value.to_string()).with_header(content_type("application/json")),
+        Ok(Answer::File(bytes, mime)) => Response::from_data(bytes).with_header(content_type(mime)),
+        Err(Failure(status, message)) => {
+            debug!("{} {} failed with {}: {}", request.method(), url, status, message);
+            Response::from_string(json!({ "error": message }).to_string())
+                .with_status_code(status)
+                .with_header(content_type("application/json"))
+        }
+    };
+    if let Err(e) = request.respond(response) {
+        error!("Could not answer {}: {}", url, e);
+    }
+}
+
+fn content_type(mime: &str) -> Header {
+    Header::from_bytes(&b"Content-Type"[..], mime.as_bytes()).expect("content types are valid header values")
+}
+
+fn read_body(request: &mut Request, limit: u64) -> Result<Vec<u8>, Failure> {
+    if request.body_length().map_or(false, |length| length as u64 > limit) {
+        return Err(Failure(413, format!("uploads are limited to {} MB", limit / 1024 / 1024)));
+    }
+    let mut body = Vec::new();
+    request.as_reader().take(limit + 1).read_to_end(&mut body)?;
+    if body.len() as u64 > limit {
+        return Err(Failure(413, format!("uploads are limited to {} MB", limit / 1024 / 1024)));
+    }
+    Ok(body)
+}
+
+fn dispatch(path: &str, params: &HashMap<String, String>, body: &[u8]) -> Result<Answer, Failure> {
+    let format_key = if path == "/ocdg/generate" { "log-format" } else { "format" };
+    let format = match params.get(format_key) {
+        Some(name) => Format::from_str(name, true).map_err(|_| bad_request(format!("unknown log format {:?}", name)))?,
+        None => Format::JsonOcel,
+    };
+    // logs are read from files, so the upload is stored in one first
+    let suffix = match format {
+        Format::XmlOcel | Format::Ocel2Xml => ".xmlocel",
+        Format::Ocel2Sqlite => ".sqlite",
+        Format::JsonOcel | Format::Ocel2Json => ".jsonocel",
+    };
+    let upload = tempfile::Builder::new().suffix(suffix).tempfile()?.into_temp_path();
+    fs::write(&upload, body)?;
+
+    match path {
+        "/validate" => validate(&upload),
+        "/stats" => Ok(Answer::Json(stats::summary_json(&import(&upload)?))),
+        "/filter" => filter(&upload, params),
+        _ => generate(&upload, params),
+    }
+}
+
+fn import(path: &Path) -> Result<OcelLog, Failure> {
+    OcelLog::import(path).map_err(|e| bad_request(format!("could not import the log: {}", e)))
+}
+
+fn validate(upload: &Path) -> Result<Answer, Failure> {
+    let source = as_jsonocel(upload).map_err(|e| bad_request(format!("could not read the log: {}", e)))?;
+    let errors = validate_ocel_verbose(&source.to_string_lossy()).map_err(|e| bad_request(e.to_string()))?;
+    let errors: Vec<String> = errors.iter().map(|(message, location)| format!("{} at {}", message, location)).collect();
+    Ok(Answer::Json(json!({ "valid": errors.is_empty(), "errors": errors })))
+}
+
+fn filter(upload: &Path, params: &HashMap<String, String>) -> Result<Answer, Failure> {
+    let expression = params.get("expression").ok_or_else(|| bad_request("the expression parameter is missing"))?;
+    let log = import(upload)?;
+    let matching: HashSet<&str> = query::select(&log, expression).map_err(bad_request)?.into_iter().collect();
+    let subset = log.subset(&matching, &HashSet::new());
+    Ok(Answer::Json(serde_json::to_value(&subset)?))
+}
+
+fn generate(upload: &Path, params: &HashMap<String, String>) -> Result<Answer, Failure> {
+    let relations = match params.get("relations") {
+        Some(names) => {
+            let names: Vec<String> = names.split(',').map(str::to_string).collect();
+            parse_relations(&names).map_err(bad_request)?
+        }
+        None => Relations::iter().collect(),
+    };
+    let format = match params.get("format") {
+        Some(name) => GraphFormat::from_str(name, true).map_err(|_| bad_request(format!("unknown graph format {:?}", name)))?,
+        None => GraphFormat::Gexf,
+    };
+
+    let source = as_jsonocel(upload).map_err(|e| bad_request(format!("could not read the log: {}", e)))?;
+    let log = import_ocel(&source.to_string_lossy()).map_err(|e| bad_request(format!("could not import the log: {:?}", e)))?;
+    let ocdg = generate_ocdg(&log, &relations);
+
+    let staging = tempfile::Builder::new().suffix(".gexf").tempfile()?.into_temp_path();
+    export_ocdg(&ocdg, &staging.to_string_lossy()).map_err(|e| Failure(500, format!("{:?}", e)))?;
+    if format == GraphFormat::Gexf {
+        return Ok(Answer::File(fs::read(&staging)?, "application/gexf+xml"));
+    }
+    let output = tempfile::Builder::new().tempfile()?.into_temp_path();
+    formats::convert(&staging, &output, format).map_err(|e| Failure(500, e.to_string()))?;
+    let mime = match format {
+        GraphFormat::Dot => "text/vnd.graphviz",
+        GraphFormat::Graphml => "application/graphml+xml",
+        GraphFormat::Json => "application/json",
+        _ => "text/csv",
+    };
+    Ok(Answer::File(fs::read(&output)?, mime))
+}
+
+/// `a=1&b=x%20y` as a map, decoding `+` and percent escapes.
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(key), decode(value))
+        })
+        .collect()
+}
+
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => match (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                (Some(high), Some(low)) => {
+                    decoded.push(high * 16 + low);
+                    i += 2;
+                }
+                _ => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn hex(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|value| value as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_query_parameters() {
+        let params = parse_query("expression=activity+%3D%3D+%22pay%20order%22&relations=A,B&&flag");
+        assert_eq!(params["expression"], r#"activity == "pay order""#);
+        assert_eq!(params["relations"], "A,B");
+        assert_eq!(params["flag"], "");
+        assert_eq!(params.len(), 3);
+    }
+
+    #[test]
+    fn keeps_incomplete_escapes() {
+        assert_eq!(decode("100%"), "100%");
+        assert_eq!(decode("%2"), "%2");
+        assert_eq!(decode("%zz%41"), "%zzA");
+    }
+}
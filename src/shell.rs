@@ -0,0 +1,154 @@
+//! `pmrs-cli shell`: an interactive session on a log that is imported once. Commands
+//! work on the current selection, which `filter` narrows and `reset` restores, so a
+//! large log can be explored without paying for the import on every step.
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use log::debug;
+use pmrs::objects::ocdg::exporter::export_ocdg;
+use pmrs::objects::ocdg::{generate_ocdg, Relations};
+use pmrs::objects::ocel::importer::import_ocel;
+use rustyline::error::ReadlineError;
+use rustyline::Editor;
+use strum::IntoEnumIterator;
+
+use crate::ocdg::formats::{self, GraphFormat};
+use crate::ocdg::relations::parse_relations;
+use crate::ocel::head::print_event;
+use crate::ocel::model::{Format, OcelLog};
+use crate::ocel::{query, stats};
+
+#[derive(Args, Debug)]
+pub struct Shell {
+    /// Path to OCEL file
+    pub path: PathBuf,
+}
+
+const HELP: &str = "\
+commands:
+    stats                      summarize the selection
+    activities                 list the activities of the selection
+    types                      list the object types of the selection
+    head [N]                   print the first N events, 10 by default
+    tail [N]                   print the last N events, 10 by default
+    query EXPR                 print the events matching EXPR, see `ocel query --help`
+    filter EXPR                narrow the selection to the events matching EXPR
+    reset                      select the whole log again
+    save PATH                  write the selection as a log
+    generate PATH [RELATIONS]  generate an OCDG of the selection, all relations if none are given
+    help                       print this list
+    exit                       leave the shell";
+
+pub fn run(shell: &Shell) -> Result<(), Box<dyn Error>> {
+    debug!("Importing log: {:?}", shell.path);
+    let log = OcelLog::import(&shell.path)?;
+    println!("{} events and {} objects loaded, type `help` for the commands", log.events.len(), log.objects.len());
+
+    let mut selection: Option<OcelLog> = None;
+    let mut editor = Editor::<()>::new()?;
+    loop {
+        let line = match editor.readline("pmrs> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line);
+        let (command, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let argument = argument.trim();
+        if command == "exit" || command == "quit" {
+            break;
+        }
+
+        let current = selection.as_ref().unwrap_or(&log);
+        let result = match command {
+            "filter" => filter(current, argument).map(|narrowed| {
+                println!("{} of {} events selected", narrowed.events.len(), log.events.len());
+                selection = Some(narrowed);
+            }),
+            "reset" => {
+                selection = None;
+                println!("{} events selected", log.events.len());
+                Ok(())
+            }
+            _ => execute(current, command, argument),
+        };
+        if let Err(e) = result {
+            println!("error: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Runs a command that does not change the selection.
+fn execute(log: &OcelLog, command: &str, argument: &str) -> Result<(), Box<dyn Error>> {
+    match command {
+        "help" => println!("{}", HELP),
+        "stats" => stats::summary(log, false)?,
+        "activities" => println!("{}", log.activities().join("\n")),
+        "types" => println!("{}", log.object_types().join("\n")),
+        "head" | "tail" => {
+            let count = if argument.is_empty() { 10 } else { argument.parse().map_err(|_| format!("not a number of events: {:?}", argument))? };
+            let skip = if command == "tail" { log.events.len().saturating_sub(count) } else { 0 };
+            for (id, event) in log.events.iter().skip(skip).take(count) {
+                print_event(log, id, event);
+            }
+        }
+        "query" => {
+            let matching = query::select(log, argument)?;
+            for id in matching.iter().take(10) {
+                print_event(log, id, &log.events[*id]);
+            }
+            if matching.len() > 10 {
+                println!("...");
+            }
+            println!("{} of {} events match", matching.len(), log.events.len());
+        }
+        "save" => {
+            if argument.is_empty() {
+                return Err("save needs the path to write to".into());
+            }
+            log.export(Path::new(argument))?;
+            println!("{} events and {} objects -> {}", log.events.len(), log.objects.len(), argument);
+        }
+        "generate" => generate(log, argument)?,
+        _ => return Err(format!("unknown command {:?}, type `help` for the commands", command).into()),
+    }
+    Ok(())
+}
+
+fn filter(log: &OcelLog, expression: &str) -> Result<OcelLog, Box<dyn Error>> {
+    if expression.is_empty() {
+        return Err("filter needs an expression, e.g. `filter activity == \"ship\"`".into());
+    }
+    let matching: HashSet<&str> = query::select(log, expression)?.into_iter().collect();
+    Ok(log.subset(&matching, &HashSet::new()))
+}
+
+/// `generate PATH [RELATIONS]`. pmrs generates from a JSON-OCEL file, so the
+/// selection is written to a temporary one first.
+fn generate(log: &OcelLog, argument: &str) -> Result<(), Box<dyn Error>> {
+    let mut words = argument.split_whitespace();
+    let output = PathBuf::from(words.next().ok_or("generate needs the path to write to")?);
+    let names: Vec<String> = words.flat_map(|word| word.split(',')).filter(|name| !name.is_empty()).map(str::to_string).collect();
+    let relations = if names.is_empty() { Relations::iter().collect() } else { parse_relations(&names)? };
+
+    let source = tempfile::Builder::new().suffix(".jsonocel").tempfile()?.into_temp_path();
+    log.export_as(&source, Format::JsonOcel)?;
+    let ocdg = generate_ocdg(&import_ocel(&source.to_string_lossy())?, &relations);
+
+    let format = GraphFormat::resolve(None, &output);
+    let staging = formats::staging_file(&output, format, false)?;
+    export_ocdg(&ocdg, &staging.as_deref().unwrap_or(&output).to_string_lossy())?;
+    if let Some(staging) = &staging {
+        formats::convert(staging, &output, format)?;
+    }
+    println!("{} relations -> {}", relations.len(), output.display());
+    Ok(())
+}
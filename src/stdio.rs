@@ -0,0 +1,43 @@
+//! `-` as a path: standard input when reading, standard output when writing. While
+//! either carries a log or graph, summary lines go to stderr instead, see
+//! [`piped`].
+use std::cell::RefCell;
+use std::io::{self, Cursor, Read};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub const STDIO: &str = "-";
+
+static PIPED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static STDIN: RefCell<Option<Rc<[u8]>>> = RefCell::new(None);
+}
+
+pub fn is_stdio(path: &Path) -> bool {
+    path == Path::new(STDIO)
+}
+
+/// Marks the run as part of a pipeline, set when any argument is `-`.
+pub fn set_piped(piped: bool) {
+    PIPED.store(piped, Ordering::Relaxed);
+}
+
+pub fn piped() -> bool {
+    PIPED.load(Ordering::Relaxed)
+}
+
+/// Standard input, read to the end on first use and kept in memory so that it can
+/// be opened more than once, e.g. to detect the format before importing.
+pub fn stdin() -> io::Result<Cursor<Rc<[u8]>>> {
+    let cached = STDIN.with(|cell| cell.borrow().clone());
+    if let Some(data) = cached {
+        return Ok(Cursor::new(data));
+    }
+    let mut buffer = Vec::new();
+    io::stdin().lock().read_to_end(&mut buffer)?;
+    let data: Rc<[u8]> = buffer.into();
+    STDIN.with(|cell| *cell.borrow_mut() = Some(data.clone()));
+    Ok(Cursor::new(data))
+}
@@ -0,0 +1,169 @@
+//! Aligned tables for the text output of reports, shared by all commands. Cells may
+//! be styled; the styles are shown as ANSI colors only when stdout is a terminal
+//! that does not carry data, see [`stdio::piped`], and `NO_COLOR` is unset.
+use std::env;
+use std::fmt::Display;
+use std::io::{self, Write};
+
+use crate::stdio;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    Plain,
+    /// Passed checks and additions
+    Good,
+    /// Failures and removals
+    Bad,
+    /// Results worth a second look, e.g. incomplete attribute coverage
+    Warn,
+    Bold,
+}
+
+impl Style {
+    fn code(self) -> Option<&'static str> {
+        match self {
+            Style::Plain => None,
+            Style::Good => Some("32"),
+            Style::Bad => Some("31"),
+            Style::Warn => Some("33"),
+            Style::Bold => Some("1"),
+        }
+    }
+}
+
+/// Whether styles are shown as colors on stdout.
+pub fn color() -> bool {
+    let disabled = env::var_os("NO_COLOR").map_or(false, |value| !value.is_empty());
+    !disabled && atty::is(atty::Stream::Stdout) && !stdio::piped()
+}
+
+/// `text` in `style`, for single values printed outside of a table.
+pub fn paint(text: &str, style: Style) -> String {
+    match style.code() {
+        Some(code) if color() => format!("\x1b[{}m{}\x1b[0m", code, text),
+        _ => text.to_string(),
+    }
+}
+
+pub struct Cell {
+    text: String,
+    style: Style,
+}
+
+impl Cell {
+    pub fn styled(text: impl Display, style: Style) -> Cell {
+        Cell { text: text.to_string(), style }
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Cell {
+        Cell::styled(text, Style::Plain)
+    }
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Cell {
+        Cell { text, style: Style::Plain }
+    }
+}
+
+impl From<usize> for Cell {
+    fn from(count: usize) -> Cell {
+        Cell::styled(count, Style::Plain)
+    }
+}
+
+/// Columns are left aligned unless marked with [`Table::right`], which suits numbers.
+pub struct Table {
+    header: Vec<String>,
+    right: Vec<bool>,
+    rows: Vec<Vec<Cell>>,
+    indent: usize,
+}
+
+impl Table {
+    pub fn new(header: &[&str]) -> Table {
+        Table {
+            header: header.iter().map(|title| title.to_string()).collect(),
+            right: vec![false; header.len()],
+            rows: Vec::new(),
+            indent: 0,
+        }
+    }
+
+    /// Right aligns the given columns.
+    pub fn right(mut self, columns: &[usize]) -> Table {
+        for &column in columns {
+            self.right[column] = true;
+        }
+        self
+    }
+
+    /// Indents every line by `indent` spaces, for tables below a heading.
+    pub fn indent(mut self, indent: usize) -> Table {
+        self.indent = indent;
+        self
+    }
+
+    /// Adds a row. Missing cells are left empty and surplus cells are dropped.
+    pub fn row(&mut self, cells: Vec<Cell>) {
+        self.rows.push(cells);
+    }
+
+    pub fn print(&self) {
+        // stdout going away mid-report is not worth failing the command over
+        let _ = self.write(&mut io::stdout().lock(), color());
+    }
+
+    pub fn write<W: Write>(&self, out: &mut W, color: bool) -> io::Result<()> {
+        let mut widths: Vec<usize> = self.header.iter().map(|title| title.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.text.chars().count());
+            }
+        }
+        let header: Vec<Cell> = self.header.iter().map(|title| Cell::styled(title, Style::Bold)).collect();
+        self.write_line(out, &header, &widths, color)?;
+        for row in &self.rows {
+            self.write_line(out, row, &widths, color)?;
+        }
+        Ok(())
+    }
+
+    fn write_line<W: Write>(&self, out: &mut W, cells: &[Cell], widths: &[usize], color: bool) -> io::Result<()> {
+        let mut line = " ".repeat(self.indent);
+        for (column, width) in widths.iter().enumerate() {
+            let (text, style) = cells.get(column).map_or(("", Style::Plain), |cell| (cell.text.as_str(), cell.style));
+            // padding is measured on the plain text, the escape codes take no room
+            let padding = " ".repeat(width - text.chars().count());
+            let text = match style.code() {
+                Some(code) if color => format!("\x1b[{}m{}\x1b[0m", code, text),
+                _ => text.to_string(),
+            };
+            if column > 0 {
+                line.push_str("  ");
+            }
+            if self.right[column] {
+                line.push_str(&padding);
+                line.push_str(&text);
+            } else {
+                line.push_str(&text);
+                // the last column is not padded, leaving no trailing spaces
+                if column + 1 < widths.len() {
+                    line.push_str(&padding);
+                }
+            }
+        }
+        writeln!(out, "{}", line)
+    }
+}
+
+/// The style of a change from `before` to `after`: additions good, removals bad.
+pub fn change_style(before: usize, after: usize) -> Style {
+    match after.cmp(&before) {
+        std::cmp::Ordering::Greater => Style::Good,
+        std::cmp::Ordering::Less => Style::Bad,
+        std::cmp::Ordering::Equal => Style::Plain,
+    }
+}
@@ -0,0 +1,65 @@
+//! `--watch`: re-running a command whenever one of its inputs changes. Inputs are
+//! polled, which needs no platform support and also works on network drives.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use log::debug;
+
+const POLL: Duration = Duration::from_millis(500);
+
+/// Size and modification time of every file at or below a path.
+type Fingerprint = Vec<(PathBuf, u64, Option<SystemTime>)>;
+
+fn fingerprint<P: AsRef<Path>>(paths: &[P]) -> Fingerprint {
+    let mut files = Vec::new();
+    for path in paths {
+        collect(path.as_ref(), &mut files);
+    }
+    files.sort();
+    files
+}
+
+fn collect(path: &Path, files: &mut Fingerprint) {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.is_dir() => {
+            if let Ok(entries) = fs::read_dir(path) {
+                for entry in entries.flatten() {
+                    collect(&entry.path(), files);
+                }
+            }
+        }
+        Ok(metadata) => files.push((path.to_path_buf(), metadata.len(), metadata.modified().ok())),
+        // a file that is being replaced may briefly not exist
+        Err(_) => files.push((path.to_path_buf(), 0, None)),
+    }
+}
+
+/// Runs `run` now and again after every change to `paths`, until interrupted.
+/// Directories are watched with everything below them.
+pub fn watch<P: AsRef<Path>>(paths: &[P], mut run: impl FnMut()) -> ! {
+    let mut last = fingerprint(paths);
+    loop {
+        run();
+        eprintln!("watching {} input(s) for changes, press Ctrl-C to stop", paths.len());
+        loop {
+            thread::sleep(POLL);
+            let current = fingerprint(paths);
+            if current != last {
+                last = current;
+                break;
+            }
+        }
+        // wait for the writer to finish before running again
+        loop {
+            thread::sleep(POLL);
+            let current = fingerprint(paths);
+            if current == last {
+                break;
+            }
+            last = current;
+        }
+        debug!("Inputs changed, running again");
+    }
+}
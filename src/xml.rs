@@ -0,0 +1,32 @@
+//! Small helpers shared by the XML based readers and writers.
+use std::collections::HashMap;
+use std::error::Error;
+
+use quick_xml::events::BytesStart;
+
+/// Collects the attributes of a tag into an owned, unescaped map.
+pub fn attributes(tag: &BytesStart) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    let mut map = HashMap::new();
+    for attr in tag.attributes() {
+        let attr = attr?;
+        let key = String::from_utf8_lossy(attr.key.as_ref()).into_owned();
+        map.insert(key, attr.unescape_value()?.into_owned());
+    }
+    Ok(map)
+}
+
+/// Escapes a string for use inside an attribute value or text node.
+pub fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}